@@ -0,0 +1,230 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use rand::Rng;
+use crate::game_space::game_space::{CampoGioco, Cella, Posizione};
+use crate::multiplayer::{Agente, PartitaMultigiocatore};
+use crate::player::Esito;
+
+/// Statistiche aggregate su più partite giocate senza interfaccia sullo stesso campo:
+/// visite e morti per cella, turni di sopravvivenza e risorse incontrate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Analisi {
+    dimensione: usize,
+    visite: Vec<Vec<u32>>,
+    morti: Vec<Vec<u32>>,
+    partite: usize,
+    giocatori: usize,
+    turni_sopravvissuti: usize,
+    cibo: u32,
+    veleno: u32,
+}
+
+impl Analisi {
+    /// Crea un'analisi vuota per campi della dimensione indicata.
+    /// # Argomenti
+    ///
+    /// * `dimensione`: Dimensione del campo di gioco.
+    pub fn nuova(dimensione: usize) -> Analisi {
+        Analisi {
+            dimensione,
+            visite: vec![vec![0; dimensione]; dimensione],
+            morti: vec![vec![0; dimensione]; dimensione],
+            partite: 0,
+            giocatori: 0,
+            turni_sopravvissuti: 0,
+            cibo: 0,
+            veleno: 0,
+        }
+    }
+
+    /// Gioca la partita come `PartitaMultigiocatore::esegui_headless`, registrando le celle visitate
+    /// da ogni giocatore e quelle in cui i giocatori vengono eliminati (per una mossa fatale,
+    /// la cella in cui il giocatore ha provato a entrare).
+    ///
+    /// Restituisce il vincitore, se c'è.
+    /// # Argomenti
+    ///
+    /// * `partita`: La partita da giocare, su un campo della dimensione dell'analisi.
+    /// * `agenti`: Un agente per ciascun giocatore, nello stesso ordine.
+    /// * `rng`: Generatore di numeri casuali.
+    /// * `max_turni`: Numero massimo di turni giocati prima di interrompere la partita.
+    pub fn registra<R: Rng + ?Sized>(
+        &mut self,
+        partita: &mut PartitaMultigiocatore,
+        agenti: &mut [Box<dyn Agente>],
+        rng: &mut R,
+        max_turni: usize,
+    ) -> Option<usize> {
+        assert_eq!(partita.campo.dimensione, self.dimensione, "Il campo deve avere la dimensione dell'analisi");
+        assert_eq!(agenti.len(), partita.giocatori.len(), "Serve un agente per ogni giocatore");
+        for g in &partita.giocatori {
+            self.visita(g.player.posizione);
+        }
+        for _ in 0..max_turni {
+            let Some(id) = partita.turno_corrente() else {
+                break;
+            };
+            let in_campo: Vec<bool> = partita.giocatori.iter().map(|g| g.in_campo()).collect();
+            let direzione = agenti[id].scegli(&partita.osservazione(id));
+            let precedente = partita.giocatori[id].player.posizione;
+            partita.gioca_turno(direzione, rng);
+
+            let posizione = partita.giocatori[id].player.posizione;
+            if posizione != precedente {
+                self.visita(posizione);
+            }
+            // Anche l'avversario può essere eliminato, ad esempio in uno scontro
+            for (g, prima) in partita.giocatori.iter().zip(in_campo) {
+                if prima && g.esito == Esito::Perso {
+                    let p = g.eliminato_in.unwrap_or(g.player.posizione);
+                    self.morti[p.riga][p.colonna] += 1;
+                }
+            }
+        }
+
+        self.partite += 1;
+        for g in &partita.giocatori {
+            self.giocatori += 1;
+            self.turni_sopravvissuti += g.player.statistiche.mosse_fatte;
+            self.cibo += g.player.statistiche.cibo_mangiato;
+            self.veleno += g.player.statistiche.veleno_preso;
+        }
+        partita.vincitore()
+    }
+
+    /// Registra il passaggio di un giocatore nella cella indicata.
+    fn visita(&mut self, p: Posizione) {
+        self.visite[p.riga][p.colonna] += 1;
+    }
+
+    /// Restituisce il numero di partite registrate.
+    pub fn partite(&self) -> usize {
+        self.partite
+    }
+
+    /// Restituisce quante volte un giocatore è passato dalla cella indicata (posizioni iniziali comprese).
+    pub fn visite(&self, p: Posizione) -> u32 {
+        self.visite[p.riga][p.colonna]
+    }
+
+    /// Restituisce quanti giocatori sono stati eliminati nella cella indicata.
+    pub fn morti(&self, p: Posizione) -> u32 {
+        self.morti[p.riga][p.colonna]
+    }
+
+    /// Restituisce il numero medio di turni giocati da ciascun giocatore prima di uscire di scena.
+    pub fn sopravvivenza_media(&self) -> f64 {
+        if self.giocatori == 0 {
+            0.0
+        } else {
+            self.turni_sopravvissuti as f64 / self.giocatori as f64
+        }
+    }
+
+    /// Restituisce il rapporto tra celle di cibo e di veleno incontrate dai giocatori,
+    /// o `None` se nessuno ha incontrato veleno.
+    pub fn rapporto_cibo_veleno(&self) -> Option<f64> {
+        if self.veleno == 0 {
+            None
+        } else {
+            Some(self.cibo as f64 / self.veleno as f64)
+        }
+    }
+
+    /// Restituisce visite e morti di ogni cella in formato CSV, con intestazione `riga,colonna,visite,morti`.
+    pub fn in_csv(&self) -> String {
+        let mut csv = String::from("riga,colonna,visite,morti\n");
+        for p in CampoGioco::posizioni(self.dimensione) {
+            csv.push_str(&format!("{},{},{},{}\n", p.riga, p.colonna, self.visite(p), self.morti(p)));
+        }
+        csv
+    }
+
+    /// Salva visite e morti di ogni cella in un file CSV.
+    /// # Argomenti
+    ///
+    /// * `percorso`: Il file da scrivere.
+    pub fn salva_csv<P: AsRef<Path>>(&self, percorso: P) -> io::Result<()> {
+        fs::write(percorso, self.in_csv())
+    }
+
+    /// Restituisce la mappa di calore delle visite sovrapposta al campo, da visualizzare con `Display`.
+    /// # Argomenti
+    ///
+    /// * `campo`: Il campo su cui sono state giocate le partite.
+    pub fn mappa_visite<'a>(&'a self, campo: &'a CampoGioco) -> MappaCalore<'a> {
+        MappaCalore { campo, conteggi: &self.visite }
+    }
+
+    /// Restituisce la mappa di calore delle morti sovrapposta al campo, da visualizzare con `Display`.
+    /// # Argomenti
+    ///
+    /// * `campo`: Il campo su cui sono state giocate le partite.
+    pub fn mappa_morti<'a>(&'a self, campo: &'a CampoGioco) -> MappaCalore<'a> {
+        MappaCalore { campo, conteggi: &self.morti }
+    }
+}
+
+impl fmt::Display for Analisi {
+    /// Implementazione della formattazione per visualizzare le statistiche aggregate.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Partite: {}", self.partite)?;
+        writeln!(f, "Turni di sopravvivenza medi: {:.1}", self.sopravvivenza_media())?;
+        writeln!(f, "Cibo incontrato: {}, veleno incontrato: {}", self.cibo, self.veleno)?;
+        match self.rapporto_cibo_veleno() {
+            Some(rapporto) => writeln!(f, "Rapporto cibo/veleno: {:.2}", rapporto),
+            None => writeln!(f, "Rapporto cibo/veleno: nessun veleno incontrato"),
+        }
+    }
+}
+
+/// Colori di sfondo ANSI usati da `MappaCalore` con `{:#}`, dal livello più basso al più alto.
+const COLORI: [u8; 5] = [44, 46, 42, 43, 41];
+
+/// Mappa di calore di un conteggio per cella, sovrapposta al campo di gioco.
+///
+/// Con `{}` ogni cella mostra `.` se il conteggio è zero, altrimenti una cifra da 1 a 9
+/// proporzionale al conteggio massimo; i muri restano `X`. Con `{:#}` ogni cella mostra
+/// il simbolo del campo su uno sfondo colorato (dal blu al rosso) tramite i colori ANSI.
+pub struct MappaCalore<'a> {
+    campo: &'a CampoGioco,
+    conteggi: &'a Vec<Vec<u32>>,
+}
+
+impl MappaCalore<'_> {
+    /// Restituisce il livello (da 1 a `livelli`) di un conteggio non nullo rispetto al massimo.
+    fn livello(conteggio: u32, massimo: u32, livelli: u32) -> u32 {
+        (conteggio * livelli).div_ceil(massimo).clamp(1, livelli)
+    }
+}
+
+impl fmt::Display for MappaCalore<'_> {
+    /// Implementazione della formattazione per visualizzare la mappa di calore.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let massimo = self.conteggi.iter().flatten().copied().max().unwrap_or(0);
+        for (i, riga) in self.conteggi.iter().enumerate() {
+            for (j, &conteggio) in riga.iter().enumerate() {
+                let cella = self.campo.celle[i][j];
+                if f.alternate() {
+                    let simbolo = cella.effetto().simbolo();
+                    if conteggio == 0 {
+                        write!(f, "{} ", simbolo)?;
+                    } else {
+                        let colore = COLORI[Self::livello(conteggio, massimo, COLORI.len() as u32) as usize - 1];
+                        write!(f, "\x1b[{}m{}\x1b[0m ", colore, simbolo)?;
+                    }
+                } else if cella == Cella::Muro {
+                    write!(f, "X ")?;
+                } else if conteggio == 0 {
+                    write!(f, ". ")?;
+                } else {
+                    write!(f, "{} ", Self::livello(conteggio, massimo, 9))?;
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,170 @@
+use crate::game_space::game_space::Posizione;
+use crate::player::{Esito, Player};
+
+/// Definisce l'effetto che una cella ha sul giocatore che vi entra.
+///
+/// Ogni tipo di cella ha la propria implementazione, così `Player::muovi` può applicare
+/// l'effetto senza conoscere i singoli tipi.
+pub trait Effetto {
+    /// Simbolo usato per rappresentare la cella nel campo di gioco.
+    fn simbolo(&self) -> &'static str;
+
+    /// Indica se la cella impedisce al giocatore di entrarvi.
+    fn blocca(&self) -> bool {
+        false
+    }
+
+    /// Indica se la cella si svuota dopo che il giocatore vi è entrato.
+    fn consumabile(&self) -> bool {
+        false
+    }
+
+    /// Applica l'effetto al giocatore appena entrato nella cella e restituisce l'esito della mossa.
+    /// #Argomenti
+    /// * 'player': Il giocatore che è entrato nella cella
+    fn applica(&self, player: &mut Player) -> Esito;
+}
+
+/// Cella vuota: nessun effetto.
+pub struct Vuoto;
+
+impl Effetto for Vuoto {
+    fn simbolo(&self) -> &'static str {
+        "O"
+    }
+
+    fn applica(&self, _: &mut Player) -> Esito {
+        Esito::InCorso
+    }
+}
+
+/// Cibo: aumenta la forza del giocatore della quantità indicata.
+pub struct Cibo(pub u32);
+
+impl Effetto for Cibo {
+    fn simbolo(&self) -> &'static str {
+        "$"
+    }
+
+    fn consumabile(&self) -> bool {
+        true
+    }
+
+    fn applica(&self, player: &mut Player) -> Esito {
+        player.forza = player.forza.saturating_add(i32::try_from(self.0).unwrap_or(i32::MAX));
+        Esito::InCorso
+    }
+}
+
+/// Veleno: diminuisce la forza del giocatore della quantità indicata, a meno che non sia immune.
+pub struct Veleno(pub u32);
+
+impl Effetto for Veleno {
+    fn simbolo(&self) -> &'static str {
+        "-"
+    }
+
+    fn consumabile(&self) -> bool {
+        true
+    }
+
+    fn applica(&self, player: &mut Player) -> Esito {
+        if player.immunita == 0 {
+            player.forza = player.forza.saturating_sub(i32::try_from(self.0).unwrap_or(i32::MAX));
+        }
+        if player.forza <= 0 {
+            Esito::Perso
+        } else {
+            Esito::InCorso
+        }
+    }
+}
+
+/// Muro: il giocatore non può attraversarlo e rimbalza come sul bordo del campo.
+pub struct Muro;
+
+impl Effetto for Muro {
+    fn simbolo(&self) -> &'static str {
+        "X"
+    }
+
+    fn blocca(&self) -> bool {
+        true
+    }
+
+    fn applica(&self, _: &mut Player) -> Esito {
+        Esito::InCorso
+    }
+}
+
+/// Teletrasporto: sposta il giocatore nella posizione collegata.
+pub struct Teletrasporto(pub Posizione);
+
+impl Effetto for Teletrasporto {
+    fn simbolo(&self) -> &'static str {
+        "T"
+    }
+
+    fn applica(&self, player: &mut Player) -> Esito {
+        player.posizione = self.0;
+        Esito::InCorso
+    }
+}
+
+/// Trappola: fa perdere al giocatore il numero di mosse indicato.
+pub struct Trappola(pub u32);
+
+impl Effetto for Trappola {
+    fn simbolo(&self) -> &'static str {
+        "^"
+    }
+
+    fn applica(&self, player: &mut Player) -> Esito {
+        player.mosse = player.mosse.saturating_sub(self.0 as usize);
+        Esito::InCorso
+    }
+}
+
+/// Potenziamenti che il giocatore può raccogliere.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PowerUp {
+    /// Rende il giocatore immune al veleno per il numero di mosse indicato.
+    Immunita(u32),
+    /// Aggiunge il numero di mosse indicato.
+    MosseExtra(u32),
+}
+
+impl Effetto for PowerUp {
+    fn simbolo(&self) -> &'static str {
+        match self {
+            PowerUp::Immunita(_) => "*",
+            PowerUp::MosseExtra(_) => "+",
+        }
+    }
+
+    fn consumabile(&self) -> bool {
+        true
+    }
+
+    fn applica(&self, player: &mut Player) -> Esito {
+        match self {
+            PowerUp::Immunita(mosse) => player.immunita = player.immunita.saturating_add(*mosse),
+            PowerUp::MosseExtra(mosse) => player.mosse = player.mosse.saturating_add(*mosse as usize),
+        }
+        Esito::InCorso
+    }
+}
+
+/// Uscita: il giocatore che la raggiunge vince la partita.
+pub struct Uscita;
+
+impl Effetto for Uscita {
+    fn simbolo(&self) -> &'static str {
+        "E"
+    }
+
+    fn applica(&self, _: &mut Player) -> Esito {
+        Esito::Vinto
+    }
+}
@@ -1,9 +1,16 @@
 pub mod game_space {
     use std::fmt;
-    use rand::{random, Rng};
+    use rand::Rng;
+    use rand::seq::IndexedRandom;
+    use crate::effetti::{self, Effetto, PowerUp};
+    use crate::nemici::Nemico;
+    use crate::player::Direzione;
+    #[cfg(feature = "serde")]
+    use crate::salvataggio::{self, ErroreCaricamento};
 
     /// Rappresenta una posizione nel campo di gioco.
-    #[derive(Debug, Clone, Copy)]
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct Posizione {
         /// Coordinata della riga.
         pub riga: usize,
@@ -11,8 +18,27 @@ pub mod game_space {
         pub colonna: usize,
     }
 
+    impl Posizione {
+        /// Restituisce la posizione adiacente nella direzione indicata, se interna al campo.
+        /// # Argomenti
+        ///
+        /// * `direzione`: Direzione in cui cercare la posizione adiacente.
+        /// * `dimensione`: Dimensione del campo di gioco.
+        pub fn vicina(&self, direzione: Direzione, dimensione: usize) -> Option<Posizione> {
+            let Posizione { riga, colonna } = *self;
+            match direzione {
+                Direzione::Su if riga > 0 => Some(Posizione { riga: riga - 1, colonna }),
+                Direzione::Giu if riga + 1 < dimensione => Some(Posizione { riga: riga + 1, colonna }),
+                Direzione::Sinistra if colonna > 0 => Some(Posizione { riga, colonna: colonna - 1 }),
+                Direzione::Destra if colonna + 1 < dimensione => Some(Posizione { riga, colonna: colonna + 1 }),
+                _ => None,
+            }
+        }
+    }
+
     /// Rappresenta il contenuto di una cella nel campo di gioco.
     #[derive(Debug, Clone, Copy, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub enum Cella {
         /// La cella è vuota.
         Vuota,
@@ -20,10 +46,82 @@ pub mod game_space {
         Cibo(u32),
         /// La cella contiene veleno con una quantità specifica.
         Veleno(u32),
+        /// La cella è un muro che blocca il movimento.
+        Muro,
+        /// La cella teletrasporta il giocatore nella posizione collegata.
+        Teletrasporto(Posizione),
+        /// La cella è una trappola che fa perdere un numero specifico di mosse.
+        Trappola(u32),
+        /// La cella contiene un potenziamento.
+        PowerUp(PowerUp),
+        /// La cella è l'uscita del campo di gioco.
+        Uscita,
+    }
+
+    impl Cella {
+        /// Restituisce l'effetto che la cella ha sul giocatore che vi entra.
+        pub fn effetto(&self) -> Box<dyn Effetto> {
+            match *self {
+                Cella::Vuota => Box::new(effetti::Vuoto),
+                Cella::Cibo(q) => Box::new(effetti::Cibo(q)),
+                Cella::Veleno(q) => Box::new(effetti::Veleno(q)),
+                Cella::Muro => Box::new(effetti::Muro),
+                Cella::Teletrasporto(destinazione) => Box::new(effetti::Teletrasporto(destinazione)),
+                Cella::Trappola(mosse) => Box::new(effetti::Trappola(mosse)),
+                Cella::PowerUp(power_up) => Box::new(power_up),
+                Cella::Uscita => Box::new(effetti::Uscita),
+            }
+        }
+    }
+
+    /// Errori nella generazione di un campo di gioco.
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum ErroreGenerazione {
+        /// Sono stati chiesti più oggetti delle celle libere in cui piazzarli.
+        TroppiOggetti { richiesti: usize, disponibili: usize },
+        /// Nessuno dei campi generati è risolvibile.
+        NonRisolvibile { tentativi: usize },
+        /// Sono stati chiesti più giocatori delle celle vuote da cui farli partire.
+        TroppiGiocatori { richiesti: usize, disponibili: usize },
+        /// È stato chiesto un campo di dimensione zero.
+        DimensioneNulla,
+        /// La densità indicata non è un numero finito e non negativo.
+        DensitaNonValida { densita: f64 },
     }
 
+    impl fmt::Display for ErroreGenerazione {
+        /// Implementazione della formattazione per visualizzare un errore di generazione.
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                ErroreGenerazione::TroppiOggetti { richiesti, disponibili } => write!(
+                    f,
+                    "impossibile piazzare {} oggetti: ci sono solo {} celle libere",
+                    richiesti, disponibili
+                ),
+                ErroreGenerazione::NonRisolvibile { tentativi } => {
+                    write!(f, "nessun campo risolvibile in {} tentativi", tentativi)
+                }
+                ErroreGenerazione::TroppiGiocatori { richiesti, disponibili } => write!(
+                    f,
+                    "impossibile far partire {} giocatori: ci sono solo {} celle vuote",
+                    richiesti, disponibili
+                ),
+                ErroreGenerazione::DimensioneNulla => write!(f, "il campo deve avere almeno una cella"),
+                ErroreGenerazione::DensitaNonValida { densita } => {
+                    write!(f, "densità non valida: {} (deve essere un numero finito e non negativo)", densita)
+                }
+            }
+        }
+    }
+
+    impl std::error::Error for ErroreGenerazione {}
+
     /// Rappresenta il campo di gioco con la sua dimensione, le celle e la posizione del giocatore.
-    #[derive(Debug)]
+    ///
+    /// Con serde il campo letto passa per gli stessi controlli di `salvataggio::carica_campo`.
+    #[derive(Debug, Clone)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[cfg_attr(feature = "serde", serde(try_from = "DatiCampo"))]
     pub struct CampoGioco {
         /// La dimensione del campo di gioco.
         pub dimensione: usize,
@@ -31,6 +129,36 @@ pub mod game_space {
         pub celle: Vec<Vec<Cella>>,
         /// La posizione del giocatore.
         pub player_position: Posizione,
+        /// I nemici presenti sul campo.
+        #[cfg_attr(feature = "serde", serde(default))]
+        pub nemici: Vec<Nemico>,
+    }
+
+    /// Campo di gioco letto con serde, prima dei controlli di coerenza.
+    #[cfg(feature = "serde")]
+    #[derive(serde::Deserialize)]
+    struct DatiCampo {
+        dimensione: usize,
+        celle: Vec<Vec<Cella>>,
+        player_position: Posizione,
+        #[serde(default)]
+        nemici: Vec<Nemico>,
+    }
+
+    #[cfg(feature = "serde")]
+    impl TryFrom<DatiCampo> for CampoGioco {
+        type Error = ErroreCaricamento;
+
+        fn try_from(dati: DatiCampo) -> Result<CampoGioco, ErroreCaricamento> {
+            let campo = CampoGioco {
+                dimensione: dati.dimensione,
+                celle: dati.celle,
+                player_position: dati.player_position,
+                nemici: dati.nemici,
+            };
+            salvataggio::verifica_campo(&campo)?;
+            Ok(campo)
+        }
     }
 
     impl CampoGioco {
@@ -39,58 +167,62 @@ pub mod game_space {
         ///
         /// * `dimensione`: Dimensione del campo di gioco.
         /// * `m`: Numero di celle veleno e cibo in campo.
-        pub fn nuovo(dimensione: usize, m: usize) -> CampoGioco {
+        pub fn nuovo(dimensione: usize, m: usize) -> Result<CampoGioco, ErroreGenerazione> {
+            let mut rng = rand::thread_rng();
+            CampoGioco::nuovo_con_rng(dimensione, m, &mut rng)
+        }
+
+        /// Come `nuovo`, ma usa il generatore di numeri casuali specificato.
+        ///
+        /// Le `m` celle vengono scelte tutte distinte tra loro e dalla posizione del giocatore;
+        /// ognuna contiene cibo o veleno con la stessa probabilità. Restituisce un errore se la
+        /// dimensione è zero o se `m` supera le `dimensione * dimensione - 1` celle libere.
+        /// # Argomenti
+        ///
+        /// * `dimensione`: Dimensione del campo di gioco.
+        /// * `m`: Numero di celle veleno e cibo in campo.
+        /// * `rng`: Generatore di numeri casuali.
+        pub fn nuovo_con_rng<R: Rng + ?Sized>(dimensione: usize, m: usize, rng: &mut R) -> Result<CampoGioco, ErroreGenerazione> {
+            if dimensione == 0 {
+                return Err(ErroreGenerazione::DimensioneNulla);
+            }
             // Inizializza tutte le celle come vuote
             let mut celle = vec![vec![Cella::Vuota; dimensione]; dimensione];
-            let mut x_prec=0;
-            let mut y_prec=0;
-            let mut p_pos_x=0;
-            let mut p_pos_y=0;
             // Posizione casuale del giocatore
-            let (x, y) = CampoGioco::pos_casuale(dimensione, (x_prec, y_prec), (p_pos_x,p_pos_y));
-            celle[x][y] = Cella::Vuota; // Rimuovi qualsiasi oggetto presente nella cella del giocatore
-            x_prec= x;
-            y_prec=y;
-            p_pos_x=x;
-            p_pos_y=y;
-            // Posiziona m cibo e m veleno in posizioni casuali
-            for _ in 0..m {
-                let (x, y) = CampoGioco::pos_casuale(dimensione, (x_prec, y_prec),(p_pos_x,p_pos_y));
-                x_prec= x;
-                y_prec=y;
-                if random::<bool>() {
-                    celle[x_prec][y_prec] = Cella::Cibo(10)
-                } else {
-                    celle[x_prec][y_prec] = Cella::Veleno(10)
-                };
-            }
-
             let player_position = Posizione {
-                riga: x,
-                colonna: y,
+                riga: rng.gen_range(0..dimensione),
+                colonna: rng.gen_range(0..dimensione),
             };
-            CampoGioco {
+            // Posiziona m cibo e veleno in posizioni casuali distinte
+            let libere: Vec<Posizione> = CampoGioco::posizioni(dimensione)
+                .filter(|p| *p != player_position)
+                .collect();
+            if m > libere.len() {
+                return Err(ErroreGenerazione::TroppiOggetti { richiesti: m, disponibili: libere.len() });
+            }
+            for p in libere.choose_multiple(rng, m) {
+                celle[p.riga][p.colonna] = if rng.gen_bool(0.5) { Cella::Cibo(10) } else { Cella::Veleno(10) };
+            }
+
+            Ok(CampoGioco {
                 dimensione,
                 celle,
                 player_position,
-            }
+                nemici: Vec::new(),
+            })
         }
 
-        /// Genera casualmente una posizione nel campo di gioco, evitando la posizione precedente e la posizione del giocatore.
-        /// #Argomenti
+        /// Restituisce tutte le posizioni di un campo di gioco della dimensione indicata, riga per riga.
+        /// # Argomenti
         ///
-        /// * 'dimensione': Dimensione del campo di gioco.
-        /// * 'precedente': Posizione precedente generata
-        /// * 'p_pos': Posizione del giocatore
-        fn pos_casuale(dimensione: usize, precedente: (usize, usize), p_pos:(usize,usize)) -> (usize, usize) {
-            let mut rng = rand::thread_rng();
-            loop {
-                let riga = rng.gen_range(0..dimensione);
-                let colonna = rng.gen_range(0..dimensione);
-                if (riga, colonna) != precedente && (riga, colonna)!= p_pos {
-                    return (riga, colonna);
-                }
-            }
+        /// * `dimensione`: Dimensione del campo di gioco.
+        pub fn posizioni(dimensione: usize) -> impl Iterator<Item = Posizione> {
+            (0..dimensione).flat_map(move |riga| (0..dimensione).map(move |colonna| Posizione { riga, colonna }))
+        }
+
+        /// Restituisce la cella nella posizione indicata.
+        pub fn cella(&self, posizione: Posizione) -> Cella {
+            self.celle[posizione.riga][posizione.colonna]
         }
     }
 
@@ -102,10 +234,9 @@ pub mod game_space {
 
             for (i, riga) in self.celle.iter().enumerate() {
                 for (j, cella) in riga.iter().enumerate() {
-                    let simbolo = match cella {
-                        Cella::Vuota => "O",
-                        Cella::Cibo(_) => "$",
-                        Cella::Veleno(_) => "-",
+                    let simbolo = match self.nemico_in(Posizione { riga: i, colonna: j }) {
+                        Some(nemico) => nemico.simbolo(),
+                        None => cella.effetto().simbolo(),
                     };
 
                     // Se la posizione corrente è la stessa del giocatore, rappresenta il giocatore come 'P'
@@ -0,0 +1,294 @@
+use std::collections::VecDeque;
+use rand::seq::IndexedRandom;
+use rand::{Rng, RngCore};
+use crate::game_space::game_space::{CampoGioco, Cella, ErroreGenerazione, Posizione};
+use crate::player::Direzione;
+
+/// Strategia di generazione di un campo di gioco.
+pub trait Generatore {
+    /// Genera un campo di gioco della dimensione indicata.
+    /// # Argomenti
+    ///
+    /// * `dimensione`: Dimensione del campo di gioco.
+    /// * `rng`: Generatore di numeri casuali.
+    fn genera(&self, dimensione: usize, rng: &mut dyn RngCore) -> Result<CampoGioco, ErroreGenerazione>;
+}
+
+/// Distribuisce cibo e veleno uniformemente nel campo, come `CampoGioco::nuovo`.
+pub struct Uniforme {
+    /// Numero di celle con cibo o veleno.
+    pub oggetti: usize,
+}
+
+impl Generatore for Uniforme {
+    fn genera(&self, dimensione: usize, rng: &mut dyn RngCore) -> Result<CampoGioco, ErroreGenerazione> {
+        CampoGioco::nuovo_con_rng(dimensione, self.oggetti, rng)
+    }
+}
+
+/// Raggruppa le risorse in ammassi: ogni ammasso contiene solo cibo o solo veleno.
+pub struct Ammassi {
+    /// Numero di ammassi.
+    pub ammassi: usize,
+    /// Numero di celle occupate da ciascun ammasso.
+    pub oggetti_per_ammasso: usize,
+    /// Distanza massima (in mosse) di una cella dal centro del suo ammasso.
+    pub raggio: usize,
+}
+
+impl Generatore for Ammassi {
+    fn genera(&self, dimensione: usize, rng: &mut dyn RngCore) -> Result<CampoGioco, ErroreGenerazione> {
+        let mut campo = CampoGioco::nuovo_con_rng(dimensione, 0, rng)?;
+        for _ in 0..self.ammassi {
+            let centro = Posizione {
+                riga: rng.gen_range(0..dimensione),
+                colonna: rng.gen_range(0..dimensione),
+            };
+            let cella = if rng.gen_bool(0.5) { Cella::Cibo(10) } else { Cella::Veleno(10) };
+            let vicine: Vec<Posizione> = CampoGioco::posizioni(dimensione)
+                .filter(|p| p.riga.abs_diff(centro.riga) + p.colonna.abs_diff(centro.colonna) <= self.raggio)
+                .filter(|p| *p != campo.player_position && campo.cella(*p) == Cella::Vuota)
+                .collect();
+            for p in vicine.choose_multiple(rng, self.oggetti_per_ammasso) {
+                campo.celle[p.riga][p.colonna] = cella;
+            }
+        }
+        Ok(campo)
+    }
+}
+
+/// Algoritmo usato per scavare i corridoi di un labirinto.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AlgoritmoLabirinto {
+    /// Visita in profondità con ritorno sui propri passi: corridoi lunghi e pochi bivi.
+    Backtracker,
+    /// Algoritmo di Prim randomizzato: molti corridoi corti e ramificati.
+    Prim,
+}
+
+/// Genera un labirinto di muri con il giocatore in (0, 0), l'uscita nella stanza più lontana
+/// e cibo e veleno sparsi nei corridoi.
+///
+/// Le stanze del labirinto sono le celle con coordinate pari; le celle dispari tra due stanze
+/// sono corridoi o muri.
+pub struct Labirinto {
+    /// Algoritmo di generazione.
+    pub algoritmo: AlgoritmoLabirinto,
+    /// Numero di celle con cibo o veleno.
+    pub oggetti: usize,
+}
+
+impl Labirinto {
+    /// Restituisce le stanze adiacenti (a distanza 2) a quella indicata.
+    fn stanze_vicine(p: Posizione, dimensione: usize) -> Vec<Posizione> {
+        Direzione::TUTTE
+            .iter()
+            .filter_map(|d| p.vicina(*d, dimensione).and_then(|v| v.vicina(*d, dimensione)))
+            .collect()
+    }
+
+    /// Abbatte il muro tra due stanze adiacenti.
+    fn collega(celle: &mut [Vec<Cella>], a: Posizione, b: Posizione) {
+        celle[a.riga][a.colonna] = Cella::Vuota;
+        celle[b.riga][b.colonna] = Cella::Vuota;
+        celle[(a.riga + b.riga) / 2][(a.colonna + b.colonna) / 2] = Cella::Vuota;
+    }
+}
+
+impl Generatore for Labirinto {
+    fn genera(&self, dimensione: usize, rng: &mut dyn RngCore) -> Result<CampoGioco, ErroreGenerazione> {
+        if dimensione == 0 {
+            return Err(ErroreGenerazione::DimensioneNulla);
+        }
+        let mut celle = vec![vec![Cella::Muro; dimensione]; dimensione];
+        let inizio = Posizione { riga: 0, colonna: 0 };
+        let mut visitate = vec![vec![false; dimensione]; dimensione];
+        visitate[0][0] = true;
+        celle[0][0] = Cella::Vuota;
+
+        match self.algoritmo {
+            AlgoritmoLabirinto::Backtracker => {
+                let mut pila = vec![inizio];
+                while let Some(&corrente) = pila.last() {
+                    let libere: Vec<Posizione> = Labirinto::stanze_vicine(corrente, dimensione)
+                        .into_iter()
+                        .filter(|p| !visitate[p.riga][p.colonna])
+                        .collect();
+                    match libere.choose(rng) {
+                        Some(&prossima) => {
+                            visitate[prossima.riga][prossima.colonna] = true;
+                            Labirinto::collega(&mut celle, corrente, prossima);
+                            pila.push(prossima);
+                        }
+                        None => {
+                            pila.pop();
+                        }
+                    }
+                }
+            }
+            AlgoritmoLabirinto::Prim => {
+                let mut frontiera: Vec<(Posizione, Posizione)> = Labirinto::stanze_vicine(inizio, dimensione)
+                    .into_iter()
+                    .map(|p| (inizio, p))
+                    .collect();
+                while !frontiera.is_empty() {
+                    let (da, a) = frontiera.swap_remove(rng.gen_range(0..frontiera.len()));
+                    if visitate[a.riga][a.colonna] {
+                        continue;
+                    }
+                    visitate[a.riga][a.colonna] = true;
+                    Labirinto::collega(&mut celle, da, a);
+                    frontiera.extend(
+                        Labirinto::stanze_vicine(a, dimensione)
+                            .into_iter()
+                            .filter(|p| !visitate[p.riga][p.colonna])
+                            .map(|p| (a, p)),
+                    );
+                }
+            }
+        }
+
+        let mut campo = CampoGioco {
+            dimensione,
+            celle,
+            player_position: inizio,
+            nemici: Vec::new(),
+        };
+        let distanze = distanze(&campo);
+        if let Some(uscita) = CampoGioco::posizioni(dimensione)
+            .filter(|p| distanze[p.riga][p.colonna].is_some())
+            .max_by_key(|p| distanze[p.riga][p.colonna])
+        {
+            if uscita != inizio {
+                campo.celle[uscita.riga][uscita.colonna] = Cella::Uscita;
+            }
+        }
+        let corridoi: Vec<Posizione> = CampoGioco::posizioni(dimensione)
+            .filter(|p| *p != inizio && campo.cella(*p) == Cella::Vuota)
+            .collect();
+        if self.oggetti > corridoi.len() {
+            return Err(ErroreGenerazione::TroppiOggetti { richiesti: self.oggetti, disponibili: corridoi.len() });
+        }
+        for p in corridoi.choose_multiple(rng, self.oggetti) {
+            campo.celle[p.riga][p.colonna] = if rng.gen_bool(0.5) { Cella::Cibo(10) } else { Cella::Veleno(10) };
+        }
+        Ok(campo)
+    }
+}
+
+/// Distribuisce cibo e veleno secondo una mappa di densità a rumore continuo (stile Perlin):
+/// le risorse si concentrano in zone dense e il tipo prevalente cambia gradualmente nel campo.
+pub struct Densita {
+    /// Ampiezza (in celle) delle zone di densità: valori più grandi producono zone più estese.
+    pub scala: f64,
+    /// Probabilità massima che una cella contenga una risorsa, raggiunta nelle zone più dense.
+    /// Deve essere un numero finito e non negativo; i valori oltre 1 saturano le zone più dense.
+    pub densita_massima: f64,
+}
+
+/// Rumore a valori su reticolo con interpolazione smussata, con valori in [0, 1].
+struct Rumore {
+    reticolo: Vec<Vec<f64>>,
+    scala: f64,
+}
+
+impl Rumore {
+    /// Crea un rumore che copre un campo della dimensione indicata.
+    fn nuovo(dimensione: usize, scala: f64, rng: &mut dyn RngCore) -> Rumore {
+        let scala = scala.max(1.0);
+        let punti = (dimensione as f64 / scala).ceil() as usize + 2;
+        let reticolo = (0..punti).map(|_| (0..punti).map(|_| rng.gen_range(0.0..1.0)).collect()).collect();
+        Rumore { reticolo, scala }
+    }
+
+    /// Valore del rumore nella posizione indicata.
+    fn valore(&self, p: Posizione) -> f64 {
+        let smussa = |t: f64| t * t * (3.0 - 2.0 * t);
+        let (y, x) = (p.riga as f64 / self.scala, p.colonna as f64 / self.scala);
+        let (y0, x0) = (y.floor() as usize, x.floor() as usize);
+        let (ty, tx) = (smussa(y - y0 as f64), smussa(x - x0 as f64));
+        let r = &self.reticolo;
+        let alto = r[y0][x0] + (r[y0][x0 + 1] - r[y0][x0]) * tx;
+        let basso = r[y0 + 1][x0] + (r[y0 + 1][x0 + 1] - r[y0 + 1][x0]) * tx;
+        alto + (basso - alto) * ty
+    }
+}
+
+impl Generatore for Densita {
+    fn genera(&self, dimensione: usize, rng: &mut dyn RngCore) -> Result<CampoGioco, ErroreGenerazione> {
+        if !self.densita_massima.is_finite() || self.densita_massima < 0.0 {
+            return Err(ErroreGenerazione::DensitaNonValida { densita: self.densita_massima });
+        }
+        let mut campo = CampoGioco::nuovo_con_rng(dimensione, 0, rng)?;
+        let densita = Rumore::nuovo(dimensione, self.scala, rng);
+        let tipo = Rumore::nuovo(dimensione, self.scala, rng);
+        for p in CampoGioco::posizioni(dimensione).filter(|p| *p != campo.player_position) {
+            if rng.gen_bool((densita.valore(p) * self.densita_massima).clamp(0.0, 1.0)) {
+                campo.celle[p.riga][p.colonna] = if tipo.valore(p) > 0.5 { Cella::Cibo(10) } else { Cella::Veleno(10) };
+            }
+        }
+        Ok(campo)
+    }
+}
+
+/// Calcola la distanza in mosse di ogni cella dalla posizione del giocatore, senza attraversare muri
+/// e seguendo i teletrasporti. Le celle irraggiungibili hanno distanza `None`.
+/// # Argomenti
+///
+/// * `campo`: Il campo di gioco.
+pub fn distanze(campo: &CampoGioco) -> Vec<Vec<Option<usize>>> {
+    let mut distanze = vec![vec![None; campo.dimensione]; campo.dimensione];
+    let mut coda = VecDeque::new();
+    distanze[campo.player_position.riga][campo.player_position.colonna] = Some(0);
+    coda.push_back(campo.player_position);
+    while let Some(p) = coda.pop_front() {
+        let distanza = distanze[p.riga][p.colonna].unwrap_or(0);
+        for direzione in Direzione::TUTTE {
+            let Some(mut vicina) = p.vicina(direzione, campo.dimensione) else {
+                continue;
+            };
+            if campo.cella(vicina).effetto().blocca() {
+                continue;
+            }
+            if let Cella::Teletrasporto(destinazione) = campo.cella(vicina) {
+                vicina = destinazione;
+            }
+            if distanze[vicina.riga][vicina.colonna].is_none() {
+                distanze[vicina.riga][vicina.colonna] = Some(distanza + 1);
+                coda.push_back(vicina);
+            }
+        }
+    }
+    distanze
+}
+
+/// Indica se tutto il cibo del campo è raggiungibile dal giocatore.
+/// # Argomenti
+///
+/// * `campo`: Il campo di gioco.
+pub fn risolvibile(campo: &CampoGioco) -> bool {
+    let distanze = distanze(campo);
+    CampoGioco::posizioni(campo.dimensione)
+        .filter(|p| matches!(campo.cella(*p), Cella::Cibo(_)))
+        .all(|p| distanze[p.riga][p.colonna].is_some())
+}
+
+/// Genera campi con il generatore indicato finché non ne trova uno risolvibile.
+///
+/// Restituisce `ErroreGenerazione::NonRisolvibile` se nessuno dei tentativi produce un campo
+/// risolvibile, oppure l'errore del generatore.
+/// # Argomenti
+///
+/// * `generatore`: Il generatore da usare.
+/// * `dimensione`: Dimensione del campo di gioco.
+/// * `rng`: Generatore di numeri casuali.
+/// * `tentativi`: Numero massimo di campi generati.
+pub fn genera_risolvibile(generatore: &dyn Generatore, dimensione: usize, rng: &mut dyn RngCore, tentativi: usize) -> Result<CampoGioco, ErroreGenerazione> {
+    for _ in 0..tentativi {
+        let campo = generatore.genera(dimensione, rng)?;
+        if risolvibile(&campo) {
+            return Ok(campo);
+        }
+    }
+    Err(ErroreGenerazione::NonRisolvibile { tentativi })
+}
@@ -1,3 +1,24 @@
+#[allow(clippy::module_inception)]
 pub mod game_space;
 
-pub mod player;
\ No newline at end of file
+pub mod player;
+
+pub mod effetti;
+
+pub mod salvataggio;
+
+pub mod multiplayer;
+
+pub mod generazione;
+
+pub mod nemici;
+
+pub mod visibilita;
+
+pub mod server;
+
+pub mod punteggio;
+
+pub mod storia;
+
+pub mod analisi;
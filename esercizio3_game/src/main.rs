@@ -1,56 +1,486 @@
+use std::env;
 use std::io;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use esercizio3_game::analisi::Analisi;
 use esercizio3_game::game_space::game_space::CampoGioco;
-use esercizio3_game::player::{Direzione, Player};
-
+use esercizio3_game::generazione::{self, AlgoritmoLabirinto, Ammassi, Densita, Generatore, Labirinto, Uniforme};
+use esercizio3_game::nemici;
+use esercizio3_game::multiplayer::{Agente, AgenteCasuale, AgenteGoloso, PartitaMultigiocatore, RegolaCollisione};
+use esercizio3_game::player::{Direzione, Esito, Player};
+use esercizio3_game::punteggio::{self, Classifica, Punteggio, VoceClassifica};
+use esercizio3_game::salvataggio;
+use esercizio3_game::server::Server;
+use esercizio3_game::storia::{Replay, Storia};
+use esercizio3_game::visibilita::{ModalitaVisibilita, Nebbia};
 
+/// Richiede all'utente la dimensione della mappa e la quantità di cibo e veleno, quindi gestisce il gioco.
+///
+/// Modalità disponibili in base agli argomenti:
+/// * nessun argomento: nuova partita a giocatore singolo;
+/// * `multi <giocatori> [blocca|combatti|condividi] [raggio]`: partita multigiocatore a turni sullo stesso
+///   terminale, con nebbia di guerra se è indicato il raggio visibile;
+/// * `headless <giocatori> [blocca|combatti|condividi] [raggio]`: partita multigiocatore giocata da agenti;
+/// * `nebbia <raggio> [vista]`: partita a giocatore singolo in cui si vedono solo le celle entro il raggio
+///   (e non nascoste dai muri con `vista`);
+/// * `genera <uniforme|ammassi|labirinto|prim|densita> [seme]`: partita a giocatore singolo su un campo
+///   generato con la strategia indicata (sempre risolvibile);
+/// * `server <porta> [blocca|combatti|condividi]`: partita multigiocatore servita su TCP (localhost)
+///   con il protocollo a righe JOIN, MOVE <direzione>, STATE, QUIT;
+/// * `analisi <partite> [giocatori] [blocca|combatti|condividi] [file.csv]`: gioca molte partite con
+///   agenti sullo stesso campo e mostra le mappe di calore di visite e morti, con statistiche aggregate;
+/// * `replay <file>`: mostra passo passo una partita registrata con il comando `REPLAY <file>`;
+/// * `classifica`: mostra la classifica locale, divisa per regole e seme;
+/// * `<file>`: riprende la partita salvata nel file.
+///
+/// Questa funzione avvia il gioco e gestisce l'interazione con l'utente.
 fn main() {
-    /// Richiede all'utente la dimensione della mappa e la quantità di cibo e veleno, quindi gestisce il gioco.
-    ///
-    /// Questa funzione avvia il gioco e gestisce l'interazione con l'utente.
-    fn main() {
-        // Richiedi all'utente di inserire la dimensione della mappa
-        println!("Inserisci la dimensione della mappa:");
-        let mut dimensione = String::new();
-        io::stdin().read_line(&mut dimensione)
-            .expect("Errore durante la lettura dell'input");
-        let dimensione: usize = dimensione.trim()
-            .parse()
-            .expect("Inserisci un numero valido");
-
-
-        // Richiedi all'utente di inserire la quantità di cibo e veleno
-        println!("Inserisci la quantità di cibo e veleno:");
-        let mut qty = String::new();
-        io::stdin().read_line(&mut qty)
-            .expect("Errore durante la lettura dell'input");
-        let qty: usize = qty.trim()
-            .parse()
-            .expect("Inserisci un numero valido");
-
-        // Crea il campo di gioco e il giocatore
-        let mut gamespace = CampoGioco::nuovo(dimensione, qty);
-        let mut player = Player::nuovo(&gamespace);
-
-        loop {
-            // Visualizza lo stato attuale del gioco
-            println!("{}", gamespace);
-            println!("{}", player);
-            println!("Che mossa vuoi fare? (G/S/D/L):");
-
-            // Leggi l'input dell'utente
-            let mut input = String::new();
-            io::stdin().read_line(&mut input)
-                .expect("Errore durante la lettura dell'input");
-            let input = input.trim().to_ascii_uppercase();
-
-            // Esegui la mossa del giocatore in base all'input
-            match input.as_str() {
-                "G" => player.muovi(&mut gamespace, Direzione::Giu),
-                "S" => player.muovi(&mut gamespace, Direzione::Su),
-                "D" => player.muovi(&mut gamespace, Direzione::Destra),
-                "L" => player.muovi(&mut gamespace, Direzione::Sinistra),
-                _ => println!("Input non valido! Usa G, S, D o L per muoverti."),
+    let argomenti: Vec<String> = env::args().skip(1).collect();
+    match argomenti.first().map(String::as_str) {
+        Some("multi") => gioca_multigiocatore(&argomenti[1..], false),
+        Some("headless") => gioca_multigiocatore(&argomenti[1..], true),
+        Some("server") => avvia_server(&argomenti[1..]),
+        Some("analisi") => analizza(&argomenti[1..]),
+        Some("classifica") => match Classifica::carica(FILE_CLASSIFICA) {
+            Ok(classifica) if classifica == Classifica::default() => println!("La classifica è vuota."),
+            Ok(classifica) => print!("{}", classifica),
+            Err(e) => println!("Impossibile leggere la classifica: {}", e),
+        },
+        Some("nebbia") => {
+            let raggio: usize = argomenti.get(1)
+                .and_then(|r| r.parse().ok())
+                .expect("Inserisci un raggio valido");
+            let modalita = match argomenti.get(2).map(String::as_str) {
+                Some("vista") => ModalitaVisibilita::LineaDiVista(raggio),
+                _ => ModalitaVisibilita::Raggio(raggio),
+            };
+            let regole = match modalita {
+                ModalitaVisibilita::LineaDiVista(_) => format!("nebbia vista {}", raggio),
+                _ => format!("nebbia {}", raggio),
+            };
+            let (gamespace, player, storia, info) = nuova_partita(&regole);
+            let nebbia = Nebbia::nuova(gamespace.dimensione, modalita);
+            gioca_con_nebbia(gamespace, player, storia, Some(nebbia), Some(info));
+        }
+        Some("genera") => {
+            let (gamespace, player, storia, info) = genera_partita(&argomenti[1..]);
+            gioca_con_nebbia(gamespace, player, storia, None, Some(info));
+        }
+        Some("replay") => {
+            let percorso = argomenti.get(1).expect("Inserisci il file della registrazione");
+            let replay = Replay::carica_da_file(percorso)
+                .unwrap_or_else(|e| panic!("Impossibile caricare la registrazione {}: {}", percorso, e));
+            guarda_replay(&replay);
+        }
+        Some(percorso) => {
+            let (gamespace, player) = salvataggio::carica_da_file(percorso)
+                .unwrap_or_else(|e| panic!("Impossibile caricare la partita {}: {}", percorso, e));
+            gioca(gamespace, player);
+        }
+        None => {
+            let (gamespace, player, storia, info) = nuova_partita("singolo");
+            gioca_con_nebbia(gamespace, player, storia, None, Some(info));
+        }
+    }
+}
+
+/// File della classifica locale.
+const FILE_CLASSIFICA: &str = "classifica.txt";
+
+/// Regole e seme di una partita, usati per la classifica.
+struct InfoPartita {
+    /// Descrizione delle regole e dei parametri del campo.
+    regole: String,
+    /// Seme da cui derivano il campo e lo svolgimento della partita.
+    seme: u64,
+}
+
+/// Gestisce la partita a giocatore singolo.
+fn gioca(gamespace: CampoGioco, player: Player) {
+    gioca_con_nebbia(gamespace, player, Storia::nuova(rand::random()), None, None);
+}
+
+/// Gestisce la partita a giocatore singolo, mostrando il campo attraverso la nebbia di guerra se presente.
+///
+/// Le mosse sono registrate in `storia`, da cui derivano anche i loro generatori di numeri casuali.
+/// A fine partita mostra il riepilogo del punteggio e, se la partita ha un seme noto e nessuna mossa
+/// è stata annullata, lo registra in classifica.
+fn gioca_con_nebbia(mut gamespace: CampoGioco, mut player: Player, mut storia: Storia, mut nebbia: Option<Nebbia>, info: Option<InfoPartita>) {
+    let veleno_iniziale = punteggio::conta_veleno(&gamespace);
+    let mut replay = Replay::nuovo(&gamespace, &player, storia.seme());
+    let mut annullata = false;
+    let esito = loop {
+        // Visualizza lo stato attuale del gioco
+        match nebbia.as_mut() {
+            Some(nebbia) => {
+                nebbia.aggiorna(&gamespace, player.posizione);
+                println!("{:#}", nebbia.vista(&gamespace, player.posizione));
+            }
+            None => println!("{}", gamespace),
+        }
+        println!("{}", player);
+        println!("Che mossa vuoi fare? (G/S/D/L, U annulla, R ripeti, SALVA <file>, REPLAY <file>):");
+
+        // Leggi l'input dell'utente
+        let input = leggi_riga();
+
+        // Salvataggio della partita in corso
+        if let Some(percorso) = input.strip_prefix("SALVA ") {
+            match salvataggio::salva_su_file(percorso.trim(), &gamespace, &player) {
+                Ok(()) => println!("Partita salvata in {}", percorso.trim()),
+                Err(e) => println!("Errore durante il salvataggio: {}", e),
+            }
+            continue;
+        }
+
+        // Salvataggio della registrazione delle mosse fatte finora
+        if let Some(percorso) = input.strip_prefix("REPLAY ") {
+            replay.aggiorna(&storia);
+            match replay.salva_su_file(percorso.trim()) {
+                Ok(()) => println!("Registrazione salvata in {}", percorso.trim()),
+                Err(e) => println!("Errore durante il salvataggio: {}", e),
             }
+            continue;
         }
+
+        // Esegui la mossa del giocatore in base all'input
+        let esito = match input.to_ascii_uppercase().as_str() {
+            "U" => {
+                if storia.annulla(&mut gamespace, &mut player) {
+                    annullata = true;
+                } else {
+                    println!("Non ci sono mosse da annullare.");
+                }
+                continue;
+            }
+            "R" => match storia.ripeti(&mut gamespace, &mut player) {
+                Some(esito) => esito,
+                None => {
+                    println!("Non ci sono mosse da ripetere.");
+                    continue;
+                }
+            },
+            _ => match direzione_da_input(&input) {
+                Some(direzione) => storia.esegui(&mut gamespace, &mut player, direzione),
+                None => {
+                    println!("Input non valido! Usa G, S, D o L per muoverti.");
+                    continue;
+                }
+            },
+        };
+        match esito {
+            Esito::Vinto => {
+                println!("{}", gamespace);
+                print!("HAI VINTO!\n IL NUMERO DI MOSSE è ARRIVATO A 0 O HAI TROVATO L'USCITA\n");
+                break esito;
+            }
+            Esito::Perso => {
+                print!("HAI PERSO!\n SEI STATO AVVELENATO O SCONFITTO DA UN NEMICO\n");
+                break esito;
+            }
+            Esito::InCorso => {}
+        }
+    };
+
+    let punteggio = Punteggio::calcola(&player, esito, veleno_iniziale);
+    print!("{}", punteggio);
+    match info {
+        Some(_) if annullata => println!("Sono state annullate delle mosse: la partita non entra in classifica."),
+        Some(info) => registra_in_classifica(info, punteggio.totale),
+        None => {}
+    }
+}
+
+/// Mostra passo passo una partita registrata: Invio o A avanza di una mossa, I torna indietro, Q esce.
+fn guarda_replay(replay: &Replay) {
+    let mut visore = replay.visore().expect("Registrazione non valida");
+    loop {
+        println!("{}", visore.campo);
+        println!("{}", visore.player);
+        if let Some(mossa) = visore.ultima_mossa() {
+            println!("Mossa {}/{}: richiesta {}, esito {:?}", visore.passo(), visore.totale(), mossa.richiesta, mossa.esito);
+        } else {
+            println!("Inizio della partita (0/{})", visore.totale());
+        }
+        println!("Invio/A avanti, I indietro, Q esci:");
+        match leggi_riga().to_ascii_uppercase().as_str() {
+            "" | "A" => {
+                if visore.avanti().is_none() {
+                    println!("Fine della registrazione.");
+                }
+            }
+            "I" => {
+                if !visore.indietro() {
+                    println!("Sei già all'inizio.");
+                }
+            }
+            "Q" => break,
+            _ => println!("Comando non valido."),
+        }
+    }
+}
+
+/// Chiede il nome del giocatore e registra il punteggio nella classifica locale, mostrando
+/// i migliori punteggi per le stesse regole e lo stesso seme.
+fn registra_in_classifica(info: InfoPartita, punti: u64) {
+    println!("Inserisci il tuo nome per la classifica:");
+    let nome = leggi_riga().replace('\t', " ");
+    let mut classifica = match Classifica::carica(FILE_CLASSIFICA) {
+        Ok(classifica) => classifica,
+        Err(e) => {
+            println!("Impossibile leggere la classifica: {}", e);
+            return;
+        }
+    };
+    let voce = VoceClassifica { regole: info.regole.clone(), seme: info.seme, nome, punti };
+    match classifica.registra(voce, punteggio::VOCI_PREDEFINITE) {
+        Some(posizione) => println!("Sei entrato in classifica al posto {}!", posizione + 1),
+        None => println!("Punteggio non sufficiente per entrare in classifica."),
+    }
+    if let Err(e) = classifica.salva(FILE_CLASSIFICA) {
+        println!("Impossibile salvare la classifica: {}", e);
     }
+    println!("== {} (seme {}) ==", info.regole, info.seme);
+    for (i, v) in classifica.migliori(&info.regole, info.seme).iter().enumerate() {
+        println!("{:>3}. {:<20} {}", i + 1, v.nome, v.punti);
+    }
+}
+
+/// Gestisce una partita multigiocatore, a turni sullo stesso terminale oppure giocata da agenti.
+/// #Argomenti
+/// * 'argomenti': Numero di giocatori e, facoltativamente, la regola di collisione e il raggio visibile
+/// * 'headless': Se vero le mosse vengono scelte da agenti
+fn gioca_multigiocatore(argomenti: &[String], headless: bool) {
+    let numero: usize = argomenti.first()
+        .and_then(|n| n.parse().ok())
+        .expect("Inserisci un numero di giocatori valido");
+    let regola = regola_da_argomento(argomenti.get(1));
+    let dimensione = chiedi_numero("Inserisci la dimensione della mappa:");
+    let qty = chiedi_numero("Inserisci la quantità di cibo e veleno:");
+    let nomi: Vec<String> = (1..=numero).map(|i| format!("Giocatore {}", i)).collect();
+    let nomi: Vec<&str> = nomi.iter().map(String::as_str).collect();
+    let mut rng = rand::thread_rng();
+    let raggio: Option<usize> = argomenti.get(2).map(|r| r.parse().expect("Inserisci un raggio valido"));
+    let mut partita = match PartitaMultigiocatore::nuova(campo_casuale(dimensione, qty), &nomi, regola, &mut rng) {
+        Ok(partita) => partita,
+        Err(e) => {
+            println!("Impossibile iniziare la partita: {}", e);
+            return;
+        }
+    };
+    if let Some(raggio) = raggio {
+        partita = partita.con_visibilita(ModalitaVisibilita::LineaDiVista(raggio));
+    }
+
+    if headless {
+        let mut agenti: Vec<Box<dyn Agente>> = (0..numero)
+            .map(|i| -> Box<dyn Agente> {
+                if i % 2 == 0 {
+                    Box::new(AgenteGoloso)
+                } else {
+                    Box::new(AgenteCasuale::nuovo(i as u64))
+                }
+            })
+            .collect();
+        partita.esegui_headless(&mut agenti, &mut rng, usize::MAX);
+    } else {
+        while let Some(id) = partita.turno_corrente() {
+            if raggio.is_some() {
+                println!("{:#}", partita.vista(id));
+            } else {
+                println!("{}", partita);
+            }
+            println!("Turno di {}. Che mossa vuoi fare? (G/S/D/L):", partita.giocatori[id].nome);
+            match direzione_da_input(&leggi_riga()) {
+                Some(direzione) => {
+                    partita.gioca_turno(direzione, &mut rng);
+                }
+                None => println!("Input non valido! Usa G, S, D o L per muoverti."),
+            }
+        }
+    }
+
+    println!("{}", partita);
+    match partita.vincitore() {
+        Some(id) => println!("HA VINTO {}!", partita.giocatori[id].nome),
+        None => println!("PAREGGIO!"),
+    }
+}
+
+/// Avvia il server di gioco TCP sulla porta indicata, su un campo con dimensione e quantità
+/// di cibo e veleno richieste all'utente.
+/// #Argomenti
+/// * 'argomenti': Porta e, facoltativamente, la regola di collisione
+fn avvia_server(argomenti: &[String]) {
+    let porta: u16 = argomenti.first()
+        .and_then(|p| p.parse().ok())
+        .expect("Inserisci una porta valida");
+    let regola = regola_da_argomento(argomenti.get(1));
+    let dimensione = chiedi_numero("Inserisci la dimensione della mappa:");
+    let qty = chiedi_numero("Inserisci la quantità di cibo e veleno:");
+    let server = Server::nuovo(("127.0.0.1", porta), campo_casuale(dimensione, qty), regola, rand::random())
+        .unwrap_or_else(|e| panic!("Impossibile avviare il server sulla porta {}: {}", porta, e));
+    println!("Server in ascolto su {}", server.indirizzo().expect("Indirizzo del server non disponibile"));
+    if let Err(e) = server.esegui() {
+        println!("Errore del server: {}", e);
+    }
+}
+
+/// Gioca molte partite senza interfaccia sullo stesso campo e mostra le mappe di calore di visite
+/// e morti e le statistiche aggregate, salvando i conteggi per cella in CSV se è indicato un file.
+/// #Argomenti
+/// * 'argomenti': Numero di partite e, facoltativamente, numero di giocatori, regola di collisione e file CSV
+fn analizza(argomenti: &[String]) {
+    let partite: usize = argomenti.first()
+        .and_then(|n| n.parse().ok())
+        .expect("Inserisci un numero di partite valido");
+    let numero: usize = argomenti.get(1).map_or(1, |n| n.parse().expect("Inserisci un numero di giocatori valido"));
+    let regola = regola_da_argomento(argomenti.get(2));
+    let dimensione = chiedi_numero("Inserisci la dimensione della mappa:");
+    let qty = chiedi_numero("Inserisci la quantità di cibo e veleno:");
+    let campo = campo_casuale(dimensione, qty);
+    let nomi: Vec<String> = (1..=numero).map(|i| format!("Giocatore {}", i)).collect();
+    let nomi: Vec<&str> = nomi.iter().map(String::as_str).collect();
+
+    let mut analisi = Analisi::nuova(dimensione);
+    for seme in 0..partite as u64 {
+        let mut rng = StdRng::seed_from_u64(seme);
+        let mut partita = match PartitaMultigiocatore::nuova(campo.clone(), &nomi, regola, &mut rng) {
+            Ok(partita) => partita,
+            Err(e) => {
+                println!("Impossibile iniziare la partita: {}", e);
+                return;
+            }
+        };
+        let mut agenti: Vec<Box<dyn Agente>> = (0..numero)
+            .map(|i| -> Box<dyn Agente> {
+                if i % 2 == 0 {
+                    Box::new(AgenteGoloso)
+                } else {
+                    Box::new(AgenteCasuale::nuovo(seme * numero as u64 + i as u64))
+                }
+            })
+            .collect();
+        analisi.registra(&mut partita, &mut agenti, &mut rng, dimensione * dimensione * numero);
+    }
+
+    println!("{}", campo);
+    println!("Visite:\n{:#}", analisi.mappa_visite(&campo));
+    println!("Morti:\n{:#}", analisi.mappa_morti(&campo));
+    print!("{}", analisi);
+    if let Some(percorso) = argomenti.get(3) {
+        match analisi.salva_csv(percorso) {
+            Ok(()) => println!("Conteggi salvati in {}", percorso),
+            Err(e) => println!("Errore durante il salvataggio: {}", e),
+        }
+    }
+}
+
+/// Converte l'argomento della riga di comando nella regola di collisione (predefinita: blocca).
+fn regola_da_argomento(argomento: Option<&String>) -> RegolaCollisione {
+    match argomento.map(String::as_str) {
+        None | Some("blocca") => RegolaCollisione::Blocca,
+        Some("combatti") => RegolaCollisione::Combatti,
+        Some("condividi") => RegolaCollisione::Condividi,
+        Some(altro) => panic!("Regola di collisione sconosciuta: {}", altro),
+    }
+}
+
+/// Crea una partita su un campo risolvibile generato con la strategia indicata negli argomenti.
+/// Campo, nemici, giocatore e mosse derivano tutti dal seme, restituito con le regole per la classifica.
+/// #Argomenti
+/// * 'argomenti': Nome della strategia e, facoltativamente, il seme
+fn genera_partita(argomenti: &[String]) -> (CampoGioco, Player, Storia, InfoPartita) {
+    let dimensione = chiedi_numero("Inserisci la dimensione della mappa:");
+    let qty = chiedi_numero("Inserisci la quantità di cibo e veleno:");
+    let generatore: Box<dyn Generatore> = match argomenti.first().map(String::as_str) {
+        None | Some("uniforme") => Box::new(Uniforme { oggetti: qty }),
+        Some("ammassi") => Box::new(Ammassi { ammassi: qty.div_ceil(4), oggetti_per_ammasso: 4, raggio: 2 }),
+        Some("labirinto") => Box::new(Labirinto { algoritmo: AlgoritmoLabirinto::Backtracker, oggetti: qty }),
+        Some("prim") => Box::new(Labirinto { algoritmo: AlgoritmoLabirinto::Prim, oggetti: qty }),
+        Some("densita") => Box::new(Densita {
+            scala: 4.0,
+            densita_massima: qty as f64 / (dimensione * dimensione).max(1) as f64 * 2.0,
+        }),
+        Some(altro) => panic!("Generatore sconosciuto: {}", altro),
+    };
+    let seme: u64 = match argomenti.get(1) {
+        Some(seme) => seme.parse().expect("Inserisci un seme valido"),
+        None => rand::random(),
+    };
+    let mut rng = StdRng::seed_from_u64(seme);
+    let mut campo = generazione::genera_risolvibile(generatore.as_ref(), dimensione, &mut rng, 100)
+        .unwrap_or_else(|e| panic!("Impossibile generare il campo: {}", e));
+    let n = chiedi_numero("Inserisci il numero di nemici:");
+    nemici::aggiungi_nemici(&mut campo, n, &mut rng);
+    let player = Player::nuovo_con_rng(&campo, &mut rng);
+    let storia = Storia::nuova(rng.gen());
+    let regole = format!(
+        "genera {} {}x{} {} oggetti {} nemici",
+        argomenti.first().map_or("uniforme", String::as_str),
+        dimensione,
+        dimensione,
+        qty,
+        n
+    );
+    (campo, player, storia, InfoPartita { regole, seme })
+}
+
+/// Crea un campo casuale con la dimensione e la quantità di cibo e veleno indicate.
+fn campo_casuale(dimensione: usize, qty: usize) -> CampoGioco {
+    CampoGioco::nuovo(dimensione, qty).unwrap_or_else(|e| panic!("Impossibile generare il campo: {}", e))
+}
+
+/// Converte l'input dell'utente (G/S/D/L) nella direzione corrispondente.
+fn direzione_da_input(input: &str) -> Option<Direzione> {
+    match input.to_ascii_uppercase().as_str() {
+        "G" => Some(Direzione::Giu),
+        "S" => Some(Direzione::Su),
+        "D" => Some(Direzione::Destra),
+        "L" => Some(Direzione::Sinistra),
+        _ => None,
+    }
+}
+
+/// Legge una riga dall'input standard, senza spazi iniziali e finali.
+fn leggi_riga() -> String {
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)
+        .expect("Errore durante la lettura dell'input");
+    input.trim().to_string()
+}
+
+/// Mostra la richiesta e legge un numero dall'input standard.
+fn chiedi_numero(richiesta: &str) -> usize {
+    println!("{}", richiesta);
+    leggi_riga()
+        .parse()
+        .expect("Inserisci un numero valido")
+}
+
+/// Crea una nuova partita chiedendo all'utente dimensione della mappa e quantità di cibo e veleno.
+/// Campo, nemici, giocatore e mosse derivano tutti da un seme casuale, registrato insieme alle
+/// regole per la classifica: la stessa partita con lo stesso seme si svolge sempre allo stesso modo.
+/// #Argomenti
+/// * 'modalita': Descrizione della modalità di gioco, usata nelle regole della classifica
+fn nuova_partita(modalita: &str) -> (CampoGioco, Player, Storia, InfoPartita) {
+    // Richiedi all'utente di inserire la dimensione della mappa
+    let dimensione = chiedi_numero("Inserisci la dimensione della mappa:");
+
+    // Richiedi all'utente di inserire la quantità di cibo e veleno
+    let qty = chiedi_numero("Inserisci la quantità di cibo e veleno:");
+
+    // Richiedi all'utente di inserire il numero di nemici
+    let n = chiedi_numero("Inserisci il numero di nemici:");
+
+    // Crea il campo di gioco, i nemici e il giocatore
+    let seme: u64 = rand::random();
+    let mut rng = StdRng::seed_from_u64(seme);
+    let mut gamespace = CampoGioco::nuovo_con_rng(dimensione, qty, &mut rng)
+        .unwrap_or_else(|e| panic!("Impossibile generare il campo: {}", e));
+    nemici::aggiungi_nemici(&mut gamespace, n, &mut rng);
+    let player = Player::nuovo_con_rng(&gamespace, &mut rng);
+    let storia = Storia::nuova(rng.gen());
+    let regole = format!("{} {}x{} {} oggetti {} nemici", modalita, dimensione, dimensione, qty, n);
+    (gamespace, player, storia, InfoPartita { regole, seme })
 }
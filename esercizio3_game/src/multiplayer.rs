@@ -0,0 +1,569 @@
+use std::fmt;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use crate::game_space::game_space::{CampoGioco, Cella, ErroreGenerazione, Posizione};
+use crate::player::{Direzione, Esito, Player};
+use crate::visibilita::{ModalitaVisibilita, Nebbia, StatoCella, VistaCampo};
+
+/// Regola applicata quando un giocatore prova a entrare nella cella occupata da un altro giocatore.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RegolaCollisione {
+    /// Il giocatore che si muove resta fermo.
+    Blocca,
+    /// I due giocatori si scontrano: chi ha più forza elimina l'altro, in caso di parità nessuno si muove.
+    Combatti,
+    /// I due giocatori condividono la cella.
+    Condividi,
+}
+
+/// Un partecipante alla partita multigiocatore.
+pub struct Giocatore {
+    /// Nome del giocatore.
+    pub nome: String,
+    /// Stato del giocatore sul campo.
+    pub player: Player,
+    /// Esito della partita per questo giocatore (`InCorso` finché può muoversi).
+    pub esito: Esito,
+    /// Cella in cui il giocatore è stato eliminato. Dopo una mossa fatale è la cella in cui ha
+    /// provato a entrare, anche se la sua posizione resta quella da cui è partito.
+    pub eliminato_in: Option<Posizione>,
+}
+
+impl Giocatore {
+    /// Indica se il giocatore deve ancora giocare.
+    pub fn attivo(&self) -> bool {
+        self.esito == Esito::InCorso
+    }
+
+    /// Indica se il giocatore è ancora sul campo (non è stato eliminato).
+    pub fn in_campo(&self) -> bool {
+        self.esito != Esito::Perso
+    }
+}
+
+/// Ciò che un giocatore conosce del campo quando deve scegliere la mossa.
+#[derive(Debug, Clone)]
+pub struct Osservazione {
+    /// Indice del giocatore che osserva.
+    pub id: usize,
+    /// Posizione del giocatore.
+    pub posizione: Posizione,
+    /// Forza del giocatore.
+    pub forza: i32,
+    /// Mosse rimaste al giocatore.
+    pub mosse: usize,
+    /// Dimensione del campo di gioco.
+    pub dimensione: usize,
+    /// Celle del campo: `None` per le celle che il giocatore non conosce.
+    pub celle: Vec<Vec<Option<Cella>>>,
+    /// Posizioni degli altri giocatori ancora in campo.
+    pub avversari: Vec<Posizione>,
+}
+
+/// Un agente in grado di giocare senza intervento umano.
+pub trait Agente {
+    /// Sceglie la direzione della prossima mossa a partire dall'osservazione del campo.
+    /// #Argomenti
+    /// * 'osservazione': Ciò che il giocatore conosce del campo
+    fn scegli(&mut self, osservazione: &Osservazione) -> Direzione;
+}
+
+/// Agente che sceglie una direzione a caso.
+pub struct AgenteCasuale {
+    rng: StdRng,
+}
+
+impl AgenteCasuale {
+    /// Crea un agente casuale con il seme specificato.
+    /// #Argomenti
+    /// * 'seme': Seme del generatore di numeri casuali
+    pub fn nuovo(seme: u64) -> AgenteCasuale {
+        AgenteCasuale { rng: StdRng::seed_from_u64(seme) }
+    }
+}
+
+impl Agente for AgenteCasuale {
+    fn scegli(&mut self, _: &Osservazione) -> Direzione {
+        match self.rng.gen_range(0..4) {
+            0 => Direzione::Su,
+            1 => Direzione::Giu,
+            2 => Direzione::Destra,
+            _ => Direzione::Sinistra,
+        }
+    }
+}
+
+/// Agente che si dirige verso il cibo conosciuto più vicino, evitando il veleno adiacente.
+pub struct AgenteGoloso;
+
+impl Agente for AgenteGoloso {
+    fn scegli(&mut self, osservazione: &Osservazione) -> Direzione {
+        let p = osservazione.posizione;
+        let mut bersaglio: Option<(usize, Posizione)> = None;
+        for (i, riga) in osservazione.celle.iter().enumerate() {
+            for (j, cella) in riga.iter().enumerate() {
+                if let Some(Cella::Cibo(_)) = cella {
+                    let distanza = p.riga.abs_diff(i) + p.colonna.abs_diff(j);
+                    if bersaglio.is_none_or(|(d, _)| distanza < d) {
+                        bersaglio = Some((distanza, Posizione { riga: i, colonna: j }));
+                    }
+                }
+            }
+        }
+        let sicura = |direzione: &Direzione| match p.vicina(*direzione, osservazione.dimensione) {
+            Some(v) => !matches!(osservazione.celle[v.riga][v.colonna], Some(Cella::Veleno(_)) | Some(Cella::Muro)),
+            None => false,
+        };
+        let preferite = match bersaglio {
+            Some((_, b)) => {
+                let mut direzioni = Vec::new();
+                if b.riga < p.riga {
+                    direzioni.push(Direzione::Su);
+                }
+                if b.riga > p.riga {
+                    direzioni.push(Direzione::Giu);
+                }
+                if b.colonna < p.colonna {
+                    direzioni.push(Direzione::Sinistra);
+                }
+                if b.colonna > p.colonna {
+                    direzioni.push(Direzione::Destra);
+                }
+                direzioni
+            }
+            None => Vec::new(),
+        };
+        preferite
+            .into_iter()
+            .chain(Direzione::TUTTE)
+            .find(sicura)
+            .unwrap_or(Direzione::Su)
+    }
+}
+
+/// Partita con più giocatori sullo stesso campo, che muovono a turno.
+pub struct PartitaMultigiocatore {
+    /// Il campo di gioco condiviso.
+    pub campo: CampoGioco,
+    /// I giocatori, nell'ordine di turno.
+    pub giocatori: Vec<Giocatore>,
+    /// Regola applicata alle collisioni tra giocatori.
+    pub regola: RegolaCollisione,
+    /// Indice del giocatore di turno.
+    turno: usize,
+    /// Giocatore che ha raggiunto l'uscita, se presente.
+    uscita_raggiunta: Option<usize>,
+    /// Modalità di visibilità dei giocatori.
+    modalita: ModalitaVisibilita,
+    /// Nebbia di guerra di ciascun giocatore.
+    nebbie: Vec<Nebbia>,
+}
+
+impl PartitaMultigiocatore {
+    /// Crea una nuova partita posizionando i giocatori in celle vuote distinte.
+    ///
+    /// Il primo giocatore parte dalla posizione del giocatore del campo.
+    /// # Argomenti
+    ///
+    /// * `campo`: Il campo di gioco.
+    /// * `nomi`: I nomi dei giocatori, nell'ordine di turno.
+    /// * `regola`: La regola di collisione.
+    /// * `rng`: Generatore di numeri casuali.
+    pub fn nuova<R: Rng + ?Sized>(
+        campo: CampoGioco,
+        nomi: &[&str],
+        regola: RegolaCollisione,
+        rng: &mut R,
+    ) -> Result<PartitaMultigiocatore, ErroreGenerazione> {
+        let mut libere: Vec<Posizione> = CampoGioco::posizioni(campo.dimensione)
+            .filter(|p| *p != campo.player_position && campo.cella(*p) == Cella::Vuota && campo.nemico_in(*p).is_none())
+            .collect();
+        if libere.len() + 1 < nomi.len() {
+            return Err(ErroreGenerazione::TroppiGiocatori { richiesti: nomi.len(), disponibili: libere.len() + 1 });
+        }
+
+        let mut giocatori = Vec::with_capacity(nomi.len());
+        for (i, nome) in nomi.iter().enumerate() {
+            let mut player = Player::nuovo_con_rng(&campo, rng);
+            if i > 0 {
+                player.posizione = libere.swap_remove(rng.gen_range(0..libere.len()));
+            }
+            giocatori.push(Giocatore {
+                nome: nome.to_string(),
+                player,
+                esito: Esito::InCorso,
+                eliminato_in: None,
+            });
+        }
+        let nebbie = vec![Nebbia::nuova(campo.dimensione, ModalitaVisibilita::Completa); giocatori.len()];
+        Ok(PartitaMultigiocatore {
+            campo,
+            giocatori,
+            regola,
+            turno: 0,
+            uscita_raggiunta: None,
+            modalita: ModalitaVisibilita::Completa,
+            nebbie,
+        })
+    }
+
+    /// Imposta la visibilità dei giocatori: ognuno vede solo le celle permesse dalla modalità
+    /// e ricorda quelle già esplorate.
+    /// # Argomenti
+    ///
+    /// * `modalita`: La modalità di visibilità.
+    pub fn con_visibilita(mut self, modalita: ModalitaVisibilita) -> PartitaMultigiocatore {
+        self.modalita = modalita;
+        for (nebbia, giocatore) in self.nebbie.iter_mut().zip(&self.giocatori) {
+            *nebbia = Nebbia::nuova(self.campo.dimensione, modalita);
+            nebbia.aggiorna(&self.campo, giocatore.player.posizione);
+        }
+        self
+    }
+
+    /// Aggiunge un giocatore a partita iniziata, in una cella vuota non occupata da altri giocatori.
+    ///
+    /// Il primo giocatore entra nella posizione del giocatore del campo, se è libera.
+    /// Restituisce l'indice del nuovo giocatore, o `None` se non ci sono celle libere.
+    /// # Argomenti
+    ///
+    /// * `nome`: Il nome del giocatore.
+    /// * `rng`: Generatore di numeri casuali.
+    pub fn aggiungi_giocatore<R: Rng + ?Sized>(&mut self, nome: &str, rng: &mut R) -> Option<usize> {
+        let occupata = |p: Posizione| self.giocatori.iter().any(|g| g.in_campo() && g.player.posizione == p);
+        let posizione = if !occupata(self.campo.player_position) {
+            self.campo.player_position
+        } else {
+            let libere: Vec<Posizione> = CampoGioco::posizioni(self.campo.dimensione)
+                .filter(|p| self.campo.cella(*p) == Cella::Vuota && !occupata(*p) && self.campo.nemico_in(*p).is_none())
+                .collect();
+            *libere.get(rng.gen_range(0..libere.len().max(1)))?
+        };
+        let mut player = Player::nuovo_con_rng(&self.campo, rng);
+        player.posizione = posizione;
+        let mut nebbia = Nebbia::nuova(self.campo.dimensione, self.modalita);
+        nebbia.aggiorna(&self.campo, posizione);
+
+        let id = self.giocatori.len();
+        self.giocatori.push(Giocatore {
+            nome: nome.to_string(),
+            player,
+            esito: Esito::InCorso,
+            eliminato_in: None,
+        });
+        self.nebbie.push(nebbia);
+        if !self.giocatori[self.turno].attivo() {
+            self.turno = id;
+        }
+        Some(id)
+    }
+
+    /// Ritira dalla partita il giocatore indicato, che viene considerato eliminato.
+    ///
+    /// Non ha effetto se il giocatore ha già finito di giocare.
+    /// # Argomenti
+    ///
+    /// * `id`: L'indice del giocatore.
+    pub fn ritira(&mut self, id: usize) {
+        if !self.giocatori[id].attivo() {
+            return;
+        }
+        self.elimina(id, self.giocatori[id].player.posizione);
+        if self.turno == id {
+            self.passa_turno();
+        }
+    }
+
+    /// Restituisce l'indice del giocatore di turno, o `None` se la partita è finita.
+    pub fn turno_corrente(&self) -> Option<usize> {
+        if self.finita() {
+            None
+        } else {
+            Some(self.turno)
+        }
+    }
+
+    /// Indica se la partita è finita: qualcuno ha raggiunto l'uscita, nessuno può più muoversi
+    /// oppure è rimasto in campo un solo giocatore.
+    pub fn finita(&self) -> bool {
+        let in_campo = self.giocatori.iter().filter(|g| g.in_campo()).count();
+        self.uscita_raggiunta.is_some()
+            || !self.giocatori.iter().any(Giocatore::attivo)
+            || (self.giocatori.len() > 1 && in_campo <= 1)
+    }
+
+    /// Restituisce il vincitore della partita, se è finita e non è in parità.
+    ///
+    /// Vince chi ha raggiunto l'uscita; altrimenti il giocatore rimasto in campo con più forza.
+    pub fn vincitore(&self) -> Option<usize> {
+        if !self.finita() {
+            return None;
+        }
+        if self.uscita_raggiunta.is_some() {
+            return self.uscita_raggiunta;
+        }
+        let massima = self.giocatori.iter().filter(|g| g.in_campo()).map(|g| g.player.forza).max()?;
+        let mut migliori = self
+            .giocatori
+            .iter()
+            .enumerate()
+            .filter(|(_, g)| g.in_campo() && g.player.forza == massima);
+        match (migliori.next(), migliori.next()) {
+            (Some((id, _)), None) => Some(id),
+            _ => None,
+        }
+    }
+
+    /// Restituisce l'indice del giocatore in campo, diverso da `id`, che occupa la posizione indicata.
+    fn occupante(&self, id: usize, posizione: Posizione) -> Option<usize> {
+        self.giocatori
+            .iter()
+            .enumerate()
+            .find(|(i, g)| *i != id && g.in_campo() && g.player.posizione == posizione)
+            .map(|(i, _)| i)
+    }
+
+    /// Fa muovere il giocatore di turno nella direzione indicata, poi i nemici, e passa il turno
+    /// al successivo.
+    ///
+    /// Restituisce l'esito della mossa per il giocatore che ha mosso, o `None` se la partita è finita.
+    /// # Argomenti
+    ///
+    /// * `direzione`: La direzione scelta dal giocatore di turno.
+    /// * `rng`: Generatore di numeri casuali.
+    pub fn gioca_turno<R: Rng + ?Sized>(&mut self, direzione: Direzione, rng: &mut R) -> Option<Esito> {
+        let id = self.turno_corrente()?;
+        let esito = self.muovi_giocatore(id, direzione, rng);
+        let posizione = self.giocatori[id].player.posizione;
+        match esito {
+            Esito::Perso => self.elimina(id, posizione),
+            esito => self.giocatori[id].esito = esito,
+        }
+        if esito == Esito::Vinto && self.campo.celle[posizione.riga][posizione.colonna] == Cella::Uscita {
+            self.uscita_raggiunta = Some(id);
+        }
+        self.nebbie[id].aggiorna(&self.campo, posizione);
+        self.passa_turno();
+        Some(esito)
+    }
+
+    /// Applica la mossa di un giocatore tenendo conto della regola di collisione.
+    fn muovi_giocatore<R: Rng + ?Sized>(&mut self, id: usize, direzione: Direzione, rng: &mut R) -> Esito {
+        let player = &mut self.giocatori[id].player;
+        let immune = player.immunita > 0;
+        if let Some(esito) = player.prepara_mossa() {
+            return esito;
+        }
+        let mut esito = self.risolvi_mossa(id, direzione, rng);
+        self.turno_nemici(rng);
+        if self.giocatori[id].esito == Esito::Perso {
+            esito = Esito::Perso;
+        }
+        self.giocatori[id].player.concludi_mossa(immune);
+        esito
+    }
+
+    /// Muove i nemici dopo la mossa di un giocatore ed elimina i giocatori da loro sconfitti.
+    fn turno_nemici<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        let ids: Vec<usize> = (0..self.giocatori.len()).filter(|i| self.giocatori[*i].in_campo()).collect();
+        let mut players: Vec<&mut Player> = self
+            .giocatori
+            .iter_mut()
+            .filter(|g| g.in_campo())
+            .map(|g| &mut g.player)
+            .collect();
+        let esiti = self.campo.turno_nemici_tra(&mut players, rng);
+        for (id, esito) in ids.into_iter().zip(esiti) {
+            if esito == Esito::Perso {
+                self.elimina(id, self.giocatori[id].player.posizione);
+            }
+        }
+    }
+
+    /// Elimina il giocatore indicato, registrando la cella in cui è stato eliminato se non è già nota.
+    fn elimina(&mut self, id: usize, luogo: Posizione) {
+        let giocatore = &mut self.giocatori[id];
+        giocatore.esito = Esito::Perso;
+        giocatore.eliminato_in.get_or_insert(luogo);
+    }
+
+    /// Sposta il giocatore che ha già consumato la mossa, risolvendo le collisioni con gli altri.
+    ///
+    /// Chi perde uno scontro o muore entrando nella cella viene eliminato nella cella in cui voleva entrare.
+    fn risolvi_mossa<R: Rng + ?Sized>(&mut self, id: usize, direzione: Direzione, rng: &mut R) -> Esito {
+        let partenza = self.giocatori[id].player.posizione;
+        let destinazione = self.giocatori[id].player.destinazione(&self.campo, direzione, rng);
+        if let Some(esito) = self.scontro(id, destinazione) {
+            return esito;
+        }
+        let esito = self.giocatori[id].player.entra(&mut self.campo, destinazione);
+        if esito == Esito::Perso {
+            self.elimina(id, destinazione);
+            return esito;
+        }
+        // Un teletrasporto può portare il giocatore in una cella occupata: vale la stessa regola
+        // e, se il giocatore non può entrarvi, resta sul teletrasporto.
+        let arrivo = self.giocatori[id].player.posizione;
+        if arrivo != destinazione && arrivo != partenza {
+            if let Some(esito) = self.scontro(id, arrivo) {
+                if esito == Esito::InCorso {
+                    self.giocatori[id].player.posizione = destinazione;
+                }
+                return esito;
+            }
+        }
+        esito
+    }
+
+    /// Applica la regola di collisione al giocatore che vuole entrare nella posizione indicata.
+    ///
+    /// Restituisce l'esito con cui la mossa si ferma, oppure `None` se il giocatore può entrare.
+    /// # Argomenti
+    ///
+    /// * `id`: L'indice del giocatore che si muove.
+    /// * `posizione`: La posizione in cui vuole entrare.
+    fn scontro(&mut self, id: usize, posizione: Posizione) -> Option<Esito> {
+        let altro = self.occupante(id, posizione)?;
+        match self.regola {
+            RegolaCollisione::Blocca => Some(Esito::InCorso),
+            RegolaCollisione::Combatti => {
+                let forza = self.giocatori[id].player.forza;
+                let forza_altro = self.giocatori[altro].player.forza;
+                if forza == forza_altro {
+                    return Some(Esito::InCorso);
+                }
+                let (vincente, perdente) = if forza > forza_altro { (id, altro) } else { (altro, id) };
+                self.giocatori[vincente].player.statistiche.scontri_vinti += 1;
+                self.giocatori[perdente].player.statistiche.scontri_persi += 1;
+                self.elimina(perdente, posizione);
+                if perdente == id {
+                    Some(Esito::Perso)
+                } else {
+                    None
+                }
+            }
+            RegolaCollisione::Condividi => None,
+        }
+    }
+
+    /// Passa il turno al prossimo giocatore attivo.
+    fn passa_turno(&mut self) {
+        let n = self.giocatori.len();
+        for passo in 1..=n {
+            let prossimo = (self.turno + passo) % n;
+            if self.giocatori[prossimo].attivo() {
+                self.turno = prossimo;
+                return;
+            }
+        }
+    }
+
+    /// Restituisce ciò che il giocatore indicato osserva del campo: le celle esplorate
+    /// e gli avversari visibili, secondo la modalità di visibilità della partita.
+    /// # Argomenti
+    ///
+    /// * `id`: L'indice del giocatore.
+    pub fn osservazione(&self, id: usize) -> Osservazione {
+        let player = &self.giocatori[id].player;
+        let nebbia = &self.nebbie[id];
+        Osservazione {
+            id,
+            posizione: player.posizione,
+            forza: player.forza,
+            mosse: player.mosse,
+            dimensione: self.campo.dimensione,
+            celle: nebbia
+                .stato(&self.campo, player.posizione)
+                .iter()
+                .map(|riga| riga.iter().map(StatoCella::cella).collect())
+                .collect(),
+            avversari: self
+                .giocatori
+                .iter()
+                .enumerate()
+                .filter(|(i, g)| *i != id && g.in_campo())
+                .map(|(_, g)| g.player.posizione)
+                .filter(|p| nebbia.visibile(&self.campo, player.posizione, *p))
+                .collect(),
+        }
+    }
+
+    /// Restituisce la vista del campo del giocatore indicato, filtrata dalla nebbia di guerra,
+    /// con gli avversari visibili numerati da 1 come in `Display`.
+    /// # Argomenti
+    ///
+    /// * `id`: L'indice del giocatore.
+    pub fn vista(&self, id: usize) -> VistaCampo<'_> {
+        let avversari = self
+            .giocatori
+            .iter()
+            .enumerate()
+            .filter(|(i, g)| *i != id && g.in_campo())
+            .map(|(i, g)| (g.player.posizione, i + 1))
+            .collect();
+        self.nebbie[id]
+            .vista(&self.campo, self.giocatori[id].player.posizione)
+            .con_giocatori(avversari)
+    }
+
+    /// Gioca la partita senza interfaccia, facendo scegliere le mosse agli agenti.
+    ///
+    /// Restituisce il vincitore, se c'è.
+    /// # Argomenti
+    ///
+    /// * `agenti`: Un agente per ciascun giocatore, nello stesso ordine.
+    /// * `rng`: Generatore di numeri casuali.
+    /// * `max_turni`: Numero massimo di turni giocati prima di interrompere la partita.
+    pub fn esegui_headless<R: Rng + ?Sized>(&mut self, agenti: &mut [Box<dyn Agente>], rng: &mut R, max_turni: usize) -> Option<usize> {
+        assert_eq!(agenti.len(), self.giocatori.len(), "Serve un agente per ogni giocatore");
+        for _ in 0..max_turni {
+            let Some(id) = self.turno_corrente() else {
+                break;
+            };
+            let direzione = agenti[id].scegli(&self.osservazione(id));
+            self.gioca_turno(direzione, rng);
+        }
+        self.vincitore()
+    }
+}
+
+impl fmt::Display for PartitaMultigiocatore {
+    /// Implementazione della formattazione per visualizzare il campo con i giocatori numerati da 1
+    /// e le statistiche di ciascun giocatore.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, riga) in self.campo.celle.iter().enumerate() {
+            for (j, cella) in riga.iter().enumerate() {
+                let posizione = Posizione { riga: i, colonna: j };
+                match self.giocatori.iter().position(|g| g.in_campo() && g.player.posizione == posizione) {
+                    Some(id) => write!(f, "{} ", id + 1)?,
+                    None => match self.campo.nemico_in(posizione) {
+                        Some(nemico) => write!(f, "{} ", nemico.simbolo())?,
+                        None => write!(f, "{} ", cella.effetto().simbolo())?,
+                    },
+                }
+            }
+            writeln!(f)?;
+        }
+        for (id, g) in self.giocatori.iter().enumerate() {
+            let s = &g.player.statistiche;
+            writeln!(
+                f,
+                "{} {}: forza {}, mosse {}, cibo {}, veleno {}, scontri {}/{}{}",
+                id + 1,
+                g.nome,
+                g.player.forza,
+                g.player.mosse,
+                s.cibo_mangiato,
+                s.veleno_preso,
+                s.scontri_vinti,
+                s.scontri_persi,
+                match g.esito {
+                    Esito::InCorso => "",
+                    Esito::Vinto => " (finito)",
+                    Esito::Perso => " (eliminato)",
+                }
+            )?;
+        }
+        Ok(())
+    }
+}
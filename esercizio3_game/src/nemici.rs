@@ -0,0 +1,240 @@
+use std::collections::VecDeque;
+use rand::seq::IndexedRandom;
+use rand::Rng;
+use crate::game_space::game_space::{CampoGioco, Cella, Posizione};
+use crate::player::{Direzione, Esito, Player};
+
+/// Modo in cui un nemico si muove sul campo.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Comportamento {
+    /// Macchia di veleno che vaga a caso e avvelena il giocatore che tocca, poi scompare.
+    Vagabondo,
+    /// Insegue il giocatore lungo il percorso più breve.
+    Inseguitore,
+    /// Pattuglia ciclicamente le tappe del percorso indicato.
+    Guardia {
+        /// Tappe del percorso di pattuglia.
+        percorso: Vec<Posizione>,
+        /// Indice della prossima tappa da raggiungere.
+        prossima: usize,
+    },
+}
+
+/// Un nemico che si muove sul campo dopo ogni mossa del giocatore.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Nemico {
+    /// Posizione attuale del nemico.
+    pub posizione: Posizione,
+    /// Forza del nemico: veleno inflitto dal vagabondo, forza nello scontro per gli altri.
+    pub forza: i32,
+    /// Comportamento del nemico.
+    pub comportamento: Comportamento,
+}
+
+impl Nemico {
+    /// Crea un nemico nella posizione indicata.
+    /// #Argomenti
+    /// * 'posizione': Posizione iniziale
+    /// * 'forza': Forza del nemico
+    /// * 'comportamento': Comportamento del nemico
+    pub fn nuovo(posizione: Posizione, forza: i32, comportamento: Comportamento) -> Nemico {
+        Nemico { posizione, forza, comportamento }
+    }
+
+    /// Simbolo usato per rappresentare il nemico nel campo di gioco.
+    pub fn simbolo(&self) -> &'static str {
+        match self.comportamento {
+            Comportamento::Vagabondo => "~",
+            Comportamento::Inseguitore => "!",
+            Comportamento::Guardia { .. } => "&",
+        }
+    }
+
+    /// Calcola la prossima posizione del nemico. Restituisce `None` se il nemico resta fermo.
+    fn prossima_posizione<R: Rng + ?Sized>(&mut self, campo: &CampoGioco, giocatore: Posizione, rng: &mut R) -> Option<Posizione> {
+        match &mut self.comportamento {
+            Comportamento::Vagabondo => {
+                let libere: Vec<Posizione> = Direzione::TUTTE
+                    .iter()
+                    .filter_map(|d| self.posizione.vicina(*d, campo.dimensione))
+                    .filter(|p| !campo.cella(*p).effetto().blocca())
+                    .collect();
+                libere.choose(rng).copied()
+            }
+            Comportamento::Inseguitore => passo_verso(campo, self.posizione, giocatore),
+            Comportamento::Guardia { percorso, prossima } => {
+                if percorso.is_empty() {
+                    return None;
+                }
+                if percorso[*prossima % percorso.len()] == self.posizione {
+                    *prossima = (*prossima + 1) % percorso.len();
+                }
+                passo_verso(campo, self.posizione, percorso[*prossima % percorso.len()])
+            }
+        }
+    }
+
+    /// Applica al giocatore l'effetto del contatto con il nemico.
+    ///
+    /// Il vagabondo avvelena il giocatore (se non è immune) e scompare. Inseguitori e guardie
+    /// combattono: se il giocatore ha più forza il nemico viene sconfitto, altrimenti il
+    /// giocatore perde tanta forza quanta quella del nemico.
+    ///
+    /// Restituisce `true` se il nemico va rimosso dal campo.
+    /// #Argomenti
+    /// * 'player': Il giocatore toccato dal nemico
+    pub fn colpisci(&self, player: &mut Player) -> bool {
+        match self.comportamento {
+            Comportamento::Vagabondo => {
+                if player.immunita == 0 {
+                    player.forza = player.forza.saturating_sub(self.forza);
+                }
+                player.statistiche.colpi_nemici += 1;
+                true
+            }
+            Comportamento::Inseguitore | Comportamento::Guardia { .. } => {
+                if player.forza > self.forza {
+                    player.statistiche.scontri_vinti += 1;
+                    true
+                } else {
+                    player.forza = player.forza.saturating_sub(self.forza);
+                    player.statistiche.scontri_persi += 1;
+                    false
+                }
+            }
+        }
+    }
+}
+
+/// Restituisce il primo passo del percorso più breve da `da` ad `a` che non attraversa muri,
+/// o `None` se `a` non è raggiungibile oppure coincide con `da`.
+/// #Argomenti
+/// * 'campo': Il campo di gioco
+/// * 'da': Posizione di partenza
+/// * 'a': Posizione di arrivo
+pub fn passo_verso(campo: &CampoGioco, da: Posizione, a: Posizione) -> Option<Posizione> {
+    if da == a {
+        return None;
+    }
+    // Visita in ampiezza a partire dall'arrivo: il passo migliore è il vicino di `da` più vicino ad `a`
+    let mut distanze = vec![vec![usize::MAX; campo.dimensione]; campo.dimensione];
+    let mut coda = VecDeque::from([a]);
+    distanze[a.riga][a.colonna] = 0;
+    while let Some(p) = coda.pop_front() {
+        for direzione in Direzione::TUTTE {
+            if let Some(v) = p.vicina(direzione, campo.dimensione) {
+                if distanze[v.riga][v.colonna] == usize::MAX && (v == da || !campo.cella(v).effetto().blocca()) {
+                    distanze[v.riga][v.colonna] = distanze[p.riga][p.colonna] + 1;
+                    coda.push_back(v);
+                }
+            }
+        }
+    }
+    Direzione::TUTTE
+        .iter()
+        .filter_map(|d| da.vicina(*d, campo.dimensione))
+        .filter(|v| distanze[v.riga][v.colonna] != usize::MAX && !campo.cella(*v).effetto().blocca())
+        .min_by_key(|v| distanze[v.riga][v.colonna])
+}
+
+impl CampoGioco {
+    /// Muove tutti i nemici di un passo e risolve i contatti con il giocatore.
+    ///
+    /// Va chiamata dopo ogni mossa del giocatore (`Player::muovi` lo fa già).
+    /// Restituisce `Esito::Perso` se il giocatore è stato sconfitto.
+    /// # Argomenti
+    ///
+    /// * `player`: Il giocatore.
+    /// * `rng`: Generatore di numeri casuali.
+    pub fn turno_nemici<R: Rng + ?Sized>(&mut self, player: &mut Player, rng: &mut R) -> Esito {
+        self.turno_nemici_tra(&mut [player], rng)[0]
+    }
+
+    /// Come `turno_nemici`, ma con più giocatori sul campo: ogni nemico si dirige verso il
+    /// giocatore ancora in forze più vicino e colpisce chi si trova nella sua cella.
+    ///
+    /// Restituisce l'esito di ciascun giocatore, nello stesso ordine.
+    /// # Argomenti
+    ///
+    /// * `giocatori`: I giocatori in campo.
+    /// * `rng`: Generatore di numeri casuali.
+    pub fn turno_nemici_tra<R: Rng + ?Sized>(&mut self, giocatori: &mut [&mut Player], rng: &mut R) -> Vec<Esito> {
+        let mut nemici = std::mem::take(&mut self.nemici);
+        let mut rimossi = vec![false; nemici.len()];
+        for i in 0..nemici.len() {
+            let vivi = || giocatori.iter().filter(|g| g.forza > 0);
+            let Some(bersaglio) = vivi()
+                .map(|g| g.posizione)
+                .min_by_key(|p| p.riga.abs_diff(nemici[i].posizione.riga) + p.colonna.abs_diff(nemici[i].posizione.colonna))
+            else {
+                break;
+            };
+            // Un nemico già su un giocatore lo colpisce senza muoversi
+            if !vivi().any(|g| g.posizione == nemici[i].posizione) {
+                if let Some(p) = nemici[i].prossima_posizione(self, bersaglio, rng) {
+                    let occupata = nemici.iter().enumerate().any(|(j, n)| j != i && !rimossi[j] && n.posizione == p);
+                    if !occupata {
+                        nemici[i].posizione = p;
+                    }
+                }
+            }
+            if let Some(player) = giocatori.iter_mut().find(|g| g.forza > 0 && g.posizione == nemici[i].posizione) {
+                rimossi[i] = nemici[i].colpisci(player);
+            }
+        }
+        let mut rimossi = rimossi.into_iter();
+        nemici.retain(|_| !rimossi.next().unwrap_or(false));
+        self.nemici = nemici;
+        giocatori
+            .iter()
+            .map(|g| if g.forza <= 0 { Esito::Perso } else { Esito::InCorso })
+            .collect()
+    }
+
+    /// Restituisce il nemico nella posizione indicata, se presente.
+    pub fn nemico_in(&self, posizione: Posizione) -> Option<&Nemico> {
+        self.nemici.iter().find(|n| n.posizione == posizione)
+    }
+}
+
+/// Aggiunge al campo `n` nemici di tipo casuale in celle vuote distinte, lontane dal giocatore.
+///
+/// Le guardie pattugliano il rettangolo che ha per vertici la posizione iniziale e una cella casuale.
+/// # Argomenti
+///
+/// * `campo`: Il campo di gioco.
+/// * `n`: Numero di nemici da aggiungere.
+/// * `rng`: Generatore di numeri casuali.
+pub fn aggiungi_nemici<R: Rng + ?Sized>(campo: &mut CampoGioco, n: usize, rng: &mut R) {
+    let giocatore = campo.player_position;
+    let libere: Vec<Posizione> = CampoGioco::posizioni(campo.dimensione)
+        .filter(|p| campo.cella(*p) == Cella::Vuota && p.riga.abs_diff(giocatore.riga) + p.colonna.abs_diff(giocatore.colonna) > 2)
+        .filter(|p| campo.nemico_in(*p).is_none())
+        .collect();
+    let scelte: Vec<Posizione> = libere.choose_multiple(rng, n).copied().collect();
+    for posizione in scelte {
+        let comportamento = match rng.gen_range(0..3) {
+            0 => Comportamento::Vagabondo,
+            1 => Comportamento::Inseguitore,
+            _ => {
+                let angolo = Posizione {
+                    riga: rng.gen_range(0..campo.dimensione),
+                    colonna: rng.gen_range(0..campo.dimensione),
+                };
+                Comportamento::Guardia {
+                    percorso: vec![
+                        posizione,
+                        Posizione { riga: posizione.riga, colonna: angolo.colonna },
+                        angolo,
+                        Posizione { riga: angolo.riga, colonna: posizione.colonna },
+                    ],
+                    prossima: 0,
+                }
+            }
+        };
+        let forza = rng.gen_range(5..=50);
+        campo.nemici.push(Nemico::nuovo(posizione, forza, comportamento));
+    }
+}
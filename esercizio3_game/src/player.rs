@@ -1,11 +1,13 @@
 use std::fmt;
 use std::fmt::Formatter;
+use std::str::FromStr;
 use rand::Rng;
 use crate::game_space::game_space::CampoGioco;
 use crate::game_space::game_space::Posizione;
 use crate::game_space::game_space::Cella;
 
 /// Rappresenta il giocatore con le sue caratteristiche.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Player {
     /// Numero di mosse disponibili.
     pub mosse: usize,
@@ -15,10 +17,63 @@ pub struct Player {
     pub direzione: Direzione,
     /// Forza del giocatore.
     pub forza: i32,
+    /// Numero di mosse per cui il giocatore è immune al veleno.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub immunita: u32,
+    /// Statistiche della partita del giocatore.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub statistiche: Statistiche,
+}
+
+/// Statistiche raccolte durante la partita di un giocatore.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Statistiche {
+    /// Numero di mosse effettuate.
+    pub mosse_fatte: usize,
+    /// Numero di celle di cibo mangiate.
+    pub cibo_mangiato: u32,
+    /// Numero di celle di veleno in cui si è entrati.
+    pub veleno_preso: u32,
+    /// Numero di potenziamenti raccolti.
+    pub power_up_raccolti: u32,
+    /// Numero di volte in cui si è stati colpiti da un nemico vagabondo.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub colpi_nemici: u32,
+    /// Numero di scontri vinti contro altri giocatori.
+    pub scontri_vinti: u32,
+    /// Numero di scontri persi contro altri giocatori.
+    pub scontri_persi: u32,
+}
+
+impl Statistiche {
+    /// Aggiorna le statistiche con la cella in cui il giocatore è appena entrato.
+    /// #Argomenti
+    /// * 'cella': La cella in cui si è entrati
+    pub fn registra(&mut self, cella: &Cella) {
+        match cella {
+            Cella::Cibo(_) => self.cibo_mangiato += 1,
+            Cella::Veleno(_) => self.veleno_preso += 1,
+            Cella::PowerUp(_) => self.power_up_raccolti += 1,
+            _ => (),
+        }
+    }
+}
+
+/// Esito di una mossa del giocatore.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Esito {
+    /// La partita continua.
+    InCorso,
+    /// Il giocatore ha vinto (ha raggiunto l'uscita o ha esaurito le mosse senza morire).
+    Vinto,
+    /// Il giocatore ha perso (la sua forza è arrivata a 0).
+    Perso,
 }
 
 /// Enumerazione delle direzioni possibili del giocatore.
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Direzione {
     Su,
     Giu,
@@ -26,12 +81,25 @@ pub enum Direzione {
     Sinistra,
 }
 
+impl Direzione {
+    /// Tutte le direzioni possibili.
+    pub const TUTTE: [Direzione; 4] = [Direzione::Su, Direzione::Destra, Direzione::Giu, Direzione::Sinistra];
+}
+
 impl Player {
     /// Crea e restituisce un nuovo Player in una posizione casuale all'interno del campo di gioco specificato.
     /// #Argomenti
     /// * 'campo': Un puntatore ad una struttura CampoGioco
     pub fn nuovo(campo: &CampoGioco) -> Player {
         let mut rng = rand::thread_rng();
+        Player::nuovo_con_rng(campo, &mut rng)
+    }
+
+    /// Come `nuovo`, ma usa il generatore di numeri casuali specificato.
+    /// #Argomenti
+    /// * 'campo': Un puntatore ad una struttura CampoGioco
+    /// * 'rng': Generatore di numeri casuali
+    pub fn nuovo_con_rng<R: Rng + ?Sized>(campo: &CampoGioco, rng: &mut R) -> Player {
         let posizione = campo.player_position;
         let mosse = campo.dimensione * campo.dimensione;
         let direzione = match rng.gen_range(0..4) {
@@ -46,7 +114,9 @@ impl Player {
             posizione,
             direzione,
             forza,
-            mosse
+            mosse,
+            immunita: 0,
+            statistiche: Statistiche::default(),
         }
     }
 
@@ -54,14 +124,69 @@ impl Player {
    /// #Argomenti
    /// * 'gamespace': Un puntatore ad una struttura CampoGioco
     /// * 'direction': Direzione in cui ci si vuole spostare
-    pub fn muovi(&mut self, gamespace: &mut CampoGioco, mut direction: Direzione) {
+    pub fn muovi(&mut self, gamespace: &mut CampoGioco, direction: Direzione) -> Esito {
         let mut rng = rand::thread_rng();
+        self.muovi_con_rng(gamespace, direction, &mut rng)
+    }
+
+    /// Come `muovi`, ma usa il generatore di numeri casuali specificato (utile per partite riproducibili).
+    ///
+    /// Dopo la mossa del giocatore si muovono i nemici presenti sul campo.
+    /// #Argomenti
+    /// * 'gamespace': Un puntatore ad una struttura CampoGioco
+    /// * 'direction': Direzione in cui ci si vuole spostare
+    /// * 'rng': Generatore di numeri casuali
+    pub fn muovi_con_rng<R: Rng + ?Sized>(&mut self, gamespace: &mut CampoGioco, direction: Direzione, rng: &mut R) -> Esito {
+        let immune = self.immunita > 0;
+        if let Some(esito) = self.prepara_mossa() {
+            return esito;
+        }
+        let destinazione = self.destinazione(gamespace, direction, rng);
+        let esito = match self.entra(gamespace, destinazione) {
+            Esito::InCorso => gamespace.turno_nemici(self, rng),
+            esito => esito,
+        };
+        gamespace.player_position = self.posizione;
+        self.concludi_mossa(immune);
+        esito
+    }
+
+    /// Controlla se il giocatore può ancora muoversi e, in tal caso, consuma una mossa.
+    ///
+    /// Restituisce l'esito della partita se il giocatore non può più muoversi.
+    pub fn prepara_mossa(&mut self) -> Option<Esito> {
         //gestione numero di mosse
-        if self.mosse == 0 && self.forza > 0 {
-            print!("HAI VINTO!\n IL NUMERO DI MOSSE è ARRIVATO A 0\n");
-            return;
+        if self.forza <= 0 {
+            return Some(Esito::Perso);
+        }
+        if self.mosse == 0 {
+            return Some(Esito::Vinto);
         }
         self.mosse -= 1;
+        self.statistiche.mosse_fatte += 1;
+        None
+    }
+
+    /// Conclude una mossa già risolta (effetto della cella e turno dei nemici compresi),
+    /// consumando un turno di immunità se il giocatore era immune all'inizio della mossa.
+    ///
+    /// Così `Immunita(n)` protegge le `n` mosse successive a quella in cui viene raccolto.
+    /// #Argomenti
+    /// * 'immune': Se il giocatore era immune prima di `prepara_mossa`
+    pub fn concludi_mossa(&mut self, immune: bool) {
+        if immune {
+            self.immunita = self.immunita.saturating_sub(1);
+        }
+    }
+
+    /// Calcola la cella in cui il giocatore prova a spostarsi, aggiornandone la direzione.
+    ///
+    /// Con probabilità 0.5 il movimento non riesce: il giocatore resta fermo e cambia direzione a caso.
+    /// #Argomenti
+    /// * 'gamespace': Un puntatore ad una struttura CampoGioco
+    /// * 'direction': Direzione in cui ci si vuole spostare
+    /// * 'rng': Generatore di numeri casuali
+    pub fn destinazione<R: Rng + ?Sized>(&mut self, gamespace: &CampoGioco, mut direction: Direzione, rng: &mut R) -> Posizione {
         let mut row = self.posizione.riga;
         let mut column = self.posizione.colonna;
         //Succeso del movimento nella direzione voluta e riassegnazione se necessario
@@ -109,25 +234,38 @@ impl Player {
                 }
             }
         }
-        match gamespace.celle[row][column] {
-            Cella::Cibo(10) => {
-                gamespace.celle[row][column] = Cella::Vuota;
-                self.forza += 10;
-            },
-            Cella::Veleno(10) => {
-                gamespace.celle[row][column] = Cella::Vuota;
-                self.forza -= 10
-            },
-            _ => gamespace.celle[row][column] = Cella::Vuota,
-        };
-        if self.forza <= 0 {
-            print!("HAI PERSO!\n SEI STATO AVVELENATO\n");
-            return;
+        Posizione { riga: row, colonna: column }
+    }
+
+    /// Fa entrare il giocatore nella cella indicata applicandone l'effetto.
+    ///
+    /// Non aggiorna `player_position` del campo, che in una partita multigiocatore resta la
+    /// posizione di partenza: lo fa chi gestisce la partita a giocatore singolo.
+    /// #Argomenti
+    /// * 'gamespace': Un puntatore ad una struttura CampoGioco
+    /// * 'destinazione': Cella in cui entrare
+    pub fn entra(&mut self, gamespace: &mut CampoGioco, destinazione: Posizione) -> Esito {
+        //Il giocatore è rimasto fermo: nessun effetto da applicare
+        if destinazione == self.posizione {
+            return Esito::InCorso;
+        }
+        let cella = gamespace.celle[destinazione.riga][destinazione.colonna];
+        let effetto = cella.effetto();
+        if effetto.blocca() {
+            self.direzione = wall(&self.direzione);
+            return Esito::InCorso;
+        }
+        if effetto.consumabile() {
+            gamespace.celle[destinazione.riga][destinazione.colonna] = Cella::Vuota;
         }
-        self.posizione.riga = row;
-        self.posizione.colonna = column;
-        gamespace.player_position.riga = row;
-        gamespace.player_position.colonna = column;
+        self.statistiche.registra(&cella);
+        let precedente = self.posizione;
+        self.posizione = destinazione;
+        let esito = effetto.applica(self);
+        if esito == Esito::Perso {
+            self.posizione = precedente;
+        }
+        esito
     }
 }
 
@@ -135,14 +273,40 @@ impl Player {
 /// #Argomenti
 /// * 'dir': Direzione del giocatore attauale
 fn wall(dir: &Direzione) -> Direzione {
-    let direction;
     match dir {
-        Direzione::Su => direction = Direzione::Giu,
-        Direzione::Giu => direction = Direzione::Su,
-        Direzione::Sinistra => direction = Direzione::Destra,
-        Direzione::Destra => direction = Direzione::Sinistra,
-    };
-    direction
+        Direzione::Su => Direzione::Giu,
+        Direzione::Giu => Direzione::Su,
+        Direzione::Sinistra => Direzione::Destra,
+        Direzione::Destra => Direzione::Sinistra,
+    }
+}
+
+impl fmt::Display for Direzione {
+    /// Implementazione della formattazione per visualizzare il nome della direzione.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let nome = match self {
+            Direzione::Su => "Su",
+            Direzione::Destra => "Destra",
+            Direzione::Sinistra => "Sinistra",
+            Direzione::Giu => "Giu",
+        };
+        write!(f, "{}", nome)
+    }
+}
+
+impl FromStr for Direzione {
+    type Err = String;
+
+    /// Converte il nome di una direzione (come prodotto da `Display`) nella `Direzione` corrispondente.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "Su" => Ok(Direzione::Su),
+            "Giu" => Ok(Direzione::Giu),
+            "Destra" => Ok(Direzione::Destra),
+            "Sinistra" => Ok(Direzione::Sinistra),
+            altro => Err(format!("direzione sconosciuta: {}", altro)),
+        }
+    }
 }
 
 impl fmt::Display for Player {
@@ -150,12 +314,10 @@ impl fmt::Display for Player {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         // Scrivi direttamente sul formatter anziché utilizzare una stringa temporanea
         write!(f, "Giocatore\nDirezione: {}\nForza: {}\nPosizione: {}\nMosse: {}\n",
-               match self.direzione {
-                   Direzione::Su => "Su",
-                   Direzione::Destra => "Destra",
-                   Direzione::Sinistra => "Sinistra",
-                   Direzione::Giu => "Giu",
-               },
-               self.forza, self.posizione, self.mosse)
+               self.direzione, self.forza, self.posizione, self.mosse)?;
+        if self.immunita > 0 {
+            writeln!(f, "Immunità: {}", self.immunita)?;
+        }
+        Ok(())
     }
 }
@@ -0,0 +1,238 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use crate::game_space::game_space::{CampoGioco, Cella};
+use crate::player::{Esito, Player};
+
+/// Punti per ogni cella di cibo mangiata.
+pub const PUNTI_CIBO: u32 = 10;
+/// Punti per ogni cella di veleno presente all'inizio ed evitata.
+pub const PUNTI_VELENO_EVITATO: u32 = 5;
+/// Punti per ogni mossa rimasta a fine partita (solo se il giocatore non ha perso).
+pub const PUNTI_MOSSA_RIMASTA: u32 = 1;
+/// Punti assegnati a un percorso di efficienza massima.
+pub const PUNTI_EFFICIENZA: u32 = 50;
+/// Numero di voci tenute in classifica per ogni combinazione di regole e seme.
+pub const VOCI_PREDEFINITE: usize = 10;
+
+/// Conta le celle di veleno presenti nel campo.
+/// # Argomenti
+///
+/// * `campo`: Il campo di gioco.
+pub fn conta_veleno(campo: &CampoGioco) -> u32 {
+    CampoGioco::posizioni(campo.dimensione)
+        .filter(|p| matches!(campo.cella(*p), Cella::Veleno(_)))
+        .count() as u32
+}
+
+/// Punteggio di fine partita di un giocatore, con il dettaglio delle voci che lo compongono.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Punteggio {
+    /// Celle di cibo mangiate.
+    pub cibo_mangiato: u32,
+    /// Celle di veleno presenti all'inizio in cui il giocatore non è entrato.
+    pub veleno_evitato: u32,
+    /// Mosse rimaste a fine partita (0 se il giocatore ha perso).
+    pub mosse_rimaste: usize,
+    /// Efficienza del percorso: cibo mangiato per mossa effettuata, tra 0 e 1.
+    pub efficienza: f64,
+    /// Punteggio totale.
+    pub totale: u64,
+}
+
+impl Punteggio {
+    /// Calcola il punteggio di fine partita.
+    /// # Argomenti
+    ///
+    /// * `player`: Il giocatore a fine partita.
+    /// * `esito`: L'esito della partita.
+    /// * `veleno_iniziale`: Numero di celle di veleno presenti a inizio partita (vedi `conta_veleno`).
+    pub fn calcola(player: &Player, esito: Esito, veleno_iniziale: u32) -> Punteggio {
+        let s = &player.statistiche;
+        let veleno_evitato = veleno_iniziale.saturating_sub(s.veleno_preso);
+        let mosse_rimaste = if esito == Esito::Perso { 0 } else { player.mosse };
+        let efficienza = if s.mosse_fatte == 0 {
+            0.0
+        } else {
+            (s.cibo_mangiato as f64 / s.mosse_fatte as f64).min(1.0)
+        };
+        let mut punteggio = Punteggio {
+            cibo_mangiato: s.cibo_mangiato,
+            veleno_evitato,
+            mosse_rimaste,
+            efficienza,
+            totale: 0,
+        };
+        punteggio.totale = punteggio.voci().into_iter().fold(0, u64::saturating_add);
+        punteggio
+    }
+
+    /// Restituisce i punti di ciascuna voce: cibo, veleno evitato, mosse rimaste ed efficienza.
+    fn voci(&self) -> [u64; 4] {
+        [
+            u64::from(self.cibo_mangiato) * u64::from(PUNTI_CIBO),
+            u64::from(self.veleno_evitato) * u64::from(PUNTI_VELENO_EVITATO),
+            (self.mosse_rimaste as u64).saturating_mul(u64::from(PUNTI_MOSSA_RIMASTA)),
+            (self.efficienza * PUNTI_EFFICIENZA as f64).round() as u64,
+        ]
+    }
+}
+
+impl fmt::Display for Punteggio {
+    /// Implementazione della formattazione per il riepilogo di fine partita.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [cibo, veleno, mosse, efficienza] = self.voci();
+        writeln!(f, "Cibo mangiato: {} (+{})", self.cibo_mangiato, cibo)?;
+        writeln!(f, "Veleno evitato: {} (+{})", self.veleno_evitato, veleno)?;
+        writeln!(f, "Mosse rimaste: {} (+{})", self.mosse_rimaste, mosse)?;
+        writeln!(f, "Efficienza del percorso: {:.0}% (+{})", self.efficienza * 100.0, efficienza)?;
+        writeln!(f, "PUNTEGGIO: {}", self.totale)
+    }
+}
+
+/// Una voce della classifica.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VoceClassifica {
+    /// Insieme di regole con cui è stata giocata la partita (ad esempio `singolo 10x10 20`).
+    pub regole: String,
+    /// Seme da cui derivano il campo e lo svolgimento della partita.
+    pub seme: u64,
+    /// Nome del giocatore.
+    pub nome: String,
+    /// Punteggio totale.
+    pub punti: u64,
+}
+
+/// Classifica locale: per ogni combinazione di regole e seme tiene i migliori punteggi,
+/// ordinati dal più alto.
+///
+/// Nel file ogni voce occupa una riga con regole, seme, punti e nome separati da tabulazioni.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Classifica {
+    voci: Vec<VoceClassifica>,
+}
+
+impl Classifica {
+    /// Carica la classifica dal file indicato. Un file inesistente corrisponde a una classifica vuota;
+    /// le righe non valide vengono ignorate.
+    /// # Argomenti
+    ///
+    /// * `percorso`: Il file della classifica.
+    pub fn carica<P: AsRef<Path>>(percorso: P) -> io::Result<Classifica> {
+        let testo = match fs::read_to_string(percorso) {
+            Ok(testo) => testo,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Classifica::default()),
+            Err(e) => return Err(e),
+        };
+        Ok(Classifica::da_testo(&testo))
+    }
+
+    /// Legge la classifica dal formato testuale del file.
+    /// # Argomenti
+    ///
+    /// * `testo`: Il contenuto del file.
+    pub fn da_testo(testo: &str) -> Classifica {
+        let voci = testo
+            .lines()
+            .filter_map(|riga| {
+                let mut campi = riga.splitn(4, '\t');
+                Some(VoceClassifica {
+                    regole: campi.next()?.to_string(),
+                    seme: campi.next()?.parse().ok()?,
+                    punti: campi.next()?.parse().ok()?,
+                    nome: campi.next()?.to_string(),
+                })
+            })
+            .collect();
+        Classifica { voci }
+    }
+
+    /// Restituisce la classifica nel formato testuale del file.
+    pub fn in_testo(&self) -> String {
+        self.voci
+            .iter()
+            .map(|v| format!("{}\t{}\t{}\t{}\n", v.regole, v.seme, v.punti, v.nome))
+            .collect()
+    }
+
+    /// Salva la classifica nel file indicato.
+    /// # Argomenti
+    ///
+    /// * `percorso`: Il file della classifica.
+    pub fn salva<P: AsRef<Path>>(&self, percorso: P) -> io::Result<()> {
+        fs::write(percorso, self.in_testo())
+    }
+
+    /// Registra un punteggio, tenendo solo le migliori `massimo` voci con le stesse regole e lo stesso seme.
+    ///
+    /// Restituisce la posizione in classifica (da 0), o `None` se il punteggio non è entrato.
+    /// A parità di punti resta davanti chi li ha ottenuti prima.
+    /// # Argomenti
+    ///
+    /// * `voce`: La voce da registrare.
+    /// * `massimo`: Numero massimo di voci per regole e seme.
+    pub fn registra(&mut self, voce: VoceClassifica, massimo: usize) -> Option<usize> {
+        let (regole, seme) = (voce.regole.clone(), voce.seme);
+        let stesso_gruppo = |v: &VoceClassifica| v.regole == regole && v.seme == seme;
+        let posizione = self
+            .voci
+            .iter()
+            .filter(|v| stesso_gruppo(v))
+            .take_while(|v| v.punti >= voce.punti)
+            .count();
+        if posizione >= massimo {
+            return None;
+        }
+        let indice = self
+            .voci
+            .iter()
+            .position(|v| stesso_gruppo(v) && v.punti < voce.punti)
+            .or_else(|| self.voci.iter().rposition(stesso_gruppo).map(|i| i + 1))
+            .unwrap_or(self.voci.len());
+        self.voci.insert(indice, voce);
+        // Rimuove le voci scese oltre il limite
+        let mut contate = 0;
+        self.voci.retain(|v| {
+            if !stesso_gruppo(v) {
+                return true;
+            }
+            contate += 1;
+            contate <= massimo
+        });
+        Some(posizione)
+    }
+
+    /// Restituisce le voci con le regole e il seme indicati, dalla migliore.
+    /// # Argomenti
+    ///
+    /// * `regole`: L'insieme di regole.
+    /// * `seme`: Il seme del campo.
+    pub fn migliori(&self, regole: &str, seme: u64) -> Vec<&VoceClassifica> {
+        self.voci.iter().filter(|v| v.regole == regole && v.seme == seme).collect()
+    }
+
+    /// Restituisce le combinazioni di regole e seme presenti in classifica, nell'ordine di comparsa.
+    pub fn gruppi(&self) -> Vec<(&str, u64)> {
+        let mut gruppi: Vec<(&str, u64)> = Vec::new();
+        for v in &self.voci {
+            if !gruppi.contains(&(v.regole.as_str(), v.seme)) {
+                gruppi.push((v.regole.as_str(), v.seme));
+            }
+        }
+        gruppi
+    }
+}
+
+impl fmt::Display for Classifica {
+    /// Implementazione della formattazione per visualizzare la classifica, un gruppo di regole e seme alla volta.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (regole, seme) in self.gruppi() {
+            writeln!(f, "== {} (seme {}) ==", regole, seme)?;
+            for (i, v) in self.migliori(regole, seme).iter().enumerate() {
+                writeln!(f, "{:>3}. {:<20} {}", i + 1, v.nome, v.punti)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,508 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use crate::effetti::PowerUp;
+use crate::game_space::game_space::{CampoGioco, Cella, Posizione};
+use crate::nemici::{Comportamento, Nemico};
+use crate::player::{Direzione, Player, Statistiche};
+
+/// Quantità usata per `Cibo` e `Veleno` quando il simbolo nella mappa non la specifica.
+pub const QUANTITA_PREDEFINITA: u32 = 10;
+
+/// Errori che possono verificarsi durante il caricamento di una mappa o di una partita salvata.
+#[derive(Debug)]
+pub enum ErroreCaricamento {
+    /// Errore di lettura o scrittura del file.
+    Io(io::Error),
+    /// Il testo non contiene alcuna riga della mappa.
+    MappaVuota,
+    /// Una riga della mappa non ha lo stesso numero di celle delle altre (il campo deve essere quadrato).
+    DimensioneNonValida { riga: usize, attesa: usize, trovata: usize },
+    /// Nella mappa compare un simbolo che non corrisponde a nessuna cella
+    /// (o un teletrasporto con destinazione fuori dal campo).
+    SimboloSconosciuto { riga: usize, colonna: usize, simbolo: String },
+    /// Un teletrasporto porta in una cella non vuota (un muro, un oggetto o un altro teletrasporto).
+    TeletrasportoNonValido { riga: usize, colonna: usize, destinazione: Posizione },
+    /// La mappa non contiene il giocatore `P`.
+    GiocatoreMancante,
+    /// La mappa contiene più di un giocatore `P`.
+    GiocatoriMultipli,
+    /// Nell'intestazione manca un campo obbligatorio.
+    CampoMancante(&'static str),
+    /// Un campo dell'intestazione ha un valore non valido.
+    ValoreNonValido { campo: String, valore: String },
+}
+
+impl fmt::Display for ErroreCaricamento {
+    /// Implementazione della formattazione per visualizzare un errore di caricamento.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErroreCaricamento::Io(e) => write!(f, "errore di I/O: {}", e),
+            ErroreCaricamento::MappaVuota => write!(f, "la mappa è vuota"),
+            ErroreCaricamento::DimensioneNonValida { riga, attesa, trovata } => write!(
+                f,
+                "la riga {} ha {} celle, ne erano attese {}",
+                riga, trovata, attesa
+            ),
+            ErroreCaricamento::SimboloSconosciuto { riga, colonna, simbolo } => write!(
+                f,
+                "simbolo sconosciuto '{}' in posizione ({}, {})",
+                simbolo, riga, colonna
+            ),
+            ErroreCaricamento::TeletrasportoNonValido { riga, colonna, destinazione } => write!(
+                f,
+                "il teletrasporto in posizione ({}, {}) porta nella cella occupata {}",
+                riga, colonna, destinazione
+            ),
+            ErroreCaricamento::GiocatoreMancante => write!(f, "la mappa non contiene il giocatore 'P'"),
+            ErroreCaricamento::GiocatoriMultipli => write!(f, "la mappa contiene più di un giocatore 'P'"),
+            ErroreCaricamento::CampoMancante(campo) => write!(f, "campo mancante nell'intestazione: {}", campo),
+            ErroreCaricamento::ValoreNonValido { campo, valore } => {
+                write!(f, "valore non valido per '{}': {}", campo, valore)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ErroreCaricamento {}
+
+impl From<io::Error> for ErroreCaricamento {
+    fn from(e: io::Error) -> Self {
+        ErroreCaricamento::Io(e)
+    }
+}
+
+/// Mosse perse per una trappola `^` scritta senza quantità.
+pub const MOSSE_TRAPPOLA_PREDEFINITE: u32 = 2;
+
+/// Durata (o mosse aggiunte) di un potenziamento `*`/`+` scritto senza quantità.
+pub const POWER_UP_PREDEFINITO: u32 = 5;
+
+/// Restituisce il simbolo testuale di una cella, con la quantità solo se diversa da quella predefinita.
+///
+/// Il simbolo è quello di `Display`; il teletrasporto è seguito dalla destinazione (es. `T3,4`).
+fn simbolo_cella(cella: &Cella) -> String {
+    let simbolo = cella.effetto().simbolo();
+    let con_quantita = |q: u32, predefinita: u32| {
+        if q == predefinita {
+            simbolo.to_string()
+        } else {
+            format!("{}{}", simbolo, q)
+        }
+    };
+    match cella {
+        Cella::Cibo(q) | Cella::Veleno(q) => con_quantita(*q, QUANTITA_PREDEFINITA),
+        Cella::Trappola(q) => con_quantita(*q, MOSSE_TRAPPOLA_PREDEFINITE),
+        Cella::PowerUp(PowerUp::Immunita(q)) | Cella::PowerUp(PowerUp::MosseExtra(q)) => {
+            con_quantita(*q, POWER_UP_PREDEFINITO)
+        }
+        Cella::Teletrasporto(destinazione) => {
+            format!("{}{},{}", simbolo, destinazione.riga, destinazione.colonna)
+        }
+        Cella::Vuota | Cella::Muro | Cella::Uscita => simbolo.to_string(),
+    }
+}
+
+/// Interpreta il simbolo di una cella. Restituisce `None` se il simbolo non è riconosciuto.
+///
+/// Il giocatore `P` viene interpretato come una cella vuota: la sua posizione è gestita dal chiamante.
+fn cella_da_simbolo(simbolo: &str) -> Option<Cella> {
+    let quantita = |resto: &str, predefinita: u32| -> Option<u32> {
+        if resto.is_empty() {
+            Some(predefinita)
+        } else {
+            resto.parse().ok()
+        }
+    };
+    let mut caratteri = simbolo.chars();
+    let iniziale = caratteri.next()?;
+    let resto = caratteri.as_str();
+    match iniziale {
+        'O' | 'P' | 'X' | 'E' if !resto.is_empty() => None,
+        'O' | 'P' => Some(Cella::Vuota),
+        'X' => Some(Cella::Muro),
+        'E' => Some(Cella::Uscita),
+        '$' => quantita(resto, QUANTITA_PREDEFINITA).map(Cella::Cibo),
+        '-' => quantita(resto, QUANTITA_PREDEFINITA).map(Cella::Veleno),
+        '^' => quantita(resto, MOSSE_TRAPPOLA_PREDEFINITE).map(Cella::Trappola),
+        '*' => quantita(resto, POWER_UP_PREDEFINITO).map(|q| Cella::PowerUp(PowerUp::Immunita(q))),
+        '+' => quantita(resto, POWER_UP_PREDEFINITO).map(|q| Cella::PowerUp(PowerUp::MosseExtra(q))),
+        'T' => {
+            let (riga, colonna) = resto.split_once(',')?;
+            Some(Cella::Teletrasporto(Posizione {
+                riga: riga.parse().ok()?,
+                colonna: colonna.parse().ok()?,
+            }))
+        }
+        _ => None,
+    }
+}
+
+/// Restituisce la mappa del campo di gioco nel formato testuale, con gli stessi simboli di `Display`.
+///
+/// Le celle con una quantità diversa da `QUANTITA_PREDEFINITA` vengono scritte con la quantità
+/// subito dopo il simbolo (es. `$25`). La posizione del giocatore è scritta a parte nella prima
+/// riga (es. `posizione 1,2`), così la cella su cui si trova resta nella mappa; gli eventuali
+/// nemici seguono, uno per riga (es. `nemico inseguitore 2,3 15`), e precedono la mappa.
+/// # Argomenti
+/// * 'campo': Il campo di gioco da salvare
+pub fn salva_campo(campo: &CampoGioco) -> String {
+    let mut testo = format!("posizione {}\n", testo_posizione(&campo.player_position));
+    for nemico in &campo.nemici {
+        testo.push_str(&format!("nemico {}\n", testo_nemico(nemico)));
+    }
+    for riga in &campo.celle {
+        let simboli: Vec<String> = riga.iter().map(simbolo_cella).collect();
+        testo.push_str(&simboli.join(" "));
+        testo.push('\n');
+    }
+    testo
+}
+
+/// Crea un campo di gioco a partire da una mappa testuale.
+///
+/// La mappa deve essere quadrata e indicare il giocatore in uno solo dei due modi: con un
+/// simbolo `P` (su una cella vuota) oppure con una riga `posizione <riga>,<colonna>`.
+/// Le righe vuote e quelle che iniziano con `#` vengono ignorate.
+/// # Argomenti
+/// * 'testo': La mappa testuale
+pub fn carica_campo(testo: &str) -> Result<CampoGioco, ErroreCaricamento> {
+    let mut righe = Vec::new();
+    let mut nemici = Vec::new();
+    let mut posizione = None;
+    for riga in testo.lines().map(str::trim) {
+        if riga.is_empty() || riga.starts_with('#') {
+            continue;
+        }
+        if let Some(valore) = riga.strip_prefix("nemico ") {
+            nemici.push(nemico_da_testo(valore)?);
+        } else if let Some(valore) = riga.strip_prefix("posizione ") {
+            posizione = Some(posizione_da_testo(valore.trim())?);
+        } else {
+            righe.push(riga);
+        }
+    }
+    campo_da_righe(&righe, posizione, nemici)
+}
+
+/// Restituisce una posizione nel formato `<riga>,<colonna>`.
+fn testo_posizione(posizione: &Posizione) -> String {
+    format!("{},{}", posizione.riga, posizione.colonna)
+}
+
+/// Interpreta la riga `posizione` prodotta da `salva_campo`.
+fn posizione_da_testo(testo: &str) -> Result<Posizione, ErroreCaricamento> {
+    let non_valido = || ErroreCaricamento::ValoreNonValido {
+        campo: String::from("posizione"),
+        valore: testo.to_string(),
+    };
+    let (riga, colonna) = testo.split_once(',').ok_or_else(non_valido)?;
+    Ok(Posizione {
+        riga: riga.parse().map_err(|_| non_valido())?,
+        colonna: colonna.parse().map_err(|_| non_valido())?,
+    })
+}
+
+/// Restituisce la descrizione testuale di un nemico, nel formato
+/// `<tipo> <riga>,<colonna> <forza>` seguito, per le guardie, dalla prossima tappa e dal percorso.
+fn testo_nemico(nemico: &Nemico) -> String {
+    let inizio = |tipo: &str| format!("{} {} {}", tipo, testo_posizione(&nemico.posizione), nemico.forza);
+    match &nemico.comportamento {
+        Comportamento::Vagabondo => inizio("vagabondo"),
+        Comportamento::Inseguitore => inizio("inseguitore"),
+        Comportamento::Guardia { percorso, prossima } => {
+            let tappe: Vec<String> = percorso.iter().map(testo_posizione).collect();
+            format!("{} {} {}", inizio("guardia"), prossima, tappe.join(" "))
+        }
+    }
+}
+
+/// Interpreta la descrizione testuale di un nemico prodotta da `testo_nemico`.
+fn nemico_da_testo(testo: &str) -> Result<Nemico, ErroreCaricamento> {
+    let non_valido = || ErroreCaricamento::ValoreNonValido {
+        campo: String::from("nemico"),
+        valore: testo.to_string(),
+    };
+    let posizione = |p: &str| -> Result<Posizione, ErroreCaricamento> {
+        let (riga, colonna) = p.split_once(',').ok_or_else(non_valido)?;
+        Ok(Posizione {
+            riga: riga.parse().map_err(|_| non_valido())?,
+            colonna: colonna.parse().map_err(|_| non_valido())?,
+        })
+    };
+    let parti: Vec<&str> = testo.split_whitespace().collect();
+    if parti.len() < 3 {
+        return Err(non_valido());
+    }
+    let inizio = posizione(parti[1])?;
+    let forza: i32 = parti[2].parse().map_err(|_| non_valido())?;
+    if forza <= 0 {
+        return Err(non_valido());
+    }
+    let comportamento = match (parti[0], parti.len()) {
+        ("vagabondo", 3) => Comportamento::Vagabondo,
+        ("inseguitore", 3) => Comportamento::Inseguitore,
+        ("guardia", n) if n > 4 => Comportamento::Guardia {
+            prossima: parti[3].parse().map_err(|_| non_valido())?,
+            percorso: parti[4..].iter().map(|p| posizione(p)).collect::<Result<_, _>>()?,
+        },
+        _ => return Err(non_valido()),
+    };
+    Ok(Nemico::nuovo(inizio, forza, comportamento))
+}
+
+/// Costruisce il campo di gioco dalle righe della mappa, validandone dimensione e giocatore.
+///
+/// Il giocatore è il simbolo `P` della mappa oppure, se indicata, la `posizione` letta a parte.
+fn campo_da_righe(righe: &[&str], posizione: Option<Posizione>, nemici: Vec<Nemico>) -> Result<CampoGioco, ErroreCaricamento> {
+    let dimensione = righe.len();
+    if dimensione == 0 {
+        return Err(ErroreCaricamento::MappaVuota);
+    }
+    let mut celle = Vec::with_capacity(dimensione);
+    let mut player_position = posizione;
+    for (i, riga) in righe.iter().enumerate() {
+        let simboli: Vec<&str> = riga.split_whitespace().collect();
+        if simboli.len() != dimensione {
+            return Err(ErroreCaricamento::DimensioneNonValida {
+                riga: i,
+                attesa: dimensione,
+                trovata: simboli.len(),
+            });
+        }
+        let mut riga_celle = Vec::with_capacity(dimensione);
+        for (j, simbolo) in simboli.iter().enumerate() {
+            if *simbolo == "P" {
+                if player_position.is_some() {
+                    return Err(ErroreCaricamento::GiocatoriMultipli);
+                }
+                player_position = Some(Posizione { riga: i, colonna: j });
+            }
+            let cella = cella_da_simbolo(simbolo).ok_or_else(|| ErroreCaricamento::SimboloSconosciuto {
+                riga: i,
+                colonna: j,
+                simbolo: simbolo.to_string(),
+            })?;
+            riga_celle.push(cella);
+        }
+        celle.push(riga_celle);
+    }
+    let campo = CampoGioco {
+        dimensione,
+        celle,
+        player_position: player_position.ok_or(ErroreCaricamento::GiocatoreMancante)?,
+        nemici,
+    };
+    verifica_campo(&campo)?;
+    Ok(campo)
+}
+
+/// Verifica che un campo di gioco sia coerente: quadrato della dimensione dichiarata, con il
+/// giocatore e i nemici al suo interno e i teletrasporti diretti verso celle vuote del campo.
+///
+/// Sono i controlli di `carica_campo`, usati anche per i campi letti con serde.
+/// # Argomenti
+/// * 'campo': Il campo di gioco da verificare
+pub(crate) fn verifica_campo(campo: &CampoGioco) -> Result<(), ErroreCaricamento> {
+    let dimensione = campo.dimensione;
+    if dimensione == 0 {
+        return Err(ErroreCaricamento::MappaVuota);
+    }
+    if campo.celle.len() != dimensione {
+        return Err(ErroreCaricamento::ValoreNonValido {
+            campo: String::from("dimensione"),
+            valore: format!("{} (la mappa ha {} righe)", dimensione, campo.celle.len()),
+        });
+    }
+    if let Some((riga, celle)) = campo.celle.iter().enumerate().find(|(_, celle)| celle.len() != dimensione) {
+        return Err(ErroreCaricamento::DimensioneNonValida { riga, attesa: dimensione, trovata: celle.len() });
+    }
+    let fuori = |p: &Posizione| p.riga >= dimensione || p.colonna >= dimensione;
+    if fuori(&campo.player_position) {
+        return Err(ErroreCaricamento::ValoreNonValido {
+            campo: String::from("posizione"),
+            valore: testo_posizione(&campo.player_position),
+        });
+    }
+    for posizione in CampoGioco::posizioni(dimensione) {
+        let cella = campo.cella(posizione);
+        if let Cella::Teletrasporto(destinazione) = cella {
+            if fuori(&destinazione) {
+                return Err(ErroreCaricamento::SimboloSconosciuto {
+                    riga: posizione.riga,
+                    colonna: posizione.colonna,
+                    simbolo: simbolo_cella(&cella),
+                });
+            }
+            if campo.cella(destinazione) != Cella::Vuota {
+                return Err(ErroreCaricamento::TeletrasportoNonValido {
+                    riga: posizione.riga,
+                    colonna: posizione.colonna,
+                    destinazione,
+                });
+            }
+        }
+    }
+    for nemico in &campo.nemici {
+        let percorso_fuori = match &nemico.comportamento {
+            Comportamento::Guardia { percorso, .. } => percorso.iter().any(fuori),
+            _ => false,
+        };
+        if fuori(&nemico.posizione) || percorso_fuori || nemico.forza <= 0 {
+            return Err(ErroreCaricamento::ValoreNonValido {
+                campo: String::from("nemico"),
+                valore: testo_nemico(nemico),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Restituisce la partita in corso (intestazione con lo stato del giocatore seguita dalla mappa).
+///
+/// L'intestazione è composta da righe `chiave valore` (`immunita` e `statistiche` sono facoltative).
+/// La riga `statistiche` riporta, nell'ordine, mosse fatte, cibo mangiato, veleno preso,
+/// potenziamenti raccolti, colpi dei nemici, scontri vinti e scontri persi:
+/// ```text
+/// dimensione 5
+/// mosse 24
+/// forza 40
+/// immunita 0
+/// direzione Su
+/// statistiche 1 0 0 0 0 0 0
+/// posizione 0,1
+/// O O $ O O
+/// ...
+/// ```
+/// # Argomenti
+/// * 'campo': Il campo di gioco da salvare
+/// * 'player': Il giocatore da salvare
+pub fn salva_partita(campo: &CampoGioco, player: &Player) -> String {
+    let s = &player.statistiche;
+    format!(
+        "dimensione {}\nmosse {}\nforza {}\nimmunita {}\ndirezione {}\nstatistiche {} {} {} {} {} {} {}\n{}",
+        campo.dimensione,
+        player.mosse,
+        player.forza,
+        player.immunita,
+        player.direzione,
+        s.mosse_fatte,
+        s.cibo_mangiato,
+        s.veleno_preso,
+        s.power_up_raccolti,
+        s.colpi_nemici,
+        s.scontri_vinti,
+        s.scontri_persi,
+        salva_campo(campo)
+    )
+}
+
+/// Interpreta la riga `statistiche` dell'intestazione prodotta da `salva_partita`.
+fn statistiche_da_testo(testo: &str) -> Result<Statistiche, ErroreCaricamento> {
+    let non_valido = || ErroreCaricamento::ValoreNonValido {
+        campo: String::from("statistiche"),
+        valore: testo.to_string(),
+    };
+    let valori: Vec<u32> = testo
+        .split_whitespace()
+        .map(|v| v.parse().map_err(|_| non_valido()))
+        .collect::<Result<_, _>>()?;
+    let [mosse_fatte, cibo_mangiato, veleno_preso, power_up_raccolti, colpi_nemici, scontri_vinti, scontri_persi] = valori[..] else {
+        return Err(non_valido());
+    };
+    Ok(Statistiche {
+        mosse_fatte: mosse_fatte as usize,
+        cibo_mangiato,
+        veleno_preso,
+        power_up_raccolti,
+        colpi_nemici,
+        scontri_vinti,
+        scontri_persi,
+    })
+}
+
+/// Legge un valore numerico dell'intestazione.
+fn valore_numerico<T: std::str::FromStr>(campo: &str, valore: &str) -> Result<T, ErroreCaricamento> {
+    valore.parse().map_err(|_| ErroreCaricamento::ValoreNonValido {
+        campo: campo.to_string(),
+        valore: valore.to_string(),
+    })
+}
+
+/// Ripristina una partita salvata con `salva_partita`.
+///
+/// Oltre ai controlli di `carica_campo`, verifica che la dimensione dichiarata nell'intestazione
+/// corrisponda a quella della mappa.
+/// # Argomenti
+/// * 'testo': Il testo della partita salvata
+pub fn carica_partita(testo: &str) -> Result<(CampoGioco, Player), ErroreCaricamento> {
+    let mut dimensione: Option<usize> = None;
+    let mut mosse: Option<usize> = None;
+    let mut forza: Option<i32> = None;
+    let mut direzione: Option<Direzione> = None;
+    let mut immunita: u32 = 0;
+    let mut statistiche = Statistiche::default();
+    let mut nemici = Vec::new();
+    let mut posizione = None;
+    let mut righe_mappa = Vec::new();
+
+    for riga in testo.lines().map(str::trim) {
+        if riga.is_empty() || riga.starts_with('#') {
+            continue;
+        }
+        let (chiave, valore) = riga.split_once(' ').unwrap_or((riga, ""));
+        let valore = valore.trim();
+        match chiave {
+            "dimensione" => dimensione = Some(valore_numerico(chiave, valore)?),
+            "mosse" => mosse = Some(valore_numerico(chiave, valore)?),
+            "forza" => forza = Some(valore_numerico(chiave, valore)?),
+            "immunita" => immunita = valore_numerico(chiave, valore)?,
+            "statistiche" => statistiche = statistiche_da_testo(valore)?,
+            "nemico" => nemici.push(nemico_da_testo(valore)?),
+            "posizione" => posizione = Some(posizione_da_testo(valore)?),
+            "direzione" => {
+                direzione = Some(valore.parse().map_err(|_| ErroreCaricamento::ValoreNonValido {
+                    campo: chiave.to_string(),
+                    valore: valore.to_string(),
+                })?)
+            }
+            _ => righe_mappa.push(riga),
+        }
+    }
+
+    let campo = campo_da_righe(&righe_mappa, posizione, nemici)?;
+    let dimensione = dimensione.ok_or(ErroreCaricamento::CampoMancante("dimensione"))?;
+    if dimensione != campo.dimensione {
+        return Err(ErroreCaricamento::ValoreNonValido {
+            campo: String::from("dimensione"),
+            valore: format!("{} (la mappa è {}x{})", dimensione, campo.dimensione, campo.dimensione),
+        });
+    }
+    let player = Player {
+        mosse: mosse.ok_or(ErroreCaricamento::CampoMancante("mosse"))?,
+        posizione: campo.player_position,
+        direzione: direzione.ok_or(ErroreCaricamento::CampoMancante("direzione"))?,
+        forza: forza.ok_or(ErroreCaricamento::CampoMancante("forza"))?,
+        immunita,
+        statistiche,
+    };
+    Ok((campo, player))
+}
+
+/// Salva la partita in corso su file.
+/// # Argomenti
+/// * 'percorso': Il file di destinazione
+/// * 'campo': Il campo di gioco da salvare
+/// * 'player': Il giocatore da salvare
+pub fn salva_su_file<P: AsRef<Path>>(percorso: P, campo: &CampoGioco, player: &Player) -> io::Result<()> {
+    fs::write(percorso, salva_partita(campo, player))
+}
+
+/// Carica una partita salvata da file.
+/// # Argomenti
+/// * 'percorso': Il file da leggere
+pub fn carica_da_file<P: AsRef<Path>>(percorso: P) -> Result<(CampoGioco, Player), ErroreCaricamento> {
+    let testo = fs::read_to_string(percorso)?;
+    carica_partita(&testo)
+}
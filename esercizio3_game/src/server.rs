@@ -0,0 +1,314 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use crate::game_space::game_space::CampoGioco;
+use crate::multiplayer::{PartitaMultigiocatore, RegolaCollisione};
+use crate::player::{Direzione, Esito};
+
+/// Lunghezza massima in byte di una riga ricevuta da un client, a capo compreso.
+pub const RIGA_MASSIMA: usize = 1024;
+
+/// Numero massimo di messaggi in attesa di essere scritti verso un client.
+pub const CODA_MASSIMA: usize = 64;
+
+/// Comando inviato da un client, una riga per comando.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Comando {
+    /// `JOIN <nome>`: entra in partita con il nome indicato.
+    Join(String),
+    /// `MOVE <direzione>`: muove il proprio giocatore (Su, Giu, Destra, Sinistra o G/S/D/L).
+    Move(Direzione),
+    /// `STATE`: richiede lo stato della partita.
+    State,
+    /// `QUIT`: abbandona la partita e chiude la connessione.
+    Quit,
+}
+
+impl Comando {
+    /// Interpreta una riga del protocollo. Restituisce il messaggio d'errore da inviare al client
+    /// se la riga non è un comando valido.
+    /// # Argomenti
+    ///
+    /// * `riga`: La riga ricevuta dal client.
+    pub fn interpreta(riga: &str) -> Result<Comando, String> {
+        let riga = riga.trim();
+        let (nome, argomento) = match riga.split_once(char::is_whitespace) {
+            Some((nome, argomento)) => (nome, argomento.trim()),
+            None => (riga, ""),
+        };
+        match (nome.to_ascii_uppercase().as_str(), argomento) {
+            ("JOIN", "") => Err("JOIN richiede un nome".to_string()),
+            ("JOIN", nome) => Ok(Comando::Join(nome.to_string())),
+            ("MOVE", direzione) => direzione_da_testo(direzione).map(Comando::Move),
+            ("STATE", "") => Ok(Comando::State),
+            ("QUIT", "") => Ok(Comando::Quit),
+            _ => Err(format!("comando sconosciuto: {}", riga)),
+        }
+    }
+}
+
+/// Converte il nome di una direzione, senza distinguere maiuscole e minuscole, o la sua iniziale
+/// (G/S/D/L come nel gioco da terminale).
+fn direzione_da_testo(testo: &str) -> Result<Direzione, String> {
+    match testo.to_ascii_uppercase().as_str() {
+        "G" | "GIU" => Ok(Direzione::Giu),
+        "S" | "SU" => Ok(Direzione::Su),
+        "D" | "DESTRA" => Ok(Direzione::Destra),
+        "L" | "SINISTRA" => Ok(Direzione::Sinistra),
+        _ => Err(format!("direzione sconosciuta: {}", testo)),
+    }
+}
+
+/// Nome dell'esito usato nel protocollo.
+fn nome_esito(esito: Esito) -> &'static str {
+    match esito {
+        Esito::InCorso => "INCORSO",
+        Esito::Vinto => "VINTO",
+        Esito::Perso => "PERSO",
+    }
+}
+
+/// Rappresentazione testuale dello stato della partita inviata ai client.
+///
+/// Il blocco inizia con `STATO <dimensione> <turno>` (`-` se la partita è finita), prosegue con
+/// una riga per ogni riga del campo (simboli separati da spazi), una riga
+/// `GIOCATORE <id> <riga> <colonna> <forza> <mosse> <esito> <nome>` per ogni giocatore, l'eventuale
+/// riga `VINCITORE <id>` e termina con `FINE`. I giocatori sono numerati da 1 ovunque, come
+/// nelle righe del campo.
+/// # Argomenti
+///
+/// * `partita`: La partita da descrivere.
+pub fn stato_testuale(partita: &PartitaMultigiocatore) -> String {
+    let turno = partita.turno_corrente().map_or("-".to_string(), |id| (id + 1).to_string());
+    let mut testo = format!("STATO {} {}\n", partita.campo.dimensione, turno);
+    // Il Display della partita contiene le righe del campo seguite da quelle dei giocatori
+    for riga in partita.to_string().lines().take(partita.campo.dimensione) {
+        testo.push_str(riga.trim_end());
+        testo.push('\n');
+    }
+    for (id, g) in partita.giocatori.iter().enumerate() {
+        testo.push_str(&format!(
+            "GIOCATORE {} {} {} {} {} {} {}\n",
+            id + 1,
+            g.player.posizione.riga,
+            g.player.posizione.colonna,
+            g.player.forza,
+            g.player.mosse,
+            nome_esito(g.esito),
+            g.nome
+        ));
+    }
+    if let Some(id) = partita.vincitore() {
+        testo.push_str(&format!("VINCITORE {}\n", id + 1));
+    }
+    testo.push_str("FINE\n");
+    testo
+}
+
+/// Un client connesso: la coda dei messaggi da scrivergli e la sua connessione.
+struct Client {
+    coda: SyncSender<String>,
+    stream: TcpStream,
+}
+
+impl Client {
+    /// Accoda un messaggio senza attendere. Se la coda è piena il client non legge abbastanza
+    /// in fretta e la sua connessione viene chiusa; gli altri errori vengono ignorati, perché un
+    /// client irraggiungibile viene rimosso quando la sua connessione si chiude.
+    fn accoda(&self, messaggio: String) {
+        if let Err(TrySendError::Full(_)) = self.coda.try_send(messaggio) {
+            let _ = self.stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+/// Stato condiviso tra le connessioni.
+struct Condiviso {
+    partita: PartitaMultigiocatore,
+    rng: StdRng,
+    /// I client connessi, per numero di connessione.
+    client: HashMap<usize, Client>,
+}
+
+impl Condiviso {
+    /// Accoda un messaggio per un client.
+    fn invia(&mut self, connessione: usize, messaggio: &str) {
+        if let Some(client) = self.client.get(&connessione) {
+            client.accoda(messaggio.to_string());
+        }
+    }
+
+    /// Accoda lo stato della partita per tutti i client connessi.
+    fn trasmetti_stato(&mut self) {
+        let stato = stato_testuale(&self.partita);
+        for client in self.client.values() {
+            client.accoda(stato.clone());
+        }
+    }
+
+    /// Indica se la partita è finita e non accetta più giocatori: una partita ancora senza
+    /// giocatori è in attesa del primo.
+    fn conclusa(&self) -> bool {
+        !self.partita.giocatori.is_empty() && self.partita.finita()
+    }
+}
+
+/// Avvia il thread che scrive sulla connessione i messaggi accodati per il client, così un client
+/// lento non blocca gli altri mentre lo stato condiviso è bloccato. La coda contiene al più
+/// [`CODA_MASSIMA`] messaggi.
+///
+/// Il thread termina quando la coda viene chiusa, dopo aver scritto i messaggi rimasti, o al
+/// primo errore di scrittura.
+fn avvia_scrittore(mut stream: TcpStream) -> SyncSender<String> {
+    let (coda, messaggi) = mpsc::sync_channel::<String>(CODA_MASSIMA);
+    thread::spawn(move || {
+        for messaggio in messaggi {
+            if stream.write_all(messaggio.as_bytes()).is_err() {
+                break;
+            }
+        }
+    });
+    coda
+}
+
+/// Server di gioco su TCP: possiede la partita e la fa giocare ai client connessi.
+///
+/// Il protocollo è a righe. Ogni comando (vedi [`Comando`]) riceve una risposta `OK ...`, `ERR ...`
+/// o `BYE`; `JOIN` risponde `OK <id>` con il numero del giocatore (da 1) e `STATE` riceve
+/// direttamente il blocco di [`stato_testuale`]. Dopo ogni `JOIN`, `MOVE` e `QUIT` andati a buon
+/// fine lo stato viene inviato a tutti i client connessi, anche a quelli che non sono entrati in
+/// partita e la osservano soltanto. I giocatori muovono a turno; a partita finita non si può più
+/// entrare.
+///
+/// Una riga più lunga di [`RIGA_MASSIMA`] byte riceve `ERR riga troppo lunga` e chiude la
+/// connessione, così come un client che lascia accumulare più di [`CODA_MASSIMA`] messaggi.
+pub struct Server {
+    listener: TcpListener,
+    condiviso: Arc<Mutex<Condiviso>>,
+}
+
+impl Server {
+    /// Crea un server in ascolto sull'indirizzo indicato, con una partita senza giocatori sul campo dato.
+    /// # Argomenti
+    ///
+    /// * `indirizzo`: Indirizzo di ascolto (ad esempio `127.0.0.1:7878`; porta 0 per una porta libera).
+    /// * `campo`: Il campo di gioco.
+    /// * `regola`: La regola di collisione tra giocatori.
+    /// * `seme`: Seme del generatore di numeri casuali usato per le mosse.
+    pub fn nuovo<A: ToSocketAddrs>(indirizzo: A, campo: CampoGioco, regola: RegolaCollisione, seme: u64) -> io::Result<Server> {
+        let mut rng = StdRng::seed_from_u64(seme);
+        let partita = PartitaMultigiocatore::nuova(campo, &[], regola, &mut rng)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        Ok(Server {
+            listener: TcpListener::bind(indirizzo)?,
+            condiviso: Arc::new(Mutex::new(Condiviso {
+                partita,
+                rng,
+                client: HashMap::new(),
+            })),
+        })
+    }
+
+    /// Restituisce l'indirizzo su cui il server è in ascolto.
+    pub fn indirizzo(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Accetta connessioni servendo ogni client in un thread dedicato. Un errore nell'accettare
+    /// una connessione viene segnalato sullo standard error e non ferma il server.
+    pub fn esegui(self) -> io::Result<()> {
+        for (connessione, stream) in self.listener.incoming().enumerate() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    eprintln!("Connessione rifiutata: {}", e);
+                    continue;
+                }
+            };
+            let condiviso = Arc::clone(&self.condiviso);
+            thread::spawn(move || {
+                // Un errore di rete riguarda solo questo client
+                let _ = servi_client(connessione, stream, &condiviso);
+            });
+        }
+        Ok(())
+    }
+
+    /// Avvia il server in un thread separato.
+    pub fn avvia(self) -> JoinHandle<io::Result<()>> {
+        thread::spawn(move || self.esegui())
+    }
+}
+
+/// Gestisce i comandi di un client fino a `QUIT` o alla chiusura della connessione.
+fn servi_client(connessione: usize, stream: TcpStream, condiviso: &Mutex<Condiviso>) -> io::Result<()> {
+    let mut lettore = BufReader::new(stream.try_clone()?);
+    let client = Client {
+        coda: avvia_scrittore(stream.try_clone()?),
+        stream,
+    };
+    condiviso.lock().unwrap().client.insert(connessione, client);
+    let mut giocatore: Option<usize> = None;
+
+    loop {
+        let mut riga = String::new();
+        // Una connessione chiusa o illeggibile fa uscire il giocatore come un `QUIT`
+        let letti = match (&mut lettore).take(RIGA_MASSIMA as u64).read_line(&mut riga) {
+            Ok(0) | Err(_) => break,
+            Ok(letti) => letti,
+        };
+        if letti == RIGA_MASSIMA && !riga.ends_with('\n') {
+            condiviso.lock().unwrap().invia(connessione, "ERR riga troppo lunga\n");
+            break;
+        }
+        if riga.trim().is_empty() {
+            continue;
+        }
+        let mut stato = condiviso.lock().unwrap();
+        let stato = &mut *stato;
+        match Comando::interpreta(&riga) {
+            Err(errore) => stato.invia(connessione, &format!("ERR {}\n", errore)),
+            Ok(Comando::Join(_)) if giocatore.is_some() => stato.invia(connessione, "ERR sei già in partita\n"),
+            Ok(Comando::Join(_)) if stato.conclusa() => stato.invia(connessione, "ERR la partita è finita\n"),
+            Ok(Comando::Join(nome)) => match stato.partita.aggiungi_giocatore(&nome, &mut stato.rng) {
+                Some(id) => {
+                    giocatore = Some(id);
+                    stato.invia(connessione, &format!("OK {}\n", id + 1));
+                    stato.trasmetti_stato();
+                }
+                None => stato.invia(connessione, "ERR nessuna cella libera\n"),
+            },
+            Ok(Comando::Move(direzione)) => match giocatore {
+                None => stato.invia(connessione, "ERR devi prima entrare in partita con JOIN\n"),
+                Some(id) if stato.partita.turno_corrente() != Some(id) => {
+                    stato.invia(connessione, "ERR non è il tuo turno\n")
+                }
+                Some(_) => {
+                    let esito = stato.partita.gioca_turno(direzione, &mut stato.rng).unwrap_or(Esito::InCorso);
+                    stato.invia(connessione, &format!("OK {}\n", nome_esito(esito)));
+                    stato.trasmetti_stato();
+                }
+            },
+            Ok(Comando::State) => {
+                let testo = stato_testuale(&stato.partita);
+                stato.invia(connessione, &testo);
+            }
+            Ok(Comando::Quit) => break,
+        }
+    }
+
+    // Uscita esplicita o connessione chiusa: il giocatore abbandona la partita
+    let mut stato = condiviso.lock().unwrap();
+    stato.invia(connessione, "BYE\n");
+    stato.client.remove(&connessione);
+    if let Some(id) = giocatore {
+        stato.partita.ritira(id);
+        stato.trasmetti_stato();
+    }
+    Ok(())
+}
@@ -0,0 +1,325 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use crate::game_space::game_space::{CampoGioco, Cella, Posizione};
+use crate::nemici::Nemico;
+use crate::player::{Direzione, Esito, Player, Statistiche};
+use crate::salvataggio::{carica_partita, salva_partita, ErroreCaricamento};
+
+/// Stato del giocatore e della parte di campo che una mossa può modificare.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Istantanea {
+    /// Contenuto della cella di destinazione della mossa.
+    pub cella: Cella,
+    /// Posizione del giocatore.
+    pub posizione: Posizione,
+    /// Forza del giocatore.
+    pub forza: i32,
+    /// Direzione del giocatore.
+    pub direzione: Direzione,
+    /// Mosse rimaste al giocatore.
+    pub mosse: usize,
+    /// Mosse di immunità rimaste al giocatore.
+    pub immunita: u32,
+    /// Statistiche del giocatore.
+    pub statistiche: Statistiche,
+    /// Nemici sul campo.
+    pub nemici: Vec<Nemico>,
+}
+
+impl Istantanea {
+    /// Registra lo stato attuale, con il contenuto della cella di destinazione indicata.
+    fn cattura(campo: &CampoGioco, player: &Player, destinazione: Posizione) -> Istantanea {
+        Istantanea {
+            cella: campo.cella(destinazione),
+            posizione: player.posizione,
+            forza: player.forza,
+            direzione: player.direzione,
+            mosse: player.mosse,
+            immunita: player.immunita,
+            statistiche: player.statistiche.clone(),
+            nemici: campo.nemici.clone(),
+        }
+    }
+
+    /// Riporta campo e giocatore allo stato registrato.
+    fn ripristina(&self, campo: &mut CampoGioco, player: &mut Player, destinazione: Posizione) {
+        campo.celle[destinazione.riga][destinazione.colonna] = self.cella;
+        campo.nemici = self.nemici.clone();
+        campo.player_position = self.posizione;
+        player.posizione = self.posizione;
+        player.forza = self.forza;
+        player.direzione = self.direzione;
+        player.mosse = self.mosse;
+        player.immunita = self.immunita;
+        player.statistiche = self.statistiche.clone();
+    }
+}
+
+/// Una mossa eseguita, reversibile: contiene lo stato prima e dopo la mossa.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mossa {
+    /// Direzione richiesta dal giocatore.
+    pub richiesta: Direzione,
+    /// Cella in cui il giocatore ha provato a entrare.
+    pub destinazione: Posizione,
+    /// Stato prima della mossa.
+    pub prima: Istantanea,
+    /// Stato dopo la mossa (compreso il turno dei nemici).
+    pub dopo: Istantanea,
+    /// Esito della mossa.
+    pub esito: Esito,
+}
+
+/// Storia delle mosse di una partita a giocatore singolo, con annullamento e ripetizione.
+///
+/// L'i-esima mossa usa un generatore di numeri casuali ottenuto dal seme e da `i`: rigiocare
+/// le stesse direzioni a partire dalla stessa partita produce sempre gli stessi risultati.
+#[derive(Debug, Clone)]
+pub struct Storia {
+    seme: u64,
+    fatte: Vec<Mossa>,
+    annullate: Vec<Mossa>,
+}
+
+impl Storia {
+    /// Crea una storia vuota.
+    /// # Argomenti
+    ///
+    /// * `seme`: Seme da cui derivano i generatori di numeri casuali delle mosse.
+    pub fn nuova(seme: u64) -> Storia {
+        Storia {
+            seme,
+            fatte: Vec::new(),
+            annullate: Vec::new(),
+        }
+    }
+
+    /// Restituisce il seme della storia.
+    pub fn seme(&self) -> u64 {
+        self.seme
+    }
+
+    /// Muove il giocatore come `Player::muovi_con_rng` e registra la mossa.
+    ///
+    /// Le mosse annullate non possono più essere ripetute. Se il giocatore non può più muoversi
+    /// non viene registrato nulla.
+    /// # Argomenti
+    ///
+    /// * `campo`: Il campo di gioco.
+    /// * `player`: Il giocatore.
+    /// * `direzione`: La direzione richiesta.
+    pub fn esegui(&mut self, campo: &mut CampoGioco, player: &mut Player, direzione: Direzione) -> Esito {
+        let mut rng = StdRng::seed_from_u64(self.seme.wrapping_add(self.fatte.len() as u64));
+        // La cella di destinazione è nota solo dopo `destinazione`, che cambia la direzione del giocatore
+        let mut prima = Istantanea::cattura(campo, player, player.posizione);
+        let immune = player.immunita > 0;
+        if let Some(esito) = player.prepara_mossa() {
+            return esito;
+        }
+        let destinazione = player.destinazione(campo, direzione, &mut rng);
+        prima.cella = campo.cella(destinazione);
+        let esito = match player.entra(campo, destinazione) {
+            Esito::InCorso => campo.turno_nemici(player, &mut rng),
+            esito => esito,
+        };
+        campo.player_position = player.posizione;
+        player.concludi_mossa(immune);
+        self.fatte.push(Mossa {
+            richiesta: direzione,
+            destinazione,
+            prima,
+            dopo: Istantanea::cattura(campo, player, destinazione),
+            esito,
+        });
+        self.annullate.clear();
+        esito
+    }
+
+    /// Annulla l'ultima mossa. Restituisce `false` se non ci sono mosse da annullare.
+    /// # Argomenti
+    ///
+    /// * `campo`: Il campo di gioco.
+    /// * `player`: Il giocatore.
+    pub fn annulla(&mut self, campo: &mut CampoGioco, player: &mut Player) -> bool {
+        let Some(mossa) = self.fatte.pop() else {
+            return false;
+        };
+        mossa.prima.ripristina(campo, player, mossa.destinazione);
+        self.annullate.push(mossa);
+        true
+    }
+
+    /// Ripete l'ultima mossa annullata. Restituisce l'esito della mossa, o `None` se non ci sono
+    /// mosse da ripetere.
+    /// # Argomenti
+    ///
+    /// * `campo`: Il campo di gioco.
+    /// * `player`: Il giocatore.
+    pub fn ripeti(&mut self, campo: &mut CampoGioco, player: &mut Player) -> Option<Esito> {
+        let mossa = self.annullate.pop()?;
+        mossa.dopo.ripristina(campo, player, mossa.destinazione);
+        let esito = mossa.esito;
+        self.fatte.push(mossa);
+        Some(esito)
+    }
+
+    /// Restituisce le mosse eseguite e non annullate, dalla prima.
+    pub fn mosse(&self) -> &[Mossa] {
+        &self.fatte
+    }
+
+    /// Restituisce il numero di mosse che possono essere ripetute.
+    pub fn da_ripetere(&self) -> usize {
+        self.annullate.len()
+    }
+}
+
+/// Registrazione di una partita: la partita iniziale, il seme e le direzioni richieste.
+///
+/// Nel testo la prima riga è `replay <seme>`, la seconda `percorso <direzione> ...`
+/// e le successive contengono la partita iniziale nel formato di `salva_partita`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replay {
+    /// Seme della storia registrata.
+    pub seme: u64,
+    /// Partita iniziale, nel formato di `salva_partita`.
+    pub iniziale: String,
+    /// Direzioni richieste, nell'ordine.
+    pub direzioni: Vec<Direzione>,
+}
+
+impl Replay {
+    /// Crea la registrazione di una partita, ancora senza mosse, a partire dal suo stato iniziale.
+    /// # Argomenti
+    ///
+    /// * `campo`: Il campo all'inizio della partita.
+    /// * `player`: Il giocatore all'inizio della partita.
+    /// * `seme`: Il seme della storia delle mosse.
+    pub fn nuovo(campo: &CampoGioco, player: &Player, seme: u64) -> Replay {
+        Replay {
+            seme,
+            iniziale: salva_partita(campo, player),
+            direzioni: Vec::new(),
+        }
+    }
+
+    /// Aggiorna le direzioni registrate con le mosse della storia, escluse quelle annullate.
+    /// # Argomenti
+    ///
+    /// * `storia`: La storia delle mosse della partita.
+    pub fn aggiorna(&mut self, storia: &Storia) {
+        self.direzioni = storia.mosse().iter().map(|m| m.richiesta).collect();
+    }
+
+    /// Restituisce la registrazione in formato testuale.
+    pub fn in_testo(&self) -> String {
+        let percorso: Vec<String> = self.direzioni.iter().map(Direzione::to_string).collect();
+        format!("replay {}\npercorso {}\n{}", self.seme, percorso.join(" "), self.iniziale)
+    }
+
+    /// Legge una registrazione in formato testuale, verificando che la partita iniziale sia valida.
+    /// # Argomenti
+    ///
+    /// * `testo`: Il testo della registrazione.
+    pub fn da_testo(testo: &str) -> Result<Replay, ErroreCaricamento> {
+        let mut righe = testo.lines();
+        let seme = righe
+            .next()
+            .and_then(|r| r.trim().strip_prefix("replay "))
+            .ok_or(ErroreCaricamento::CampoMancante("replay"))?;
+        let seme = seme.trim().parse().map_err(|_| ErroreCaricamento::ValoreNonValido {
+            campo: "replay".to_string(),
+            valore: seme.to_string(),
+        })?;
+        let percorso = righe
+            .next()
+            .and_then(|r| r.trim().strip_prefix("percorso"))
+            .ok_or(ErroreCaricamento::CampoMancante("percorso"))?;
+        let direzioni = percorso
+            .split_whitespace()
+            .map(|d| {
+                d.parse().map_err(|_| ErroreCaricamento::ValoreNonValido {
+                    campo: "percorso".to_string(),
+                    valore: d.to_string(),
+                })
+            })
+            .collect::<Result<Vec<Direzione>, ErroreCaricamento>>()?;
+        let iniziale: String = righe.map(|r| format!("{}\n", r)).collect();
+        carica_partita(&iniziale)?;
+        Ok(Replay { seme, iniziale, direzioni })
+    }
+
+    /// Salva la registrazione su file.
+    /// # Argomenti
+    ///
+    /// * `percorso`: Il file da scrivere.
+    pub fn salva_su_file<P: AsRef<Path>>(&self, percorso: P) -> io::Result<()> {
+        fs::write(percorso, self.in_testo())
+    }
+
+    /// Carica una registrazione da file.
+    /// # Argomenti
+    ///
+    /// * `percorso`: Il file da leggere.
+    pub fn carica_da_file<P: AsRef<Path>>(percorso: P) -> Result<Replay, ErroreCaricamento> {
+        Replay::da_testo(&fs::read_to_string(percorso)?)
+    }
+
+    /// Crea un visore posizionato all'inizio della partita registrata.
+    pub fn visore(&self) -> Result<Visore, ErroreCaricamento> {
+        let (campo, player) = carica_partita(&self.iniziale)?;
+        Ok(Visore {
+            campo,
+            player,
+            storia: Storia::nuova(self.seme),
+            direzioni: self.direzioni.clone(),
+        })
+    }
+}
+
+/// Visore passo-passo di una partita registrata.
+pub struct Visore {
+    /// Il campo al passo corrente.
+    pub campo: CampoGioco,
+    /// Il giocatore al passo corrente.
+    pub player: Player,
+    storia: Storia,
+    direzioni: Vec<Direzione>,
+}
+
+impl Visore {
+    /// Restituisce il numero di mosse già mostrate.
+    pub fn passo(&self) -> usize {
+        self.storia.mosse().len()
+    }
+
+    /// Restituisce il numero totale di mosse registrate.
+    pub fn totale(&self) -> usize {
+        self.direzioni.len()
+    }
+
+    /// Restituisce l'ultima mossa mostrata, se presente.
+    pub fn ultima_mossa(&self) -> Option<&Mossa> {
+        self.storia.mosse().last()
+    }
+
+    /// Avanza di una mossa. Restituisce l'esito della mossa, o `None` se la registrazione è finita.
+    pub fn avanti(&mut self) -> Option<Esito> {
+        if let Some(esito) = self.storia.ripeti(&mut self.campo, &mut self.player) {
+            return Some(esito);
+        }
+        let direzione = *self.direzioni.get(self.passo())?;
+        let prima = self.passo();
+        let esito = self.storia.esegui(&mut self.campo, &mut self.player, direzione);
+        // Una mossa non registrata indica che la partita era già finita
+        (self.passo() > prima).then_some(esito)
+    }
+
+    /// Torna indietro di una mossa. Restituisce `false` se si è già all'inizio.
+    pub fn indietro(&mut self) -> bool {
+        self.storia.annulla(&mut self.campo, &mut self.player)
+    }
+}
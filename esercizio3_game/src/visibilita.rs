@@ -0,0 +1,213 @@
+use std::fmt;
+use crate::game_space::game_space::{CampoGioco, Cella, Posizione};
+
+/// Quanto del campo può vedere il giocatore.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ModalitaVisibilita {
+    /// Tutto il campo è visibile.
+    Completa,
+    /// Sono visibili le celle entro il raggio indicato.
+    Raggio(usize),
+    /// Sono visibili le celle entro il raggio indicato non nascoste da un muro.
+    LineaDiVista(usize),
+}
+
+/// Ciò che il giocatore sa di una cella.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StatoCella {
+    /// La cella non è mai stata vista.
+    Sconosciuta,
+    /// La cella è stata vista in passato: contiene l'ultimo contenuto osservato.
+    Vista(Cella),
+    /// La cella è visibile in questo momento.
+    Visibile(Cella),
+}
+
+impl StatoCella {
+    /// Restituisce il contenuto conosciuto della cella, se ce n'è uno.
+    pub fn cella(&self) -> Option<Cella> {
+        match self {
+            StatoCella::Sconosciuta => None,
+            StatoCella::Vista(cella) | StatoCella::Visibile(cella) => Some(*cella),
+        }
+    }
+}
+
+/// Nebbia di guerra di un giocatore: memoria delle celle esplorate e regole di visibilità.
+#[derive(Debug, Clone)]
+pub struct Nebbia {
+    /// Modalità di visibilità.
+    pub modalita: ModalitaVisibilita,
+    /// Ultimo contenuto osservato di ogni cella (`None` se mai vista).
+    esplorate: Vec<Vec<Option<Cella>>>,
+}
+
+impl Nebbia {
+    /// Crea una nebbia per un campo della dimensione indicata, senza celle esplorate.
+    /// # Argomenti
+    ///
+    /// * `dimensione`: Dimensione del campo di gioco.
+    /// * `modalita`: Modalità di visibilità.
+    pub fn nuova(dimensione: usize, modalita: ModalitaVisibilita) -> Nebbia {
+        Nebbia {
+            modalita,
+            esplorate: vec![vec![None; dimensione]; dimensione],
+        }
+    }
+
+    /// Indica se la cella `p` è visibile da un giocatore in `osservatore`.
+    /// # Argomenti
+    ///
+    /// * `campo`: Il campo di gioco.
+    /// * `osservatore`: Posizione del giocatore.
+    /// * `p`: Posizione della cella.
+    pub fn visibile(&self, campo: &CampoGioco, osservatore: Posizione, p: Posizione) -> bool {
+        let entro = |raggio: usize| {
+            let (dr, dc) = (osservatore.riga.abs_diff(p.riga), osservatore.colonna.abs_diff(p.colonna));
+            dr * dr + dc * dc <= raggio * raggio
+        };
+        match self.modalita {
+            ModalitaVisibilita::Completa => true,
+            ModalitaVisibilita::Raggio(raggio) => entro(raggio),
+            ModalitaVisibilita::LineaDiVista(raggio) => entro(raggio) && linea_libera(campo, osservatore, p),
+        }
+    }
+
+    /// Memorizza il contenuto delle celle visibili dalla posizione indicata.
+    /// # Argomenti
+    ///
+    /// * `campo`: Il campo di gioco.
+    /// * `osservatore`: Posizione del giocatore.
+    pub fn aggiorna(&mut self, campo: &CampoGioco, osservatore: Posizione) {
+        for p in CampoGioco::posizioni(campo.dimensione) {
+            if self.visibile(campo, osservatore, p) {
+                self.esplorate[p.riga][p.colonna] = Some(campo.cella(p));
+            }
+        }
+    }
+
+    /// Restituisce lo stato di conoscenza di ogni cella per un giocatore in `osservatore`.
+    /// # Argomenti
+    ///
+    /// * `campo`: Il campo di gioco.
+    /// * `osservatore`: Posizione del giocatore.
+    pub fn stato(&self, campo: &CampoGioco, osservatore: Posizione) -> Vec<Vec<StatoCella>> {
+        (0..campo.dimensione)
+            .map(|riga| {
+                (0..campo.dimensione)
+                    .map(|colonna| {
+                        let p = Posizione { riga, colonna };
+                        if self.visibile(campo, osservatore, p) {
+                            StatoCella::Visibile(campo.cella(p))
+                        } else {
+                            self.esplorate[riga][colonna].map_or(StatoCella::Sconosciuta, StatoCella::Vista)
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Restituisce la vista del campo per un giocatore in `osservatore`, da visualizzare con `Display`.
+    /// # Argomenti
+    ///
+    /// * `campo`: Il campo di gioco.
+    /// * `osservatore`: Posizione del giocatore.
+    pub fn vista<'a>(&self, campo: &'a CampoGioco, osservatore: Posizione) -> VistaCampo<'a> {
+        VistaCampo {
+            campo,
+            osservatore,
+            stato: self.stato(campo, osservatore),
+            giocatori: Vec::new(),
+        }
+    }
+}
+
+/// Indica se il segmento tra `da` e `a` non attraversa muri (esclusi gli estremi).
+/// # Argomenti
+///
+/// * `campo`: Il campo di gioco.
+/// * `da`: Posizione di partenza.
+/// * `a`: Posizione di arrivo.
+pub fn linea_libera(campo: &CampoGioco, da: Posizione, a: Posizione) -> bool {
+    // Algoritmo di Bresenham
+    let (mut r, mut c) = (da.riga as isize, da.colonna as isize);
+    let (r1, c1) = (a.riga as isize, a.colonna as isize);
+    let (dr, dc) = ((r1 - r).abs(), -(c1 - c).abs());
+    let (sr, sc) = (if r < r1 { 1 } else { -1 }, if c < c1 { 1 } else { -1 });
+    let mut errore = dr + dc;
+    while (r, c) != (r1, c1) {
+        let doppio = 2 * errore;
+        if doppio >= dc {
+            errore += dc;
+            r += sr;
+        }
+        if doppio <= dr {
+            errore += dr;
+            c += sc;
+        }
+        if (r, c) != (r1, c1) && campo.celle[r as usize][c as usize] == Cella::Muro {
+            return false;
+        }
+    }
+    true
+}
+
+/// Vista del campo filtrata dalla nebbia di guerra.
+///
+/// Con `{}` le celle mai viste sono `?`, quelle viste in passato mostrano l'ultimo contenuto
+/// osservato (con `.` per le celle vuote) e quelle visibili usano i simboli di `CampoGioco`.
+/// Con `{:#}` le celle viste in passato sono mostrate attenuate tramite i colori ANSI.
+/// Gli altri giocatori aggiunti con `con_giocatori` compaiono con il loro numero solo nelle
+/// celle visibili.
+pub struct VistaCampo<'a> {
+    campo: &'a CampoGioco,
+    osservatore: Posizione,
+    stato: Vec<Vec<StatoCella>>,
+    giocatori: Vec<(Posizione, usize)>,
+}
+
+impl VistaCampo<'_> {
+    /// Restituisce lo stato di conoscenza di ogni cella.
+    pub fn stato(&self) -> &Vec<Vec<StatoCella>> {
+        &self.stato
+    }
+
+    /// Aggiunge alla vista gli altri giocatori in campo, ciascuno con il numero da mostrare.
+    /// # Argomenti
+    ///
+    /// * `giocatori`: Posizione e numero di ciascun giocatore.
+    pub fn con_giocatori(mut self, giocatori: Vec<(Posizione, usize)>) -> Self {
+        self.giocatori = giocatori;
+        self
+    }
+}
+
+impl fmt::Display for VistaCampo<'_> {
+    /// Implementazione della formattazione per visualizzare il campo attraverso la nebbia.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, riga) in self.stato.iter().enumerate() {
+            for (j, stato) in riga.iter().enumerate() {
+                let p = Posizione { riga: i, colonna: j };
+                match stato {
+                    _ if p == self.osservatore => write!(f, "P ")?,
+                    StatoCella::Sconosciuta => write!(f, "? ")?,
+                    StatoCella::Vista(cella) if f.alternate() => {
+                        write!(f, "\x1b[2m{}\x1b[0m ", cella.effetto().simbolo())?
+                    }
+                    StatoCella::Vista(Cella::Vuota) => write!(f, ". ")?,
+                    StatoCella::Vista(cella) => write!(f, "{} ", cella.effetto().simbolo())?,
+                    StatoCella::Visibile(cella) => match self.giocatori.iter().find(|(q, _)| *q == p) {
+                        Some((_, numero)) => write!(f, "{} ", numero)?,
+                        None => match self.campo.nemico_in(p) {
+                            Some(nemico) => write!(f, "{} ", nemico.simbolo())?,
+                            None => write!(f, "{} ", cella.effetto().simbolo())?,
+                        },
+                    },
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
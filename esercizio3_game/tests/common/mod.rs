@@ -0,0 +1,23 @@
+use rand::RngCore;
+
+/// Generatore che fa sempre riuscire il movimento nella direzione voluta.
+pub struct SempreRiuscito;
+
+impl RngCore for SempreRiuscito {
+    fn next_u32(&mut self) -> u32 {
+        u32::MAX
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        u64::MAX
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        dest.fill(u8::MAX);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
@@ -0,0 +1,64 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use esercizio3_game::analisi::Analisi;
+use esercizio3_game::game_space::game_space::{CampoGioco, Posizione};
+use esercizio3_game::multiplayer::{Agente, AgenteCasuale, Osservazione, PartitaMultigiocatore, RegolaCollisione};
+use esercizio3_game::player::Direzione;
+use esercizio3_game::salvataggio::carica_campo;
+
+/// Agente che prova sempre ad andare a destra.
+struct SempreDestra;
+
+impl Agente for SempreDestra {
+    fn scegli(&mut self, _: &Osservazione) -> Direzione {
+        Direzione::Destra
+    }
+}
+
+#[test]
+fn test_visite_e_statistiche() {
+    let campo = carica_campo("P O O\nO X O\nO $ -").unwrap();
+    let mut analisi = Analisi::nuova(campo.dimensione);
+    for seme in 0..5 {
+        let mut rng = StdRng::seed_from_u64(seme);
+        let mut partita = PartitaMultigiocatore::nuova(campo.clone(), &["a"], RegolaCollisione::Blocca, &mut rng).unwrap();
+        partita.giocatori[0].player.forza = 100;
+        let mut agenti: Vec<Box<dyn Agente>> = vec![Box::new(AgenteCasuale::nuovo(seme))];
+        analisi.registra(&mut partita, &mut agenti, &mut rng, 20);
+    }
+
+    assert_eq!(analisi.partite(), 5);
+    assert!(analisi.visite(Posizione { riga: 0, colonna: 0 }) >= 5);
+    assert_eq!(analisi.visite(Posizione { riga: 1, colonna: 1 }), 0);
+    assert!(analisi.sopravvivenza_media() > 0.0 && analisi.sopravvivenza_media() <= 9.0);
+
+    let csv = analisi.in_csv();
+    assert_eq!(csv.lines().next(), Some("riga,colonna,visite,morti"));
+    assert_eq!(csv.lines().count(), 10);
+    assert!(csv.contains("\n1,1,0,0\n"));
+
+    let mappa = format!("{}", analisi.mappa_visite(&campo));
+    assert_eq!(mappa.lines().nth(1).map(|r| &r[2..4]), Some("X "));
+    assert!(mappa.contains('9'));
+    assert!(format!("{:#}", analisi.mappa_visite(&campo)).contains("\x1b[41m"));
+}
+
+#[test]
+fn test_luogo_delle_morti() {
+    let campo = carica_campo("P -\n- -").unwrap();
+    let mut analisi = Analisi::nuova(campo.dimensione);
+    let mut rng = StdRng::seed_from_u64(3);
+    let mut partita = PartitaMultigiocatore::nuova(campo.clone(), &["a"], RegolaCollisione::Blocca, &mut rng).unwrap();
+    partita.giocatori[0].player.forza = 1;
+    let mut agenti: Vec<Box<dyn Agente>> = vec![Box::new(SempreDestra)];
+    analisi.registra(&mut partita, &mut agenti, &mut rng, 100);
+
+    // Il veleno elimina il giocatore nella cella in cui entra, anche se resta in quella di partenza
+    let morti: u32 = CampoGioco::posizioni(2).map(|p| analisi.morti(p)).sum();
+    assert_eq!(morti, 1);
+    assert_eq!(partita.giocatori[0].player.posizione, Posizione { riga: 0, colonna: 0 });
+    assert_eq!(analisi.morti(Posizione { riga: 0, colonna: 1 }), 1);
+    assert_eq!(format!("{}", analisi.mappa_morti(&campo)), ". 9 \n. . \n");
+    assert_eq!(analisi.rapporto_cibo_veleno(), Some(0.0));
+    assert!(analisi.to_string().contains("Partite: 1"));
+}
@@ -0,0 +1,134 @@
+mod common;
+
+use common::SempreRiuscito;
+use esercizio3_game::effetti::PowerUp;
+use esercizio3_game::game_space::game_space::{Cella, Posizione};
+use esercizio3_game::player::{Direzione, Esito, Player, Statistiche};
+use esercizio3_game::salvataggio::{carica_campo, salva_campo, ErroreCaricamento};
+
+/// Crea un giocatore nella posizione del giocatore del campo, con forza e mosse note.
+fn player_in(posizione: Posizione) -> Player {
+    Player {
+        mosse: 20,
+        posizione,
+        direzione: Direzione::Destra,
+        forza: 50,
+        immunita: 0,
+        statistiche: Statistiche::default(),
+    }
+}
+
+#[test]
+fn test_muro_blocca() {
+    let mut campo = carica_campo("P X O\nO O O\nO O O").unwrap();
+    let mut player = player_in(campo.player_position);
+
+    assert_eq!(player.muovi_con_rng(&mut campo, Direzione::Destra, &mut SempreRiuscito), Esito::InCorso);
+    assert_eq!(player.posizione, Posizione { riga: 0, colonna: 0 });
+    assert_eq!(player.direzione, Direzione::Sinistra);
+    assert_eq!(campo.celle[0][1], Cella::Muro);
+    assert_eq!(player.mosse, 19);
+}
+
+#[test]
+fn test_teletrasporto() {
+    let mut campo = carica_campo("P T2,2 O\nO O O\nO O O").unwrap();
+    let mut player = player_in(campo.player_position);
+
+    player.muovi_con_rng(&mut campo, Direzione::Destra, &mut SempreRiuscito);
+    assert_eq!(player.posizione, Posizione { riga: 2, colonna: 2 });
+    assert_eq!(campo.player_position, Posizione { riga: 2, colonna: 2 });
+    // Il teletrasporto resta sul campo
+    assert_eq!(campo.celle[0][1], Cella::Teletrasporto(Posizione { riga: 2, colonna: 2 }));
+}
+
+#[test]
+fn test_trappola_costa_mosse() {
+    let mut campo = carica_campo("P ^5 O\nO O O\nO O O").unwrap();
+    let mut player = player_in(campo.player_position);
+
+    player.muovi_con_rng(&mut campo, Direzione::Destra, &mut SempreRiuscito);
+    assert_eq!(player.mosse, 14);
+    assert_eq!(campo.celle[0][1], Cella::Trappola(5));
+}
+
+#[test]
+fn test_power_up() {
+    let mut campo = carica_campo("P *3 - +4\nO O O O\nO O O O\nO O O O").unwrap();
+    let mut player = player_in(campo.player_position);
+
+    player.muovi_con_rng(&mut campo, Direzione::Destra, &mut SempreRiuscito);
+    assert_eq!(player.immunita, 3);
+    assert_eq!(campo.celle[0][1], Cella::Vuota);
+
+    // Immune: il veleno non toglie forza
+    player.muovi_con_rng(&mut campo, Direzione::Destra, &mut SempreRiuscito);
+    assert_eq!(player.forza, 50);
+    assert_eq!(player.immunita, 2);
+
+    player.muovi_con_rng(&mut campo, Direzione::Destra, &mut SempreRiuscito);
+    assert_eq!(player.mosse, 17 + 4);
+}
+
+#[test]
+fn test_veleno_e_uscita() {
+    let mut campo = carica_campo("P -60 O\nE O O\nO O O").unwrap();
+    let mut player = player_in(campo.player_position);
+
+    assert_eq!(player.muovi_con_rng(&mut campo, Direzione::Giu, &mut SempreRiuscito), Esito::Vinto);
+    assert_eq!(player.posizione, Posizione { riga: 1, colonna: 0 });
+
+    let mut campo = carica_campo("P -60 O\nE O O\nO O O").unwrap();
+    let mut player = player_in(campo.player_position);
+    assert_eq!(player.muovi_con_rng(&mut campo, Direzione::Destra, &mut SempreRiuscito), Esito::Perso);
+    assert_eq!(player.forza, -10);
+}
+
+#[test]
+fn test_simboli_nuove_celle() {
+    let campo = carica_campo("P X T2,1\n^ * +7\nE O -").unwrap();
+    assert_eq!(campo.celle[1][1], Cella::PowerUp(PowerUp::Immunita(5)));
+    assert_eq!(campo.celle[1][2], Cella::PowerUp(PowerUp::MosseExtra(7)));
+
+    assert_eq!(format!("{}", campo), "P X T \n^ * + \nE O - \n");
+    assert_eq!(salva_campo(&campo), "posizione 0,0\nO X T2,1\n^ * +7\nE O -\n");
+    assert!(carica_campo("P T9,9\nO O").is_err());
+}
+
+#[test]
+fn test_teletrasporto_verso_cella_occupata() {
+    // Muri, oggetti e altri teletrasporti non sono destinazioni valide
+    for mappa in ["P T0,2 X\nO O O\nO O O", "P T1,1 O\nO $ O\nO O O", "P T0,1 O\nO O O\nO O O"] {
+        assert!(matches!(
+            carica_campo(mappa),
+            Err(ErroreCaricamento::TeletrasportoNonValido { riga: 0, colonna: 1, .. })
+        ));
+    }
+    assert!(carica_campo("P T0,0 O\nO O O\nO O O").is_ok());
+}
+
+#[test]
+fn test_immunita_protegge_tutte_le_mosse() {
+    let mut campo = carica_campo("P *3 - - - -\nO O O O O O\nO O O O O O\nO O O O O O\nO O O O O O\nO O O O O O").unwrap();
+    let mut player = player_in(campo.player_position);
+
+    player.muovi_con_rng(&mut campo, Direzione::Destra, &mut SempreRiuscito);
+    assert_eq!(player.immunita, 3);
+    // Le tre mosse dopo aver raccolto il potenziamento sono protette
+    for rimaste in [2, 1, 0] {
+        player.muovi_con_rng(&mut campo, Direzione::Destra, &mut SempreRiuscito);
+        assert_eq!(player.forza, 50);
+        assert_eq!(player.immunita, rimaste);
+    }
+    player.muovi_con_rng(&mut campo, Direzione::Destra, &mut SempreRiuscito);
+    assert_eq!(player.forza, 40);
+}
+
+#[test]
+fn test_quantita_enormi_non_traboccano() {
+    let mut campo = carica_campo("P $4294967295 O\nO O O\nO O O").unwrap();
+    let mut player = player_in(campo.player_position);
+
+    player.muovi_con_rng(&mut campo, Direzione::Destra, &mut SempreRiuscito);
+    assert_eq!(player.forza, i32::MAX);
+}
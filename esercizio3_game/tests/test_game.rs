@@ -6,7 +6,7 @@ use esercizio3_game::player::Player;
 fn test_nuovo_campo_gioco() {
     let dimensione = 5;
     let m = 3;
-    let campo = CampoGioco::nuovo(dimensione, m);
+    let campo = CampoGioco::nuovo(dimensione, m).unwrap();
 
     // Verifica che la dimensione del campo di gioco sia corretta
     assert_eq!(campo.dimensione, dimensione);
@@ -32,10 +32,13 @@ fn test_nuovo_campo_gioco() {
 fn test_display_campo_gioco() {
     let dimensione = 5;
     let m = 3;
-    let mut campo = CampoGioco::nuovo(dimensione, m);
+    let mut campo = CampoGioco::nuovo(dimensione, m).unwrap();
 
     // Modifica la posizione del giocatore per il tests
     campo.player_position = Posizione { riga: 1, colonna: 1 };
+    // Ogni oggetto è cibo o veleno a caso: garantisce la presenza di entrambi
+    campo.celle[0][0] = Cella::Cibo(10);
+    campo.celle[0][1] = Cella::Veleno(10);
 
     // Genera la rappresentazione del campo di gioco
     let display = format!("{}", campo);
@@ -50,7 +53,7 @@ fn test_display_campo_gioco() {
 #[test]
 fn test_nuovo_player() {
     // Crea un campo di gioco
-    let campo = CampoGioco::nuovo(5, 3);
+    let campo = CampoGioco::nuovo(5, 3).unwrap();
 
     // Crea un nuovo giocatore
     let player = Player::nuovo(&campo);
@@ -0,0 +1,110 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use esercizio3_game::game_space::game_space::{CampoGioco, Cella, ErroreGenerazione, Posizione};
+use esercizio3_game::generazione::{
+    distanze, genera_risolvibile, risolvibile, AlgoritmoLabirinto, Ammassi, Densita, Generatore, Labirinto, Uniforme,
+};
+use esercizio3_game::salvataggio::carica_campo;
+
+/// Conta le celle del campo che soddisfano il predicato.
+fn conta(campo: &CampoGioco, predicato: impl Fn(Cella) -> bool) -> usize {
+    CampoGioco::posizioni(campo.dimensione).filter(|p| predicato(campo.cella(*p))).count()
+}
+
+#[test]
+fn test_nuovo_posizioni_distinte() {
+    let mut rng = StdRng::seed_from_u64(5);
+    for m in 0..25 {
+        let campo = CampoGioco::nuovo_con_rng(5, m, &mut rng).unwrap();
+        let cibo = conta(&campo, |c| matches!(c, Cella::Cibo(_)));
+        let veleno = conta(&campo, |c| matches!(c, Cella::Veleno(_)));
+        assert_eq!(cibo + veleno, m);
+        assert_eq!(campo.cella(campo.player_position), Cella::Vuota);
+    }
+    // Non si possono piazzare più oggetti delle celle libere
+    assert_eq!(conta(&CampoGioco::nuovo_con_rng(3, 8, &mut rng).unwrap(), |c| c != Cella::Vuota), 8);
+    assert_eq!(
+        CampoGioco::nuovo_con_rng(3, 100, &mut rng).unwrap_err(),
+        ErroreGenerazione::TroppiOggetti { richiesti: 100, disponibili: 8 }
+    );
+}
+
+#[test]
+fn test_uniforme() {
+    let campo = Uniforme { oggetti: 10 }.genera(6, &mut StdRng::seed_from_u64(1)).unwrap();
+    assert_eq!(conta(&campo, |c| c != Cella::Vuota), 10);
+}
+
+#[test]
+fn test_ammassi() {
+    let generatore = Ammassi { ammassi: 2, oggetti_per_ammasso: 3, raggio: 1 };
+    let campo = generatore.genera(10, &mut StdRng::seed_from_u64(2)).unwrap();
+    let oggetti = conta(&campo, |c| c != Cella::Vuota);
+    assert!(oggetti > 0 && oggetti <= 6);
+}
+
+#[test]
+fn test_labirinto() {
+    for algoritmo in [AlgoritmoLabirinto::Backtracker, AlgoritmoLabirinto::Prim] {
+        let campo = Labirinto { algoritmo, oggetti: 5 }.genera(9, &mut StdRng::seed_from_u64(3)).unwrap();
+        let distanze = distanze(&campo);
+
+        assert_eq!(campo.player_position, Posizione { riga: 0, colonna: 0 });
+        assert!(conta(&campo, |c| c == Cella::Muro) > 0);
+        assert_eq!(conta(&campo, |c| c == Cella::Uscita), 1);
+        // Ogni stanza (coordinate pari) è raggiungibile
+        for p in CampoGioco::posizioni(9).filter(|p| p.riga % 2 == 0 && p.colonna % 2 == 0) {
+            assert!(distanze[p.riga][p.colonna].is_some(), "{:?} non raggiungibile con {:?}", p, algoritmo);
+        }
+        assert!(risolvibile(&campo));
+    }
+    let troppi = Labirinto { algoritmo: AlgoritmoLabirinto::Prim, oggetti: 100 }.genera(5, &mut StdRng::seed_from_u64(3));
+    assert!(matches!(troppi, Err(ErroreGenerazione::TroppiOggetti { richiesti: 100, .. })));
+}
+
+#[test]
+fn test_densita() {
+    let campo = Densita { scala: 3.0, densita_massima: 1.0 }.genera(12, &mut StdRng::seed_from_u64(4)).unwrap();
+    assert!(conta(&campo, |c| c != Cella::Vuota) > 0);
+    assert_eq!(campo.cella(campo.player_position), Cella::Vuota);
+}
+
+#[test]
+fn test_risolvibile() {
+    assert!(risolvibile(&carica_campo("P O $\nO X O\n$ O O").unwrap()));
+    assert!(!risolvibile(&carica_campo("P X $\nX O O\nO O O").unwrap()));
+    // Il cibo oltre il muro è raggiungibile tramite il teletrasporto
+    assert!(risolvibile(&carica_campo("P T2,2 X\nX X $\nO O O").unwrap()));
+}
+
+#[test]
+fn test_genera_risolvibile() {
+    let generatore = Densita { scala: 2.0, densita_massima: 0.8 };
+    let campo = genera_risolvibile(&generatore, 8, &mut StdRng::seed_from_u64(9), 10).unwrap();
+    assert!(risolvibile(&campo));
+    assert_eq!(
+        genera_risolvibile(&generatore, 8, &mut StdRng::seed_from_u64(9), 0).unwrap_err(),
+        ErroreGenerazione::NonRisolvibile { tentativi: 0 }
+    );
+}
+
+#[test]
+fn test_parametri_non_validi() {
+    let mut rng = StdRng::seed_from_u64(6);
+    let generatori: Vec<Box<dyn Generatore>> = vec![
+        Box::new(Uniforme { oggetti: 0 }),
+        Box::new(Ammassi { ammassi: 1, oggetti_per_ammasso: 1, raggio: 1 }),
+        Box::new(Labirinto { algoritmo: AlgoritmoLabirinto::Backtracker, oggetti: 0 }),
+        Box::new(Labirinto { algoritmo: AlgoritmoLabirinto::Prim, oggetti: 0 }),
+        Box::new(Densita { scala: 2.0, densita_massima: 0.5 }),
+    ];
+    for generatore in &generatori {
+        assert_eq!(generatore.genera(0, &mut rng).unwrap_err(), ErroreGenerazione::DimensioneNulla);
+    }
+    assert_eq!(CampoGioco::nuovo_con_rng(0, 0, &mut rng).unwrap_err(), ErroreGenerazione::DimensioneNulla);
+
+    for densita_massima in [f64::NAN, f64::INFINITY, -0.5] {
+        let errore = Densita { scala: 2.0, densita_massima }.genera(5, &mut rng).unwrap_err();
+        assert!(matches!(errore, ErroreGenerazione::DensitaNonValida { .. }), "{:?}", errore);
+    }
+}
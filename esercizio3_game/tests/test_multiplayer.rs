@@ -0,0 +1,181 @@
+mod common;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use common::SempreRiuscito;
+use esercizio3_game::game_space::game_space::{Cella, ErroreGenerazione, Posizione};
+use esercizio3_game::multiplayer::{Agente, AgenteCasuale, AgenteGoloso, PartitaMultigiocatore, RegolaCollisione};
+use esercizio3_game::player::{Direzione, Esito};
+use esercizio3_game::salvataggio::carica_campo;
+
+/// Crea una partita a due giocatori affiancati in (0, 0) e (0, 1), con forza nota.
+fn partita_affiancati(mappa: &str, regola: RegolaCollisione, forze: (i32, i32)) -> PartitaMultigiocatore {
+    let campo = carica_campo(mappa).unwrap();
+    let mut partita = PartitaMultigiocatore::nuova(campo, &["Anna", "Bruno"], regola, &mut StdRng::seed_from_u64(1)).unwrap();
+    partita.giocatori[0].player.posizione = Posizione { riga: 0, colonna: 0 };
+    partita.giocatori[1].player.posizione = Posizione { riga: 0, colonna: 1 };
+    partita.giocatori[0].player.forza = forze.0;
+    partita.giocatori[1].player.forza = forze.1;
+    partita
+}
+
+#[test]
+fn test_posizioni_iniziali_distinte() {
+    let campo = carica_campo("P O O\nO O O\nO O O").unwrap();
+    let partita = PartitaMultigiocatore::nuova(campo, &["a", "b", "c", "d"], RegolaCollisione::Blocca, &mut StdRng::seed_from_u64(7)).unwrap();
+
+    assert_eq!(partita.giocatori[0].player.posizione, Posizione { riga: 0, colonna: 0 });
+    for (i, a) in partita.giocatori.iter().enumerate() {
+        for b in partita.giocatori.iter().skip(i + 1) {
+            assert_ne!(a.player.posizione, b.player.posizione);
+        }
+    }
+}
+
+#[test]
+fn test_collisione_blocca() {
+    let mut partita = partita_affiancati("P O O\nO O O\nO O O", RegolaCollisione::Blocca, (50, 20));
+
+    assert_eq!(partita.turno_corrente(), Some(0));
+    partita.gioca_turno(Direzione::Destra, &mut SempreRiuscito);
+    assert_eq!(partita.giocatori[0].player.posizione, Posizione { riga: 0, colonna: 0 });
+    assert_eq!(partita.turno_corrente(), Some(1));
+}
+
+#[test]
+fn test_collisione_combatti() {
+    let mut partita = partita_affiancati("P O O\nO O O\nO O O", RegolaCollisione::Combatti, (50, 20));
+
+    partita.gioca_turno(Direzione::Destra, &mut SempreRiuscito);
+    assert_eq!(partita.giocatori[0].player.posizione, Posizione { riga: 0, colonna: 1 });
+    assert_eq!(partita.giocatori[1].esito, Esito::Perso);
+    assert_eq!(partita.giocatori[0].player.statistiche.scontri_vinti, 1);
+    assert_eq!(partita.giocatori[1].player.statistiche.scontri_persi, 1);
+    assert!(partita.finita());
+    assert_eq!(partita.vincitore(), Some(0));
+}
+
+#[test]
+fn test_collisione_combatti_perdente_attaccante() {
+    let mut partita = partita_affiancati("P O O\nO O O\nO O O", RegolaCollisione::Combatti, (10, 20));
+
+    assert_eq!(partita.gioca_turno(Direzione::Destra, &mut SempreRiuscito), Some(Esito::Perso));
+    assert_eq!(partita.vincitore(), Some(1));
+}
+
+#[test]
+fn test_collisione_condividi() {
+    let mut partita = partita_affiancati("P $ O\nO O O\nO O O", RegolaCollisione::Condividi, (50, 20));
+    partita.campo.celle[0][1] = Cella::Vuota;
+
+    partita.gioca_turno(Direzione::Destra, &mut SempreRiuscito);
+    assert_eq!(partita.giocatori[0].player.posizione, partita.giocatori[1].player.posizione);
+    assert!(!partita.finita());
+}
+
+#[test]
+fn test_teletrasporto_su_giocatore_blocca() {
+    let mut partita = partita_affiancati("P O O\nT0,1 O O\nO O O", RegolaCollisione::Blocca, (50, 20));
+
+    partita.gioca_turno(Direzione::Giu, &mut SempreRiuscito);
+    assert_eq!(partita.giocatori[0].player.posizione, Posizione { riga: 1, colonna: 0 });
+    assert_eq!(partita.giocatori[1].player.posizione, Posizione { riga: 0, colonna: 1 });
+    assert!(!partita.finita());
+}
+
+#[test]
+fn test_teletrasporto_su_giocatore_combatti() {
+    let mut partita = partita_affiancati("P O O\nT0,1 O O\nO O O", RegolaCollisione::Combatti, (50, 20));
+
+    partita.gioca_turno(Direzione::Giu, &mut SempreRiuscito);
+    assert_eq!(partita.giocatori[0].player.posizione, Posizione { riga: 0, colonna: 1 });
+    assert_eq!(partita.giocatori[1].esito, Esito::Perso);
+    assert_eq!(partita.giocatori[1].eliminato_in, Some(Posizione { riga: 0, colonna: 1 }));
+    assert_eq!(partita.vincitore(), Some(0));
+
+    let mut partita = partita_affiancati("P O O\nT0,1 O O\nO O O", RegolaCollisione::Combatti, (10, 20));
+    assert_eq!(partita.gioca_turno(Direzione::Giu, &mut SempreRiuscito), Some(Esito::Perso));
+    assert_eq!(partita.vincitore(), Some(1));
+}
+
+#[test]
+fn test_troppi_giocatori() {
+    let campo = carica_campo("P X\nO X").unwrap();
+    let errore = PartitaMultigiocatore::nuova(campo, &["a", "b", "c"], RegolaCollisione::Blocca, &mut StdRng::seed_from_u64(1));
+    assert_eq!(errore.err(), Some(ErroreGenerazione::TroppiGiocatori { richiesti: 3, disponibili: 2 }));
+}
+
+#[test]
+fn test_uscita_determina_vincitore() {
+    let mut partita = partita_affiancati("P O O\nE O O\nO O O", RegolaCollisione::Blocca, (10, 90));
+
+    partita.gioca_turno(Direzione::Giu, &mut SempreRiuscito);
+    assert!(partita.finita());
+    assert_eq!(partita.vincitore(), Some(0));
+    assert_eq!(partita.turno_corrente(), None);
+}
+
+#[test]
+fn test_statistiche_per_giocatore() {
+    let mut partita = partita_affiancati("P O O\n$ - O\nO O O", RegolaCollisione::Blocca, (50, 50));
+
+    partita.gioca_turno(Direzione::Giu, &mut SempreRiuscito);
+    partita.gioca_turno(Direzione::Giu, &mut SempreRiuscito);
+    assert_eq!(partita.giocatori[0].player.statistiche.cibo_mangiato, 1);
+    assert_eq!(partita.giocatori[0].player.forza, 60);
+    assert_eq!(partita.giocatori[1].player.statistiche.veleno_preso, 1);
+    assert_eq!(partita.giocatori[1].player.forza, 40);
+    assert_eq!(partita.giocatori[0].player.statistiche.mosse_fatte, 1);
+}
+
+#[test]
+fn test_headless_termina() {
+    let campo = carica_campo("P O $ O\nO - O $\n$ O O -\nO $ O O").unwrap();
+    let mut rng = StdRng::seed_from_u64(42);
+    let mut partita = PartitaMultigiocatore::nuova(campo, &["goloso", "casuale"], RegolaCollisione::Combatti, &mut rng).unwrap();
+    let mut agenti: Vec<Box<dyn Agente>> = vec![Box::new(AgenteGoloso), Box::new(AgenteCasuale::nuovo(3))];
+
+    partita.esegui_headless(&mut agenti, &mut rng, 1000);
+    assert!(partita.finita());
+    let display = format!("{}", partita);
+    assert!(display.contains("goloso"));
+    assert!(display.contains("casuale"));
+}
+
+#[test]
+fn test_giocatori_entrano_ed_escono() {
+    let campo = carica_campo("P O\nO O").unwrap();
+    let mut partita = PartitaMultigiocatore::nuova(campo, &[], RegolaCollisione::Blocca, &mut StdRng::seed_from_u64(2)).unwrap();
+    assert_eq!(partita.turno_corrente(), None);
+
+    assert_eq!(partita.aggiungi_giocatore("Anna", &mut StdRng::seed_from_u64(2)), Some(0));
+    assert_eq!(partita.giocatori[0].player.posizione, Posizione { riga: 0, colonna: 0 });
+    assert_eq!(partita.turno_corrente(), Some(0));
+    assert_eq!(partita.aggiungi_giocatore("Bruno", &mut StdRng::seed_from_u64(2)), Some(1));
+    assert_ne!(partita.giocatori[1].player.posizione, partita.giocatori[0].player.posizione);
+
+    // Chi esce durante il proprio turno lo passa al successivo
+    partita.ritira(0);
+    assert_eq!(partita.giocatori[0].esito, Esito::Perso);
+    assert!(partita.finita());
+    assert_eq!(partita.vincitore(), Some(1));
+}
+
+#[test]
+fn test_nemici_in_multigiocatore() {
+    let mappa = "nemico inseguitore 2,2 90\nP O O\nO O O\nO O O";
+    let mut partita = partita_affiancati(mappa, RegolaCollisione::Blocca, (50, 50));
+    assert!(format!("{}", partita).starts_with("1 2 O \nO O O \nO O ! \n"));
+
+    partita.gioca_turno(Direzione::Giu, &mut SempreRiuscito);
+    assert_ne!(partita.campo.nemici[0].posizione, Posizione { riga: 2, colonna: 2 });
+    // La posizione del campo resta quella di partenza, anche dopo la mossa del secondo giocatore
+    partita.gioca_turno(Direzione::Giu, &mut SempreRiuscito);
+    assert_eq!(partita.campo.player_position, Posizione { riga: 0, colonna: 0 });
+
+    let mappa = "nemico inseguitore 1,1 90\nP O O\nO O O\nO O O";
+    let mut partita = partita_affiancati(mappa, RegolaCollisione::Blocca, (50, 50));
+    assert_eq!(partita.gioca_turno(Direzione::Giu, &mut SempreRiuscito), Some(Esito::Perso));
+    assert_eq!(partita.giocatori[0].player.statistiche.scontri_persi, 1);
+    assert_eq!(partita.vincitore(), Some(1));
+}
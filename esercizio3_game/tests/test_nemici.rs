@@ -0,0 +1,130 @@
+mod common;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use common::SempreRiuscito;
+use esercizio3_game::game_space::game_space::Posizione;
+use esercizio3_game::nemici::{aggiungi_nemici, passo_verso, Comportamento, Nemico};
+use esercizio3_game::player::{Direzione, Esito, Player, Statistiche};
+use esercizio3_game::salvataggio::{carica_campo, carica_partita, salva_partita};
+
+/// Crea un giocatore nella posizione indicata con la forza specificata.
+fn player_in(posizione: Posizione, forza: i32) -> Player {
+    Player {
+        mosse: 20,
+        posizione,
+        direzione: Direzione::Destra,
+        forza,
+        immunita: 0,
+        statistiche: Statistiche::default(),
+    }
+}
+
+fn pos(riga: usize, colonna: usize) -> Posizione {
+    Posizione { riga, colonna }
+}
+
+#[test]
+fn test_passo_verso_aggira_i_muri() {
+    let campo = carica_campo("P X O\nO X O\nO O O").unwrap();
+
+    // Da (0, 2) a (0, 0) l'unico percorso passa dalla riga in basso
+    assert_eq!(passo_verso(&campo, pos(0, 2), pos(0, 0)), Some(pos(1, 2)));
+    assert_eq!(passo_verso(&campo, pos(0, 0), pos(0, 0)), None);
+    let chiuso = carica_campo("P X O\nX X O\nO O O").unwrap();
+    assert_eq!(passo_verso(&chiuso, pos(2, 2), pos(0, 0)), None);
+}
+
+#[test]
+fn test_inseguitore_raggiunge_e_combatte() {
+    let mut campo = carica_campo("nemico inseguitore 0,3 30\nP O O O\nO O O O\nO O O O\nO O O O").unwrap();
+    let mut player = player_in(campo.player_position, 50);
+
+    // Il giocatore si avvicina, l'inseguitore gli va incontro e viene sconfitto
+    player.muovi_con_rng(&mut campo, Direzione::Destra, &mut SempreRiuscito);
+    assert_eq!(campo.nemici[0].posizione, pos(0, 2));
+    player.muovi_con_rng(&mut campo, Direzione::Destra, &mut SempreRiuscito);
+    assert!(campo.nemici.is_empty());
+    assert_eq!(player.statistiche.scontri_vinti, 1);
+    assert_eq!(player.forza, 50);
+}
+
+#[test]
+fn test_inseguitore_piu_forte() {
+    let mut campo = carica_campo("nemico inseguitore 0,2 30\nP O O\nO O O\nO O O").unwrap();
+    let mut player = player_in(campo.player_position, 20);
+
+    assert_eq!(player.muovi_con_rng(&mut campo, Direzione::Destra, &mut SempreRiuscito), Esito::Perso);
+    assert_eq!(player.forza, -10);
+    assert_eq!(campo.nemici.len(), 1);
+}
+
+#[test]
+fn test_vagabondo_avvelena() {
+    let mut campo = carica_campo("P O O\nO O O\nO O O").unwrap();
+    campo.nemici.push(Nemico::nuovo(pos(0, 1), 15, Comportamento::Vagabondo));
+    let mut player = player_in(campo.player_position, 50);
+
+    // Il giocatore entra nella cella del vagabondo
+    player.muovi_con_rng(&mut campo, Direzione::Destra, &mut SempreRiuscito);
+    assert_eq!(player.forza, 35);
+    assert!(campo.nemici.is_empty());
+    // Il colpo del nemico non conta come veleno raccolto
+    assert_eq!(player.statistiche.colpi_nemici, 1);
+    assert_eq!(player.statistiche.veleno_preso, 0);
+}
+
+#[test]
+fn test_guardia_pattuglia() {
+    let mut campo = carica_campo("nemico guardia 2,0 10 0 2,0 2,2\nP O O\nO O O\nO O O").unwrap();
+    let mut player = player_in(campo.player_position, 50);
+    let mut rng = StdRng::seed_from_u64(0);
+
+    let mut visitate = Vec::new();
+    for _ in 0..6 {
+        campo.turno_nemici(&mut player, &mut rng);
+        visitate.push(campo.nemici[0].posizione);
+    }
+    assert_eq!(visitate, vec![pos(2, 1), pos(2, 2), pos(2, 1), pos(2, 0), pos(2, 1), pos(2, 2)]);
+}
+
+#[test]
+fn test_display_e_salvataggio_nemici() {
+    let mut campo = carica_campo("P O O\nO O O\nO O O").unwrap();
+    campo.nemici.push(Nemico::nuovo(pos(0, 2), 5, Comportamento::Vagabondo));
+    campo.nemici.push(Nemico::nuovo(pos(1, 1), 7, Comportamento::Inseguitore));
+    campo.nemici.push(Nemico::nuovo(
+        pos(2, 0),
+        9,
+        Comportamento::Guardia { percorso: vec![pos(2, 0), pos(2, 2)], prossima: 1 },
+    ));
+    assert_eq!(format!("{}", campo), "P O ~ \nO ! O \n& O O \n");
+
+    let player = player_in(campo.player_position, 50);
+    let (ricaricato, _) = carica_partita(&salva_partita(&campo, &player)).unwrap();
+    assert_eq!(ricaricato.nemici, campo.nemici);
+    assert!(carica_campo("nemico inseguitore 5,5 3\nP O\nO O").is_err());
+    assert!(carica_campo("nemico drago 0,1 3\nP O\nO O").is_err());
+    assert!(carica_campo("nemico vagabondo 0,1 0\nP O\nO O").is_err());
+    assert!(carica_campo("nemico inseguitore 0,1 -5\nP O\nO O").is_err());
+}
+
+#[test]
+fn test_colpo_non_trabocca() {
+    let mut player = player_in(pos(0, 0), -10);
+    assert!(!Nemico::nuovo(pos(0, 1), i32::MAX, Comportamento::Inseguitore).colpisci(&mut player));
+    assert_eq!(player.forza, i32::MIN);
+    assert!(Nemico::nuovo(pos(0, 1), i32::MAX, Comportamento::Vagabondo).colpisci(&mut player));
+    assert_eq!(player.forza, i32::MIN);
+}
+
+#[test]
+fn test_aggiungi_nemici() {
+    let mut campo = carica_campo("P O O O O\nO O O O O\nO O O O O\nO O O O O\nO O O O O").unwrap();
+    aggiungi_nemici(&mut campo, 4, &mut StdRng::seed_from_u64(11));
+
+    assert_eq!(campo.nemici.len(), 4);
+    for nemico in &campo.nemici {
+        assert!(nemico.posizione.riga + nemico.posizione.colonna > 2);
+    }
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use std::env;
+use std::fs;
+use common::SempreRiuscito;
+use esercizio3_game::player::{Direzione, Esito, Player};
+use esercizio3_game::punteggio::{conta_veleno, Classifica, Punteggio, VoceClassifica};
+use esercizio3_game::salvataggio::carica_campo;
+
+fn voce(regole: &str, seme: u64, nome: &str, punti: u64) -> VoceClassifica {
+    VoceClassifica {
+        regole: regole.to_string(),
+        seme,
+        nome: nome.to_string(),
+        punti,
+    }
+}
+
+#[test]
+fn test_punteggio_fine_partita() {
+    let mut campo = carica_campo("P $ -\nO O O\n- O O").unwrap();
+    let mut player = Player::nuovo(&campo);
+    player.mosse = 10;
+    let veleno_iniziale = conta_veleno(&campo);
+    assert_eq!(veleno_iniziale, 2);
+
+    player.muovi_con_rng(&mut campo, Direzione::Destra, &mut SempreRiuscito);
+    player.muovi_con_rng(&mut campo, Direzione::Giu, &mut SempreRiuscito);
+    let punteggio = Punteggio::calcola(&player, Esito::InCorso, veleno_iniziale);
+
+    // 1 cibo (10) + 2 veleni evitati (10) + 8 mosse rimaste (8) + efficienza 50% (25)
+    assert_eq!(punteggio.cibo_mangiato, 1);
+    assert_eq!(punteggio.veleno_evitato, 2);
+    assert_eq!(punteggio.mosse_rimaste, 8);
+    assert_eq!(punteggio.efficienza, 0.5);
+    assert_eq!(punteggio.totale, 53);
+    assert!(punteggio.to_string().ends_with("PUNTEGGIO: 53\n"));
+
+    // Chi perde non guadagna punti per le mosse rimaste
+    assert_eq!(Punteggio::calcola(&player, Esito::Perso, veleno_iniziale).totale, 45);
+}
+
+#[test]
+fn test_punteggio_oltre_u32() {
+    let campo = carica_campo("P O\nO O").unwrap();
+    let mut player = Player::nuovo(&campo);
+    player.mosse = u32::MAX as usize + 1;
+    let punteggio = Punteggio::calcola(&player, Esito::Vinto, 0);
+    assert_eq!(punteggio.totale, u32::MAX as u64 + 1);
+    assert!(punteggio.to_string().contains(&format!("(+{})", u32::MAX as u64 + 1)));
+
+    player.mosse = usize::MAX;
+    player.statistiche.cibo_mangiato = u32::MAX;
+    assert_eq!(Punteggio::calcola(&player, Esito::Vinto, u32::MAX).totale, u64::MAX);
+
+    let mut classifica = Classifica::default();
+    classifica.registra(voce("singolo", 1, "Anna", u64::MAX), 10);
+    assert_eq!(Classifica::da_testo(&classifica.in_testo()), classifica);
+}
+
+#[test]
+fn test_classifica_top_n_per_regole_e_seme() {
+    let mut classifica = Classifica::default();
+    assert_eq!(classifica.registra(voce("singolo", 1, "Anna", 50), 2), Some(0));
+    assert_eq!(classifica.registra(voce("singolo", 2, "Bruno", 10), 2), Some(0));
+    assert_eq!(classifica.registra(voce("singolo", 1, "Carla", 70), 2), Some(0));
+    assert_eq!(classifica.registra(voce("singolo", 1, "Dario", 50), 2), None);
+    assert_eq!(classifica.registra(voce("singolo", 1, "Dario", 60), 2), Some(1));
+    assert_eq!(classifica.registra(voce("singolo", 1, "Elena", 20), 2), None);
+
+    let nomi: Vec<&str> = classifica.migliori("singolo", 1).iter().map(|v| v.nome.as_str()).collect();
+    assert_eq!(nomi, vec!["Carla", "Dario"]);
+    assert_eq!(classifica.migliori("singolo", 2).len(), 1);
+    assert_eq!(classifica.gruppi(), vec![("singolo", 1), ("singolo", 2)]);
+}
+
+#[test]
+fn test_classifica_su_file() {
+    let percorso = env::temp_dir().join(format!("classifica_test_{}.txt", std::process::id()));
+    let _ = fs::remove_file(&percorso);
+    assert_eq!(Classifica::carica(&percorso).unwrap(), Classifica::default());
+
+    let mut classifica = Classifica::default();
+    classifica.registra(voce("nebbia 3 5x5", 42, "Anna Maria", 31), 10);
+    classifica.registra(voce("singolo", 7, "Bruno", 12), 10);
+    classifica.salva(&percorso).unwrap();
+    let ricaricata = Classifica::carica(&percorso).unwrap();
+    fs::remove_file(&percorso).unwrap();
+
+    assert_eq!(ricaricata, classifica);
+    assert!(ricaricata.to_string().contains("== nebbia 3 5x5 (seme 42) =="));
+    assert_eq!(Classifica::da_testo("riga non valida\nsingolo\t1\t5\tAnna\n").migliori("singolo", 1).len(), 1);
+}
@@ -0,0 +1,154 @@
+use esercizio3_game::game_space::game_space::{Cella, Posizione};
+use esercizio3_game::player::{Direzione, Statistiche};
+use esercizio3_game::salvataggio::{carica_campo, carica_partita, salva_campo, salva_partita, ErroreCaricamento};
+
+const MAPPA: &str = "\
+# mappa di prova
+O O $ O
+O P - O
+O O O $25
+-3 O O O
+";
+
+#[test]
+fn test_carica_campo() {
+    let campo = carica_campo(MAPPA).unwrap();
+
+    assert_eq!(campo.dimensione, 4);
+    assert_eq!(campo.player_position, Posizione { riga: 1, colonna: 1 });
+    assert_eq!(campo.celle[0][2], Cella::Cibo(10));
+    assert_eq!(campo.celle[1][2], Cella::Veleno(10));
+    assert_eq!(campo.celle[2][3], Cella::Cibo(25));
+    assert_eq!(campo.celle[3][0], Cella::Veleno(3));
+    assert_eq!(campo.celle[1][1], Cella::Vuota);
+}
+
+#[test]
+fn test_salva_carica_campo() {
+    let campo = carica_campo(MAPPA).unwrap();
+    let testo = salva_campo(&campo);
+
+    // Il formato usa gli stessi simboli di Display, con la posizione del giocatore a parte
+    assert!(testo.starts_with("posizione 1,1\nO O $ O\nO O - O\n"));
+    let ricaricato = carica_campo(&testo).unwrap();
+    assert_eq!(ricaricato.celle, campo.celle);
+    assert_eq!(ricaricato.player_position, campo.player_position);
+}
+
+#[test]
+fn test_salva_carica_partita() {
+    let (campo, mut player) = carica_partita(&format!("dimensione 4\nmosse 16\nforza 50\ndirezione Su\n{}", MAPPA)).unwrap();
+    player.forza = 42;
+    player.mosse = 7;
+    player.direzione = Direzione::Sinistra;
+
+    let (campo2, player2) = carica_partita(&salva_partita(&campo, &player)).unwrap();
+    assert_eq!(campo2.celle, campo.celle);
+    assert_eq!(player2.posizione, campo.player_position);
+    assert_eq!(player2.forza, 42);
+    assert_eq!(player2.mosse, 7);
+    assert_eq!(player2.direzione, Direzione::Sinistra);
+}
+
+#[test]
+fn test_salva_carica_statistiche() {
+    let (campo, mut player) = carica_partita(&format!("dimensione 4\nmosse 16\nforza 50\ndirezione Su\n{}", MAPPA)).unwrap();
+    assert_eq!(player.statistiche, Statistiche::default());
+    player.statistiche = Statistiche {
+        mosse_fatte: 9,
+        cibo_mangiato: 3,
+        veleno_preso: 2,
+        power_up_raccolti: 1,
+        colpi_nemici: 4,
+        scontri_vinti: 5,
+        scontri_persi: 6,
+    };
+
+    let testo = salva_partita(&campo, &player);
+    assert!(testo.contains("\nstatistiche 9 3 2 1 4 5 6\n"));
+    let (_, ricaricato) = carica_partita(&testo).unwrap();
+    assert_eq!(ricaricato.statistiche, player.statistiche);
+    assert!(matches!(
+        carica_partita(&format!("dimensione 4\nmosse 16\nforza 50\ndirezione Su\nstatistiche 1 2 3\n{}", MAPPA)),
+        Err(ErroreCaricamento::ValoreNonValido { .. })
+    ));
+}
+
+#[test]
+fn test_cella_sotto_il_giocatore() {
+    let (mut campo, mut player) = carica_partita(&format!("dimensione 4\nmosse 16\nforza 50\ndirezione Su\n{}", MAPPA)).unwrap();
+    campo.celle[1][1] = Cella::Trappola(2);
+    campo.player_position = Posizione { riga: 2, colonna: 3 };
+    player.posizione = campo.player_position;
+
+    let (campo2, player2) = carica_partita(&salva_partita(&campo, &player)).unwrap();
+    assert_eq!(campo2.celle, campo.celle);
+    assert_eq!(campo2.celle[2][3], Cella::Cibo(25));
+    assert_eq!(player2.posizione, Posizione { riga: 2, colonna: 3 });
+
+    // La posizione va indicata in un solo modo e deve essere interna al campo
+    assert!(matches!(carica_campo("posizione 0,1\nP O\nO O\n"), Err(ErroreCaricamento::GiocatoriMultipli)));
+    assert!(matches!(carica_campo("posizione 2,0\nO O\nO O\n"), Err(ErroreCaricamento::ValoreNonValido { .. })));
+    assert!(matches!(carica_campo("posizione 1\nO O\nO O\n"), Err(ErroreCaricamento::ValoreNonValido { .. })));
+}
+
+#[test]
+fn test_carica_campo_non_quadrato() {
+    let errore = carica_campo("O O O\nO P O\n").unwrap_err();
+    assert!(matches!(errore, ErroreCaricamento::DimensioneNonValida { riga: 0, attesa: 2, trovata: 3 }));
+}
+
+#[test]
+fn test_carica_campo_giocatore_non_valido() {
+    assert!(matches!(carica_campo("O O\nO O\n"), Err(ErroreCaricamento::GiocatoreMancante)));
+    assert!(matches!(carica_campo("P O\nO P\n"), Err(ErroreCaricamento::GiocatoriMultipli)));
+    assert!(matches!(
+        carica_campo("P Z\nO O\n"),
+        Err(ErroreCaricamento::SimboloSconosciuto { riga: 0, colonna: 1, .. })
+    ));
+}
+
+#[test]
+fn test_carica_partita_intestazione_non_valida() {
+    assert!(matches!(
+        carica_partita("mosse 4\nforza 10\ndirezione Su\nP O\nO O\n"),
+        Err(ErroreCaricamento::CampoMancante("dimensione"))
+    ));
+    assert!(matches!(
+        carica_partita("dimensione 3\nmosse 4\nforza 10\ndirezione Su\nP O\nO O\n"),
+        Err(ErroreCaricamento::ValoreNonValido { .. })
+    ));
+    assert!(matches!(
+        carica_partita("dimensione 2\nmosse 4\nforza 10\ndirezione Nord\nP O\nO O\n"),
+        Err(ErroreCaricamento::ValoreNonValido { .. })
+    ));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_campo_e_player() {
+    use esercizio3_game::game_space::game_space::CampoGioco;
+    use esercizio3_game::player::Player;
+
+    let (campo, player) = carica_partita(&format!("dimensione 4\nmosse 16\nforza 50\ndirezione Giu\n{}", MAPPA)).unwrap();
+    let json = serde_json::to_string(&campo).unwrap();
+    let campo2: CampoGioco = serde_json::from_str(&json).unwrap();
+    assert_eq!(campo2.celle, campo.celle);
+
+    // Il campo letto con serde passa per gli stessi controlli della mappa testuale
+    for (json, attesa) in [
+        (r#"{"dimensione":2,"celle":[["Vuota","Vuota"]],"player_position":{"riga":0,"colonna":0}}"#, "righe"),
+        (r#"{"dimensione":2,"celle":[["Vuota","Vuota"],["Vuota"]],"player_position":{"riga":0,"colonna":0}}"#, "celle"),
+        (r#"{"dimensione":1,"celle":[["Vuota"]],"player_position":{"riga":3,"colonna":0}}"#, "posizione"),
+        (r#"{"dimensione":1,"celle":[[{"Teletrasporto":{"riga":5,"colonna":5}}]],"player_position":{"riga":0,"colonna":0}}"#, "T5,5"),
+        (r#"{"dimensione":0,"celle":[],"player_position":{"riga":0,"colonna":0}}"#, "vuota"),
+    ] {
+        let errore = serde_json::from_str::<CampoGioco>(json).unwrap_err().to_string();
+        assert!(errore.contains(attesa), "{}", errore);
+    }
+
+    let json = serde_json::to_string(&player).unwrap();
+    let player2: Player = serde_json::from_str(&json).unwrap();
+    assert_eq!(player2.direzione, Direzione::Giu);
+    assert_eq!(player2.forza, 50);
+}
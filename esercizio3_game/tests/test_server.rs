@@ -0,0 +1,165 @@
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::time::Duration;
+use esercizio3_game::multiplayer::RegolaCollisione;
+use esercizio3_game::player::Direzione;
+use esercizio3_game::salvataggio::carica_campo;
+use esercizio3_game::server::{Comando, Server, RIGA_MASSIMA};
+
+/// Client di prova collegato al server in loopback.
+struct Client {
+    stream: TcpStream,
+    lettore: BufReader<TcpStream>,
+}
+
+impl Client {
+    fn connetti(indirizzo: SocketAddr) -> Client {
+        let stream = TcpStream::connect(indirizzo).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let lettore = BufReader::new(stream.try_clone().unwrap());
+        Client { stream, lettore }
+    }
+
+    fn invia(&mut self, comando: &str) {
+        writeln!(self.stream, "{}", comando).unwrap();
+    }
+
+    fn riga(&mut self) -> String {
+        let mut riga = String::new();
+        self.lettore.read_line(&mut riga).unwrap();
+        riga.trim_end().to_string()
+    }
+
+    /// Legge un blocco di stato fino alla riga `FINE` compresa.
+    fn stato(&mut self) -> Vec<String> {
+        let mut righe = vec![self.riga()];
+        assert!(righe[0].starts_with("STATO "), "atteso lo stato, ricevuto {:?}", righe[0]);
+        while righe.last().map(String::as_str) != Some("FINE") {
+            righe.push(self.riga());
+        }
+        righe
+    }
+}
+
+fn avvia_server() -> SocketAddr {
+    let campo = carica_campo("P O O\nO O O\nO O O").unwrap();
+    let server = Server::nuovo("127.0.0.1:0", campo, RegolaCollisione::Blocca, 3).unwrap();
+    let indirizzo = server.indirizzo().unwrap();
+    server.avvia();
+    indirizzo
+}
+
+#[test]
+fn test_interpreta_comandi() {
+    assert_eq!(Comando::interpreta("JOIN Anna"), Ok(Comando::Join("Anna".to_string())));
+    assert_eq!(Comando::interpreta("move destra"), Ok(Comando::Move(Direzione::Destra)));
+    assert_eq!(Comando::interpreta("MOVE G"), Ok(Comando::Move(Direzione::Giu)));
+    assert_eq!(Comando::interpreta(" STATE "), Ok(Comando::State));
+    assert_eq!(Comando::interpreta("QUIT"), Ok(Comando::Quit));
+    assert!(Comando::interpreta("JOIN").is_err());
+    assert!(Comando::interpreta("MOVE avanti").is_err());
+    assert!(Comando::interpreta("SALTA").is_err());
+}
+
+#[test]
+fn test_partita_in_rete() {
+    let indirizzo = avvia_server();
+
+    let mut anna = Client::connetti(indirizzo);
+    anna.invia("MOVE Su");
+    assert!(anna.riga().starts_with("ERR"));
+    anna.invia("JOIN Anna");
+    assert_eq!(anna.riga(), "OK 1");
+    let stato = anna.stato();
+    assert_eq!(stato[0], "STATO 3 1");
+    assert_eq!(stato[1], "1 O O");
+    assert!(stato[4].starts_with("GIOCATORE 1 0 0 "));
+    assert!(stato[4].ends_with(" INCORSO Anna"));
+
+    let mut bruno = Client::connetti(indirizzo);
+    bruno.invia("JOIN Bruno");
+    assert_eq!(bruno.riga(), "OK 2");
+    assert_eq!(bruno.stato(), anna.stato());
+
+    // Solo il giocatore di turno può muovere; ogni mossa viene trasmessa a tutti
+    bruno.invia("MOVE Giu");
+    assert_eq!(bruno.riga(), "ERR non è il tuo turno");
+    anna.invia("MOVE Destra");
+    assert!(anna.riga().starts_with("OK "));
+    let dopo_mossa = anna.stato();
+    assert_eq!(dopo_mossa[0], "STATO 3 2");
+    assert_eq!(bruno.stato(), dopo_mossa);
+
+    bruno.invia("STATE");
+    assert_eq!(bruno.stato(), dopo_mossa);
+
+    // Chi esce viene eliminato e, rimasto solo un giocatore, la partita finisce
+    bruno.invia("QUIT");
+    assert_eq!(bruno.riga(), "BYE");
+    let finale = anna.stato();
+    assert_eq!(finale[0], "STATO 3 -");
+    assert!(finale.iter().any(|r| r.starts_with("GIOCATORE 2 ") && r.ends_with(" PERSO Bruno")));
+    assert!(finale.contains(&"VINCITORE 1".to_string()));
+}
+
+#[test]
+fn test_partita_patta_non_accetta_giocatori() {
+    let indirizzo = avvia_server();
+
+    // L'unico giocatore esce: la partita finisce senza vincitore
+    let mut anna = Client::connetti(indirizzo);
+    anna.invia("JOIN Anna");
+    assert_eq!(anna.riga(), "OK 1");
+    anna.stato();
+    anna.invia("QUIT");
+    assert_eq!(anna.riga(), "BYE");
+
+    let mut carlo = Client::connetti(indirizzo);
+    carlo.invia("STATE");
+    let finale = carlo.stato();
+    assert_eq!(finale[0], "STATO 3 -");
+    assert!(!finale.iter().any(|r| r.starts_with("VINCITORE")));
+    carlo.invia("JOIN Carlo");
+    assert_eq!(carlo.riga(), "ERR la partita è finita");
+}
+
+#[test]
+fn test_riga_troppo_lunga() {
+    let indirizzo = avvia_server();
+
+    let mut anna = Client::connetti(indirizzo);
+    anna.stream.write_all("X".repeat(RIGA_MASSIMA * 4).as_bytes()).unwrap();
+    assert_eq!(anna.riga(), "ERR riga troppo lunga");
+    assert_eq!(anna.riga(), "BYE");
+    assert_eq!(anna.riga(), "");
+
+    // Il server continua a servire gli altri client
+    let mut bruno = Client::connetti(indirizzo);
+    bruno.invia("JOIN Bruno");
+    assert_eq!(bruno.riga(), "OK 1");
+}
+
+#[test]
+fn test_client_che_non_legge_viene_disconnesso() {
+    let indirizzo = avvia_server();
+
+    // Il client chiede lo stato senza mai leggere le risposte finché la coda non si riempie
+    let mut lento = Client::connetti(indirizzo);
+    let richieste = "STATE\n".repeat(1000);
+    for _ in 0..200 {
+        if lento.stream.write_all(richieste.as_bytes()).is_err() {
+            break;
+        }
+    }
+    let mut ricevuto = Vec::new();
+    match lento.lettore.read_to_end(&mut ricevuto) {
+        Ok(_) => {}
+        Err(e) => assert_eq!(e.kind(), ErrorKind::ConnectionReset),
+    }
+    let risposte = String::from_utf8_lossy(&ricevuto).matches("FINE\n").count();
+    assert!(risposte < 200_000, "il server ha risposto a tutte le {} richieste", risposte);
+
+    let mut bruno = Client::connetti(indirizzo);
+    bruno.invia("STATE");
+    assert_eq!(bruno.stato()[0], "STATO 3 -");
+}
@@ -0,0 +1,110 @@
+use esercizio3_game::game_space::game_space::{Cella, Posizione};
+use esercizio3_game::player::{Direzione, Player};
+use esercizio3_game::salvataggio::{carica_campo, carica_partita, salva_partita};
+use esercizio3_game::storia::{Replay, Storia};
+
+const MAPPA: &str = "\
+nemico vagabondo 3,3 5
+P $ - O
+$ O O O
+- O $ O
+O O O O";
+
+/// Gioca una sequenza di mosse e restituisce lo stato finale nel formato di `salva_partita`.
+fn gioca(storia: &mut Storia, direzioni: &[Direzione]) -> String {
+    let mut campo = carica_campo(MAPPA).unwrap();
+    let mut player = Player::nuovo(&campo);
+    player.forza = 100;
+    for direzione in direzioni {
+        storia.esegui(&mut campo, &mut player, *direzione);
+    }
+    salva_partita(&campo, &player)
+}
+
+#[test]
+fn test_annulla_ripristina_lo_stato() {
+    let mut campo = carica_campo(MAPPA).unwrap();
+    let mut player = Player::nuovo(&campo);
+    player.forza = 100;
+    let iniziale = salva_partita(&campo, &player);
+    let mut storia = Storia::nuova(4);
+
+    let mut stati = vec![iniziale.clone()];
+    for direzione in [Direzione::Destra, Direzione::Giu, Direzione::Destra, Direzione::Giu] {
+        storia.esegui(&mut campo, &mut player, direzione);
+        stati.push(salva_partita(&campo, &player));
+    }
+    let statistiche = player.statistiche.clone();
+    assert_eq!(storia.mosse().len(), 4);
+
+    // Annullando si ripercorrono a ritroso gli stessi stati, celle consumate e nemici compresi
+    for stato in stati.iter().rev().skip(1) {
+        assert!(storia.annulla(&mut campo, &mut player));
+        assert_eq!(&salva_partita(&campo, &player), stato);
+    }
+    assert!(!storia.annulla(&mut campo, &mut player));
+    assert_eq!(campo.cella(Posizione { riga: 0, colonna: 1 }), Cella::Cibo(10));
+    assert_eq!(player.statistiche.mosse_fatte, 0);
+
+    // Ripetendo si ritorna all'ultimo stato
+    while storia.ripeti(&mut campo, &mut player).is_some() {}
+    assert_eq!(salva_partita(&campo, &player), stati[4]);
+    assert_eq!(player.statistiche, statistiche);
+}
+
+#[test]
+fn test_nuova_mossa_cancella_le_ripetizioni() {
+    let mut campo = carica_campo(MAPPA).unwrap();
+    let mut player = Player::nuovo(&campo);
+    let mut storia = Storia::nuova(1);
+
+    storia.esegui(&mut campo, &mut player, Direzione::Destra);
+    storia.esegui(&mut campo, &mut player, Direzione::Giu);
+    storia.annulla(&mut campo, &mut player);
+    assert_eq!(storia.da_ripetere(), 1);
+    storia.esegui(&mut campo, &mut player, Direzione::Sinistra);
+    assert_eq!(storia.da_ripetere(), 0);
+    assert_eq!(storia.ripeti(&mut campo, &mut player), None);
+    assert_eq!(storia.mosse()[1].richiesta, Direzione::Sinistra);
+}
+
+#[test]
+fn test_mosse_riproducibili_dal_seme() {
+    let direzioni = [Direzione::Giu, Direzione::Destra, Direzione::Destra, Direzione::Su, Direzione::Giu];
+    assert_eq!(gioca(&mut Storia::nuova(9), &direzioni), gioca(&mut Storia::nuova(9), &direzioni));
+}
+
+#[test]
+fn test_replay_e_visore() {
+    let mut campo = carica_campo(MAPPA).unwrap();
+    let mut player = Player::nuovo(&campo);
+    player.forza = 100;
+    let mut storia = Storia::nuova(21);
+    let mut replay = Replay::nuovo(&campo, &player, storia.seme());
+    let mut stati = vec![salva_partita(&campo, &player)];
+    for direzione in [Direzione::Destra, Direzione::Giu, Direzione::Giu] {
+        storia.esegui(&mut campo, &mut player, direzione);
+        stati.push(salva_partita(&campo, &player));
+    }
+    replay.aggiorna(&storia);
+
+    let ricaricato = Replay::da_testo(&replay.in_testo()).unwrap();
+    assert_eq!(ricaricato, replay);
+    assert!(ricaricato.in_testo().starts_with("replay 21\npercorso Destra Giu Giu\n"));
+
+    let mut visore = ricaricato.visore().unwrap();
+    assert_eq!(visore.totale(), 3);
+    for stato in &stati[1..] {
+        assert!(visore.avanti().is_some());
+        assert_eq!(&salva_partita(&visore.campo, &visore.player), stato);
+    }
+    assert_eq!(visore.avanti(), None);
+    assert!(visore.indietro());
+    assert_eq!(salva_partita(&visore.campo, &visore.player), stati[2]);
+    assert!(visore.avanti().is_some());
+    assert_eq!(salva_partita(&visore.campo, &visore.player), stati[3]);
+
+    assert!(Replay::da_testo("percorso Su\nP O\nO O").is_err());
+    assert!(Replay::da_testo("replay 1\npercorso Avanti\nP O\nO O").is_err());
+    assert!(carica_partita(&ricaricato.iniziale).is_ok());
+}
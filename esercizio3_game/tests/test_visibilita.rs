@@ -0,0 +1,103 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use esercizio3_game::game_space::game_space::{Cella, Posizione};
+use esercizio3_game::multiplayer::{PartitaMultigiocatore, RegolaCollisione};
+use esercizio3_game::salvataggio::carica_campo;
+use esercizio3_game::visibilita::{linea_libera, ModalitaVisibilita, Nebbia, StatoCella};
+
+fn pos(riga: usize, colonna: usize) -> Posizione {
+    Posizione { riga, colonna }
+}
+
+const MAPPA: &str = "\
+P O $ O O
+O X O O O
+O O O O -
+O O O O O
+$ O O O O";
+
+#[test]
+fn test_visibilita_raggio() {
+    let campo = carica_campo(MAPPA).unwrap();
+    let nebbia = Nebbia::nuova(campo.dimensione, ModalitaVisibilita::Raggio(2));
+
+    assert!(nebbia.visibile(&campo, pos(0, 0), pos(0, 2)));
+    assert!(nebbia.visibile(&campo, pos(0, 0), pos(1, 1)));
+    assert!(!nebbia.visibile(&campo, pos(0, 0), pos(2, 2)));
+    assert!(!nebbia.visibile(&campo, pos(0, 0), pos(4, 0)));
+}
+
+#[test]
+fn test_linea_di_vista() {
+    let campo = carica_campo(MAPPA).unwrap();
+
+    // Il muro in (1, 1) nasconde (2, 2) a chi è in (0, 0)
+    assert!(!linea_libera(&campo, pos(0, 0), pos(2, 2)));
+    assert!(linea_libera(&campo, pos(0, 0), pos(0, 4)));
+    assert!(linea_libera(&campo, pos(0, 0), pos(1, 1)));
+    let nebbia = Nebbia::nuova(campo.dimensione, ModalitaVisibilita::LineaDiVista(5));
+    assert!(!nebbia.visibile(&campo, pos(0, 0), pos(2, 2)));
+    assert!(nebbia.visibile(&campo, pos(0, 0), pos(2, 0)));
+}
+
+#[test]
+fn test_memoria_celle_esplorate() {
+    let mut campo = carica_campo(MAPPA).unwrap();
+    let mut nebbia = Nebbia::nuova(campo.dimensione, ModalitaVisibilita::Raggio(2));
+    nebbia.aggiorna(&campo, pos(0, 0));
+
+    // Il giocatore si allontana: il cibo in (0, 2) resta ricordato, poi viene mangiato da qualcun altro
+    campo.celle[0][2] = Cella::Vuota;
+    let stato = nebbia.stato(&campo, pos(4, 4));
+    assert_eq!(stato[0][2], StatoCella::Vista(Cella::Cibo(10)));
+    assert_eq!(stato[4][4], StatoCella::Visibile(Cella::Vuota));
+    assert_eq!(stato[4][0], StatoCella::Sconosciuta);
+    assert_eq!(stato[2][4], StatoCella::Visibile(Cella::Veleno(10)));
+}
+
+#[test]
+fn test_vista_campo() {
+    let campo = carica_campo(MAPPA).unwrap();
+    let mut nebbia = Nebbia::nuova(campo.dimensione, ModalitaVisibilita::Raggio(1));
+    nebbia.aggiorna(&campo, pos(0, 0));
+    nebbia.aggiorna(&campo, pos(0, 1));
+
+    let vista = format!("{}", nebbia.vista(&campo, pos(0, 2)));
+    assert_eq!(vista.lines().next(), Some(". O P O ? "));
+    assert_eq!(vista.lines().nth(1), Some(". X O ? ? "));
+    assert!(format!("{:#}", nebbia.vista(&campo, pos(0, 2))).contains("\x1b[2m"));
+}
+
+#[test]
+fn test_osservazione_agente_con_nebbia() {
+    let campo = carica_campo(MAPPA).unwrap();
+    let partita = PartitaMultigiocatore::nuova(campo, &["a", "b"], RegolaCollisione::Blocca, &mut StdRng::seed_from_u64(1)).unwrap()
+        .con_visibilita(ModalitaVisibilita::Raggio(2));
+
+    let osservazione = partita.osservazione(0);
+    assert_eq!(osservazione.celle[0][2], Some(Cella::Cibo(10)));
+    assert_eq!(osservazione.celle[4][0], None);
+    let lontano = partita.giocatori[1].player.posizione;
+    let vicino = lontano.riga * lontano.riga + lontano.colonna * lontano.colonna <= 4;
+    assert_eq!(osservazione.avversari.contains(&lontano), vicino);
+
+    // Senza nebbia l'agente conosce tutto il campo
+    let campo = carica_campo(MAPPA).unwrap();
+    let partita = PartitaMultigiocatore::nuova(campo, &["a"], RegolaCollisione::Blocca, &mut StdRng::seed_from_u64(1)).unwrap();
+    assert!(partita.osservazione(0).celle.iter().flatten().all(Option::is_some));
+}
+
+#[test]
+fn test_vista_mostra_avversari_visibili() {
+    let campo = carica_campo(MAPPA).unwrap();
+    let mut partita = PartitaMultigiocatore::nuova(campo, &["a", "b", "c"], RegolaCollisione::Blocca, &mut StdRng::seed_from_u64(1)).unwrap()
+        .con_visibilita(ModalitaVisibilita::Raggio(2));
+    partita.giocatori[1].player.posizione = pos(0, 1);
+    partita.giocatori[2].player.posizione = pos(4, 4);
+
+    let vista = format!("{}", partita.vista(0));
+    assert_eq!(vista.lines().next(), Some("P 2 $ ? ? "));
+    // Il terzo giocatore è fuori dal raggio visibile
+    assert_eq!(vista.lines().nth(4), Some("? ? ? ? ? "));
+    assert!(!vista.contains('3'));
+}
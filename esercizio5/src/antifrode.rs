@@ -0,0 +1,220 @@
+/// Modulo `antifrode` per le regole che segnalano operazioni sospette prima che vengano eseguite.
+pub mod antifrode {
+    use std::fmt;
+    use chrono::{Duration, NaiveDateTime};
+    use crate::conto_bancario::conto_bancario::{ContoBancario, Operazione};
+    use crate::denaro::denaro::Denaro;
+    use crate::registro::registro::{Movimento, TipoMovimento};
+    use crate::stati::stati::Stato;
+
+    /// Operazione richiesta su un conto, valutata dalle regole prima di essere eseguita.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Richiesta {
+        /// Operazione richiesta.
+        pub operazione: Operazione,
+        /// Importo richiesto.
+        pub importo: Denaro,
+        /// Data dell'operazione.
+        pub data: NaiveDateTime,
+    }
+
+    /// Azione da intraprendere quando una regola scatta, in ordine crescente di gravità.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub enum Azione {
+        /// L'operazione viene eseguita e la segnalazione registrata sul conto.
+        Segnala,
+        /// L'operazione viene rifiutata finché non è confermata con `ContoBancario::conferma_operazione`.
+        RichiediConferma,
+        /// L'operazione viene rifiutata e il conto bloccato.
+        Blocca,
+    }
+
+    /// Segnalazione prodotta da una regola.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Segnalazione {
+        /// Nome della regola che è scattata.
+        pub regola: String,
+        /// Motivo della segnalazione.
+        pub motivo: String,
+        /// Azione associata alla regola.
+        pub azione: Azione,
+        /// Operazione segnalata.
+        pub richiesta: Richiesta,
+    }
+
+    impl fmt::Display for Segnalazione {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}: {}", self.regola, self.motivo)
+        }
+    }
+
+    /// Regola che valuta un'operazione richiesta alla luce della storia del conto.
+    pub trait Regola: Send + Sync {
+        /// Nome della regola, riportato nelle segnalazioni.
+        fn nome(&self) -> &str;
+
+        /// Restituisce il motivo della segnalazione se l'operazione è sospetta, altrimenti `None`.
+        ///
+        /// # Argomenti
+        ///
+        /// * `conto` - Il conto, con il registro dei movimenti già eseguiti.
+        /// * `richiesta` - L'operazione richiesta.
+        fn valuta(&self, conto: &ContoBancario, richiesta: &Richiesta) -> Option<String>;
+    }
+
+    /// Restituisce i movimenti del conto registrati nella finestra che precede la data indicata.
+    fn recenti(conto: &ContoBancario, data: NaiveDateTime, finestra: Duration) -> impl Iterator<Item = &Movimento> {
+        conto.registro().movimenti().iter().filter(move |m| m.data > data - finestra && m.data <= data)
+    }
+
+    /// Limite di velocità: troppi depositi e prelievi in poco tempo.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Velocita {
+        /// Numero massimo di operazioni nella finestra: la regola scatta sull'operazione che lo supererebbe,
+        /// cioè quando ne sono già state eseguite altrettante.
+        pub massimo: usize,
+        /// Durata della finestra che precede l'operazione.
+        pub finestra: Duration,
+    }
+
+    impl Regola for Velocita {
+        fn nome(&self) -> &str {
+            "velocita"
+        }
+
+        fn valuta(&self, conto: &ContoBancario, richiesta: &Richiesta) -> Option<String> {
+            let eseguite = recenti(conto, richiesta.data, self.finestra)
+                .filter(|m| matches!(m.tipo, TipoMovimento::Deposito | TipoMovimento::Prelievo))
+                .count();
+            (eseguite >= self.massimo)
+                .then(|| format!("{} operazioni nelle ultime {} ore", eseguite, self.finestra.num_hours()))
+        }
+    }
+
+    /// Prelievo molto più grande di quelli eseguiti finora.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct PrelievoAnomalo {
+        /// Quante volte il prelievo medio deve essere superato perché la regola scatti.
+        pub moltiplicatore: u32,
+        /// Numero minimo di prelievi passati perché la media sia significativa.
+        pub storico_minimo: usize,
+    }
+
+    impl Regola for PrelievoAnomalo {
+        fn nome(&self) -> &str {
+            "prelievo anomalo"
+        }
+
+        fn valuta(&self, conto: &ContoBancario, richiesta: &Richiesta) -> Option<String> {
+            if richiesta.operazione != Operazione::Prelievo {
+                return None;
+            }
+            let passati: Vec<i128> = conto
+                .registro()
+                .movimenti()
+                .iter()
+                .filter(|m| m.tipo == TipoMovimento::Prelievo)
+                .map(|m| m.variazione.unita_minime().unsigned_abs() as i128)
+                .collect();
+            if passati.is_empty() || passati.len() < self.storico_minimo {
+                return None;
+            }
+            let totale: i128 = passati.iter().sum();
+            // importo > media * moltiplicatore, senza divisioni
+            let anomalo = richiesta.importo.unita_minime() as i128 * passati.len() as i128 > totale * self.moltiplicatore as i128;
+            let media = Denaro::da_unita_minime((totale / passati.len() as i128) as i64, richiesta.importo.valuta());
+            anomalo.then(|| format!("prelievo di {} oltre {} volte la media di {}", richiesta.importo, self.moltiplicatore, media))
+        }
+    }
+
+    /// Passaggi ripetuti tra Argento e Oro in poco tempo, tipici di chi sfrutta i vantaggi della fascia più alta.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Oscillazione {
+        /// Numero di passaggi nella finestra raggiunto il quale la regola scatta.
+        pub massimo: usize,
+        /// Durata della finestra che precede l'operazione.
+        pub finestra: Duration,
+    }
+
+    impl Regola for Oscillazione {
+        fn nome(&self) -> &str {
+            "oscillazione"
+        }
+
+        fn valuta(&self, conto: &ContoBancario, richiesta: &Richiesta) -> Option<String> {
+            let passaggi = recenti(conto, richiesta.data, self.finestra)
+                .filter(|m| {
+                    matches!(
+                        m.tipo,
+                        TipoMovimento::CambioStato { da: Stato::Argento, a: Stato::Oro } | TipoMovimento::CambioStato { da: Stato::Oro, a: Stato::Argento }
+                    )
+                })
+                .count();
+            (passaggi >= self.massimo)
+                .then(|| format!("{} passaggi tra Argento e Oro nelle ultime {} ore", passaggi, self.finestra.num_hours()))
+        }
+    }
+
+    /// Motore antifrode: un elenco di regole, ciascuna con la sua azione.
+    ///
+    /// Il motore viene consultato da `ContoBancario` prima di ogni deposito e prelievo, prima che
+    /// lo stato del conto esegua l'operazione; se più regole scattano vale l'azione più grave.
+    ///
+    /// # Esempio
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use chrono::Duration;
+    /// use esercizio5::antifrode::antifrode::{Antifrode, Azione, Velocita};
+    /// use esercizio5::conto_bancario::conto_bancario::{ContoBancario, ErroreConto};
+    /// use esercizio5::denaro::denaro::{Denaro, Tasso, Valuta};
+    ///
+    /// let euro = |importo: &str| Denaro::da_decimale(importo, Valuta::EUR).unwrap();
+    /// let antifrode = Antifrode::nuovo().con_regola(Velocita { massimo: 2, finestra: Duration::hours(1) }, Azione::RichiediConferma);
+    /// let mut conto = ContoBancario::new(String::from("Mario Rossi"), euro("1000"), euro("0"), euro("5000"), Tasso::ZERO);
+    /// conto.imposta_antifrode(Arc::new(antifrode));
+    ///
+    /// conto.preleva(euro("10")).unwrap();
+    /// conto.preleva(euro("10")).unwrap();
+    /// assert!(matches!(conto.preleva(euro("10")), Err(ErroreConto::ConfermaRichiesta(_))));
+    /// conto.conferma_operazione();
+    /// assert_eq!(conto.preleva(euro("10")).unwrap().saldo, euro("970"));
+    /// ```
+    #[derive(Default)]
+    pub struct Antifrode {
+        regole: Vec<(Box<dyn Regola>, Azione)>,
+    }
+
+    impl Antifrode {
+        /// Crea un motore senza regole.
+        pub fn nuovo() -> Antifrode {
+            Antifrode::default()
+        }
+
+        /// Aggiunge una regola con l'azione da intraprendere quando scatta.
+        pub fn con_regola(mut self, regola: impl Regola + 'static, azione: Azione) -> Antifrode {
+            self.regole.push((Box::new(regola), azione));
+            self
+        }
+
+        /// Valuta un'operazione richiesta con tutte le regole e restituisce le segnalazioni, nell'ordine delle regole.
+        ///
+        /// # Argomenti
+        ///
+        /// * `conto` - Il conto su cui è richiesta l'operazione.
+        /// * `richiesta` - L'operazione richiesta.
+        pub fn valuta(&self, conto: &ContoBancario, richiesta: &Richiesta) -> Vec<Segnalazione> {
+            self.regole
+                .iter()
+                .filter_map(|(regola, azione)| {
+                    regola.valuta(conto, richiesta).map(|motivo| Segnalazione {
+                        regola: regola.nome().to_string(),
+                        motivo,
+                        azione: *azione,
+                        richiesta: *richiesta,
+                    })
+                })
+                .collect()
+        }
+    }
+}
@@ -0,0 +1,1015 @@
+/// Modulo `archivio` per salvare una banca su disco con un registro delle operazioni scritto prima
+/// di eseguirle (write-ahead log) e istantanee periodiche.
+pub mod archivio {
+    use std::collections::BTreeMap;
+    use std::fmt;
+    use std::fs::{self, File, OpenOptions};
+    use std::io::{self, Write};
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+    use chrono::{NaiveDate, NaiveDateTime};
+    use crate::banca::banca::{Banca, ErroreBanca, IdConto, RicevutaCambio, RicevutaTrasferimento};
+    use crate::cambi::cambi::FornitoreCambi;
+    use crate::conto_bancario::conto_bancario::{ContoBancario, Conversione, Ricevuta};
+    use crate::denaro::denaro::{Denaro, Tasso, Valuta};
+    use crate::registro::registro::{Attraversamento, Movimento, Registro, TipoMovimento};
+    use crate::stati::stati::Stato;
+    use crate::titolari::titolari::{Ruolo, Titolare, Titolari};
+
+    /// Nome del file con il registro delle operazioni.
+    pub const FILE_LOG: &str = "operazioni.log";
+    /// Nome del file con l'ultima istantanea della banca.
+    pub const FILE_ISTANTANEA: &str = "istantanea.txt";
+    /// Nome del file in cui viene scritta l'istantanea prima di sostituire la precedente.
+    const FILE_TEMPORANEO: &str = "istantanea.tmp";
+    /// Formato delle date nell'archivio, con le frazioni di secondo perché la rilettura sia esatta.
+    const FORMATO_ISTANTE: &str = "%Y-%m-%d %H:%M:%S%.f";
+    /// Formato dei giorni nell'archivio.
+    const FORMATO_GIORNO: &str = "%Y-%m-%d";
+
+    /// Errori nella lettura o nella scrittura di un archivio.
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum ErroreArchivio {
+        /// Errore del file system.
+        Io(String),
+        /// La somma di controllo di una riga non corrisponde: il file è stato danneggiato.
+        Corrotto {
+            /// Nome del file.
+            file: String,
+            /// Numero della riga, a partire da 1.
+            riga: usize,
+        },
+        /// Una riga integra non ha il formato atteso.
+        Formato {
+            /// Nome del file.
+            file: String,
+            /// Numero della riga, a partire da 1.
+            riga: usize,
+            /// Descrizione del problema.
+            messaggio: String,
+        },
+        /// L'archivio contiene una banca in una valuta diversa da quella richiesta.
+        ValutaDiversa {
+            /// Valuta richiesta.
+            attesa: Valuta,
+            /// Valuta dell'archivio.
+            trovata: Valuta,
+        },
+        /// L'operazione è stata registrata ma la banca l'ha rifiutata.
+        Banca(ErroreBanca),
+    }
+
+    impl fmt::Display for ErroreArchivio {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                ErroreArchivio::Io(messaggio) => write!(f, "errore di lettura o scrittura: {}", messaggio),
+                ErroreArchivio::Corrotto { file, riga } => write!(f, "{}, riga {}: somma di controllo errata", file, riga),
+                ErroreArchivio::Formato { file, riga, messaggio } => write!(f, "{}, riga {}: {}", file, riga, messaggio),
+                ErroreArchivio::ValutaDiversa { attesa, trovata } => {
+                    write!(f, "l'archivio è in {}, richiesta una banca in {}", trovata, attesa)
+                }
+                ErroreArchivio::Banca(errore) => write!(f, "{}", errore),
+            }
+        }
+    }
+
+    impl std::error::Error for ErroreArchivio {}
+
+    impl From<io::Error> for ErroreArchivio {
+        fn from(errore: io::Error) -> Self {
+            ErroreArchivio::Io(errore.to_string())
+        }
+    }
+
+    impl From<ErroreBanca> for ErroreArchivio {
+        fn from(errore: ErroreBanca) -> Self {
+            ErroreArchivio::Banca(errore)
+        }
+    }
+
+    /// Calcola la somma di controllo CRC-32 (polinomio IEEE) dei dati.
+    fn crc32(dati: &[u8]) -> u32 {
+        let mut crc = !0u32;
+        for &byte in dati {
+            crc ^= byte as u32;
+            for _ in 0..8 {
+                crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+            }
+        }
+        !crc
+    }
+
+    /// Protegge tabulazioni, ritorni a capo e barre inverse di un testo libero.
+    fn proteggi(testo: &str) -> String {
+        testo.replace('\\', "\\\\").replace('\t', "\\t").replace('\n', "\\n")
+    }
+
+    /// Inverte `proteggi`.
+    fn sproteggi(testo: &str) -> String {
+        let mut risultato = String::with_capacity(testo.len());
+        let mut caratteri = testo.chars();
+        while let Some(c) = caratteri.next() {
+            if c != '\\' {
+                risultato.push(c);
+                continue;
+            }
+            match caratteri.next() {
+                Some('t') => risultato.push('\t'),
+                Some('n') => risultato.push('\n'),
+                Some(altro) => risultato.push(altro),
+                None => {}
+            }
+        }
+        risultato
+    }
+
+    /// Compone una riga dell'archivio: i campi separati da tabulazioni, seguiti dalla loro somma di controllo.
+    fn componi(campi: &[String]) -> String {
+        let contenuto = campi.join("\t");
+        format!("{}\t{:08x}\n", contenuto, crc32(contenuto.as_bytes()))
+    }
+
+    /// Verifica la somma di controllo di una riga (senza il ritorno a capo) e ne restituisce i campi.
+    fn scomponi(riga: &str) -> Option<Vec<String>> {
+        let (contenuto, somma) = riga.rsplit_once('\t')?;
+        let somma = u32::from_str_radix(somma, 16).ok()?;
+        (crc32(contenuto.as_bytes()) == somma).then(|| contenuto.split('\t').map(sproteggi).collect())
+    }
+
+    /// Lettore dei campi di una riga, che converte ogni campo nel tipo richiesto.
+    struct Campi<'a> {
+        campi: std::slice::Iter<'a, String>,
+    }
+
+    impl<'a> Campi<'a> {
+        fn nuovi(campi: &'a [String]) -> Campi<'a> {
+            Campi { campi: campi.iter() }
+        }
+
+        fn testo(&mut self) -> Result<&'a str, String> {
+            self.campi.next().map(String::as_str).ok_or_else(|| "campi mancanti".to_string())
+        }
+
+        fn numero(&mut self) -> Result<u64, String> {
+            let testo = self.testo()?;
+            testo.parse().map_err(|_| format!("numero non valido: {}", testo))
+        }
+
+        fn id(&mut self) -> Result<IdConto, String> {
+            Ok(IdConto(self.numero()? as u32))
+        }
+
+        fn denaro(&mut self) -> Result<Denaro, String> {
+            self.testo()?.parse().map_err(|errore| format!("{}", errore))
+        }
+
+        fn valuta(&mut self) -> Result<Valuta, String> {
+            Valuta::da_codice(self.testo()?).map_err(|errore| format!("{}", errore))
+        }
+
+        fn tasso(&mut self) -> Result<Tasso, String> {
+            self.testo()?.parse().map_err(|errore| format!("{}", errore))
+        }
+
+        fn data(&mut self) -> Result<NaiveDateTime, String> {
+            let testo = self.testo()?;
+            NaiveDateTime::parse_from_str(testo, FORMATO_ISTANTE).map_err(|_| format!("data non valida: {}", testo))
+        }
+
+        fn giorno(&mut self) -> Result<NaiveDate, String> {
+            let testo = self.testo()?;
+            NaiveDate::parse_from_str(testo, FORMATO_GIORNO).map_err(|_| format!("giorno non valido: {}", testo))
+        }
+
+        /// Legge un importo facoltativo, scritto come `-` se assente.
+        fn facoltativo(&mut self) -> Result<Option<Denaro>, String> {
+            match self.testo()? {
+                "-" => Ok(None),
+                testo => testo.parse().map(Some).map_err(|errore| format!("{}", errore)),
+            }
+        }
+
+        /// Legge un titolare scritto da `campi_titolare`.
+        fn titolare(&mut self) -> Result<Titolare, String> {
+            let nome = self.testo()?;
+            let (ruolo, tetto, limite) = (self.testo()?, self.facoltativo()?, self.facoltativo()?);
+            let ruolo = match (ruolo, tetto) {
+                ("intestatario", None) => Ruolo::Intestatario,
+                ("cointestatario", None) => Ruolo::Cointestatario,
+                ("lettura", None) => Ruolo::SolaLettura,
+                ("delegato", Some(tetto)) => Ruolo::Delegato { tetto },
+                (altro, _) => return Err(format!("ruolo non valido: {}", altro)),
+            };
+            Ok(Titolare { nome: nome.to_string(), ruolo, limite_giornaliero: limite })
+        }
+
+        fn booleano(&mut self) -> Result<bool, String> {
+            match self.testo()? {
+                "0" => Ok(false),
+                "1" => Ok(true),
+                altro => Err(format!("valore non valido: {}", altro)),
+            }
+        }
+    }
+
+    /// Restituisce i campi di un titolare: nome, ruolo, tetto e limite giornaliero (`-` se assenti).
+    fn campi_titolare(titolare: &Titolare) -> Vec<String> {
+        let (ruolo, tetto) = match titolare.ruolo {
+            Ruolo::Intestatario => ("intestatario", None),
+            Ruolo::Cointestatario => ("cointestatario", None),
+            Ruolo::SolaLettura => ("lettura", None),
+            Ruolo::Delegato { tetto } => ("delegato", Some(tetto)),
+        };
+        let facoltativo = |importo: Option<Denaro>| importo.map_or("-".to_string(), |importo| importo.to_string());
+        vec![proteggi(&titolare.nome), ruolo.to_string(), facoltativo(tetto), facoltativo(titolare.limite_giornaliero)]
+    }
+
+    /// Operazione sulla banca, come viene scritta nel registro delle operazioni.
+    #[derive(Debug, Clone, PartialEq)]
+    enum Comando {
+        Apri { nome: String, saldo: Denaro, limite_inferiore: Denaro, limite_superiore: Denaro, interesse: Tasso, fido: Denaro },
+        Chiudi { id: IdConto, data: NaiveDateTime },
+        Deposita { id: IdConto, importo: Denaro, data: NaiveDateTime },
+        Preleva { id: IdConto, importo: Denaro, data: NaiveDateTime },
+        PagaInteressi { id: IdConto, data: NaiveDateTime },
+        Trasferisci { da: IdConto, a: IdConto, importo: Denaro, data: NaiveDateTime },
+        ApriDivisa { id: IdConto, valuta: Valuta },
+        /// Trasferimento con cambio, con la conversione calcolata quando è stato chiesto: il ripristino
+        /// la applica così com'è, anche se nel frattempo i tassi sono cambiati.
+        TrasferisciConCambio { da: IdConto, a: IdConto, conversione: Conversione, data: NaiveDateTime },
+        Blocca(IdConto),
+        Sblocca(IdConto),
+        AggiungiTitolare { id: IdConto, da: String, titolare: Titolare },
+        RimuoviTitolare { id: IdConto, da: String, nome: String },
+        /// Deposito, prelievo o trasferimento eseguito per conto di un titolare.
+        Come { titolare: String, comando: Box<Comando> },
+    }
+
+    impl Comando {
+        /// Restituisce i campi del comando.
+        fn campi(&self) -> Vec<String> {
+            let data = |data: &NaiveDateTime| data.format(FORMATO_ISTANTE).to_string();
+            match self {
+                Comando::Apri { nome, saldo, limite_inferiore, limite_superiore, interesse, fido } => vec![
+                    "apri".to_string(),
+                    proteggi(nome),
+                    saldo.to_string(),
+                    limite_inferiore.to_string(),
+                    limite_superiore.to_string(),
+                    interesse.to_string(),
+                    fido.to_string(),
+                ],
+                Comando::Chiudi { id, data: il } => vec!["chiudi".to_string(), id.0.to_string(), data(il)],
+                Comando::Deposita { id, importo, data: il } => vec!["deposita".to_string(), id.0.to_string(), importo.to_string(), data(il)],
+                Comando::Preleva { id, importo, data: il } => vec!["preleva".to_string(), id.0.to_string(), importo.to_string(), data(il)],
+                Comando::PagaInteressi { id, data: il } => vec!["interessi".to_string(), id.0.to_string(), data(il)],
+                Comando::Trasferisci { da, a, importo, data: il } => {
+                    vec!["trasferisci".to_string(), da.0.to_string(), a.0.to_string(), importo.to_string(), data(il)]
+                }
+                Comando::ApriDivisa { id, valuta } => vec!["divisa".to_string(), id.0.to_string(), valuta.codice().to_string()],
+                Comando::TrasferisciConCambio { da, a, conversione, data: il } => vec![
+                    "cambia".to_string(),
+                    da.0.to_string(),
+                    a.0.to_string(),
+                    conversione.importo.to_string(),
+                    conversione.commissione.to_string(),
+                    conversione.tasso.to_string(),
+                    conversione.controvalore.to_string(),
+                    data(il),
+                ],
+                Comando::Blocca(id) => vec!["blocca".to_string(), id.0.to_string()],
+                Comando::Sblocca(id) => vec!["sblocca".to_string(), id.0.to_string()],
+                Comando::AggiungiTitolare { id, da, titolare } => {
+                    let mut campi = vec!["titolare".to_string(), id.0.to_string(), proteggi(da)];
+                    campi.extend(campi_titolare(titolare));
+                    campi
+                }
+                Comando::RimuoviTitolare { id, da, nome } => vec!["rimuovi".to_string(), id.0.to_string(), proteggi(da), proteggi(nome)],
+                Comando::Come { titolare, comando } => {
+                    let mut campi = vec!["come".to_string(), proteggi(titolare)];
+                    campi.extend(comando.campi());
+                    campi
+                }
+            }
+        }
+
+        /// Legge un comando dai suoi campi.
+        fn leggi(campi: &mut Campi) -> Result<Comando, String> {
+            Ok(match campi.testo()? {
+                "apri" => Comando::Apri {
+                    nome: campi.testo()?.to_string(),
+                    saldo: campi.denaro()?,
+                    limite_inferiore: campi.denaro()?,
+                    limite_superiore: campi.denaro()?,
+                    interesse: campi.tasso()?,
+                    fido: campi.denaro()?,
+                },
+                "chiudi" => Comando::Chiudi { id: campi.id()?, data: campi.data()? },
+                "deposita" => Comando::Deposita { id: campi.id()?, importo: campi.denaro()?, data: campi.data()? },
+                "preleva" => Comando::Preleva { id: campi.id()?, importo: campi.denaro()?, data: campi.data()? },
+                "interessi" => Comando::PagaInteressi { id: campi.id()?, data: campi.data()? },
+                "trasferisci" => Comando::Trasferisci { da: campi.id()?, a: campi.id()?, importo: campi.denaro()?, data: campi.data()? },
+                "divisa" => Comando::ApriDivisa { id: campi.id()?, valuta: campi.valuta()? },
+                "cambia" => Comando::TrasferisciConCambio {
+                    da: campi.id()?,
+                    a: campi.id()?,
+                    conversione: Conversione {
+                        importo: campi.denaro()?,
+                        commissione: campi.denaro()?,
+                        tasso: campi.tasso()?,
+                        controvalore: campi.denaro()?,
+                    },
+                    data: campi.data()?,
+                },
+                "blocca" => Comando::Blocca(campi.id()?),
+                "sblocca" => Comando::Sblocca(campi.id()?),
+                "titolare" => Comando::AggiungiTitolare { id: campi.id()?, da: campi.testo()?.to_string(), titolare: campi.titolare()? },
+                "rimuovi" => Comando::RimuoviTitolare { id: campi.id()?, da: campi.testo()?.to_string(), nome: campi.testo()?.to_string() },
+                "come" => {
+                    let titolare = campi.testo()?.to_string();
+                    let comando = Comando::leggi(campi)?;
+                    if !matches!(comando, Comando::Deposita { .. } | Comando::Preleva { .. } | Comando::Trasferisci { .. } | Comando::TrasferisciConCambio { .. }) {
+                        return Err("operazione non consentita per un titolare".to_string());
+                    }
+                    Comando::Come { titolare, comando: Box::new(comando) }
+                }
+                altro => return Err(format!("operazione sconosciuta: {}", altro)),
+            })
+        }
+
+        /// Riesegue il comando sulla banca durante il ripristino. Gli errori vengono ignorati:
+        /// la banca li aveva già restituiti la prima volta, con lo stesso effetto nullo.
+        fn riesegui(&self, banca: &mut Banca, cambi: Option<&Arc<dyn FornitoreCambi>>) {
+            let _ = match self {
+                Comando::Apri { nome, saldo, limite_inferiore, limite_superiore, interesse, fido } => {
+                    banca.apri(nuovo_conto(nome, *saldo, *limite_inferiore, *limite_superiore, *interesse, *fido, cambi)).map(|_| ())
+                }
+                Comando::Chiudi { id, data } => banca.chiudi_il(*id, *data).map(|_| ()),
+                Comando::Deposita { id, importo, data } => banca.deposita_il(*id, *importo, *data).map(|_| ()),
+                Comando::Preleva { id, importo, data } => banca.preleva_il(*id, *importo, *data).map(|_| ()),
+                Comando::PagaInteressi { id, data } => banca.paga_interessi_il(*id, *data).map(|_| ()),
+                Comando::Trasferisci { da, a, importo, data } => banca.trasferisci_il(*da, *a, *importo, *data).map(|_| ()),
+                Comando::ApriDivisa { id, valuta } => banca.apri_divisa(*id, *valuta),
+                Comando::TrasferisciConCambio { da, a, conversione, data } => banca.cambia_il(*da, *a, *conversione, *data).map(|_| ()),
+                Comando::Blocca(id) => banca.blocca(*id),
+                Comando::Sblocca(id) => banca.sblocca(*id),
+                Comando::AggiungiTitolare { id, da, titolare } => banca.aggiungi_titolare(*id, da, titolare.clone()),
+                Comando::RimuoviTitolare { id, da, nome } => banca.rimuovi_titolare(*id, da, nome).map(|_| ()),
+                Comando::Come { titolare, comando } => match comando.as_ref() {
+                    Comando::Deposita { id, importo, data } => banca.deposita_come_il(titolare, *id, *importo, *data).map(|_| ()),
+                    Comando::Preleva { id, importo, data } => banca.preleva_come_il(titolare, *id, *importo, *data).map(|_| ()),
+                    Comando::Trasferisci { da, a, importo, data } => banca.trasferisci_come_il(titolare, *da, *a, *importo, *data).map(|_| ()),
+                    Comando::TrasferisciConCambio { da, a, conversione, data } => {
+                        banca.cambia_come_il(titolare, *da, *a, *conversione, *data).map(|_| ())
+                    }
+                    // `leggi` accetta solo le operazioni precedenti
+                    _ => Ok(()),
+                },
+            };
+        }
+    }
+
+    /// Crea un conto con i dati salvati nell'archivio e il fornitore dei cambi della banca, se presente.
+    fn nuovo_conto(
+        nome: &str,
+        saldo: Denaro,
+        limite_inferiore: Denaro,
+        limite_superiore: Denaro,
+        interesse: Tasso,
+        fido: Denaro,
+        cambi: Option<&Arc<dyn FornitoreCambi>>,
+    ) -> ContoBancario {
+        let mut conto = ContoBancario::new(nome.to_string(), saldo, limite_inferiore, limite_superiore, interesse);
+        conto.fido = fido;
+        if let Some(cambi) = cambi {
+            conto.imposta_cambi(Arc::clone(cambi));
+        }
+        conto
+    }
+
+    /// Legge il tipo di un movimento dal nome e dal dettaglio scritti nell'istantanea.
+    fn tipo_movimento(nome: &str, dettaglio: &str) -> Result<TipoMovimento, String> {
+        Ok(match nome {
+            "Deposito" => TipoMovimento::Deposito,
+            "Prelievo" => TipoMovimento::Prelievo,
+            "Interessi" => TipoMovimento::Interessi,
+            "Commissione" => TipoMovimento::Commissione,
+            "CommissioneCambio" => TipoMovimento::CommissioneCambio,
+            "CambioStato" => {
+                let (da, a) = dettaglio.split_once("->").ok_or_else(|| format!("cambio di stato non valido: {}", dettaglio))?;
+                TipoMovimento::CambioStato { da: Stato::da_nome(da), a: Stato::da_nome(a) }
+            }
+            "Avviso" => {
+                let (verso, soglia) = dettaglio.split_once(' ').ok_or_else(|| format!("avviso non valido: {}", dettaglio))?;
+                let verso = match verso {
+                    "sotto" => Attraversamento::Discesa,
+                    "sopra" => Attraversamento::Salita,
+                    altro => return Err(format!("verso non valido: {}", altro)),
+                };
+                TipoMovimento::Avviso { soglia: soglia.parse().map_err(|errore| format!("{}", errore))?, verso }
+            }
+            altro => return Err(format!("tipo di movimento sconosciuto: {}", altro)),
+        })
+    }
+
+    /// Voce valida del registro delle operazioni: numero di riga, numero di sequenza e comando.
+    type Voce = (usize, u64, Comando);
+
+    /// Legge il registro delle operazioni e restituisce le voci valide e la lunghezza in byte della
+    /// parte valida. Un'ultima riga incompleta o danneggiata è il segno di una scrittura interrotta
+    /// e viene scartata; una riga danneggiata seguita da altre righe è invece un errore.
+    fn leggi_log(percorso: &Path) -> Result<(Vec<Voce>, u64), ErroreArchivio> {
+        let contenuto = match fs::read(percorso) {
+            Ok(contenuto) => contenuto,
+            Err(errore) if errore.kind() == io::ErrorKind::NotFound => return Ok((Vec::new(), 0)),
+            Err(errore) => return Err(errore.into()),
+        };
+        let mut voci = Vec::new();
+        let mut valida = 0;
+        for (indice, pezzo) in contenuto.split_inclusive(|&byte| byte == b'\n').enumerate() {
+            let riga = indice + 1;
+            let campi = pezzo
+                .strip_suffix(b"\n")
+                .and_then(|pezzo| std::str::from_utf8(pezzo).ok())
+                .and_then(scomponi);
+            let Some(campi) = campi else {
+                if valida + pezzo.len() == contenuto.len() {
+                    break;
+                }
+                return Err(ErroreArchivio::Corrotto { file: FILE_LOG.to_string(), riga });
+            };
+            let errore = |messaggio| ErroreArchivio::Formato { file: FILE_LOG.to_string(), riga, messaggio };
+            let mut lettore = Campi::nuovi(&campi);
+            let sequenza = lettore.numero().map_err(errore)?;
+            let comando = Comando::leggi(&mut lettore).map_err(errore)?;
+            voci.push((riga, sequenza, comando));
+            valida += pezzo.len();
+        }
+        Ok((voci, valida as u64))
+    }
+
+    /// Registro letto dall'istantanea: saldo di apertura e movimenti.
+    type RegistroLetto = (Denaro, Vec<Movimento>);
+    /// Titolari letti dall'istantanea, con i loro prelievi: nome, giorno, importo del giorno e complessivo.
+    type TitolariLetti = (Vec<Titolare>, Vec<(String, NaiveDate, Denaro, Denaro)>);
+
+    /// Legge l'istantanea, se esiste, e restituisce la banca e il numero dell'ultima operazione che contiene.
+    ///
+    /// I movimenti seguono il conto o la divisa a cui appartengono; le divise seguono i movimenti del conto.
+    /// La data di chiusura di un conto chiuso, i titolari e i loro prelievi seguono la riga del conto.
+    fn leggi_istantanea(percorso: &Path, cambi: Option<&Arc<dyn FornitoreCambi>>) -> Result<Option<(Banca, u64)>, ErroreArchivio> {
+        let contenuto = match fs::read_to_string(percorso) {
+            Ok(contenuto) => contenuto,
+            Err(errore) if errore.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(errore) => return Err(errore.into()),
+        };
+        let corrotto = |riga| ErroreArchivio::Corrotto { file: FILE_ISTANTANEA.to_string(), riga };
+        let mut banca = None;
+        let mut conti: BTreeMap<IdConto, (ContoBancario, RegistroLetto, Vec<RegistroLetto>, Option<NaiveDateTime>)> = BTreeMap::new();
+        let mut titolari: BTreeMap<IdConto, TitolariLetti> = BTreeMap::new();
+        let mut ultimo_conto = None;
+        let mut righe = 0;
+        for (indice, testo) in contenuto.lines().enumerate() {
+            let riga = indice + 1;
+            let campi = scomponi(testo).ok_or(corrotto(riga))?;
+            let errore = |messaggio| ErroreArchivio::Formato { file: FILE_ISTANTANEA.to_string(), riga, messaggio };
+            let mut lettore = Campi::nuovi(&campi);
+            match lettore.testo().map_err(errore)? {
+                "banca" => {
+                    let lettura: Result<_, String> = (|| {
+                        let valuta = Valuta::da_codice(lettore.testo()?).map_err(|errore| format!("{}", errore))?;
+                        Ok((valuta, lettore.numero()? as u32, lettore.denaro()?, lettore.numero()?))
+                    })();
+                    banca = Some(lettura.map_err(errore)?);
+                }
+                "conto" => {
+                    let lettura: Result<_, String> = (|| {
+                        let id = lettore.id()?;
+                        let nome = lettore.testo()?;
+                        let (inferiore, superiore, interesse, fido) = (lettore.denaro()?, lettore.denaro()?, lettore.tasso()?, lettore.denaro()?);
+                        let bloccato = lettore.booleano()?;
+                        let apertura = lettore.denaro()?;
+                        let mut conto = nuovo_conto(nome, apertura, inferiore, superiore, interesse, fido, cambi);
+                        conto.bloccato = bloccato;
+                        Ok((id, conto, apertura))
+                    })();
+                    let (id, conto, apertura) = lettura.map_err(errore)?;
+                    conti.insert(id, (conto, (apertura, Vec::new()), Vec::new(), None));
+                    ultimo_conto = Some(id);
+                }
+                "chiuso" => {
+                    let data = lettore.data().map_err(errore)?;
+                    let conto = ultimo_conto.and_then(|id| conti.get_mut(&id)).ok_or_else(|| errore("chiusura senza conto".to_string()))?;
+                    conto.3 = Some(data);
+                }
+                "titolare" => {
+                    let titolare = lettore.titolare().map_err(errore)?;
+                    let id = ultimo_conto.ok_or_else(|| errore("titolare senza conto".to_string()))?;
+                    titolari.entry(id).or_default().0.push(titolare);
+                }
+                "spesa" => {
+                    let lettura: Result<_, String> = (|| Ok((lettore.testo()?.to_string(), lettore.giorno()?, lettore.denaro()?, lettore.denaro()?)))();
+                    let spesa = lettura.map_err(errore)?;
+                    let id = ultimo_conto.ok_or_else(|| errore("spesa senza conto".to_string()))?;
+                    titolari.entry(id).or_default().1.push(spesa);
+                }
+                "divisa" => {
+                    let apertura = lettore.denaro().map_err(errore)?;
+                    let conto = ultimo_conto.and_then(|id| conti.get_mut(&id)).ok_or_else(|| errore("divisa senza conto".to_string()))?;
+                    conto.2.push((apertura, Vec::new()));
+                }
+                "movimento" => {
+                    let lettura: Result<_, String> = (|| {
+                        let (sequenza, data) = (lettore.numero()?, lettore.data()?);
+                        let tipo = tipo_movimento(lettore.testo()?, lettore.testo()?)?;
+                        Ok(Movimento { sequenza, data, tipo, variazione: lettore.denaro()?, saldo: lettore.denaro()? })
+                    })();
+                    let movimento = lettura.map_err(errore)?;
+                    let conto = ultimo_conto.and_then(|id| conti.get_mut(&id)).ok_or_else(|| errore("movimento senza conto".to_string()))?;
+                    let (registro, divise) = (&mut conto.1, &mut conto.2);
+                    divise.last_mut().unwrap_or(registro).1.push(movimento);
+                }
+                "fine" => {
+                    let attese = lettore.numero().map_err(errore)?;
+                    if attese != indice as u64 {
+                        return Err(corrotto(riga));
+                    }
+                    righe = riga;
+                    break;
+                }
+                altro => return Err(errore(format!("voce sconosciuta: {}", altro))),
+            }
+        }
+        // Senza la riga finale l'istantanea è stata troncata
+        if righe == 0 {
+            return Err(corrotto(contenuto.lines().count() + 1));
+        }
+        let (valuta, prossimo_id, totale_atteso, sequenza) =
+            banca.ok_or_else(|| ErroreArchivio::Formato { file: FILE_ISTANTANEA.to_string(), riga: 1, messaggio: "intestazione mancante".to_string() })?;
+        let (mut ripristinati, mut chiusi) = (BTreeMap::new(), BTreeMap::new());
+        for (id, (mut conto, (apertura, movimenti), divise, chiusura)) in conti {
+            for (apertura, movimenti) in divise {
+                conto.ripristina_divisa(Registro::da_movimenti(apertura, movimenti));
+            }
+            conto
+                .ripristina_registro(Registro::da_movimenti(apertura, movimenti))
+                .map_err(|errore| ErroreBanca::Conto { id, errore })?;
+            // Le istantanee senza titolari lasciano il solo intestatario del conto
+            if let Some((elenco, spese)) = titolari.remove(&id) {
+                conto.ripristina_titolari(Titolari::ripristinati(conto.saldo().valuta(), elenco, spese));
+            }
+            match chiusura {
+                Some(data) => {
+                    chiusi.insert(id, (conto, data));
+                }
+                None => {
+                    ripristinati.insert(id, conto);
+                }
+            }
+        }
+        Ok(Some((Banca::ripristinata(valuta, ripristinati, chiusi, prossimo_id, totale_atteso), sequenza)))
+    }
+
+    /// Banca salvata in una cartella: ogni operazione viene scritta e sincronizzata sul registro
+    /// delle operazioni prima di essere eseguita, e ogni tanto lo stato completo della banca viene
+    /// salvato in un'istantanea, dopo la quale il registro riparte vuoto.
+    ///
+    /// All'apertura la banca viene ricostruita dall'ultima istantanea rieseguendo le operazioni
+    /// registrate dopo di essa. Ogni riga di entrambi i file ha una somma di controllo CRC-32:
+    /// un'ultima riga del registro incompleta o danneggiata (un'interruzione durante la scrittura)
+    /// viene scartata, ogni altra riga danneggiata è un errore `ErroreArchivio::Corrotto`.
+    ///
+    /// Dei conti vengono salvati i dati con cui sono stati aperti, il fido, il blocco, il registro
+    /// dei movimenti, le divise con i loro registri e i titolari con i loro prelievi; fasce, commissioni, soglie e orologio restano
+    /// quelli predefiniti. Per le divise serve il fornitore dei cambi passato a `apri_con_cambi`,
+    /// che viene assegnato a tutti i conti.
+    ///
+    /// # Esempio
+    ///
+    /// ```
+    /// use esercizio5::archivio::archivio::BancaPersistente;
+    /// use esercizio5::denaro::denaro::{Denaro, Tasso, Valuta};
+    ///
+    /// let euro = |importo: &str| Denaro::da_decimale(importo, Valuta::EUR).unwrap();
+    /// let cartella = std::env::temp_dir().join(format!("esempio-archivio-{}", std::process::id()));
+    /// let mut banca = BancaPersistente::apri(&cartella, Valuta::EUR).unwrap();
+    /// let mario = banca.apri_conto("Mario Rossi", euro("100"), euro("0"), euro("5000"), Tasso::ZERO, euro("0")).unwrap();
+    /// banca.deposita(mario, euro("50")).unwrap();
+    /// drop(banca);
+    ///
+    /// let banca = BancaPersistente::apri(&cartella, Valuta::EUR).unwrap();
+    /// assert_eq!(banca.banca().conto(mario).unwrap().saldo(), euro("150"));
+    /// # std::fs::remove_dir_all(&cartella).unwrap();
+    /// ```
+    pub struct BancaPersistente {
+        banca: Banca,
+        cartella: PathBuf,
+        cambi: Option<Arc<dyn FornitoreCambi>>,
+        log: File,
+        sequenza: u64,
+        operazioni_nel_log: u32,
+        istantanea_ogni: u32,
+    }
+
+    impl BancaPersistente {
+        /// Apre la banca salvata nella cartella indicata, creandola vuota se non esiste, e la ripristina
+        /// dall'ultima istantanea e dal registro delle operazioni.
+        ///
+        /// # Argomenti
+        ///
+        /// * `cartella` - Cartella dell'archivio.
+        /// * `valuta` - Valuta della banca.
+        pub fn apri(cartella: impl AsRef<Path>, valuta: Valuta) -> Result<BancaPersistente, ErroreArchivio> {
+            BancaPersistente::apri_archivio(cartella.as_ref(), valuta, None)
+        }
+
+        /// Come `apri`, assegnando a tutti i conti il fornitore dei cambi indicato, necessario per
+        /// aprire divise, operare su di esse e ripristinare i conti che ne hanno.
+        ///
+        /// # Argomenti
+        ///
+        /// * `cartella` - Cartella dell'archivio.
+        /// * `valuta` - Valuta della banca.
+        /// * `cambi` - Fornitore dei tassi di cambio.
+        pub fn apri_con_cambi(cartella: impl AsRef<Path>, valuta: Valuta, cambi: Arc<dyn FornitoreCambi>) -> Result<BancaPersistente, ErroreArchivio> {
+            BancaPersistente::apri_archivio(cartella.as_ref(), valuta, Some(cambi))
+        }
+
+        /// Apre l'archivio con il fornitore dei cambi indicato, se presente.
+        fn apri_archivio(cartella: &Path, valuta: Valuta, cambi: Option<Arc<dyn FornitoreCambi>>) -> Result<BancaPersistente, ErroreArchivio> {
+            let cartella = cartella.to_path_buf();
+            fs::create_dir_all(&cartella)?;
+            let istantanea = leggi_istantanea(&cartella.join(FILE_ISTANTANEA), cambi.as_ref())?;
+            let nuova = istantanea.is_none();
+            let (mut banca, mut sequenza) = istantanea.unwrap_or((Banca::nuova(valuta), 0));
+            if banca.valuta() != valuta {
+                return Err(ErroreArchivio::ValutaDiversa { attesa: valuta, trovata: banca.valuta() });
+            }
+            let percorso_log = cartella.join(FILE_LOG);
+            let (voci, valida) = leggi_log(&percorso_log)?;
+            let mut operazioni_nel_log = 0;
+            for (riga, numero, comando) in voci {
+                // Le operazioni già comprese nell'istantanea restano nel registro se l'interruzione
+                // è avvenuta prima che venisse svuotato
+                if numero <= sequenza {
+                    continue;
+                }
+                if numero != sequenza + 1 {
+                    return Err(ErroreArchivio::Corrotto { file: FILE_LOG.to_string(), riga });
+                }
+                comando.riesegui(&mut banca, cambi.as_ref());
+                sequenza = numero;
+                operazioni_nel_log += 1;
+            }
+            let log = OpenOptions::new().create(true).append(true).open(&percorso_log)?;
+            log.set_len(valida)?;
+            let mut archivio = BancaPersistente { banca, cartella, cambi, log, sequenza, operazioni_nel_log, istantanea_ogni: 100 };
+            // Un archivio nuovo parte da un'istantanea vuota, che ne fissa la valuta
+            if nuova {
+                archivio.istantanea()?;
+            }
+            Ok(archivio)
+        }
+
+        /// Imposta ogni quante operazioni registrate salvare un'istantanea (zero per non salvarle mai da sola).
+        pub fn con_istantanea_ogni(mut self, operazioni: u32) -> BancaPersistente {
+            self.istantanea_ogni = operazioni;
+            self
+        }
+
+        /// Restituisce la banca, in sola lettura: le modifiche passano dai metodi che le registrano.
+        pub fn banca(&self) -> &Banca {
+            &self.banca
+        }
+
+        /// Restituisce la cartella dell'archivio.
+        pub fn cartella(&self) -> &Path {
+            &self.cartella
+        }
+
+        /// Restituisce il numero dell'ultima operazione registrata.
+        pub fn sequenza(&self) -> u64 {
+            self.sequenza
+        }
+
+        /// Apre un nuovo conto con le regole di `ContoBancario::new` e il fido indicato.
+        ///
+        /// # Argomenti
+        ///
+        /// * `nome_cliente` - Nome del cliente associato al conto.
+        /// * `saldo` - Saldo iniziale del conto, nella valuta della banca.
+        /// * `limite_inferiore` - Limite inferiore del saldo del conto.
+        /// * `limite_superiore` - Limite superiore del saldo del conto.
+        /// * `interesse` - Tasso di interesse applicato al conto.
+        /// * `fido` - Fido accordato.
+        pub fn apri_conto(
+            &mut self,
+            nome_cliente: &str,
+            saldo: Denaro,
+            limite_inferiore: Denaro,
+            limite_superiore: Denaro,
+            interesse: Tasso,
+            fido: Denaro,
+        ) -> Result<IdConto, ErroreArchivio> {
+            self.registra(Comando::Apri { nome: nome_cliente.to_string(), saldo, limite_inferiore, limite_superiore, interesse, fido })?;
+            let conto = nuovo_conto(nome_cliente, saldo, limite_inferiore, limite_superiore, interesse, fido, self.cambi.as_ref());
+            Ok(self.banca.apri(conto)?)
+        }
+
+        /// Chiude un conto, come `Banca::chiudi`, e lo restituisce.
+        pub fn chiudi_conto(&mut self, id: IdConto) -> Result<&ContoBancario, ErroreArchivio> {
+            let data = self.adesso(&[id])?;
+            self.registra(Comando::Chiudi { id, data })?;
+            Ok(self.banca.chiudi_il(id, data)?)
+        }
+
+        /// Deposita denaro proveniente dall'esterno della banca su un conto.
+        ///
+        /// # Argomenti
+        ///
+        /// * `id` - Identificativo del conto.
+        /// * `importo` - Importo da depositare.
+        pub fn deposita(&mut self, id: IdConto, importo: Denaro) -> Result<Ricevuta, ErroreArchivio> {
+            let data = self.adesso(&[id])?;
+            self.registra(Comando::Deposita { id, importo, data })?;
+            Ok(self.banca.deposita_il(id, importo, data)?)
+        }
+
+        /// Preleva denaro da un conto verso l'esterno della banca.
+        ///
+        /// # Argomenti
+        ///
+        /// * `id` - Identificativo del conto.
+        /// * `importo` - Importo da prelevare.
+        pub fn preleva(&mut self, id: IdConto, importo: Denaro) -> Result<Ricevuta, ErroreArchivio> {
+            let data = self.adesso(&[id])?;
+            self.registra(Comando::Preleva { id, importo, data })?;
+            Ok(self.banca.preleva_il(id, importo, data)?)
+        }
+
+        /// Accredita gli interessi su un conto secondo le regole del suo stato.
+        pub fn paga_interessi(&mut self, id: IdConto) -> Result<Ricevuta, ErroreArchivio> {
+            let data = self.adesso(&[id])?;
+            self.registra(Comando::PagaInteressi { id, data })?;
+            Ok(self.banca.paga_interessi_il(id, data)?)
+        }
+
+        /// Trasferisce un importo tra due conti in modo atomico.
+        ///
+        /// # Argomenti
+        ///
+        /// * `da` - Conto di origine.
+        /// * `a` - Conto di destinazione.
+        /// * `importo` - Importo da trasferire.
+        pub fn trasferisci(&mut self, da: IdConto, a: IdConto, importo: Denaro) -> Result<RicevutaTrasferimento, ErroreArchivio> {
+            let data = self.adesso(&[da, a])?;
+            self.registra(Comando::Trasferisci { da, a, importo, data })?;
+            Ok(self.banca.trasferisci_il(da, a, importo, data)?)
+        }
+
+        /// Apre una divisa sul conto indicato, come `Banca::apri_divisa`.
+        ///
+        /// # Argomenti
+        ///
+        /// * `id` - Identificativo del conto.
+        /// * `valuta` - Valuta della divisa.
+        pub fn apri_divisa(&mut self, id: IdConto, valuta: Valuta) -> Result<(), ErroreArchivio> {
+            self.registra(Comando::ApriDivisa { id, valuta })?;
+            Ok(self.banca.apri_divisa(id, valuta)?)
+        }
+
+        /// Trasferisce un importo convertendolo in un'altra valuta, come `Banca::trasferisci_con_cambio_il`.
+        ///
+        /// # Argomenti
+        ///
+        /// * `da` - Conto di origine.
+        /// * `a` - Conto di destinazione.
+        /// * `importo` - Importo da trasferire, nella valuta di origine.
+        /// * `verso` - Valuta in cui accreditare il controvalore.
+        pub fn trasferisci_con_cambio(&mut self, da: IdConto, a: IdConto, importo: Denaro, verso: Valuta) -> Result<RicevutaCambio, ErroreArchivio> {
+            let data = self.adesso(&[da, a])?;
+            let conversione = self.banca.preventivo_cambio(da, importo, verso)?;
+            self.registra(Comando::TrasferisciConCambio { da, a, conversione, data })?;
+            Ok(self.banca.cambia_il(da, a, conversione, data)?)
+        }
+
+        /// Blocca un conto.
+        pub fn blocca(&mut self, id: IdConto) -> Result<(), ErroreArchivio> {
+            self.registra(Comando::Blocca(id))?;
+            Ok(self.banca.blocca(id)?)
+        }
+
+        /// Sblocca un conto.
+        pub fn sblocca(&mut self, id: IdConto) -> Result<(), ErroreArchivio> {
+            self.registra(Comando::Sblocca(id))?;
+            Ok(self.banca.sblocca(id)?)
+        }
+
+        /// Aggiunge un titolare al conto indicato, come `Banca::aggiungi_titolare`.
+        ///
+        /// # Argomenti
+        ///
+        /// * `id` - Identificativo del conto.
+        /// * `da` - Nome del titolare che chiede l'operazione.
+        /// * `titolare` - Il nuovo titolare.
+        pub fn aggiungi_titolare(&mut self, id: IdConto, da: &str, titolare: Titolare) -> Result<(), ErroreArchivio> {
+            self.registra(Comando::AggiungiTitolare { id, da: da.to_string(), titolare: titolare.clone() })?;
+            Ok(self.banca.aggiungi_titolare(id, da, titolare)?)
+        }
+
+        /// Rimuove un titolare dal conto indicato, come `Banca::rimuovi_titolare`.
+        ///
+        /// # Argomenti
+        ///
+        /// * `id` - Identificativo del conto.
+        /// * `da` - Nome del titolare che chiede l'operazione.
+        /// * `nome` - Nome del titolare da rimuovere.
+        pub fn rimuovi_titolare(&mut self, id: IdConto, da: &str, nome: &str) -> Result<Titolare, ErroreArchivio> {
+            self.registra(Comando::RimuoviTitolare { id, da: da.to_string(), nome: nome.to_string() })?;
+            Ok(self.banca.rimuovi_titolare(id, da, nome)?)
+        }
+
+        /// Come `deposita`, per conto di un titolare autorizzato al deposito.
+        ///
+        /// # Argomenti
+        ///
+        /// * `titolare` - Nome del titolare del conto.
+        /// * `id` - Identificativo del conto.
+        /// * `importo` - Importo da depositare.
+        pub fn deposita_come(&mut self, titolare: &str, id: IdConto, importo: Denaro) -> Result<Ricevuta, ErroreArchivio> {
+            let data = self.adesso(&[id])?;
+            self.registra_come(titolare, Comando::Deposita { id, importo, data })?;
+            Ok(self.banca.deposita_come_il(titolare, id, importo, data)?)
+        }
+
+        /// Come `preleva`, per conto di un titolare autorizzato al prelievo.
+        ///
+        /// # Argomenti
+        ///
+        /// * `titolare` - Nome del titolare del conto.
+        /// * `id` - Identificativo del conto.
+        /// * `importo` - Importo da prelevare.
+        pub fn preleva_come(&mut self, titolare: &str, id: IdConto, importo: Denaro) -> Result<Ricevuta, ErroreArchivio> {
+            let data = self.adesso(&[id])?;
+            self.registra_come(titolare, Comando::Preleva { id, importo, data })?;
+            Ok(self.banca.preleva_come_il(titolare, id, importo, data)?)
+        }
+
+        /// Come `trasferisci`, per conto di un titolare del conto di origine autorizzato al prelievo.
+        ///
+        /// # Argomenti
+        ///
+        /// * `titolare` - Nome del titolare del conto di origine.
+        /// * `da` - Conto di origine.
+        /// * `a` - Conto di destinazione.
+        /// * `importo` - Importo da trasferire.
+        pub fn trasferisci_come(&mut self, titolare: &str, da: IdConto, a: IdConto, importo: Denaro) -> Result<RicevutaTrasferimento, ErroreArchivio> {
+            let data = self.adesso(&[da, a])?;
+            self.registra_come(titolare, Comando::Trasferisci { da, a, importo, data })?;
+            Ok(self.banca.trasferisci_come_il(titolare, da, a, importo, data)?)
+        }
+
+        /// Come `trasferisci_con_cambio`, per conto di un titolare del conto di origine autorizzato al prelievo.
+        ///
+        /// # Argomenti
+        ///
+        /// * `titolare` - Nome del titolare del conto di origine.
+        /// * `da` - Conto di origine.
+        /// * `a` - Conto di destinazione.
+        /// * `importo` - Importo da trasferire, nella valuta di origine.
+        /// * `verso` - Valuta in cui accreditare il controvalore.
+        pub fn trasferisci_con_cambio_come(
+            &mut self,
+            titolare: &str,
+            da: IdConto,
+            a: IdConto,
+            importo: Denaro,
+            verso: Valuta,
+        ) -> Result<RicevutaCambio, ErroreArchivio> {
+            let data = self.adesso(&[da, a])?;
+            let conversione = self.banca.preventivo_cambio(da, importo, verso)?;
+            self.registra_come(titolare, Comando::TrasferisciConCambio { da, a, conversione, data })?;
+            Ok(self.banca.cambia_come_il(titolare, da, a, conversione, data)?)
+        }
+
+        /// Salva lo stato completo della banca in un'istantanea e svuota il registro delle operazioni.
+        ///
+        /// L'istantanea viene scritta in un file temporaneo e poi rinominata, così un'interruzione
+        /// lascia sempre intatta l'istantanea precedente o quella nuova. Il registro viene svuotato
+        /// solo dopo aver sincronizzato la cartella, quando la rinomina è ormai su disco.
+        pub fn istantanea(&mut self) -> Result<(), ErroreArchivio> {
+            let banca = &self.banca;
+            let mut testo = componi(&[
+                "banca".to_string(),
+                banca.valuta().codice().to_string(),
+                banca.prossimo_id().0.to_string(),
+                banca.totale_atteso().to_string(),
+                self.sequenza.to_string(),
+            ]);
+            let mut righe = 1;
+            let aperti = banca.ids().map(|id| (id, banca.conto(id).unwrap(), None));
+            let chiusi = banca.ids_chiusi().filter_map(|id| banca.conto_chiuso(id).map(|(conto, data)| (id, conto, Some(data))));
+            let mut conti: Vec<_> = aperti.chain(chiusi).collect();
+            conti.sort_by_key(|(id, _, _)| *id);
+            for (id, conto, chiusura) in conti {
+                testo.push_str(&componi(&[
+                    "conto".to_string(),
+                    id.0.to_string(),
+                    proteggi(&conto.nome_cliente),
+                    conto.limite_inferiore.to_string(),
+                    conto.limite_superiore.to_string(),
+                    conto.interesse.to_string(),
+                    conto.fido.to_string(),
+                    if conto.bloccato { "1" } else { "0" }.to_string(),
+                    conto.registro().saldo_apertura().to_string(),
+                ]));
+                righe += 1;
+                if let Some(data) = chiusura {
+                    testo.push_str(&componi(&["chiuso".to_string(), data.format(FORMATO_ISTANTE).to_string()]));
+                    righe += 1;
+                }
+                for titolare in conto.titolari().elenco() {
+                    let mut campi = vec!["titolare".to_string()];
+                    campi.extend(campi_titolare(titolare));
+                    testo.push_str(&componi(&campi));
+                    righe += 1;
+                }
+                for (nome, giorno, del_giorno, totale) in conto.titolari().spese() {
+                    testo.push_str(&componi(&[
+                        "spesa".to_string(),
+                        proteggi(nome),
+                        giorno.format(FORMATO_GIORNO).to_string(),
+                        del_giorno.to_string(),
+                        totale.to_string(),
+                    ]));
+                    righe += 1;
+                }
+                let divise = conto.divise().into_iter().filter_map(|saldo| conto.registro_divisa(saldo.valuta()));
+                for (indice, registro) in std::iter::once(conto.registro()).chain(divise).enumerate() {
+                    if indice > 0 {
+                        testo.push_str(&componi(&["divisa".to_string(), registro.saldo_apertura().to_string()]));
+                        righe += 1;
+                    }
+                    for m in registro.movimenti() {
+                        testo.push_str(&componi(&[
+                            "movimento".to_string(),
+                            m.sequenza.to_string(),
+                            m.data.format(FORMATO_ISTANTE).to_string(),
+                            m.tipo.nome().to_string(),
+                            m.tipo.dettaglio(),
+                            m.variazione.to_string(),
+                            m.saldo.to_string(),
+                        ]));
+                        righe += 1;
+                    }
+                }
+            }
+            testo.push_str(&componi(&["fine".to_string(), righe.to_string()]));
+
+            let temporaneo = self.cartella.join(FILE_TEMPORANEO);
+            let mut file = File::create(&temporaneo)?;
+            file.write_all(testo.as_bytes())?;
+            file.sync_all()?;
+            fs::rename(&temporaneo, self.cartella.join(FILE_ISTANTANEA))?;
+            File::open(&self.cartella)?.sync_all()?;
+            self.log.set_len(0)?;
+            self.log.sync_all()?;
+            self.operazioni_nel_log = 0;
+            Ok(())
+        }
+
+        /// Restituisce la data per un'operazione sui conti indicati: la più recente tra le loro.
+        fn adesso(&self, ids: &[IdConto]) -> Result<NaiveDateTime, ErroreBanca> {
+            ids.iter()
+                .map(|id| self.banca.conto(*id).map(|conto| conto.adesso()).ok_or(ErroreBanca::ContoInesistente(*id)))
+                .try_fold(NaiveDateTime::MIN, |data, adesso| Ok(data.max(adesso?)))
+        }
+
+        /// Registra un'operazione eseguita per conto di un titolare.
+        fn registra_come(&mut self, titolare: &str, comando: Comando) -> Result<(), ErroreArchivio> {
+            self.registra(Comando::Come { titolare: titolare.to_string(), comando: Box::new(comando) })
+        }
+
+        /// Scrive un'operazione in coda al registro e la sincronizza su disco, salvando prima
+        /// un'istantanea se il registro ha raggiunto la lunghezza stabilita.
+        ///
+        /// Se la scrittura fallisce il registro viene riportato alla lunghezza precedente, così una
+        /// riga scritta a metà non precede le operazioni successive.
+        fn registra(&mut self, comando: Comando) -> Result<(), ErroreArchivio> {
+            if self.istantanea_ogni > 0 && self.operazioni_nel_log >= self.istantanea_ogni {
+                self.istantanea()?;
+            }
+            let mut campi = vec![(self.sequenza + 1).to_string()];
+            campi.extend(comando.campi());
+            let lunghezza = self.log.metadata()?.len();
+            if let Err(errore) = self.log.write_all(componi(&campi).as_bytes()).and_then(|_| self.log.sync_data()) {
+                // L'errore da riportare è quello della scrittura, anche se il troncamento fallisse
+                let _ = self.log.set_len(lunghezza);
+                return Err(errore.into());
+            }
+            self.sequenza += 1;
+            self.operazioni_nel_log += 1;
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,663 @@
+/// Modulo `banca` per la gestione di più conti bancari e dei trasferimenti tra di essi.
+pub mod banca {
+    use std::collections::BTreeMap;
+    use std::fmt;
+    use chrono::NaiveDateTime;
+    use crate::conto_bancario::conto_bancario::{ContoBancario, Conversione, ErroreConto, Ricevuta};
+    use crate::denaro::denaro::{Denaro, Valuta};
+    use crate::titolari::titolari::{Permesso, Titolare};
+
+    /// Identificativo di un conto all'interno di una banca.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct IdConto(pub u32);
+
+    impl fmt::Display for IdConto {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "#{}", self.0)
+        }
+    }
+
+    /// Errori restituiti dalle operazioni della banca.
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum ErroreBanca {
+        /// Nessun conto ha l'identificativo indicato.
+        ContoInesistente(IdConto),
+        /// Conto di origine e di destinazione coincidono.
+        StessoConto(IdConto),
+        /// Il conto è in una valuta diversa da quella della banca.
+        ValutaDiversa {
+            /// Valuta della banca.
+            attesa: Valuta,
+            /// Valuta del conto.
+            trovata: Valuta,
+        },
+        /// L'operazione è stata rifiutata dal conto indicato.
+        Conto {
+            /// Conto che ha rifiutato l'operazione.
+            id: IdConto,
+            /// Errore restituito dal conto.
+            errore: ErroreConto,
+        },
+    }
+
+    impl fmt::Display for ErroreBanca {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                ErroreBanca::ContoInesistente(id) => write!(f, "il conto {} non esiste", id),
+                ErroreBanca::StessoConto(id) => write!(f, "trasferimento dal conto {} verso se stesso", id),
+                ErroreBanca::ValutaDiversa { attesa, trovata } => {
+                    write!(f, "conto in {} in una banca che opera in {}", trovata, attesa)
+                }
+                ErroreBanca::Conto { id, errore } => write!(f, "conto {}: {}", id, errore),
+            }
+        }
+    }
+
+    impl std::error::Error for ErroreBanca {}
+
+    /// Ricevute delle due metà di un trasferimento andato a buon fine.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct RicevutaTrasferimento {
+        /// Prelievo dal conto di origine.
+        pub addebito: Ricevuta,
+        /// Deposito sul conto di destinazione.
+        pub accredito: Ricevuta,
+    }
+
+    impl RicevutaTrasferimento {
+        /// Restituisce le commissioni per operazione addebitate sui due conti, che escono dalla banca.
+        pub fn commissioni(&self) -> Denaro {
+            self.addebito.commissione + self.accredito.commissione
+        }
+    }
+
+    /// Ricevute di un trasferimento con cambio di valuta andato a buon fine.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct RicevutaCambio {
+        /// Conversione applicata, con la commissione di cambio a carico del conto di origine.
+        pub conversione: Conversione,
+        /// Prelievo dal conto di origine, comprensivo della commissione di cambio.
+        pub addebito: Ricevuta,
+        /// Deposito del controvalore sul conto di destinazione.
+        pub accredito: Ricevuta,
+    }
+
+    /// Banca che gestisce più conti nella stessa valuta, identificati da `IdConto`.
+    ///
+    /// I trasferimenti tra conti sono atomici: se il conto di origine o quello di destinazione
+    /// rifiutano l'operazione secondo le regole del proprio stato, entrambi i conti tornano
+    /// esattamente come prima, registro dei movimenti compreso. La banca tiene traccia del denaro
+    /// entrato e uscito (depositi, prelievi, interessi, conti aperti e chiusi) per verificare che
+    /// i trasferimenti non creino né distruggano denaro. Il totale riguarda solo i saldi nella valuta
+    /// della banca: il denaro cambiato da o verso le divise dei conti vi entra o ne esce.
+    ///
+    /// Come per `ContoBancario`, le operazioni senza titolare sono eseguite dalla banca; quelle con
+    /// il suffisso `_come_il` vengono prima autorizzate per il titolare indicato.
+    pub struct Banca {
+        valuta: Valuta,
+        conti: BTreeMap<IdConto, ContoBancario>,
+        chiusi: BTreeMap<IdConto, (ContoBancario, NaiveDateTime)>,
+        prossimo_id: u32,
+        totale_atteso: Denaro,
+    }
+
+    impl Banca {
+        /// Crea una banca senza conti.
+        ///
+        /// # Argomenti
+        ///
+        /// * `valuta` - Valuta in cui operano i conti della banca.
+        pub fn nuova(valuta: Valuta) -> Banca {
+            Banca { valuta, conti: BTreeMap::new(), chiusi: BTreeMap::new(), prossimo_id: 1, totale_atteso: Denaro::zero(valuta) }
+        }
+
+        /// Ricostruisce una banca a partire dai suoi conti aperti e chiusi, ad esempio letti da un archivio.
+        pub(crate) fn ripristinata(
+            valuta: Valuta,
+            conti: BTreeMap<IdConto, ContoBancario>,
+            chiusi: BTreeMap<IdConto, (ContoBancario, NaiveDateTime)>,
+            prossimo_id: u32,
+            totale_atteso: Denaro,
+        ) -> Banca {
+            Banca { valuta, conti, chiusi, prossimo_id, totale_atteso }
+        }
+
+        /// Restituisce l'identificativo che verrà assegnato al prossimo conto aperto.
+        pub(crate) fn prossimo_id(&self) -> IdConto {
+            IdConto(self.prossimo_id)
+        }
+
+        /// Restituisce la valuta della banca.
+        pub fn valuta(&self) -> Valuta {
+            self.valuta
+        }
+
+        /// Aggiunge un conto alla banca e ne restituisce l'identificativo.
+        ///
+        /// # Argomenti
+        ///
+        /// * `conto` - Il conto da gestire, nella valuta della banca.
+        pub fn apri(&mut self, conto: ContoBancario) -> Result<IdConto, ErroreBanca> {
+            if conto.saldo().valuta() != self.valuta {
+                return Err(ErroreBanca::ValutaDiversa { attesa: self.valuta, trovata: conto.saldo().valuta() });
+            }
+            let id = IdConto(self.prossimo_id);
+            self.prossimo_id += 1;
+            self.totale_atteso = self.totale_atteso + conto.saldo();
+            self.conti.insert(id, conto);
+            Ok(id)
+        }
+
+        /// Chiude un conto alla sua data attuale, come `chiudi_il`.
+        ///
+        /// # Argomenti
+        ///
+        /// * `id` - Identificativo del conto.
+        pub fn chiudi(&mut self, id: IdConto) -> Result<&ContoBancario, ErroreBanca> {
+            let data = self.conto(id).ok_or(ErroreBanca::ContoInesistente(id))?.adesso();
+            self.chiudi_il(id, data)
+        }
+
+        /// Chiude un conto e lo restituisce: il conto non accetta più operazioni e il suo saldo esce
+        /// dalla banca, ma resta consultabile con `conto_chiuso` per i rapporti sui periodi passati.
+        ///
+        /// # Argomenti
+        ///
+        /// * `id` - Identificativo del conto.
+        /// * `data` - Data di chiusura.
+        pub fn chiudi_il(&mut self, id: IdConto, data: NaiveDateTime) -> Result<&ContoBancario, ErroreBanca> {
+            let conto = self.conti.remove(&id).ok_or(ErroreBanca::ContoInesistente(id))?;
+            self.totale_atteso = self.totale_atteso - conto.saldo();
+            Ok(&self.chiusi.entry(id).or_insert((conto, data)).0)
+        }
+
+        /// Apre una divisa sul conto indicato, come `ContoBancario::apri_divisa`.
+        ///
+        /// # Argomenti
+        ///
+        /// * `id` - Identificativo del conto.
+        /// * `valuta` - Valuta della divisa.
+        pub fn apri_divisa(&mut self, id: IdConto, valuta: Valuta) -> Result<(), ErroreBanca> {
+            self.conto_mut(id)?.apri_divisa(valuta).map_err(|errore| ErroreBanca::Conto { id, errore })
+        }
+
+        /// Aggiunge un titolare al conto indicato, come `ContoBancario::aggiungi_titolare`.
+        ///
+        /// # Argomenti
+        ///
+        /// * `id` - Identificativo del conto.
+        /// * `da` - Nome del titolare che chiede l'operazione.
+        /// * `titolare` - Il nuovo titolare.
+        pub fn aggiungi_titolare(&mut self, id: IdConto, da: &str, titolare: Titolare) -> Result<(), ErroreBanca> {
+            self.conto_mut(id)?.aggiungi_titolare(da, titolare).map_err(|errore| ErroreBanca::Conto { id, errore })
+        }
+
+        /// Rimuove un titolare dal conto indicato, come `ContoBancario::rimuovi_titolare`.
+        ///
+        /// # Argomenti
+        ///
+        /// * `id` - Identificativo del conto.
+        /// * `da` - Nome del titolare che chiede l'operazione.
+        /// * `nome` - Nome del titolare da rimuovere.
+        pub fn rimuovi_titolare(&mut self, id: IdConto, da: &str, nome: &str) -> Result<Titolare, ErroreBanca> {
+            self.conto_mut(id)?.rimuovi_titolare(da, nome).map_err(|errore| ErroreBanca::Conto { id, errore })
+        }
+
+        /// Restituisce il conto con l'identificativo indicato, se esiste.
+        pub fn conto(&self, id: IdConto) -> Option<&ContoBancario> {
+            self.conti.get(&id)
+        }
+
+        /// Restituisce gli identificativi dei conti aperti, in ordine crescente.
+        pub fn ids(&self) -> impl Iterator<Item = IdConto> + '_ {
+            self.conti.keys().copied()
+        }
+
+        /// Restituisce un conto chiuso con la sua data di chiusura, se esiste.
+        pub fn conto_chiuso(&self, id: IdConto) -> Option<(&ContoBancario, NaiveDateTime)> {
+            self.chiusi.get(&id).map(|(conto, data)| (conto, *data))
+        }
+
+        /// Restituisce gli identificativi dei conti chiusi, in ordine crescente.
+        pub fn ids_chiusi(&self) -> impl Iterator<Item = IdConto> + '_ {
+            self.chiusi.keys().copied()
+        }
+
+        /// Restituisce il numero di conti aperti.
+        pub fn numero_conti(&self) -> usize {
+            self.conti.len()
+        }
+
+        /// Restituisce il conto con l'identificativo indicato o l'errore `ContoInesistente`.
+        fn conto_mut(&mut self, id: IdConto) -> Result<&mut ContoBancario, ErroreBanca> {
+            self.conti.get_mut(&id).ok_or(ErroreBanca::ContoInesistente(id))
+        }
+
+        /// Blocca un conto: le operazioni successive che lo coinvolgono vengono rifiutate.
+        pub fn blocca(&mut self, id: IdConto) -> Result<(), ErroreBanca> {
+            self.conto_mut(id)?.blocca();
+            Ok(())
+        }
+
+        /// Sblocca un conto.
+        pub fn sblocca(&mut self, id: IdConto) -> Result<(), ErroreBanca> {
+            self.conto_mut(id)?.sblocca();
+            Ok(())
+        }
+
+        /// Deposita denaro proveniente dall'esterno della banca su un conto.
+        ///
+        /// # Argomenti
+        ///
+        /// * `id` - Identificativo del conto.
+        /// * `importo` - Importo da depositare.
+        pub fn deposita(&mut self, id: IdConto, importo: Denaro) -> Result<Ricevuta, ErroreBanca> {
+            let data = self.conto_mut(id)?.adesso();
+            self.deposita_il(id, importo, data)
+        }
+
+        /// Come `deposita`, registrando il movimento con la data indicata.
+        ///
+        /// # Argomenti
+        ///
+        /// * `id` - Identificativo del conto.
+        /// * `importo` - Importo da depositare.
+        /// * `data` - Data del movimento.
+        pub fn deposita_il(&mut self, id: IdConto, importo: Denaro, data: NaiveDateTime) -> Result<Ricevuta, ErroreBanca> {
+            let ricevuta = self.conto_mut(id)?.deposita_il(importo, data).map_err(|errore| ErroreBanca::Conto { id, errore })?;
+            self.movimento_esterno(&ricevuta);
+            Ok(ricevuta)
+        }
+
+        /// Preleva denaro da un conto verso l'esterno della banca.
+        ///
+        /// # Argomenti
+        ///
+        /// * `id` - Identificativo del conto.
+        /// * `importo` - Importo da prelevare.
+        pub fn preleva(&mut self, id: IdConto, importo: Denaro) -> Result<Ricevuta, ErroreBanca> {
+            let data = self.conto_mut(id)?.adesso();
+            self.preleva_il(id, importo, data)
+        }
+
+        /// Come `preleva`, registrando il movimento con la data indicata.
+        ///
+        /// # Argomenti
+        ///
+        /// * `id` - Identificativo del conto.
+        /// * `importo` - Importo da prelevare.
+        /// * `data` - Data del movimento.
+        pub fn preleva_il(&mut self, id: IdConto, importo: Denaro, data: NaiveDateTime) -> Result<Ricevuta, ErroreBanca> {
+            let ricevuta = self.conto_mut(id)?.preleva_il(importo, data).map_err(|errore| ErroreBanca::Conto { id, errore })?;
+            self.movimento_esterno(&ricevuta);
+            Ok(ricevuta)
+        }
+
+        /// Come `deposita_il`, per conto di un titolare autorizzato al deposito.
+        ///
+        /// # Argomenti
+        ///
+        /// * `titolare` - Nome del titolare del conto.
+        /// * `id` - Identificativo del conto.
+        /// * `importo` - Importo da depositare.
+        /// * `data` - Data del movimento.
+        pub fn deposita_come_il(&mut self, titolare: &str, id: IdConto, importo: Denaro, data: NaiveDateTime) -> Result<Ricevuta, ErroreBanca> {
+            let ricevuta = self.conto_mut(id)?.deposita_come_il(titolare, importo, data).map_err(|errore| ErroreBanca::Conto { id, errore })?;
+            self.movimento_esterno(&ricevuta);
+            Ok(ricevuta)
+        }
+
+        /// Come `preleva_il`, per conto di un titolare autorizzato al prelievo; l'importo conta nei
+        /// suoi limiti di spesa.
+        ///
+        /// # Argomenti
+        ///
+        /// * `titolare` - Nome del titolare del conto.
+        /// * `id` - Identificativo del conto.
+        /// * `importo` - Importo da prelevare.
+        /// * `data` - Data del movimento.
+        pub fn preleva_come_il(&mut self, titolare: &str, id: IdConto, importo: Denaro, data: NaiveDateTime) -> Result<Ricevuta, ErroreBanca> {
+            let ricevuta = self.conto_mut(id)?.preleva_come_il(titolare, importo, data).map_err(|errore| ErroreBanca::Conto { id, errore })?;
+            self.movimento_esterno(&ricevuta);
+            Ok(ricevuta)
+        }
+
+        /// Accredita gli interessi su un conto secondo le regole del suo stato.
+        ///
+        /// # Argomenti
+        ///
+        /// * `id` - Identificativo del conto.
+        pub fn paga_interessi(&mut self, id: IdConto) -> Result<Ricevuta, ErroreBanca> {
+            let data = self.conto_mut(id)?.adesso();
+            self.paga_interessi_il(id, data)
+        }
+
+        /// Come `paga_interessi`, registrando il movimento con la data indicata.
+        ///
+        /// # Argomenti
+        ///
+        /// * `id` - Identificativo del conto.
+        /// * `data` - Data del movimento.
+        pub fn paga_interessi_il(&mut self, id: IdConto, data: NaiveDateTime) -> Result<Ricevuta, ErroreBanca> {
+            let ricevuta = self.conto_mut(id)?.paga_interessi_il(data).map_err(|errore| ErroreBanca::Conto { id, errore })?;
+            self.movimento_esterno(&ricevuta);
+            Ok(ricevuta)
+        }
+
+        /// Aggiorna il totale atteso con il denaro entrato o uscito dalla banca in un'operazione.
+        fn movimento_esterno(&mut self, ricevuta: &Ricevuta) {
+            if ricevuta.saldo.valuta() != self.valuta {
+                return;
+            }
+            self.totale_atteso = self.totale_atteso + (ricevuta.saldo - ricevuta.saldo_precedente);
+        }
+
+        /// Trasferisce un importo tra due conti, con la data attuale.
+        ///
+        /// Il trasferimento riesce solo se il conto di origine consente il prelievo e quello di
+        /// destinazione il deposito; altrimenti nessuno dei due conti viene modificato.
+        ///
+        /// # Argomenti
+        ///
+        /// * `da` - Conto di origine.
+        /// * `a` - Conto di destinazione.
+        /// * `importo` - Importo da trasferire.
+        ///
+        /// # Esempio
+        ///
+        /// ```
+        /// use esercizio5::banca::banca::Banca;
+        /// use esercizio5::conto_bancario::conto_bancario::ContoBancario;
+        /// use esercizio5::denaro::denaro::{Denaro, Tasso, Valuta};
+        ///
+        /// let euro = |importo: &str| Denaro::da_decimale(importo, Valuta::EUR).unwrap();
+        /// let mut banca = Banca::nuova(Valuta::EUR);
+        /// let mario = banca.apri(ContoBancario::new(String::from("Mario Rossi"), euro("100"), euro("0"), euro("500"), Tasso::ZERO)).unwrap();
+        /// let anna = banca.apri(ContoBancario::new(String::from("Anna Bianchi"), euro("20"), euro("0"), euro("500"), Tasso::ZERO)).unwrap();
+        ///
+        /// banca.trasferisci(mario, anna, euro("30")).unwrap();
+        /// assert!(banca.trasferisci(mario, anna, euro("500")).is_err());
+        /// assert_eq!(banca.conto(mario).unwrap().saldo(), euro("70"));
+        /// assert_eq!(banca.conto(anna).unwrap().saldo(), euro("50"));
+        /// assert!(banca.conservato());
+        /// ```
+        pub fn trasferisci(&mut self, da: IdConto, a: IdConto, importo: Denaro) -> Result<RicevutaTrasferimento, ErroreBanca> {
+            let origine = self.conti.get(&da).ok_or(ErroreBanca::ContoInesistente(da))?;
+            let destinazione = self.conti.get(&a).ok_or(ErroreBanca::ContoInesistente(a))?;
+            let data = origine.adesso().max(destinazione.adesso());
+            self.trasferisci_il(da, a, importo, data)
+        }
+
+        /// Come `trasferisci`, registrando i movimenti di entrambi i conti con la data indicata.
+        ///
+        /// # Argomenti
+        ///
+        /// * `da` - Conto di origine.
+        /// * `a` - Conto di destinazione.
+        /// * `importo` - Importo da trasferire.
+        /// * `data` - Data dei movimenti.
+        pub fn trasferisci_il(
+            &mut self,
+            da: IdConto,
+            a: IdConto,
+            importo: Denaro,
+            data: NaiveDateTime,
+        ) -> Result<RicevutaTrasferimento, ErroreBanca> {
+            if da == a {
+                return Err(ErroreBanca::StessoConto(da));
+            }
+            if !self.conti.contains_key(&da) {
+                return Err(ErroreBanca::ContoInesistente(da));
+            }
+            // La destinazione viene tolta dalla mappa per poter modificare i due conti insieme
+            let mut destinazione = self.conti.remove(&a).ok_or(ErroreBanca::ContoInesistente(a))?;
+            let origine = self.conto_mut(da)?;
+            let esito = trasferisci_tra((da, origine), (a, &mut destinazione), importo, data);
+            self.conti.insert(a, destinazione);
+            let ricevuta = esito?;
+            if ricevuta.addebito.saldo.valuta() == self.valuta {
+                self.totale_atteso = self.totale_atteso - ricevuta.commissioni();
+            }
+            Ok(ricevuta)
+        }
+
+        /// Trasferisce un importo tra due conti per conto di un titolare del conto di origine, che
+        /// deve essere autorizzato al prelievo; il trasferimento conta nei suoi limiti di spesa.
+        ///
+        /// # Argomenti
+        ///
+        /// * `titolare` - Nome del titolare del conto di origine.
+        /// * `da` - Conto di origine.
+        /// * `a` - Conto di destinazione.
+        /// * `importo` - Importo da trasferire.
+        /// * `data` - Data dei movimenti.
+        pub fn trasferisci_come_il(
+            &mut self,
+            titolare: &str,
+            da: IdConto,
+            a: IdConto,
+            importo: Denaro,
+            data: NaiveDateTime,
+        ) -> Result<RicevutaTrasferimento, ErroreBanca> {
+            let speso = self.autorizza_prelievo(titolare, da, importo, data)?;
+            let ricevuta = self.trasferisci_il(da, a, importo, data)?;
+            self.conto_mut(da)?.registra_prelievo_di(titolare, speso, data);
+            Ok(ricevuta)
+        }
+
+        /// Come `trasferisci_con_cambio_il`, per conto di un titolare del conto di origine che deve
+        /// essere autorizzato al prelievo; l'importo trasferito conta nei suoi limiti di spesa.
+        ///
+        /// # Argomenti
+        ///
+        /// * `titolare` - Nome del titolare del conto di origine.
+        /// * `da` - Conto di origine.
+        /// * `a` - Conto di destinazione.
+        /// * `importo` - Importo da trasferire, nella valuta di origine.
+        /// * `verso` - Valuta in cui accreditare il controvalore.
+        /// * `data` - Data dei movimenti.
+        pub fn trasferisci_con_cambio_come_il(
+            &mut self,
+            titolare: &str,
+            da: IdConto,
+            a: IdConto,
+            importo: Denaro,
+            verso: Valuta,
+            data: NaiveDateTime,
+        ) -> Result<RicevutaCambio, ErroreBanca> {
+            let conversione = self.preventivo_cambio(da, importo, verso)?;
+            self.cambia_come_il(titolare, da, a, conversione, data)
+        }
+
+        /// Come `cambia_il`, per conto di un titolare del conto di origine che deve essere autorizzato
+        /// al prelievo; l'importo convertito conta nei suoi limiti di spesa.
+        ///
+        /// # Argomenti
+        ///
+        /// * `titolare` - Nome del titolare del conto di origine.
+        /// * `da` - Conto di origine.
+        /// * `a` - Conto di destinazione.
+        /// * `conversione` - Conversione da applicare.
+        /// * `data` - Data dei movimenti.
+        pub(crate) fn cambia_come_il(
+            &mut self,
+            titolare: &str,
+            da: IdConto,
+            a: IdConto,
+            conversione: Conversione,
+            data: NaiveDateTime,
+        ) -> Result<RicevutaCambio, ErroreBanca> {
+            let speso = self.autorizza_prelievo(titolare, da, conversione.importo, data)?;
+            let ricevuta = self.cambia_il(da, a, conversione, data)?;
+            self.conto_mut(da)?.registra_prelievo_di(titolare, speso, data);
+            Ok(ricevuta)
+        }
+
+        /// Controlla che un titolare possa prelevare l'importo dal conto e restituisce l'importo da
+        /// contare nei suoi limiti di spesa.
+        fn autorizza_prelievo(&self, titolare: &str, id: IdConto, importo: Denaro, data: NaiveDateTime) -> Result<Denaro, ErroreBanca> {
+            self.conto(id)
+                .ok_or(ErroreBanca::ContoInesistente(id))?
+                .autorizza_importo(titolare, Permesso::Prelievo, importo, data)
+                .map_err(|errore| ErroreBanca::Conto { id, errore })
+        }
+
+        /// Come `trasferisci_con_cambio_il`, registrando i movimenti alla data più recente dei due conti.
+        ///
+        /// # Argomenti
+        ///
+        /// * `da` - Conto di origine.
+        /// * `a` - Conto di destinazione.
+        /// * `importo` - Importo da trasferire, nella valuta di origine.
+        /// * `verso` - Valuta in cui accreditare il controvalore.
+        pub fn trasferisci_con_cambio(&mut self, da: IdConto, a: IdConto, importo: Denaro, verso: Valuta) -> Result<RicevutaCambio, ErroreBanca> {
+            let origine = self.conti.get(&da).ok_or(ErroreBanca::ContoInesistente(da))?;
+            let destinazione = self.conti.get(&a).ok_or(ErroreBanca::ContoInesistente(a))?;
+            let data = origine.adesso().max(destinazione.adesso());
+            self.trasferisci_con_cambio_il(da, a, importo, verso, data)
+        }
+
+        /// Trasferisce un importo tra due conti convertendolo in un'altra valuta: il conto di origine
+        /// paga importo e commissione di cambio ai suoi tassi e commissioni, quello di destinazione
+        /// riceve il controvalore sul saldo in quella valuta (la sua valuta o una divisa aperta).
+        ///
+        /// Come per `trasferisci`, se uno dei due conti rifiuta l'operazione nessuno viene modificato.
+        ///
+        /// # Argomenti
+        ///
+        /// * `da` - Conto di origine.
+        /// * `a` - Conto di destinazione.
+        /// * `importo` - Importo da trasferire, nella valuta di origine.
+        /// * `verso` - Valuta in cui accreditare il controvalore.
+        /// * `data` - Data dei movimenti.
+        pub fn trasferisci_con_cambio_il(
+            &mut self,
+            da: IdConto,
+            a: IdConto,
+            importo: Denaro,
+            verso: Valuta,
+            data: NaiveDateTime,
+        ) -> Result<RicevutaCambio, ErroreBanca> {
+            let conversione = self.preventivo_cambio(da, importo, verso)?;
+            self.cambia_il(da, a, conversione, data)
+        }
+
+        /// Calcola la conversione di un importo dal conto indicato ai suoi tassi e commissioni attuali,
+        /// come `ContoBancario::preventivo_cambio`.
+        ///
+        /// # Argomenti
+        ///
+        /// * `da` - Conto di origine.
+        /// * `importo` - Importo da convertire, nella valuta di origine.
+        /// * `verso` - Valuta di arrivo.
+        pub fn preventivo_cambio(&self, da: IdConto, importo: Denaro, verso: Valuta) -> Result<Conversione, ErroreBanca> {
+            self.conto(da)
+                .ok_or(ErroreBanca::ContoInesistente(da))?
+                .preventivo_cambio(importo, verso)
+                .map_err(|errore| ErroreBanca::Conto { id: da, errore })
+        }
+
+        /// Applica tra due conti una conversione già calcolata, ad esempio quella scritta nel registro
+        /// di un archivio, invece di chiederne una nuova ai tassi attuali.
+        ///
+        /// # Argomenti
+        ///
+        /// * `da` - Conto di origine.
+        /// * `a` - Conto di destinazione.
+        /// * `conversione` - Conversione da applicare.
+        /// * `data` - Data dei movimenti.
+        pub(crate) fn cambia_il(&mut self, da: IdConto, a: IdConto, conversione: Conversione, data: NaiveDateTime) -> Result<RicevutaCambio, ErroreBanca> {
+            if da == a {
+                return Err(ErroreBanca::StessoConto(da));
+            }
+            let mut destinazione = self.conti.remove(&a).ok_or(ErroreBanca::ContoInesistente(a))?;
+            let origine = self.conto_mut(da)?;
+            let esito = cambia_tra((da, origine), (a, &mut destinazione), conversione, data);
+            self.conti.insert(a, destinazione);
+            let ricevuta = esito?;
+            self.movimento_esterno(&ricevuta.addebito);
+            self.movimento_esterno(&ricevuta.accredito);
+            Ok(ricevuta)
+        }
+
+        /// Restituisce la somma dei saldi di tutti i conti.
+        pub fn totale(&self) -> Denaro {
+            self.conti.values().fold(Denaro::zero(self.valuta), |totale, conto| totale + conto.saldo())
+        }
+
+        /// Restituisce il totale che i conti dovrebbero avere in base al denaro entrato e uscito dalla banca.
+        pub fn totale_atteso(&self) -> Denaro {
+            self.totale_atteso
+        }
+
+        /// Indica se la somma dei saldi coincide con il denaro entrato meno quello uscito dalla banca.
+        pub fn conservato(&self) -> bool {
+            self.totale() == self.totale_atteso
+        }
+    }
+
+    /// Trasferisce un importo tra due conti distinti, riportandoli entrambi allo stato precedente
+    /// se uno dei due rifiuta l'operazione.
+    ///
+    /// # Argomenti
+    ///
+    /// * `origine` - Identificativo e conto da cui prelevare.
+    /// * `destinazione` - Identificativo e conto su cui depositare.
+    /// * `importo` - Importo da trasferire.
+    /// * `data` - Data dei movimenti.
+    pub(crate) fn trasferisci_tra(
+        (da, origine): (IdConto, &mut ContoBancario),
+        (a, destinazione): (IdConto, &mut ContoBancario),
+        importo: Denaro,
+        data: NaiveDateTime,
+    ) -> Result<RicevutaTrasferimento, ErroreBanca> {
+        let punto_origine = origine.punto_ripristino();
+        let addebito = match origine.preleva_il(importo, data) {
+            Ok(addebito) => addebito,
+            Err(errore) => {
+                // Il prelievo rifiutato non ha mosso denaro: il conto resta com'è, compreso un eventuale blocco
+                origine.conferma(punto_origine);
+                return Err(ErroreBanca::Conto { id: da, errore });
+            }
+        };
+        // Il deposito è l'ultimo passo: se viene rifiutato non ha mosso denaro sulla destinazione
+        match destinazione.deposita_il(importo, data) {
+            Ok(accredito) => {
+                origine.conferma(punto_origine);
+                Ok(RicevutaTrasferimento { addebito, accredito })
+            }
+            Err(errore) => {
+                origine.ripristina(punto_origine);
+                Err(ErroreBanca::Conto { id: a, errore })
+            }
+        }
+    }
+
+    /// Esegue una conversione tra due conti distinti, riportandoli entrambi allo stato precedente
+    /// se uno dei due rifiuta l'operazione.
+    pub(crate) fn cambia_tra(
+        (da, origine): (IdConto, &mut ContoBancario),
+        (a, destinazione): (IdConto, &mut ContoBancario),
+        conversione: Conversione,
+        data: NaiveDateTime,
+    ) -> Result<RicevutaCambio, ErroreBanca> {
+        let punto_origine = origine.punto_ripristino();
+        let addebito = match origine.addebita_cambio_il(&conversione, data) {
+            Ok(addebito) => addebito,
+            Err(errore) => {
+                origine.conferma(punto_origine);
+                return Err(ErroreBanca::Conto { id: da, errore });
+            }
+        };
+        match destinazione.deposita_il(conversione.controvalore, data) {
+            Ok(accredito) => {
+                origine.conferma(punto_origine);
+                Ok(RicevutaCambio { conversione, addebito, accredito })
+            }
+            Err(errore) => {
+                origine.ripristina(punto_origine);
+                Err(ErroreBanca::Conto { id: a, errore })
+            }
+        }
+    }
+}
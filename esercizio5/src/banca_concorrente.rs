@@ -0,0 +1,369 @@
+/// Modulo `banca_concorrente` per una banca condivisibile tra più thread.
+pub mod banca_concorrente {
+    use std::collections::BTreeMap;
+    use std::sync::{Arc, Mutex, MutexGuard, RwLock};
+    use chrono::NaiveDateTime;
+    use crate::banca::banca::{cambia_tra, trasferisci_tra, ErroreBanca, IdConto, RicevutaCambio, RicevutaTrasferimento};
+    use crate::conto_bancario::conto_bancario::{ContoBancario, ErroreConto, Ricevuta};
+    use crate::denaro::denaro::{Denaro, Valuta};
+    use crate::titolari::titolari::{Permesso, Titolare};
+
+    /// Conti aperti e prossimo identificativo libero.
+    struct Conti {
+        conti: BTreeMap<IdConto, Arc<Mutex<ContoBancario>>>,
+        prossimo_id: u32,
+    }
+
+    /// Versione di `Banca` che può essere condivisa tra thread (ad esempio in un `Arc`).
+    ///
+    /// Ogni conto è protetto dal proprio `Mutex`, così operazioni su conti diversi procedono in
+    /// parallelo. Per evitare stalli, quando servono più conti i lucchetti vengono sempre presi
+    /// in ordine crescente di `IdConto`, il lucchetto del totale atteso sempre per ultimo, e
+    /// l'elenco dei conti non viene mai richiesto da chi tiene già il lucchetto di un conto.
+    pub struct BancaConcorrente {
+        valuta: Valuta,
+        conti: RwLock<Conti>,
+        totale_atteso: Mutex<Denaro>,
+    }
+
+    impl BancaConcorrente {
+        /// Crea una banca senza conti.
+        ///
+        /// # Argomenti
+        ///
+        /// * `valuta` - Valuta in cui operano i conti della banca.
+        pub fn nuova(valuta: Valuta) -> BancaConcorrente {
+            BancaConcorrente {
+                valuta,
+                conti: RwLock::new(Conti { conti: BTreeMap::new(), prossimo_id: 1 }),
+                totale_atteso: Mutex::new(Denaro::zero(valuta)),
+            }
+        }
+
+        /// Restituisce la valuta della banca.
+        pub fn valuta(&self) -> Valuta {
+            self.valuta
+        }
+
+        /// Aggiunge un conto alla banca e ne restituisce l'identificativo.
+        ///
+        /// # Argomenti
+        ///
+        /// * `conto` - Il conto da gestire, nella valuta della banca.
+        pub fn apri(&self, conto: ContoBancario) -> Result<IdConto, ErroreBanca> {
+            if conto.saldo().valuta() != self.valuta {
+                return Err(ErroreBanca::ValutaDiversa { attesa: self.valuta, trovata: conto.saldo().valuta() });
+            }
+            let mut conti = self.conti.write().unwrap();
+            let id = IdConto(conti.prossimo_id);
+            conti.prossimo_id += 1;
+            let mut totale = self.totale_atteso.lock().unwrap();
+            *totale = *totale + conto.saldo();
+            conti.conti.insert(id, Arc::new(Mutex::new(conto)));
+            Ok(id)
+        }
+
+        /// Restituisce gli identificativi dei conti aperti, in ordine crescente.
+        pub fn ids(&self) -> Vec<IdConto> {
+            self.conti.read().unwrap().conti.keys().copied().collect()
+        }
+
+        /// Restituisce il conto con l'identificativo indicato o l'errore `ContoInesistente`.
+        fn conto(&self, id: IdConto) -> Result<Arc<Mutex<ContoBancario>>, ErroreBanca> {
+            self.conti.read().unwrap().conti.get(&id).cloned().ok_or(ErroreBanca::ContoInesistente(id))
+        }
+
+        /// Esegue una funzione sul conto indicato, tenendone il lucchetto per tutta la durata.
+        ///
+        /// # Argomenti
+        ///
+        /// * `id` - Identificativo del conto.
+        /// * `azione` - Funzione da eseguire sul conto.
+        pub fn con_conto<T>(&self, id: IdConto, azione: impl FnOnce(&ContoBancario) -> T) -> Result<T, ErroreBanca> {
+            let conto = self.conto(id)?;
+            let conto = conto.lock().unwrap();
+            Ok(azione(&conto))
+        }
+
+        /// Restituisce il saldo del conto indicato.
+        pub fn saldo(&self, id: IdConto) -> Result<Denaro, ErroreBanca> {
+            self.con_conto(id, |conto| conto.saldo())
+        }
+
+        /// Apre una divisa sul conto indicato, come `ContoBancario::apri_divisa`.
+        ///
+        /// # Argomenti
+        ///
+        /// * `id` - Identificativo del conto.
+        /// * `valuta` - Valuta della divisa.
+        pub fn apri_divisa(&self, id: IdConto, valuta: Valuta) -> Result<(), ErroreBanca> {
+            self.conto(id)?.lock().unwrap().apri_divisa(valuta).map_err(|errore| ErroreBanca::Conto { id, errore })
+        }
+
+        /// Aggiunge un titolare al conto indicato, come `ContoBancario::aggiungi_titolare`.
+        ///
+        /// # Argomenti
+        ///
+        /// * `id` - Identificativo del conto.
+        /// * `da` - Nome del titolare che chiede l'operazione.
+        /// * `titolare` - Il nuovo titolare.
+        pub fn aggiungi_titolare(&self, id: IdConto, da: &str, titolare: Titolare) -> Result<(), ErroreBanca> {
+            self.conto(id)?.lock().unwrap().aggiungi_titolare(da, titolare).map_err(|errore| ErroreBanca::Conto { id, errore })
+        }
+
+        /// Rimuove un titolare dal conto indicato, come `ContoBancario::rimuovi_titolare`.
+        ///
+        /// # Argomenti
+        ///
+        /// * `id` - Identificativo del conto.
+        /// * `da` - Nome del titolare che chiede l'operazione.
+        /// * `nome` - Nome del titolare da rimuovere.
+        pub fn rimuovi_titolare(&self, id: IdConto, da: &str, nome: &str) -> Result<Titolare, ErroreBanca> {
+            self.conto(id)?.lock().unwrap().rimuovi_titolare(da, nome).map_err(|errore| ErroreBanca::Conto { id, errore })
+        }
+
+        /// Blocca un conto: le operazioni successive che lo coinvolgono vengono rifiutate.
+        pub fn blocca(&self, id: IdConto) -> Result<(), ErroreBanca> {
+            self.conto(id)?.lock().unwrap().blocca();
+            Ok(())
+        }
+
+        /// Sblocca un conto.
+        pub fn sblocca(&self, id: IdConto) -> Result<(), ErroreBanca> {
+            self.conto(id)?.lock().unwrap().sblocca();
+            Ok(())
+        }
+
+        /// Esegue un'operazione che muove denaro da o verso l'esterno della banca e aggiorna
+        /// il totale atteso prima di rilasciare il lucchetto del conto.
+        fn esterna<F>(&self, id: IdConto, operazione: F) -> Result<Ricevuta, ErroreBanca>
+        where
+            F: FnOnce(&mut ContoBancario) -> Result<Ricevuta, ErroreConto>,
+        {
+            let conto = self.conto(id)?;
+            let mut conto = conto.lock().unwrap();
+            let ricevuta = operazione(&mut conto).map_err(|errore| ErroreBanca::Conto { id, errore })?;
+            let mut totale = self.totale_atteso.lock().unwrap();
+            // Il totale atteso riguarda solo i saldi nella valuta della banca, non le divise
+            if ricevuta.saldo.valuta() == totale.valuta() {
+                *totale = *totale + (ricevuta.saldo - ricevuta.saldo_precedente);
+            }
+            Ok(ricevuta)
+        }
+
+        /// Deposita denaro proveniente dall'esterno della banca su un conto.
+        ///
+        /// # Argomenti
+        ///
+        /// * `id` - Identificativo del conto.
+        /// * `importo` - Importo da depositare.
+        pub fn deposita(&self, id: IdConto, importo: Denaro) -> Result<Ricevuta, ErroreBanca> {
+            self.esterna(id, |conto| conto.deposita(importo))
+        }
+
+        /// Preleva denaro da un conto verso l'esterno della banca.
+        ///
+        /// # Argomenti
+        ///
+        /// * `id` - Identificativo del conto.
+        /// * `importo` - Importo da prelevare.
+        pub fn preleva(&self, id: IdConto, importo: Denaro) -> Result<Ricevuta, ErroreBanca> {
+            self.esterna(id, |conto| conto.preleva(importo))
+        }
+
+        /// Come `deposita`, per conto di un titolare autorizzato al deposito.
+        ///
+        /// # Argomenti
+        ///
+        /// * `titolare` - Nome del titolare del conto.
+        /// * `id` - Identificativo del conto.
+        /// * `importo` - Importo da depositare.
+        pub fn deposita_come(&self, titolare: &str, id: IdConto, importo: Denaro) -> Result<Ricevuta, ErroreBanca> {
+            self.esterna(id, |conto| conto.deposita_come_il(titolare, importo, conto.adesso()))
+        }
+
+        /// Come `preleva`, per conto di un titolare autorizzato al prelievo; l'importo conta nei
+        /// suoi limiti di spesa.
+        ///
+        /// # Argomenti
+        ///
+        /// * `titolare` - Nome del titolare del conto.
+        /// * `id` - Identificativo del conto.
+        /// * `importo` - Importo da prelevare.
+        pub fn preleva_come(&self, titolare: &str, id: IdConto, importo: Denaro) -> Result<Ricevuta, ErroreBanca> {
+            self.esterna(id, |conto| conto.preleva_come_il(titolare, importo, conto.adesso()))
+        }
+
+        /// Accredita gli interessi su un conto secondo le regole del suo stato.
+        ///
+        /// # Argomenti
+        ///
+        /// * `id` - Identificativo del conto.
+        pub fn paga_interessi(&self, id: IdConto) -> Result<Ricevuta, ErroreBanca> {
+            self.esterna(id, |conto| conto.paga_interessi())
+        }
+
+        /// Trasferisce un importo tra due conti in modo atomico, come `Banca::trasferisci`.
+        ///
+        /// I due conti vengono bloccati in ordine di identificativo, quindi trasferimenti
+        /// simultanei in direzioni opposte non possono bloccarsi a vicenda.
+        ///
+        /// # Argomenti
+        ///
+        /// * `da` - Conto di origine.
+        /// * `a` - Conto di destinazione.
+        /// * `importo` - Importo da trasferire.
+        pub fn trasferisci(&self, da: IdConto, a: IdConto, importo: Denaro) -> Result<RicevutaTrasferimento, ErroreBanca> {
+            self.trasferisci_per(None, da, a, importo)
+        }
+
+        /// Come `trasferisci`, per conto di un titolare del conto di origine autorizzato al prelievo;
+        /// l'importo trasferito conta nei suoi limiti di spesa.
+        ///
+        /// # Argomenti
+        ///
+        /// * `titolare` - Nome del titolare del conto di origine.
+        /// * `da` - Conto di origine.
+        /// * `a` - Conto di destinazione.
+        /// * `importo` - Importo da trasferire.
+        pub fn trasferisci_come(&self, titolare: &str, da: IdConto, a: IdConto, importo: Denaro) -> Result<RicevutaTrasferimento, ErroreBanca> {
+            self.trasferisci_per(Some(titolare), da, a, importo)
+        }
+
+        /// Esegue `trasferisci`, autorizzando prima il titolare indicato se c'è.
+        fn trasferisci_per(&self, titolare: Option<&str>, da: IdConto, a: IdConto, importo: Denaro) -> Result<RicevutaTrasferimento, ErroreBanca> {
+            self.con_due_conti(da, a, |origine, destinazione| {
+                let data = origine.adesso().max(destinazione.adesso());
+                let speso = autorizza_prelievo(titolare, da, origine, importo, data)?;
+                let ricevuta = trasferisci_tra((da, origine), (a, destinazione), importo, data)?;
+                if let Some((titolare, speso)) = speso {
+                    origine.registra_prelievo_di(titolare, speso, data);
+                }
+                let mut totale = self.totale_atteso.lock().unwrap();
+                if ricevuta.addebito.saldo.valuta() == totale.valuta() {
+                    *totale = *totale - ricevuta.commissioni();
+                }
+                Ok(ricevuta)
+            })
+        }
+
+        /// Trasferisce un importo convertendolo in un'altra valuta, come `Banca::trasferisci_con_cambio_il`,
+        /// bloccando i due conti come `trasferisci`.
+        ///
+        /// # Argomenti
+        ///
+        /// * `da` - Conto di origine.
+        /// * `a` - Conto di destinazione.
+        /// * `importo` - Importo da trasferire, nella valuta di origine.
+        /// * `verso` - Valuta in cui accreditare il controvalore.
+        pub fn trasferisci_con_cambio(&self, da: IdConto, a: IdConto, importo: Denaro, verso: Valuta) -> Result<RicevutaCambio, ErroreBanca> {
+            self.trasferisci_con_cambio_per(None, da, a, importo, verso)
+        }
+
+        /// Come `trasferisci_con_cambio`, per conto di un titolare del conto di origine autorizzato
+        /// al prelievo; l'importo trasferito conta nei suoi limiti di spesa.
+        ///
+        /// # Argomenti
+        ///
+        /// * `titolare` - Nome del titolare del conto di origine.
+        /// * `da` - Conto di origine.
+        /// * `a` - Conto di destinazione.
+        /// * `importo` - Importo da trasferire, nella valuta di origine.
+        /// * `verso` - Valuta in cui accreditare il controvalore.
+        pub fn trasferisci_con_cambio_come(
+            &self,
+            titolare: &str,
+            da: IdConto,
+            a: IdConto,
+            importo: Denaro,
+            verso: Valuta,
+        ) -> Result<RicevutaCambio, ErroreBanca> {
+            self.trasferisci_con_cambio_per(Some(titolare), da, a, importo, verso)
+        }
+
+        /// Esegue `trasferisci_con_cambio`, autorizzando prima il titolare indicato se c'è.
+        fn trasferisci_con_cambio_per(
+            &self,
+            titolare: Option<&str>,
+            da: IdConto,
+            a: IdConto,
+            importo: Denaro,
+            verso: Valuta,
+        ) -> Result<RicevutaCambio, ErroreBanca> {
+            self.con_due_conti(da, a, |origine, destinazione| {
+                let conversione = origine.preventivo_cambio(importo, verso).map_err(|errore| ErroreBanca::Conto { id: da, errore })?;
+                let data = origine.adesso().max(destinazione.adesso());
+                let speso = autorizza_prelievo(titolare, da, origine, importo, data)?;
+                let ricevuta = cambia_tra((da, origine), (a, destinazione), conversione, data)?;
+                if let Some((titolare, speso)) = speso {
+                    origine.registra_prelievo_di(titolare, speso, data);
+                }
+                let mut totale = self.totale_atteso.lock().unwrap();
+                // Il denaro che passa tra la valuta della banca e una divisa entra o esce dal totale atteso
+                for parte in [&ricevuta.addebito, &ricevuta.accredito] {
+                    if parte.saldo.valuta() == totale.valuta() {
+                        *totale = *totale + (parte.saldo - parte.saldo_precedente);
+                    }
+                }
+                Ok(ricevuta)
+            })
+        }
+
+        /// Esegue una funzione su due conti distinti, bloccandoli in ordine di identificativo.
+        fn con_due_conti<T>(
+            &self,
+            da: IdConto,
+            a: IdConto,
+            azione: impl FnOnce(&mut ContoBancario, &mut ContoBancario) -> Result<T, ErroreBanca>,
+        ) -> Result<T, ErroreBanca> {
+            if da == a {
+                return Err(ErroreBanca::StessoConto(da));
+            }
+            let (origine, destinazione) = (self.conto(da)?, self.conto(a)?);
+            let (mut origine, mut destinazione) = if da < a {
+                let origine = origine.lock().unwrap();
+                (origine, destinazione.lock().unwrap())
+            } else {
+                let destinazione = destinazione.lock().unwrap();
+                (origine.lock().unwrap(), destinazione)
+            };
+            azione(&mut origine, &mut destinazione)
+        }
+
+        /// Restituisce la somma dei saldi di tutti i conti e il totale atteso, letti nello stesso istante.
+        ///
+        /// Tutti i conti vengono bloccati in ordine di identificativo, mentre l'elenco dei conti
+        /// resta bloccato in lettura perché nessun conto venga aperto durante il calcolo.
+        pub fn totali(&self) -> (Denaro, Denaro) {
+            let conti = self.conti.read().unwrap();
+            let lucchetti: Vec<MutexGuard<ContoBancario>> = conti.conti.values().map(|c| c.lock().unwrap()).collect();
+            let totale = lucchetti.iter().fold(Denaro::zero(self.valuta), |totale, conto| totale + conto.saldo());
+            (totale, *self.totale_atteso.lock().unwrap())
+        }
+
+        /// Restituisce la somma dei saldi di tutti i conti.
+        pub fn totale(&self) -> Denaro {
+            self.totali().0
+        }
+
+        /// Indica se la somma dei saldi coincide con il denaro entrato meno quello uscito dalla banca.
+        pub fn conservato(&self) -> bool {
+            let (totale, atteso) = self.totali();
+            totale == atteso
+        }
+    }
+
+    /// Controlla che l'eventuale titolare possa prelevare l'importo dal conto di origine e restituisce
+    /// l'importo da contare nei suoi limiti di spesa.
+    fn autorizza_prelievo<'a>(
+        titolare: Option<&'a str>,
+        id: IdConto,
+        conto: &ContoBancario,
+        importo: Denaro,
+        data: NaiveDateTime,
+    ) -> Result<Option<(&'a str, Denaro)>, ErroreBanca> {
+        let Some(titolare) = titolare else { return Ok(None) };
+        let speso = conto.autorizza_importo(titolare, Permesso::Prelievo, importo, data).map_err(|errore| ErroreBanca::Conto { id, errore })?;
+        Ok(Some((titolare, speso)))
+    }
+}
@@ -0,0 +1,212 @@
+/// Modulo `cambi` per i tassi di cambio tra valute e la conversione degli importi.
+pub mod cambi {
+    use std::collections::HashMap;
+    use std::fmt;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::sync::RwLock;
+    use crate::denaro::denaro::{Arrotondamento, Denaro, Tasso, Valuta};
+
+    /// Errori nella lettura o nell'uso dei tassi di cambio.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum ErroreCambi {
+        /// Non è disponibile un tasso di cambio tra le due valute.
+        TassoMancante {
+            /// Valuta di partenza.
+            da: Valuta,
+            /// Valuta di arrivo.
+            a: Valuta,
+        },
+        /// Una riga della tabella dei cambi non è valida.
+        Formato {
+            /// Numero della riga, a partire da 1.
+            riga: usize,
+            /// Descrizione del problema.
+            messaggio: String,
+        },
+        /// Errore di lettura del file dei cambi.
+        Io(String),
+    }
+
+    impl fmt::Display for ErroreCambi {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                ErroreCambi::TassoMancante { da, a } => write!(f, "nessun tasso di cambio da {} a {}", da, a),
+                ErroreCambi::Formato { riga, messaggio } => write!(f, "tabella dei cambi, riga {}: {}", riga, messaggio),
+                ErroreCambi::Io(messaggio) => write!(f, "errore di lettura dei cambi: {}", messaggio),
+            }
+        }
+    }
+
+    impl std::error::Error for ErroreCambi {}
+
+    /// Tasso di cambio unitario, tra una valuta e se stessa.
+    pub const TASSO_UNITARIO: Tasso = Tasso::da_miliardesimi(1_000_000_000);
+
+    /// Converte un importo nella valuta indicata al tasso dato, arrotondando all'unità minima
+    /// della valuta di arrivo con l'arrotondamento bancario.
+    ///
+    /// # Argomenti
+    ///
+    /// * `importo` - Importo da convertire.
+    /// * `tasso` - Unità della valuta di arrivo per un'unità della valuta dell'importo.
+    /// * `verso` - Valuta di arrivo.
+    pub fn converti(importo: Denaro, tasso: Tasso, verso: Valuta) -> Denaro {
+        let numeratore = tasso.miliardesimi() as i128 * 10i128.pow(verso.decimali());
+        let denominatore = 10i128.pow(Tasso::DECIMALI + importo.valuta().decimali());
+        Denaro::da_unita_minime(importo.unita_minime(), verso).moltiplica_frazione(numeratore, denominatore, Arrotondamento::Bancario)
+    }
+
+    /// Fornitore di tassi di cambio, ad esempio una tabella fissa o un servizio esterno.
+    pub trait FornitoreCambi: Send + Sync {
+        /// Restituisce quante unità di `a` vale un'unità di `da`.
+        fn tasso(&self, da: Valuta, a: Valuta) -> Result<Tasso, ErroreCambi>;
+
+        /// Converte un importo nella valuta indicata al tasso attuale.
+        ///
+        /// # Argomenti
+        ///
+        /// * `importo` - Importo da convertire.
+        /// * `verso` - Valuta di arrivo.
+        fn converti(&self, importo: Denaro, verso: Valuta) -> Result<Denaro, ErroreCambi> {
+            Ok(converti(importo, self.tasso(importo.valuta(), verso)?, verso))
+        }
+    }
+
+    /// Tabella di tassi di cambio fissi.
+    ///
+    /// Se manca il tasso da `A` a `B` ma è presente quello da `B` ad `A` si usa il suo inverso,
+    /// arrotondato a nove cifre decimali.
+    ///
+    /// # Esempio
+    ///
+    /// ```
+    /// use esercizio5::cambi::cambi::{FornitoreCambi, TabellaCambi};
+    /// use esercizio5::denaro::denaro::{Denaro, Valuta};
+    ///
+    /// let tabella = TabellaCambi::leggi("# cambi del giorno\nEUR USD 1.25\nEUR JPY 160.5\n").unwrap();
+    /// let euro = Denaro::da_decimale("100", Valuta::EUR).unwrap();
+    /// assert_eq!(tabella.converti(euro, Valuta::USD).unwrap().to_string(), "125.00 USD");
+    /// assert_eq!(tabella.converti(euro, Valuta::JPY).unwrap().to_string(), "16050 JPY");
+    /// let dollari = Denaro::da_decimale("10", Valuta::USD).unwrap();
+    /// assert_eq!(tabella.converti(dollari, Valuta::EUR).unwrap().to_string(), "8.00 EUR");
+    /// assert!(tabella.converti(dollari, Valuta::GBP).is_err());
+    /// ```
+    #[derive(Debug, Clone, Default, PartialEq)]
+    pub struct TabellaCambi {
+        tassi: HashMap<(Valuta, Valuta), Tasso>,
+    }
+
+    impl TabellaCambi {
+        /// Crea una tabella vuota.
+        pub fn nuova() -> TabellaCambi {
+            TabellaCambi::default()
+        }
+
+        /// Imposta il tasso di cambio da una valuta all'altra.
+        ///
+        /// # Argomenti
+        ///
+        /// * `da` - Valuta di partenza.
+        /// * `a` - Valuta di arrivo.
+        /// * `tasso` - Unità di `a` per un'unità di `da`, positivo.
+        pub fn con_tasso(mut self, da: Valuta, a: Valuta, tasso: Tasso) -> TabellaCambi {
+            assert!(tasso.miliardesimi() > 0, "Il tasso di cambio deve essere positivo");
+            self.tassi.insert((da, a), tasso);
+            self
+        }
+
+        /// Legge una tabella dal testo indicato: una riga `DA A TASSO` per ogni cambio, ad esempio
+        /// `EUR USD 1.0850`; le righe vuote e quelle che iniziano con `#` vengono ignorate.
+        ///
+        /// # Argomenti
+        ///
+        /// * `testo` - Il contenuto della tabella.
+        pub fn leggi(testo: &str) -> Result<TabellaCambi, ErroreCambi> {
+            let mut tabella = TabellaCambi::nuova();
+            for (indice, riga) in testo.lines().enumerate() {
+                let riga = riga.trim();
+                if riga.is_empty() || riga.starts_with('#') {
+                    continue;
+                }
+                let errore = |messaggio: String| ErroreCambi::Formato { riga: indice + 1, messaggio };
+                let campi: Vec<&str> = riga.split_whitespace().collect();
+                let [da, a, tasso] = campi[..] else {
+                    return Err(errore(format!("attesi valuta, valuta e tasso, trovato \"{}\"", riga)));
+                };
+                let da = Valuta::da_codice(da).map_err(|e| errore(e.to_string()))?;
+                let a = Valuta::da_codice(a).map_err(|e| errore(e.to_string()))?;
+                let tasso = Tasso::da_decimale(tasso).map_err(|e| errore(e.to_string()))?;
+                if tasso.miliardesimi() <= 0 {
+                    return Err(errore(format!("il tasso da {} a {} deve essere positivo", da, a)));
+                }
+                tabella = tabella.con_tasso(da, a, tasso);
+            }
+            Ok(tabella)
+        }
+
+        /// Legge una tabella da file, nel formato di `leggi`.
+        ///
+        /// # Argomenti
+        ///
+        /// * `percorso` - Percorso del file.
+        pub fn da_file(percorso: impl AsRef<Path>) -> Result<TabellaCambi, ErroreCambi> {
+            let testo = fs::read_to_string(percorso).map_err(|e| ErroreCambi::Io(e.to_string()))?;
+            TabellaCambi::leggi(&testo)
+        }
+    }
+
+    impl FornitoreCambi for TabellaCambi {
+        fn tasso(&self, da: Valuta, a: Valuta) -> Result<Tasso, ErroreCambi> {
+            if da == a {
+                return Ok(TASSO_UNITARIO);
+            }
+            if let Some(tasso) = self.tassi.get(&(da, a)) {
+                return Ok(*tasso);
+            }
+            let inverso = self.tassi.get(&(a, da)).ok_or(ErroreCambi::TassoMancante { da, a })?;
+            let uno = TASSO_UNITARIO.miliardesimi() as i128;
+            let miliardesimi = (uno * uno + inverso.miliardesimi() as i128 / 2) / inverso.miliardesimi() as i128;
+            Ok(Tasso::da_miliardesimi(miliardesimi as i64))
+        }
+    }
+
+    /// Tassi di cambio letti da un file, che può essere ricaricato quando viene aggiornato.
+    ///
+    /// Sostituisce, in attesa di un servizio esterno, il fornitore dei cambi del giorno.
+    pub struct CambiDaFile {
+        percorso: PathBuf,
+        tabella: RwLock<TabellaCambi>,
+    }
+
+    impl CambiDaFile {
+        /// Legge i tassi dal file indicato, nel formato di `TabellaCambi::leggi`.
+        ///
+        /// # Argomenti
+        ///
+        /// * `percorso` - Percorso del file dei cambi.
+        pub fn apri(percorso: impl Into<PathBuf>) -> Result<CambiDaFile, ErroreCambi> {
+            let percorso = percorso.into();
+            let tabella = TabellaCambi::da_file(&percorso)?;
+            Ok(CambiDaFile { percorso, tabella: RwLock::new(tabella) })
+        }
+
+        /// Rilegge il file; se non è valido i tassi precedenti restano in uso.
+        pub fn ricarica(&self) -> Result<(), ErroreCambi> {
+            let tabella = TabellaCambi::da_file(&self.percorso)?;
+            *self.tabella.write().unwrap() = tabella;
+            Ok(())
+        }
+
+        /// Restituisce il percorso del file dei cambi.
+        pub fn percorso(&self) -> &Path {
+            &self.percorso
+        }
+    }
+
+    impl FornitoreCambi for CambiDaFile {
+        fn tasso(&self, da: Valuta, a: Valuta) -> Result<Tasso, ErroreCambi> {
+            self.tabella.read().unwrap().tasso(da, a)
+        }
+    }
+}
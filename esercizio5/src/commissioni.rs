@@ -0,0 +1,74 @@
+/// Modulo `commissioni` per le commissioni per operazione e i canoni mensili che dipendono dallo stato del conto.
+pub mod commissioni {
+    use std::collections::HashMap;
+    use crate::denaro::denaro::{Denaro, Tasso};
+    use crate::stati::stati::Stato;
+
+    /// Commissioni applicate da un conto, diverse per ogni stato.
+    ///
+    /// La commissione per operazione è addebitata dopo ogni deposito o prelievo, secondo lo stato
+    /// in cui il conto si trovava prima dell'operazione; il canone mensile secondo lo stato del
+    /// conto al momento dell'addebito. Gli stati senza importo non pagano nulla. La commissione di
+    /// cambio è una percentuale dell'importo convertito in un'altra valuta, uguale in ogni stato.
+    ///
+    /// # Esempio
+    ///
+    /// ```
+    /// use esercizio5::commissioni::commissioni::Commissioni;
+    /// use esercizio5::denaro::denaro::{Denaro, Valuta};
+    /// use esercizio5::stati::stati::Stato;
+    ///
+    /// let euro = |importo: &str| Denaro::da_decimale(importo, Valuta::EUR).unwrap();
+    /// let commissioni = Commissioni::nuove()
+    ///     .con_commissione_operazione(Stato::Argento, euro("0.50"))
+    ///     .con_canone_mensile(Stato::Argento, euro("2"));
+    /// assert_eq!(commissioni.per_operazione(Stato::Argento), Some(euro("0.50")));
+    /// assert_eq!(commissioni.canone_mensile(Stato::Oro), None);
+    /// ```
+    #[derive(Debug, Clone, Default, PartialEq)]
+    pub struct Commissioni {
+        per_operazione: HashMap<Stato, Denaro>,
+        canoni: HashMap<Stato, Denaro>,
+        cambio: Tasso,
+    }
+
+    impl Commissioni {
+        /// Crea commissioni vuote: nessuno stato paga nulla.
+        pub fn nuove() -> Commissioni {
+            Commissioni::default()
+        }
+
+        /// Imposta la commissione addebitata per ogni deposito o prelievo eseguito nello stato indicato.
+        pub fn con_commissione_operazione(mut self, stato: Stato, importo: Denaro) -> Commissioni {
+            self.per_operazione.insert(stato, importo);
+            self
+        }
+
+        /// Imposta il canone mensile di tenuta conto addebitato nello stato indicato.
+        pub fn con_canone_mensile(mut self, stato: Stato, importo: Denaro) -> Commissioni {
+            self.canoni.insert(stato, importo);
+            self
+        }
+
+        /// Imposta la commissione di cambio, come frazione dell'importo convertito (`0.01` per l'1%).
+        pub fn con_commissione_cambio(mut self, tasso: Tasso) -> Commissioni {
+            self.cambio = tasso;
+            self
+        }
+
+        /// Restituisce la commissione di cambio, come frazione dell'importo convertito.
+        pub fn commissione_cambio(&self) -> Tasso {
+            self.cambio
+        }
+
+        /// Restituisce la commissione per operazione dello stato indicato, se non nulla.
+        pub fn per_operazione(&self, stato: Stato) -> Option<Denaro> {
+            self.per_operazione.get(&stato).copied().filter(|importo| !importo.is_zero())
+        }
+
+        /// Restituisce il canone mensile dello stato indicato, se non nullo.
+        pub fn canone_mensile(&self, stato: Stato) -> Option<Denaro> {
+            self.canoni.get(&stato).copied().filter(|importo| !importo.is_zero())
+        }
+    }
+}
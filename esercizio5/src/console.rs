@@ -0,0 +1,423 @@
+/// Modulo `console` per l'interprete dei comandi testuali che gestiscono una banca, in modo
+/// interattivo o leggendo uno script.
+pub mod console {
+    use std::io::{self, BufRead, Write};
+    use std::sync::Arc;
+    use chrono::{NaiveDate, NaiveDateTime};
+    use crate::banca::banca::{Banca, ErroreBanca, IdConto, RicevutaCambio, RicevutaTrasferimento};
+    use crate::cambi::cambi::FornitoreCambi;
+    use crate::conto_bancario::conto_bancario::{ContoBancario, Ricevuta};
+    use crate::denaro::denaro::{Denaro, Tasso, Valuta};
+    use crate::titolari::titolari::{Ruolo, Titolare};
+
+    /// Elenco dei comandi, mostrato da `aiuto`.
+    pub const AIUTO: &str = "\
+Comandi disponibili (importi nella valuta della banca se non indicata, conti come 1 o #1):
+  apri <nome> <saldo> <limite inferiore> <limite superiore> [interesse]
+  divisa <conto> <valuta>
+  come <titolare> deposita <conto> <importo> [valuta]
+  come <titolare> preleva <conto> <importo> [valuta]
+  come <titolare> trasferisci <da> <a> <importo>
+  come <titolare> cambia <da> <a> <importo> <valuta> <verso>
+  interessi <conto>
+  titolare <conto> <da> <nome> <intestatario|cointestatario|lettura> [limite giornaliero]
+  titolare <conto> <da> <nome> delegato <tetto> [limite giornaliero]
+  revoca <conto> <da> <nome>
+  estratto <conto> [dal AAAA-MM-GG] [al AAAA-MM-GG]
+  stato [conto]
+  aiuto
+  esci
+I nomi con spazi vanno tra virgolette; le righe vuote e quelle che iniziano con # vengono ignorate.";
+
+    /// Comando dell'interprete, una riga per comando.
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Comando {
+        /// `apri <nome> <saldo> <limite inferiore> <limite superiore> [interesse]`: apre un conto.
+        Apri {
+            /// Nome del cliente.
+            nome: String,
+            /// Saldo iniziale.
+            saldo: Denaro,
+            /// Limite inferiore del saldo.
+            limite_inferiore: Denaro,
+            /// Limite superiore del saldo.
+            limite_superiore: Denaro,
+            /// Tasso di interesse, zero se assente.
+            interesse: Tasso,
+        },
+        /// `divisa <conto> <valuta>`: apre sul conto un saldo in un'altra valuta.
+        ApriDivisa(IdConto, Valuta),
+        /// `come <titolare> deposita <conto> <importo> [valuta]`.
+        Deposita(String, IdConto, Denaro),
+        /// `come <titolare> preleva <conto> <importo> [valuta]`.
+        Preleva(String, IdConto, Denaro),
+        /// `come <titolare> trasferisci <da> <a> <importo>`.
+        Trasferisci(String, IdConto, IdConto, Denaro),
+        /// `come <titolare> cambia <da> <a> <importo> <valuta> <verso>`: trasferisce un importo
+        /// convertendolo nella valuta `verso`.
+        Cambia(String, IdConto, IdConto, Denaro, Valuta),
+        /// `interessi <conto>`: accredita gli interessi al tasso del conto.
+        Interessi(IdConto),
+        /// `titolare <conto> <da> <nome> <ruolo> [tetto] [limite giornaliero]`: aggiunge un titolare
+        /// su richiesta dell'intestatario `da`; il tetto serve solo ai delegati.
+        Titolare(IdConto, String, Titolare),
+        /// `revoca <conto> <da> <nome>`: rimuove un titolare su richiesta dell'intestatario `da`.
+        Revoca(IdConto, String, String),
+        /// `estratto <conto> [dal] [al]`: mostra l'estratto conto, per default dall'inizio a oggi.
+        Estratto(IdConto, Option<NaiveDate>, Option<NaiveDate>),
+        /// `stato [conto]`: mostra saldo e stato di un conto o di tutti i conti.
+        Stato(Option<IdConto>),
+        /// `aiuto`: mostra l'elenco dei comandi.
+        Aiuto,
+        /// `esci`: termina la sessione.
+        Esci,
+    }
+
+    /// Divide una riga in parole separate da spazi; il testo tra virgolette forma una sola parola.
+    fn parole(riga: &str) -> Result<Vec<String>, String> {
+        let mut parole = Vec::new();
+        let mut parola: Option<String> = None;
+        let mut tra_virgolette = false;
+        for c in riga.chars() {
+            match c {
+                '"' => {
+                    tra_virgolette = !tra_virgolette;
+                    parola.get_or_insert_with(String::new);
+                }
+                c if c.is_whitespace() && !tra_virgolette => parole.extend(parola.take()),
+                c => parola.get_or_insert_with(String::new).push(c),
+            }
+        }
+        if tra_virgolette {
+            return Err("virgolette non chiuse".to_string());
+        }
+        parole.extend(parola);
+        Ok(parole)
+    }
+
+    /// Legge un identificativo di conto, con o senza `#` iniziale.
+    fn id_conto(testo: &str) -> Result<IdConto, String> {
+        testo.trim_start_matches('#').parse().map(IdConto).map_err(|_| format!("conto non valido: {}", testo))
+    }
+
+    /// Legge un importo nella valuta indicata.
+    fn importo(testo: &str, valuta: Valuta) -> Result<Denaro, String> {
+        Denaro::da_decimale(testo, valuta).map_err(|errore| format!("importo non valido: {}", errore))
+    }
+
+    /// Legge il codice di una valuta.
+    fn valuta(testo: &str) -> Result<Valuta, String> {
+        Valuta::da_codice(&testo.to_uppercase()).map_err(|errore| format!("valuta non valida: {}", errore))
+    }
+
+    /// Legge una data nel formato `AAAA-MM-GG`.
+    fn data(testo: &str) -> Result<NaiveDate, String> {
+        NaiveDate::parse_from_str(testo, "%Y-%m-%d").map_err(|_| format!("data non valida: {}", testo))
+    }
+
+    /// Legge un titolare dal nome, dal ruolo e dagli importi che lo seguono.
+    fn titolare(nome: &str, ruolo: &str, importi: &[&str], valuta: Valuta) -> Result<Titolare, String> {
+        let (ruolo, limite) = match (ruolo.to_lowercase().as_str(), importi) {
+            ("intestatario", limite) if limite.len() <= 1 => (Ruolo::Intestatario, limite.first()),
+            ("cointestatario", limite) if limite.len() <= 1 => (Ruolo::Cointestatario, limite.first()),
+            ("lettura", limite) if limite.len() <= 1 => (Ruolo::SolaLettura, limite.first()),
+            ("delegato", [tetto, limite @ ..]) if limite.len() <= 1 => (Ruolo::Delegato { tetto: importo(tetto, valuta)? }, limite.first()),
+            _ => return Err(format!("ruolo non valido: {}", ruolo)),
+        };
+        let titolare = Titolare::nuovo(nome, ruolo);
+        Ok(match limite {
+            Some(limite) => titolare.con_limite_giornaliero(importo(limite, valuta)?),
+            None => titolare,
+        })
+    }
+
+    impl Comando {
+        /// Interpreta una riga. Restituisce `None` per le righe vuote e i commenti, e il messaggio
+        /// d'errore se la riga non è un comando valido.
+        ///
+        /// # Argomenti
+        ///
+        /// * `riga` - La riga da interpretare.
+        /// * `valuta` - Valuta degli importi.
+        pub fn interpreta(riga: &str, valuta: Valuta) -> Result<Option<Comando>, String> {
+            let riga = riga.trim();
+            if riga.is_empty() || riga.starts_with('#') {
+                return Ok(None);
+            }
+            Comando::da_parole(&parole(riga)?, valuta).map(Some)
+        }
+
+        /// Interpreta un comando già diviso in parole.
+        fn da_parole(parole: &[String], valuta: Valuta) -> Result<Comando, String> {
+            let argomenti: Vec<&str> = parole[1..].iter().map(String::as_str).collect();
+            let comando = match (parole[0].to_lowercase().as_str(), argomenti.as_slice()) {
+                ("apri", [nome, saldo, inferiore, superiore, resto @ ..]) if resto.len() <= 1 => Comando::Apri {
+                    nome: nome.to_string(),
+                    saldo: importo(saldo, valuta)?,
+                    limite_inferiore: importo(inferiore, valuta)?,
+                    limite_superiore: importo(superiore, valuta)?,
+                    interesse: match resto.first() {
+                        Some(tasso) => Tasso::da_decimale(tasso).map_err(|errore| format!("tasso non valido: {}", errore))?,
+                        None => Tasso::ZERO,
+                    },
+                },
+                ("divisa", [conto, codice]) => Comando::ApriDivisa(id_conto(conto)?, self::valuta(codice)?),
+                ("deposita" | "preleva" | "trasferisci" | "cambia", _) => {
+                    return Err(format!("{} va eseguito per conto di un titolare: come <titolare> {} ...", parole[0], parole[0]));
+                }
+                ("interessi", [conto]) => Comando::Interessi(id_conto(conto)?),
+                ("titolare", [conto, da, nome, ruolo, importi @ ..]) => {
+                    Comando::Titolare(id_conto(conto)?, da.to_string(), titolare(nome, ruolo, importi, valuta)?)
+                }
+                ("revoca", [conto, da, nome]) => Comando::Revoca(id_conto(conto)?, da.to_string(), nome.to_string()),
+                ("come", [titolare, _, ..]) => Comando::operazione(titolare, &argomenti[1..], valuta)?,
+                ("estratto", [conto, date @ ..]) if date.len() <= 2 => Comando::Estratto(
+                    id_conto(conto)?,
+                    date.first().map(|d| data(d)).transpose()?,
+                    date.get(1).map(|d| data(d)).transpose()?,
+                ),
+                ("stato", []) => Comando::Stato(None),
+                ("stato", [conto]) => Comando::Stato(Some(id_conto(conto)?)),
+                ("aiuto", []) => Comando::Aiuto,
+                ("esci", []) => Comando::Esci,
+                ("apri" | "divisa" | "interessi" | "titolare" | "revoca" | "come" | "estratto" | "stato" | "aiuto" | "esci", _) => {
+                    return Err(format!("argomenti non validi per {} (scrivi aiuto per l'elenco dei comandi)", parole[0]));
+                }
+                _ => return Err(format!("comando sconosciuto: {}", parole[0])),
+            };
+            Ok(comando)
+        }
+
+        /// Interpreta l'operazione sul denaro che segue `come <titolare>`.
+        fn operazione(titolare: &str, parole: &[&str], valuta: Valuta) -> Result<Comando, String> {
+            let titolare = titolare.to_string();
+            let comando = match (parole[0].to_lowercase().as_str(), &parole[1..]) {
+                ("deposita", [conto, somma]) => Comando::Deposita(titolare, id_conto(conto)?, importo(somma, valuta)?),
+                ("deposita", [conto, somma, codice]) => Comando::Deposita(titolare, id_conto(conto)?, importo(somma, self::valuta(codice)?)?),
+                ("preleva", [conto, somma]) => Comando::Preleva(titolare, id_conto(conto)?, importo(somma, valuta)?),
+                ("preleva", [conto, somma, codice]) => Comando::Preleva(titolare, id_conto(conto)?, importo(somma, self::valuta(codice)?)?),
+                ("trasferisci", [da, a, somma]) => Comando::Trasferisci(titolare, id_conto(da)?, id_conto(a)?, importo(somma, valuta)?),
+                ("cambia", [da, a, somma, codice, verso]) => Comando::Cambia(
+                    titolare,
+                    id_conto(da)?,
+                    id_conto(a)?,
+                    importo(somma, self::valuta(codice)?)?,
+                    self::valuta(verso)?,
+                ),
+                ("deposita" | "preleva" | "trasferisci" | "cambia", _) => {
+                    return Err(format!("argomenti non validi per {} (scrivi aiuto per l'elenco dei comandi)", parole[0]));
+                }
+                _ => return Err(format!("{} non si può eseguire per conto di un titolare", parole[0])),
+            };
+            Ok(comando)
+        }
+    }
+
+    /// Descrive saldo e stato di un conto, con i saldi delle eventuali divise.
+    fn descrivi(id: IdConto, conto: &ContoBancario) -> String {
+        let mut testo = format!("{} {}: saldo {}, stato {}", id, conto.nome_cliente, conto.saldo(), conto.stato());
+        let divise: Vec<String> = conto.divise().iter().map(Denaro::to_string).collect();
+        if !divise.is_empty() {
+            testo.push_str(&format!(", divise {}", divise.join(", ")));
+        }
+        testo
+    }
+
+    /// Descrive l'esito di un'operazione su un conto, segnalando l'eventuale cambio di stato.
+    fn descrivi_ricevuta(id: IdConto, nome: &str, ricevuta: &Ricevuta) -> String {
+        let mut testo = format!("{} {}: saldo {}, stato {}", id, nome, ricevuta.saldo, ricevuta.stato);
+        if ricevuta.cambio_stato() {
+            testo.push_str(&format!(" (era {})", ricevuta.stato_precedente));
+        }
+        testo
+    }
+
+    /// Descrive l'esito di un trasferimento sui due conti.
+    fn descrivi_trasferimento(banca: &Banca, da: IdConto, a: IdConto, ricevuta: &RicevutaTrasferimento) -> String {
+        format!("{}\n{}", descrivi_ricevuta(da, &nome(banca, da), &ricevuta.addebito), descrivi_ricevuta(a, &nome(banca, a), &ricevuta.accredito))
+    }
+
+    /// Descrive l'esito di un trasferimento con cambio: la conversione e i due conti.
+    fn descrivi_cambio(banca: &Banca, da: IdConto, a: IdConto, ricevuta: &RicevutaCambio) -> String {
+        format!(
+            "Cambio {} -> {}, commissione {}\n{}\n{}",
+            ricevuta.conversione.importo,
+            ricevuta.conversione.controvalore,
+            ricevuta.conversione.commissione,
+            descrivi_ricevuta(da, &nome(banca, da), &ricevuta.addebito),
+            descrivi_ricevuta(a, &nome(banca, a), &ricevuta.accredito)
+        )
+    }
+
+    /// Restituisce il nome del cliente di un conto, vuoto se il conto non esiste.
+    fn nome(banca: &Banca, id: IdConto) -> String {
+        banca.conto(id).map_or(String::new(), |conto| conto.nome_cliente.clone())
+    }
+
+    /// Restituisce la data per un'operazione sui conti indicati: la più recente tra le loro.
+    fn adesso(banca: &Banca, ids: &[IdConto]) -> Result<NaiveDateTime, ErroreBanca> {
+        ids.iter()
+            .map(|id| banca.conto(*id).map(|conto| conto.adesso()).ok_or(ErroreBanca::ContoInesistente(*id)))
+            .try_fold(NaiveDateTime::MIN, |data, adesso| Ok(data.max(adesso?)))
+    }
+
+    /// Interprete dei comandi su una banca in memoria.
+    ///
+    /// # Esempio
+    ///
+    /// ```
+    /// use esercizio5::console::console::Interprete;
+    /// use esercizio5::denaro::denaro::Valuta;
+    ///
+    /// let mut interprete = Interprete::nuovo(Valuta::EUR);
+    /// let script = "apri \"Mario Rossi\" 100 0 500\ncome \"Mario Rossi\" deposita 1 450\n";
+    /// let mut uscita = Vec::new();
+    /// let errori = interprete.sessione(script.as_bytes(), &mut uscita, false).unwrap();
+    /// assert_eq!(errori, 0);
+    /// assert!(String::from_utf8(uscita).unwrap().ends_with("#1 Mario Rossi: saldo 550.00 EUR, stato Oro (era Argento)\n"));
+    /// ```
+    pub struct Interprete {
+        banca: Banca,
+        cambi: Option<Arc<dyn FornitoreCambi>>,
+    }
+
+    impl Interprete {
+        /// Crea un interprete con una banca vuota.
+        ///
+        /// # Argomenti
+        ///
+        /// * `valuta` - Valuta della banca e degli importi dei comandi.
+        pub fn nuovo(valuta: Valuta) -> Interprete {
+            Interprete { banca: Banca::nuova(valuta), cambi: None }
+        }
+
+        /// Imposta il fornitore dei tassi di cambio usato dai conti aperti d'ora in poi, necessario
+        /// per le divise e i trasferimenti con cambio.
+        ///
+        /// # Argomenti
+        ///
+        /// * `cambi` - Il fornitore dei tassi di cambio.
+        pub fn con_cambi(mut self, cambi: Arc<dyn FornitoreCambi>) -> Interprete {
+            self.cambi = Some(cambi);
+            self
+        }
+
+        /// Restituisce la banca gestita dall'interprete.
+        pub fn banca(&self) -> &Banca {
+            &self.banca
+        }
+
+        /// Esegue un comando e restituisce il testo da mostrare, che dopo ogni operazione riporta
+        /// saldo e stato dei conti coinvolti.
+        ///
+        /// # Argomenti
+        ///
+        /// * `comando` - Il comando da eseguire; `Esci` non ha effetto.
+        pub fn esegui(&mut self, comando: &Comando) -> Result<String, ErroreBanca> {
+            let banca = &mut self.banca;
+            Ok(match comando {
+                Comando::Apri { nome, saldo, limite_inferiore, limite_superiore, interesse } => {
+                    let mut conto = ContoBancario::new(nome.clone(), *saldo, *limite_inferiore, *limite_superiore, *interesse);
+                    if let Some(cambi) = &self.cambi {
+                        conto.imposta_cambi(Arc::clone(cambi));
+                    }
+                    let id = banca.apri(conto)?;
+                    format!("Aperto il conto {}", descrivi(id, banca.conto(id).unwrap()))
+                }
+                Comando::ApriDivisa(id, valuta) => {
+                    banca.apri_divisa(*id, *valuta)?;
+                    format!("Aperta la divisa {} sul conto {}", valuta, descrivi(*id, banca.conto(*id).unwrap()))
+                }
+                Comando::Deposita(titolare, id, importo) => {
+                    let ricevuta = banca.deposita_come_il(titolare, *id, *importo, adesso(banca, &[*id])?)?;
+                    descrivi_ricevuta(*id, &nome(banca, *id), &ricevuta)
+                }
+                Comando::Preleva(titolare, id, importo) => {
+                    let ricevuta = banca.preleva_come_il(titolare, *id, *importo, adesso(banca, &[*id])?)?;
+                    descrivi_ricevuta(*id, &nome(banca, *id), &ricevuta)
+                }
+                Comando::Interessi(id) => {
+                    let ricevuta = banca.paga_interessi(*id)?;
+                    format!("Interessi accreditati: {}\n{}", ricevuta.importo, descrivi_ricevuta(*id, &nome(banca, *id), &ricevuta))
+                }
+                Comando::Trasferisci(titolare, da, a, importo) => {
+                    let ricevuta = banca.trasferisci_come_il(titolare, *da, *a, *importo, adesso(banca, &[*da, *a])?)?;
+                    descrivi_trasferimento(banca, *da, *a, &ricevuta)
+                }
+                Comando::Cambia(titolare, da, a, importo, verso) => {
+                    let ricevuta = banca.trasferisci_con_cambio_come_il(titolare, *da, *a, *importo, *verso, adesso(banca, &[*da, *a])?)?;
+                    descrivi_cambio(banca, *da, *a, &ricevuta)
+                }
+                Comando::Titolare(id, da, titolare) => {
+                    let descrizione = format!("{} ({})", titolare.nome, titolare.ruolo);
+                    banca.aggiungi_titolare(*id, da, titolare.clone())?;
+                    format!("Aggiunto il titolare {} al conto {}", descrizione, id)
+                }
+                Comando::Revoca(id, da, titolare) => {
+                    let titolare = banca.rimuovi_titolare(*id, da, titolare)?;
+                    format!("Rimosso il titolare {} ({}) dal conto {}", titolare.nome, titolare.ruolo, id)
+                }
+                Comando::Estratto(id, dal, al) => {
+                    let conto = banca.conto(*id).ok_or(ErroreBanca::ContoInesistente(*id))?;
+                    let primo = conto.registro().movimenti().first().map(|m| m.data.date());
+                    let dal = dal.or(primo).unwrap_or(conto.adesso().date());
+                    let al = al.unwrap_or(conto.adesso().date());
+                    let estratto = conto.estratto(dal, al);
+                    format!("{}{}", estratto, descrivi(*id, conto))
+                }
+                Comando::Stato(Some(id)) => descrivi(*id, banca.conto(*id).ok_or(ErroreBanca::ContoInesistente(*id))?),
+                Comando::Stato(None) if banca.numero_conti() == 0 => "Nessun conto aperto".to_string(),
+                Comando::Stato(None) => banca.ids().map(|id| descrivi(id, banca.conto(id).unwrap())).collect::<Vec<_>>().join("\n"),
+                Comando::Aiuto => AIUTO.to_string(),
+                Comando::Esci => String::new(),
+            })
+        }
+
+        /// Legge ed esegue i comandi riga per riga fino alla fine dell'ingresso o a `esci`,
+        /// scrivendo risultati ed errori sull'uscita. Un errore non interrompe la sessione.
+        ///
+        /// In modo interattivo mostra un invito prima di ogni riga; altrimenti (script) ripete
+        /// ogni comando prima del suo risultato e indica la riga degli errori.
+        ///
+        /// Restituisce il numero di righe che hanno prodotto un errore.
+        ///
+        /// # Argomenti
+        ///
+        /// * `ingresso` - Da dove leggere i comandi.
+        /// * `uscita` - Dove scrivere i risultati.
+        /// * `interattiva` - Se la sessione è interattiva.
+        pub fn sessione(&mut self, ingresso: impl BufRead, uscita: &mut impl Write, interattiva: bool) -> io::Result<usize> {
+            let mut errori = 0;
+            let mut righe = ingresso.lines().enumerate();
+            loop {
+                if interattiva {
+                    write!(uscita, "> ")?;
+                    uscita.flush()?;
+                }
+                let Some((indice, riga)) = righe.next() else { break };
+                let riga = riga?;
+                let risultato = Comando::interpreta(&riga, self.banca.valuta());
+                if !interattiva && !matches!(risultato, Ok(None)) {
+                    writeln!(uscita, "> {}", riga.trim())?;
+                }
+                let esito = match risultato {
+                    Ok(None) => continue,
+                    Ok(Some(Comando::Esci)) => break,
+                    Ok(Some(comando)) => self.esegui(&comando).map_err(|errore| errore.to_string()),
+                    Err(messaggio) => Err(messaggio),
+                };
+                match esito {
+                    Ok(testo) => writeln!(uscita, "{}", testo)?,
+                    Err(messaggio) => {
+                        errori += 1;
+                        match interattiva {
+                            true => writeln!(uscita, "Errore: {}", messaggio)?,
+                            false => writeln!(uscita, "Errore alla riga {}: {}", indice + 1, messaggio)?,
+                        }
+                    }
+                }
+            }
+            Ok(errori)
+        }
+    }
+}
@@ -1,21 +1,224 @@
 /// Modulo `conto_bancario` per la gestione dei conti bancari.
 pub mod conto_bancario {
+    use std::collections::BTreeMap;
+    use std::fmt;
+    use std::sync::Arc;
+    use chrono::{NaiveDate, NaiveDateTime};
+    use crate::antifrode::antifrode::{Antifrode, Azione, Richiesta, Segnalazione};
+    use crate::cambi::cambi::{self, ErroreCambi, FornitoreCambi};
+    use crate::commissioni::commissioni::Commissioni;
+    use crate::denaro::denaro::{Arrotondamento, Denaro, ErroreDenaro, Tasso, Valuta};
+    use crate::fasce::fasce::{self, Fasce};
+    use crate::orologio::orologio::{Orologio, OrologioSistema};
+    use crate::registro::registro::{Attraversamento, ErroreRegistro, Estratto, Movimento, Registro, TipoMovimento};
     use crate::stati::stati::*;
+    use crate::titolari::titolari::{ErroreAutorizzazione, Permesso, Titolare, Titolari};
+
+    /// Tipo di operazione eseguita su un conto.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Operazione {
+        /// Versamento di denaro sul conto.
+        Deposito,
+        /// Prelievo di denaro dal conto.
+        Prelievo,
+        /// Pagamento degli interessi maturati.
+        Interessi,
+        /// Addebito di una commissione da parte della banca.
+        Commissione,
+    }
+
+    /// Errori restituiti dalle operazioni su un conto bancario.
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum ErroreConto {
+        /// Il saldo non copre l'importo richiesto.
+        FondiInsufficienti {
+            /// Saldo al momento della richiesta.
+            saldo: Denaro,
+            /// Importo richiesto.
+            richiesto: Denaro,
+        },
+        /// Il conto è bloccato e non accetta operazioni.
+        ContoBloccato,
+        /// L'importo è negativo.
+        ImportoNegativo(Denaro),
+        /// Il tasso di interesse del conto è negativo.
+        TassoNegativo(Tasso),
+        /// L'importo non è utilizzabile: valuta diversa da quella del conto o risultato non rappresentabile.
+        ImportoNonValido(ErroreDenaro),
+        /// L'operazione non è consentita nello stato attuale del conto.
+        OperazioneNonConsentita {
+            /// Stato del conto.
+            stato: Stato,
+            /// Operazione rifiutata.
+            operazione: Operazione,
+        },
+        /// Il movimento non può essere aggiunto al registro del conto.
+        Registro(ErroreRegistro),
+        /// Una regola antifrode ha bloccato il conto.
+        OperazioneSospetta(Segnalazione),
+        /// Una regola antifrode richiede che l'operazione sia confermata prima di eseguirla.
+        ConfermaRichiesta(Segnalazione),
+        /// Il tasso di cambio necessario non è disponibile.
+        Cambio(ErroreCambi),
+        /// Il titolare non è autorizzato all'operazione.
+        Autorizzazione(ErroreAutorizzazione),
+    }
+
+    impl fmt::Display for ErroreConto {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                ErroreConto::FondiInsufficienti { saldo, richiesto } => {
+                    write!(f, "fondi insufficienti: saldo {}, richiesti {}", saldo, richiesto)
+                }
+                ErroreConto::ContoBloccato => write!(f, "il conto è bloccato"),
+                ErroreConto::ImportoNegativo(importo) => write!(f, "importo negativo: {}", importo),
+                ErroreConto::TassoNegativo(tasso) => write!(f, "tasso di interesse negativo: {}", tasso),
+                ErroreConto::ImportoNonValido(errore) => write!(f, "importo non valido: {}", errore),
+                ErroreConto::OperazioneNonConsentita { stato, operazione } => {
+                    write!(f, "operazione {:?} non consentita nello stato {}", operazione, stato)
+                }
+                ErroreConto::Registro(errore) => write!(f, "{}", errore),
+                ErroreConto::OperazioneSospetta(segnalazione) => write!(f, "operazione sospetta, conto bloccato: {}", segnalazione),
+                ErroreConto::ConfermaRichiesta(segnalazione) => write!(f, "operazione da confermare: {}", segnalazione),
+                ErroreConto::Cambio(errore) => write!(f, "{}", errore),
+                ErroreConto::Autorizzazione(errore) => write!(f, "{}", errore),
+            }
+        }
+    }
+
+    impl std::error::Error for ErroreConto {}
+
+    impl From<ErroreDenaro> for ErroreConto {
+        fn from(errore: ErroreDenaro) -> Self {
+            ErroreConto::ImportoNonValido(errore)
+        }
+    }
+
+    impl From<ErroreRegistro> for ErroreConto {
+        fn from(errore: ErroreRegistro) -> Self {
+            ErroreConto::Registro(errore)
+        }
+    }
+
+    impl From<ErroreCambi> for ErroreConto {
+        fn from(errore: ErroreCambi) -> Self {
+            ErroreConto::Cambio(errore)
+        }
+    }
+
+    impl From<ErroreAutorizzazione> for ErroreConto {
+        fn from(errore: ErroreAutorizzazione) -> Self {
+            ErroreConto::Autorizzazione(errore)
+        }
+    }
+
+    /// Funzione chiamata a ogni avviso registrato sul conto.
+    type Notifica = Box<dyn Fn(&Movimento) + Send + Sync>;
+
+    /// Ricevuta di un'operazione andata a buon fine.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Ricevuta {
+        /// Operazione eseguita.
+        pub operazione: Operazione,
+        /// Importo effettivamente movimentato (per gli interessi, quelli accreditati).
+        pub importo: Denaro,
+        /// Saldo prima dell'operazione.
+        pub saldo_precedente: Denaro,
+        /// Saldo dopo l'operazione, compresa l'eventuale commissione.
+        pub saldo: Denaro,
+        /// Commissioni addebitate insieme all'operazione, per operazione e di cambio (zero se assenti).
+        pub commissione: Denaro,
+        /// Stato del conto prima dell'operazione.
+        pub stato_precedente: Stato,
+        /// Stato del conto dopo l'operazione.
+        pub stato: Stato,
+    }
+
+    impl Ricevuta {
+        /// Indica se l'operazione ha fatto cambiare stato al conto.
+        pub fn cambio_stato(&self) -> bool {
+            self.stato != self.stato_precedente
+        }
+    }
+
+    /// Conversione di un importo in un'altra valuta, con la commissione di cambio a carico di chi converte.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Conversione {
+        /// Importo convertito, nella valuta di partenza.
+        pub importo: Denaro,
+        /// Commissione di cambio, nella valuta di partenza, addebitata in aggiunta all'importo.
+        pub commissione: Denaro,
+        /// Tasso applicato: unità della valuta di arrivo per un'unità di quella di partenza.
+        pub tasso: Tasso,
+        /// Controvalore dell'importo nella valuta di arrivo.
+        pub controvalore: Denaro,
+    }
+
+    /// Stato di un conto salvato prima di un'operazione composta, per poterla annullare.
+    #[derive(Debug, Clone)]
+    pub(crate) struct PuntoRipristino {
+        saldo: Denaro,
+        stato: Stato,
+        movimenti: usize,
+        divise: Vec<(Valuta, usize)>,
+        avvisi: usize,
+        bloccato: bool,
+        confermata: bool,
+        segnalazioni: usize,
+    }
 
     /// Rappresenta un conto bancario con le sue caratteristiche.
+    ///
+    /// Le operazioni senza titolare (`deposita`, `preleva`, ...) sono quelle eseguite dalla banca;
+    /// quelle richieste da un titolare (`deposita_come_il`, `preleva_come_il`, `converti_come_il`,
+    /// `estratto_come`) vengono prima autorizzate secondo il suo ruolo e i suoi limiti.
     pub struct ContoBancario {
-        /// Nome del cliente associato al conto.
+        /// Nome del cliente associato al conto, il primo intestatario.
         pub nome_cliente: String,
-        /// Saldo attuale del conto.
-        pub saldo: f64,
+        /// Saldo attuale del conto, modificato solo dalle operazioni del conto.
+        saldo: Denaro,
         /// Limite inferiore del saldo del conto.
-        pub limite_inferiore: f64,
+        pub limite_inferiore: Denaro,
         /// Limite superiore del saldo del conto.
-        pub limite_superiore: f64,
+        pub limite_superiore: Denaro,
         /// Tasso di interesse applicato al conto.
-        pub interesse: f64,
-        /// Stato attuale del conto (implementa il pattern State).
-        pub stato: Box<dyn State>,
+        pub interesse: Tasso,
+        /// Modo di arrotondamento degli interessi all'unità minima della valuta.
+        pub arrotondamento: Arrotondamento,
+        /// Stato attuale del conto (implementa il pattern State), aggiornato dopo ogni operazione.
+        stato: Box<dyn State>,
+        /// Indica se il conto è bloccato: un conto bloccato rifiuta ogni operazione.
+        pub bloccato: bool,
+        /// Fido accordato: i prelievi possono portare il saldo fino a meno questo importo.
+        pub fido: Denaro,
+        /// Commissioni per operazione e canoni mensili, per stato.
+        pub commissioni: Commissioni,
+        /// Soglie del saldo il cui attraversamento registra un avviso.
+        pub soglie: Vec<Denaro>,
+        /// Registro dei movimenti del conto, in sola aggiunta.
+        registro: Registro,
+        /// Configurazione delle fasce che definisce gli stati del conto, se presente.
+        fasce: Option<Arc<Fasce>>,
+        /// Orologio usato per datare i movimenti senza data esplicita.
+        orologio: Arc<dyn Orologio>,
+        /// Funzioni da chiamare a ogni avviso.
+        notifiche: Vec<Notifica>,
+        /// Avvisi registrati durante un'operazione composta e non ancora notificati.
+        avvisi_sospesi: Vec<Movimento>,
+        /// Operazioni composte in corso: finché ce n'è almeno una gli avvisi non vengono notificati.
+        operazioni_composte: u32,
+        /// Regole antifrode consultate prima di ogni deposito e prelievo, se presenti.
+        antifrode: Option<Arc<Antifrode>>,
+        /// Indica se la prossima operazione è stata confermata dal cliente.
+        confermata: bool,
+        /// Segnalazioni antifrode ricevute dal conto.
+        segnalazioni: Vec<Segnalazione>,
+        /// Registri dei saldi in valute diverse da quella del conto, per valuta.
+        divise: BTreeMap<Valuta, Registro>,
+        /// Fornitore dei tassi di cambio per le divise, se presente.
+        cambi: Option<Arc<dyn FornitoreCambi>>,
+        /// Titolari del conto con i loro ruoli e limiti.
+        titolari: Titolari,
     }
 
     impl ContoBancario {
@@ -24,40 +227,970 @@ pub mod conto_bancario {
         /// # Argomenti
         ///
         /// * `nome_cliente` - Nome del cliente associato al conto.
-        /// * `saldo` - Saldo iniziale del conto.
+        /// * `saldo` - Saldo iniziale del conto, nella valuta del conto.
         /// * `limite_inferiore` - Limite inferiore del saldo del conto.
         /// * `limite_superiore` - Limite superiore del saldo del conto.
         /// * `interesse` - Tasso di interesse applicato al conto.
         ///
-        /// # Panico
-        ///
-        /// Questo metodo panica se il saldo è inferiore al limite inferiore specificato.
-        ///
         /// # Esempio
         ///
         /// ```
-        /// use conto_bancario::ContoBancario;
+        /// use esercizio5::conto_bancario::conto_bancario::ContoBancario;
+        /// use esercizio5::denaro::denaro::{Denaro, Tasso, Valuta};
         ///
-        /// let conto = ContoBancario::new(String::from("Mario Rossi"), 1000.0, 0.0, 5000.0, 0.03);
+        /// let euro = |importo: &str| Denaro::da_decimale(importo, Valuta::EUR).unwrap();
+        /// let conto = ContoBancario::new(String::from("Mario Rossi"), euro("1000"), euro("0"), euro("5000"), Tasso::da_decimale("0.03").unwrap());
         /// ```
-        pub fn new(nome_cliente: String, saldo: f64, limite_inferiore: f64, limite_superiore: f64, interesse: f64) -> Self {
-
+        ///
+        /// # Panico
+        ///
+        /// Panica se i limiti non sono nella valuta del saldo: lo stato del conto non sarebbe definito.
+        pub fn new(nome_cliente: String, saldo: Denaro, limite_inferiore: Denaro, limite_superiore: Denaro, interesse: Tasso) -> Self {
+            assert!(
+                limite_inferiore.valuta() == saldo.valuta() && limite_superiore.valuta() == saldo.valuta(),
+                "I limiti del conto devono essere nella valuta del saldo ({})",
+                saldo.valuta()
+            );
             ContoBancario {
+                titolari: Titolari::nuovi(&nome_cliente, saldo.valuta()),
                 nome_cliente,
                 saldo,
                 limite_inferiore,
                 limite_superiore,
                 interesse,
-                stato: {
-                    if saldo < limite_inferiore {
-                        Box::new(StatoRosso)
-                    } else if saldo < limite_superiore {
-                        Box::new(StatoArgento)
-                    } else {
-                        Box::new(StatoOro)
+                arrotondamento: Arrotondamento::Bancario,
+                stato: Stato::per_saldo(saldo, limite_inferiore, limite_superiore).comportamento(),
+                bloccato: false,
+                fido: Denaro::zero(saldo.valuta()),
+                commissioni: Commissioni::nuove(),
+                soglie: Vec::new(),
+                registro: Registro::nuovo(saldo),
+                fasce: None,
+                orologio: Arc::new(OrologioSistema),
+                notifiche: Vec::new(),
+                avvisi_sospesi: Vec::new(),
+                operazioni_composte: 0,
+                antifrode: None,
+                confermata: false,
+                segnalazioni: Vec::new(),
+                divise: BTreeMap::new(),
+                cambi: None,
+            }
+        }
+
+        /// Crea un conto i cui stati sono definiti da una configurazione di fasce invece che dai
+        /// limiti inferiore e superiore (che restano a zero e vengono ignorati).
+        ///
+        /// Il tasso di interesse è quello della fascia in cui si trova il conto.
+        ///
+        /// # Argomenti
+        ///
+        /// * `nome_cliente` - Nome del cliente associato al conto.
+        /// * `saldo` - Saldo iniziale del conto, nella valuta della configurazione.
+        /// * `fasce` - La configurazione delle fasce, eventualmente condivisa tra più conti.
+        pub fn con_fasce(nome_cliente: String, saldo: Denaro, fasce: Arc<Fasce>) -> Result<Self, ErroreConto> {
+            if saldo.valuta() != fasce.valuta() {
+                return Err(ErroreConto::ImportoNonValido(ErroreDenaro::ValuteDiverse {
+                    attesa: fasce.valuta(),
+                    trovata: saldo.valuta(),
+                }));
+            }
+            let zero = Denaro::zero(saldo.valuta());
+            let stato = fasce.stato_per(saldo, None);
+            Ok(ContoBancario {
+                titolari: Titolari::nuovi(&nome_cliente, saldo.valuta()),
+                nome_cliente,
+                saldo,
+                limite_inferiore: zero,
+                limite_superiore: zero,
+                interesse: Tasso::ZERO,
+                arrotondamento: Arrotondamento::Bancario,
+                stato: fasce::comportamento(&fasce, stato),
+                bloccato: false,
+                fido: Denaro::zero(saldo.valuta()),
+                commissioni: Commissioni::nuove(),
+                soglie: Vec::new(),
+                registro: Registro::nuovo(saldo),
+                fasce: Some(fasce),
+                orologio: Arc::new(OrologioSistema),
+                notifiche: Vec::new(),
+                avvisi_sospesi: Vec::new(),
+                operazioni_composte: 0,
+                antifrode: None,
+                confermata: false,
+                segnalazioni: Vec::new(),
+                divise: BTreeMap::new(),
+                cambi: None,
+            })
+        }
+
+        /// Restituisce la configurazione delle fasce del conto, se presente.
+        pub fn fasce(&self) -> Option<&Fasce> {
+            self.fasce.as_deref()
+        }
+
+        /// Deposita un importo sul conto secondo le regole dello stato attuale.
+        ///
+        /// La commissione per operazione non può portare il saldo oltre il fido, a meno che il deposito
+        /// non la copra. Un importo nella valuta di una divisa aperta con `apri_divisa` viene accreditato
+        /// sul saldo in quella valuta, senza commissione per operazione.
+        ///
+        /// # Argomenti
+        ///
+        /// * `importo` - Importo da depositare, non negativo e nella valuta del conto o di una sua divisa.
+        ///
+        /// # Esempio
+        ///
+        /// ```
+        /// use esercizio5::conto_bancario::conto_bancario::ContoBancario;
+        /// use esercizio5::denaro::denaro::{Denaro, Tasso, Valuta};
+        /// use esercizio5::stati::stati::Stato;
+        ///
+        /// let euro = |importo: &str| Denaro::da_decimale(importo, Valuta::EUR).unwrap();
+        /// let mut conto = ContoBancario::new(String::from("Mario Rossi"), euro("100"), euro("50"), euro("150"), Tasso::ZERO);
+        /// let ricevuta = conto.deposita(euro("100")).unwrap();
+        /// assert_eq!(ricevuta.saldo, euro("200"));
+        /// assert_eq!(ricevuta.stato, Stato::Oro);
+        /// ```
+        pub fn deposita(&mut self, importo: Denaro) -> Result<Ricevuta, ErroreConto> {
+            self.deposita_il(importo, self.adesso())
+        }
+
+        /// Come `deposita`, registrando il movimento con la data indicata.
+        ///
+        /// # Argomenti
+        ///
+        /// * `importo` - Importo da depositare, non negativo e nella valuta del conto.
+        /// * `data` - Data del movimento, non precedente all'ultimo movimento registrato.
+        pub fn deposita_il(&mut self, importo: Denaro, data: NaiveDateTime) -> Result<Ricevuta, ErroreConto> {
+            if self.divise.contains_key(&importo.valuta()) {
+                let zero = Denaro::zero(importo.valuta());
+                return self.esegui_divisa(Operazione::Deposito, importo, zero, zero, data, |stato, saldo| stato.deposita(saldo, importo));
+            }
+            self.verifica_importo(importo)?;
+            self.controlla_antifrode(Operazione::Deposito, importo, data)?;
+            let commissione = self.commissione_operazione();
+            let fido = self.fido;
+            self.esegui(Operazione::Deposito, data, commissione, Denaro::zero(importo.valuta()), move |stato, saldo| {
+                if importo < commissione && saldo.somma(fido)?.somma(importo)? < commissione {
+                    return Err(ErroreConto::FondiInsufficienti { saldo: *saldo, richiesto: commissione - importo });
+                }
+                stato.deposita(saldo, importo)
+            })
+        }
+
+        /// Preleva un importo dal conto secondo le regole dello stato attuale, usando se serve il fido.
+        ///
+        /// Il saldo disponibile, compreso il fido, deve coprire anche l'eventuale commissione per operazione.
+        /// Un importo nella valuta di una divisa aperta viene addebitato sul saldo in quella valuta,
+        /// senza fido né commissione per operazione.
+        ///
+        /// # Argomenti
+        ///
+        /// * `importo` - Importo da prelevare, non negativo e nella valuta del conto.
+        pub fn preleva(&mut self, importo: Denaro) -> Result<Ricevuta, ErroreConto> {
+            self.preleva_il(importo, self.adesso())
+        }
+
+        /// Come `preleva`, registrando il movimento con la data indicata.
+        ///
+        /// # Argomenti
+        ///
+        /// * `importo` - Importo da prelevare, non negativo e nella valuta del conto.
+        /// * `data` - Data del movimento, non precedente all'ultimo movimento registrato.
+        pub fn preleva_il(&mut self, importo: Denaro, data: NaiveDateTime) -> Result<Ricevuta, ErroreConto> {
+            self.preleva_con_cambio_il(importo, Denaro::zero(importo.valuta()), data)
+        }
+
+        /// Come `preleva_il`, addebitando dopo la commissione per operazione quella di cambio indicata,
+        /// registrata come movimento a sé: il saldo disponibile deve coprire importo e commissioni insieme.
+        fn preleva_con_cambio_il(&mut self, importo: Denaro, commissione_cambio: Denaro, data: NaiveDateTime) -> Result<Ricevuta, ErroreConto> {
+            let divisa = self.divise.contains_key(&importo.valuta());
+            let (fido, per_operazione) = if divisa {
+                (Denaro::zero(importo.valuta()), Denaro::zero(importo.valuta()))
+            } else {
+                self.verifica_importo(importo)?;
+                self.controlla_antifrode(Operazione::Prelievo, importo, data)?;
+                (self.fido, self.commissione_operazione())
+            };
+            let commissione = per_operazione.somma(commissione_cambio)?;
+            let azione = move |stato: &dyn State, saldo: &mut Denaro| {
+                // Lo stato vede come saldo il disponibile, al netto della commissione che seguirà
+                let mut disponibile = saldo.somma(fido)?.sottrai(commissione)?;
+                stato.preleva(&mut disponibile, importo).map_err(|errore| match errore {
+                    ErroreConto::FondiInsufficienti { richiesto, .. } => {
+                        ErroreConto::FondiInsufficienti { saldo: *saldo, richiesto: richiesto + commissione }
                     }
+                    errore => errore,
+                })?;
+                *saldo = disponibile.somma(commissione)?.sottrai(fido)?;
+                Ok(())
+            };
+            if divisa {
+                self.esegui_divisa(Operazione::Prelievo, importo, per_operazione, commissione_cambio, data, azione)
+            } else {
+                self.esegui(Operazione::Prelievo, data, per_operazione, commissione_cambio, azione)
+            }
+        }
+
+        /// Accredita gli interessi secondo le regole dello stato attuale, al tasso del conto,
+        /// che non può essere negativo (gli interessi passivi passano da `accredita_interessi_il`).
+        pub fn paga_interessi(&mut self) -> Result<Ricevuta, ErroreConto> {
+            self.paga_interessi_il(self.adesso())
+        }
+
+        /// Come `paga_interessi`, registrando il movimento con la data indicata.
+        ///
+        /// # Argomenti
+        ///
+        /// * `data` - Data del movimento, non precedente all'ultimo movimento registrato.
+        pub fn paga_interessi_il(&mut self, data: NaiveDateTime) -> Result<Ricevuta, ErroreConto> {
+            if self.bloccato {
+                return Err(ErroreConto::ContoBloccato);
+            }
+            if self.interesse.is_negativo() {
+                return Err(ErroreConto::TassoNegativo(self.interesse));
+            }
+            let (interesse, arrotondamento) = (self.interesse, self.arrotondamento);
+            let zero = Denaro::zero(self.saldo.valuta());
+            self.esegui(Operazione::Interessi, data, zero, zero, |stato, saldo| stato.paga_interessi(saldo, interesse, arrotondamento))
+        }
+
+        /// Accredita (o, se negativo, addebita) un importo di interessi calcolato altrove,
+        /// ad esempio da `MotoreInteressi`, secondo le regole dello stato attuale.
+        ///
+        /// # Argomenti
+        ///
+        /// * `importo` - Interessi da accreditare, nella valuta del conto; negativi per gli interessi passivi.
+        /// * `data` - Data del movimento, non precedente all'ultimo movimento registrato.
+        pub fn accredita_interessi_il(&mut self, importo: Denaro, data: NaiveDateTime) -> Result<Ricevuta, ErroreConto> {
+            self.verifica_importo(importo.abs())?;
+            let zero = Denaro::zero(self.saldo.valuta());
+            self.esegui(Operazione::Interessi, data, zero, zero, |stato, saldo| stato.accredita_interessi(saldo, importo))
+        }
+
+        /// Addebita una commissione secondo le regole dello stato attuale.
+        ///
+        /// # Argomenti
+        ///
+        /// * `importo` - Commissione da addebitare, non negativa e nella valuta del conto.
+        /// * `data` - Data del movimento, non precedente all'ultimo movimento registrato.
+        pub fn addebita_commissione_il(&mut self, importo: Denaro, data: NaiveDateTime) -> Result<Ricevuta, ErroreConto> {
+            if self.divise.contains_key(&importo.valuta()) {
+                let zero = Denaro::zero(importo.valuta());
+                return self.esegui_divisa(Operazione::Commissione, importo, zero, zero, data, |stato, saldo| stato.addebita_commissione(saldo, importo));
+            }
+            self.verifica_importo(importo)?;
+            let zero = Denaro::zero(self.saldo.valuta());
+            self.esegui(Operazione::Commissione, data, zero, zero, |stato, saldo| stato.addebita_commissione(saldo, importo))
+        }
+
+        /// Addebita il canone mensile dello stato attuale, se previsto dalle commissioni del conto.
+        ///
+        /// Restituisce `None` se nello stato attuale non è previsto alcun canone.
+        ///
+        /// # Argomenti
+        ///
+        /// * `data` - Data del movimento, non precedente all'ultimo movimento registrato.
+        pub fn addebita_canone_il(&mut self, data: NaiveDateTime) -> Result<Option<Ricevuta>, ErroreConto> {
+            match self.commissioni.canone_mensile(self.stato.stato()) {
+                Some(canone) => self.addebita_commissione_il(canone, data).map(Some),
+                None => Ok(None),
+            }
+        }
+
+        /// Restituisce il fido non ancora utilizzato: l'importo che si può ancora portare sotto zero.
+        pub fn fido_disponibile(&self) -> Denaro {
+            if !self.saldo.is_negativo() {
+                return self.fido;
+            }
+            // Commissioni e interessi passivi possono portare il saldo oltre il fido
+            let residuo = self.fido + self.saldo;
+            if residuo.is_negativo() { Denaro::zero(residuo.valuta()) } else { residuo }
+        }
+
+        /// Registra una funzione da chiamare ogni volta che il saldo attraversa una delle soglie del conto.
+        ///
+        /// La funzione riceve il movimento di avviso appena registrato.
+        ///
+        /// # Argomenti
+        ///
+        /// * `notifica` - La funzione da chiamare.
+        pub fn on_avviso(&mut self, notifica: impl Fn(&Movimento) + Send + Sync + 'static) {
+            self.notifiche.push(Box::new(notifica));
+        }
+
+        /// Imposta il fornitore dei tassi di cambio usato per le divise del conto.
+        ///
+        /// # Argomenti
+        ///
+        /// * `cambi` - Il fornitore dei cambi, eventualmente condiviso tra più conti.
+        pub fn imposta_cambi(&mut self, cambi: Arc<dyn FornitoreCambi>) {
+            self.cambi = Some(cambi);
+        }
+
+        /// Apre una divisa: il conto potrà tenere un saldo, con un proprio registro, anche nella valuta indicata.
+        ///
+        /// Serve un fornitore dei cambi con il tasso verso la valuta del conto, perché lo stato del conto
+        /// dipende dal saldo complessivo convertito. Aprire una divisa già aperta non ha effetto.
+        ///
+        /// # Argomenti
+        ///
+        /// * `valuta` - Valuta della divisa.
+        ///
+        /// # Esempio
+        ///
+        /// ```
+        /// use std::sync::Arc;
+        /// use esercizio5::cambi::cambi::TabellaCambi;
+        /// use esercizio5::conto_bancario::conto_bancario::ContoBancario;
+        /// use esercizio5::denaro::denaro::{Denaro, Tasso, Valuta};
+        /// use esercizio5::stati::stati::Stato;
+        ///
+        /// let euro = |importo: &str| Denaro::da_decimale(importo, Valuta::EUR).unwrap();
+        /// let cambi = TabellaCambi::nuova().con_tasso(Valuta::USD, Valuta::EUR, Tasso::da_decimale("0.8").unwrap());
+        /// let mut conto = ContoBancario::new(String::from("Mario Rossi"), euro("400"), euro("0"), euro("500"), Tasso::ZERO);
+        /// conto.imposta_cambi(Arc::new(cambi));
+        /// conto.apri_divisa(Valuta::USD).unwrap();
+        ///
+        /// let ricevuta = conto.deposita(Denaro::da_decimale("250", Valuta::USD).unwrap()).unwrap();
+        /// assert_eq!(ricevuta.saldo.to_string(), "250.00 USD");
+        /// assert_eq!(conto.saldo(), euro("400"));
+        /// assert_eq!(conto.saldo_totale().unwrap(), euro("600"));
+        /// assert_eq!(conto.stato(), Stato::Oro);
+        /// ```
+        pub fn apri_divisa(&mut self, valuta: Valuta) -> Result<(), ErroreConto> {
+            if valuta == self.saldo.valuta() || self.divise.contains_key(&valuta) {
+                return Ok(());
+            }
+            self.fornitore_cambi(valuta, self.saldo.valuta())?.tasso(valuta, self.saldo.valuta())?;
+            self.divise.insert(valuta, Registro::nuovo(Denaro::zero(valuta)));
+            Ok(())
+        }
+
+        /// Restituisce i saldi nelle divise aperte, in ordine di valuta.
+        pub fn divise(&self) -> Vec<Denaro> {
+            self.divise.values().map(saldo_registro).collect()
+        }
+
+        /// Restituisce il saldo nella valuta indicata, se è quella del conto o di una divisa aperta.
+        pub fn saldo_in(&self, valuta: Valuta) -> Option<Denaro> {
+            if valuta == self.saldo.valuta() {
+                Some(self.saldo)
+            } else {
+                self.divise.get(&valuta).map(saldo_registro)
+            }
+        }
+
+        /// Restituisce il registro dei movimenti della divisa indicata, se aperta.
+        pub fn registro_divisa(&self, valuta: Valuta) -> Option<&Registro> {
+            self.divise.get(&valuta)
+        }
+
+        /// Restituisce il saldo complessivo nella valuta del conto, convertendo le divise ai tassi attuali.
+        pub fn saldo_totale(&self) -> Result<Denaro, ErroreConto> {
+            self.divise.values().try_fold(self.saldo, |totale, registro| {
+                let saldo = saldo_registro(registro);
+                let controvalore = self.fornitore_cambi(saldo.valuta(), self.saldo.valuta())?.converti(saldo, self.saldo.valuta())?;
+                Ok(totale.somma(controvalore)?)
+            })
+        }
+
+        /// Restituisce il controvalore di un importo nella valuta indicata, ai tassi attuali del
+        /// fornitore dei cambi del conto e senza commissioni.
+        ///
+        /// # Argomenti
+        ///
+        /// * `importo` - Importo da convertire.
+        /// * `verso` - Valuta del controvalore.
+        pub fn controvalore(&self, importo: Denaro, verso: Valuta) -> Result<Denaro, ErroreCambi> {
+            if importo.valuta() == verso {
+                return Ok(importo);
+            }
+            self.fornitore_cambi(importo.valuta(), verso)?.converti(importo, verso)
+        }
+
+        /// Calcola la conversione di un importo in un'altra valuta ai tassi attuali, con la commissione
+        /// di cambio prevista dalle commissioni del conto.
+        ///
+        /// Tra importi nella stessa valuta la conversione è senza commissione.
+        ///
+        /// # Argomenti
+        ///
+        /// * `importo` - Importo da convertire, nella valuta del conto o di una divisa.
+        /// * `verso` - Valuta di arrivo.
+        pub fn preventivo_cambio(&self, importo: Denaro, verso: Valuta) -> Result<Conversione, ErroreConto> {
+            if importo.is_negativo() {
+                return Err(ErroreConto::ImportoNegativo(importo));
+            }
+            if importo.valuta() == verso {
+                let commissione = Denaro::zero(verso);
+                return Ok(Conversione { importo, commissione, tasso: cambi::TASSO_UNITARIO, controvalore: importo });
+            }
+            let tasso = self.fornitore_cambi(importo.valuta(), verso)?.tasso(importo.valuta(), verso)?;
+            Ok(Conversione {
+                importo,
+                commissione: importo.moltiplica(self.commissioni.commissione_cambio(), self.arrotondamento),
+                tasso,
+                controvalore: cambi::converti(importo, tasso, verso),
+            })
+        }
+
+        /// Converte un importo tra due saldi del conto (quello nella valuta del conto o quelli delle
+        /// divise): addebita importo e commissione di cambio sul primo e accredita il controvalore sul
+        /// secondo. Se una delle operazioni viene rifiutata il conto torna com'era.
+        ///
+        /// # Argomenti
+        ///
+        /// * `importo` - Importo da convertire.
+        /// * `verso` - Valuta del saldo su cui accreditare il controvalore.
+        /// * `data` - Data dei movimenti.
+        pub fn converti_il(&mut self, importo: Denaro, verso: Valuta, data: NaiveDateTime) -> Result<Conversione, ErroreConto> {
+            let conversione = self.preventivo_cambio(importo, verso)?;
+            let punto = self.punto_ripristino();
+            if let Err(errore) = self.addebita_cambio_il(&conversione, data) {
+                // Il prelievo rifiutato non ha mosso denaro: il conto resta com'è, compreso un eventuale blocco
+                self.conferma(punto);
+                return Err(errore);
+            }
+            if let Err(errore) = self.deposita_il(conversione.controvalore, data) {
+                self.ripristina(punto);
+                return Err(errore);
+            }
+            self.conferma(punto);
+            Ok(conversione)
+        }
+
+        /// Preleva l'importo di una conversione; la commissione di cambio viene registrata come movimento
+        /// a sé dopo quella per operazione, e compare insieme a questa nella commissione della ricevuta.
+        pub(crate) fn addebita_cambio_il(&mut self, conversione: &Conversione, data: NaiveDateTime) -> Result<Ricevuta, ErroreConto> {
+            self.preleva_con_cambio_il(conversione.importo, conversione.commissione, data)
+        }
+
+        /// Restituisce il fornitore dei cambi, o l'errore di tasso mancante tra le valute indicate.
+        fn fornitore_cambi(&self, da: Valuta, a: Valuta) -> Result<&dyn FornitoreCambi, ErroreCambi> {
+            self.cambi.as_deref().ok_or(ErroreCambi::TassoMancante { da, a })
+        }
+
+        /// Esegue un'operazione sul saldo di una divisa secondo le regole dello stato attuale, come
+        /// `esegui`, poi aggiorna lo stato in base al saldo complessivo. Fido, commissione per
+        /// operazione e avvisi riguardano solo il saldo nella valuta del conto.
+        fn esegui_divisa<F>(
+            &mut self,
+            operazione: Operazione,
+            importo: Denaro,
+            commissione: Denaro,
+            commissione_cambio: Denaro,
+            data: NaiveDateTime,
+            azione: F,
+        ) -> Result<Ricevuta, ErroreConto>
+        where
+            F: FnOnce(&dyn State, &mut Denaro) -> Result<(), ErroreConto>,
+        {
+            if self.bloccato {
+                return Err(ErroreConto::ContoBloccato);
+            }
+            if importo.is_negativo() {
+                return Err(ErroreConto::ImportoNegativo(importo));
+            }
+            let valuta = importo.valuta();
+            self.registro.verifica_data(data)?;
+            self.divise[&valuta].verifica_data(data)?;
+            if operazione != Operazione::Commissione {
+                // Le regole antifrode confrontano importi nella valuta del conto
+                let controvalore = self.fornitore_cambi(valuta, self.saldo.valuta())?.converti(importo, self.saldo.valuta())?;
+                self.controlla_antifrode(operazione, controvalore, data)?;
+            }
+            // Lo stato dipende dal saldo complessivo: senza i tassi delle divise non si muove denaro
+            self.saldo_totale()?;
+            let saldo_precedente = saldo_registro(&self.divise[&valuta]);
+            let stato_precedente = self.stato.stato();
+            let mut saldo = saldo_precedente;
+            azione(self.stato.as_ref(), &mut saldo)?;
+            let dopo_operazione = saldo;
+            let addebiti = addebita_commissioni(self.stato.as_ref(), &mut saldo, commissione, commissione_cambio)?;
+            let registro = self.divise.get_mut(&valuta).expect("divisa aperta");
+            registro.registra(data, operazione.into(), dopo_operazione - saldo_precedente, dopo_operazione)?;
+            for (tipo, variazione, saldo) in addebiti {
+                registro.registra(data, tipo, variazione, saldo)?;
+            }
+            self.aggiorna_stato_il(data)?;
+            Ok(Ricevuta {
+                operazione,
+                importo: (dopo_operazione - saldo_precedente).abs(),
+                saldo_precedente,
+                saldo,
+                commissione: dopo_operazione - saldo,
+                stato_precedente,
+                stato: self.stato.stato(),
+            })
+        }
+
+        /// Imposta le regole antifrode consultate prima di ogni deposito e prelievo.
+        ///
+        /// # Argomenti
+        ///
+        /// * `antifrode` - Il motore antifrode, eventualmente condiviso tra più conti.
+        pub fn imposta_antifrode(&mut self, antifrode: Arc<Antifrode>) {
+            self.antifrode = Some(antifrode);
+        }
+
+        /// Conferma la prossima operazione: una regola con azione `Azione::RichiediConferma` non la rifiuterà.
+        ///
+        /// La conferma vale per una sola operazione, anche se questa non la richiede.
+        pub fn conferma_operazione(&mut self) {
+            self.confermata = true;
+        }
+
+        /// Restituisce le segnalazioni antifrode ricevute dal conto, dalla più vecchia.
+        pub fn segnalazioni(&self) -> &[Segnalazione] {
+            &self.segnalazioni
+        }
+
+        /// Valuta un'operazione con le regole antifrode prima che lo stato la esegua.
+        ///
+        /// Tutte le segnalazioni vengono conservate; decide l'azione più grave: `Blocca` blocca il
+        /// conto e rifiuta l'operazione, `RichiediConferma` la rifiuta se non è stata confermata.
+        fn controlla_antifrode(&mut self, operazione: Operazione, importo: Denaro, data: NaiveDateTime) -> Result<(), ErroreConto> {
+            let Some(antifrode) = self.antifrode.clone() else {
+                return Ok(());
+            };
+            let segnalazioni = antifrode.valuta(self, &Richiesta { operazione, importo, data });
+            let confermata = std::mem::take(&mut self.confermata);
+            let piu_grave = segnalazioni.iter().max_by_key(|segnalazione| segnalazione.azione).cloned();
+            self.segnalazioni.extend(segnalazioni);
+            match piu_grave {
+                Some(segnalazione) if segnalazione.azione == Azione::Blocca => {
+                    self.bloccato = true;
+                    Err(ErroreConto::OperazioneSospetta(segnalazione))
+                }
+                Some(segnalazione) if segnalazione.azione == Azione::RichiediConferma && !confermata => {
+                    Err(ErroreConto::ConfermaRichiesta(segnalazione))
+                }
+                _ => Ok(()),
+            }
+        }
+
+        /// Restituisce la commissione per operazione dello stato attuale, zero se assente.
+        fn commissione_operazione(&self) -> Denaro {
+            self.commissioni.per_operazione(self.stato.stato()).unwrap_or(Denaro::zero(self.saldo.valuta()))
+        }
+
+        /// Sostituisce l'orologio usato per datare i movimenti senza data esplicita.
+        ///
+        /// # Argomenti
+        ///
+        /// * `orologio` - Il nuovo orologio, ad esempio un `OrologioVirtuale` condiviso con una simulazione.
+        pub fn imposta_orologio(&mut self, orologio: Arc<dyn Orologio>) {
+            self.orologio = orologio;
+        }
+
+        /// Restituisce il saldo attuale del conto, nella valuta del conto.
+        pub fn saldo(&self) -> Denaro {
+            self.saldo
+        }
+
+        /// Restituisce lo stato attuale del conto.
+        pub fn stato(&self) -> Stato {
+            self.stato.stato()
+        }
+
+        /// Restituisce il registro dei movimenti del conto.
+        pub fn registro(&self) -> &Registro {
+            &self.registro
+        }
+
+        /// Restituisce i titolari del conto.
+        pub fn titolari(&self) -> &Titolari {
+            &self.titolari
+        }
+
+        /// Aggiunge un titolare al conto, se chi lo chiede è un intestatario.
+        ///
+        /// # Argomenti
+        ///
+        /// * `da` - Nome del titolare che chiede l'operazione.
+        /// * `titolare` - Il nuovo titolare, con limiti e tetti nella valuta del conto.
+        ///
+        /// # Esempio
+        ///
+        /// ```
+        /// use esercizio5::conto_bancario::conto_bancario::{ContoBancario, ErroreConto};
+        /// use esercizio5::denaro::denaro::{Denaro, Tasso, Valuta};
+        /// use esercizio5::titolari::titolari::{ErroreAutorizzazione, Ruolo, Titolare};
+        ///
+        /// let euro = |importo: &str| Denaro::da_decimale(importo, Valuta::EUR).unwrap();
+        /// let mut conto = ContoBancario::new(String::from("Mario Rossi"), euro("1000"), euro("0"), euro("5000"), Tasso::ZERO);
+        /// conto.aggiungi_titolare("Mario Rossi", Titolare::nuovo("Anna Rossi", Ruolo::SolaLettura)).unwrap();
+        /// let oggi = chrono::NaiveDate::from_ymd_opt(2024, 1, 1).unwrap().and_hms_opt(9, 0, 0).unwrap();
+        ///
+        /// assert!(matches!(
+        ///     conto.preleva_come_il("Anna Rossi", euro("10"), oggi),
+        ///     Err(ErroreConto::Autorizzazione(ErroreAutorizzazione::NonConsentito { .. }))
+        /// ));
+        /// assert_eq!(conto.preleva_come_il("Mario Rossi", euro("10"), oggi).unwrap().saldo, euro("990"));
+        /// ```
+        pub fn aggiungi_titolare(&mut self, da: &str, titolare: Titolare) -> Result<(), ErroreConto> {
+            Ok(self.titolari.aggiungi(da, titolare)?)
+        }
+
+        /// Rimuove un titolare dal conto e lo restituisce, se chi lo chiede è un intestatario.
+        ///
+        /// # Argomenti
+        ///
+        /// * `da` - Nome del titolare che chiede l'operazione.
+        /// * `nome` - Nome del titolare da rimuovere.
+        pub fn rimuovi_titolare(&mut self, da: &str, nome: &str) -> Result<Titolare, ErroreConto> {
+            Ok(self.titolari.rimuovi(da, nome)?)
+        }
+
+        /// Controlla che un titolare possa eseguire un'operazione; gli importi nelle divise vengono
+        /// convertiti nella valuta del conto per confrontarli con limiti e tetti.
+        ///
+        /// # Argomenti
+        ///
+        /// * `titolare` - Nome del titolare.
+        /// * `permesso` - Operazione richiesta.
+        /// * `importo` - Importo dell'operazione.
+        /// * `data` - Data dell'operazione.
+        pub fn autorizza(&self, titolare: &str, permesso: Permesso, importo: Denaro, data: NaiveDateTime) -> Result<(), ErroreConto> {
+            self.autorizza_importo(titolare, permesso, importo, data).map(|_| ())
+        }
+
+        /// Come `autorizza`, restituendo l'importo convertito nella valuta del conto: è quello da
+        /// contare nei limiti di spesa con `registra_prelievo_di` dopo l'operazione.
+        pub(crate) fn autorizza_importo(&self, titolare: &str, permesso: Permesso, importo: Denaro, data: NaiveDateTime) -> Result<Denaro, ErroreConto> {
+            let importo = self.in_valuta_del_conto(importo)?;
+            self.titolari.autorizza(titolare, permesso, importo, data)?;
+            Ok(importo)
+        }
+
+        /// Deposita un importo per conto di un titolare, dopo averne verificato l'autorizzazione.
+        ///
+        /// # Argomenti
+        ///
+        /// * `titolare` - Nome del titolare.
+        /// * `importo` - Importo da depositare, nella valuta del conto o di una sua divisa.
+        /// * `data` - Data del movimento.
+        pub fn deposita_come_il(&mut self, titolare: &str, importo: Denaro, data: NaiveDateTime) -> Result<Ricevuta, ErroreConto> {
+            self.autorizza(titolare, Permesso::Deposito, importo, data)?;
+            self.deposita_il(importo, data)
+        }
+
+        /// Preleva un importo per conto di un titolare, dopo averne verificato ruolo, limite
+        /// giornaliero e, per i delegati, tetto complessivo.
+        ///
+        /// # Argomenti
+        ///
+        /// * `titolare` - Nome del titolare.
+        /// * `importo` - Importo da prelevare, nella valuta del conto o di una sua divisa.
+        /// * `data` - Data del movimento.
+        pub fn preleva_come_il(&mut self, titolare: &str, importo: Denaro, data: NaiveDateTime) -> Result<Ricevuta, ErroreConto> {
+            let speso = self.autorizza_importo(titolare, Permesso::Prelievo, importo, data)?;
+            let ricevuta = self.preleva_il(importo, data)?;
+            self.registra_prelievo_di(titolare, speso, data);
+            Ok(ricevuta)
+        }
+
+        /// Converte un importo tra due saldi del conto per conto di un titolare, come `converti_il`,
+        /// se il suo ruolo consente i prelievi. Il cambio non conta nei limiti di spesa perché il
+        /// denaro resta sul conto.
+        ///
+        /// # Argomenti
+        ///
+        /// * `titolare` - Nome del titolare.
+        /// * `importo` - Importo da convertire.
+        /// * `verso` - Valuta del saldo su cui accreditare il controvalore.
+        /// * `data` - Data dei movimenti.
+        pub fn converti_come_il(&mut self, titolare: &str, importo: Denaro, verso: Valuta, data: NaiveDateTime) -> Result<Conversione, ErroreConto> {
+            self.autorizza(titolare, Permesso::Prelievo, Denaro::zero(self.saldo.valuta()), data)?;
+            self.converti_il(importo, verso, data)
+        }
+
+        /// Restituisce l'estratto conto per un titolare autorizzato a consultare il conto.
+        ///
+        /// # Argomenti
+        ///
+        /// * `titolare` - Nome del titolare.
+        /// * `dal` - Primo giorno dell'estratto.
+        /// * `al` - Ultimo giorno dell'estratto.
+        pub fn estratto_come(&self, titolare: &str, dal: NaiveDate, al: NaiveDate) -> Result<Estratto, ErroreConto> {
+            self.autorizza(titolare, Permesso::Consultazione, Denaro::zero(self.saldo.valuta()), self.adesso())?;
+            Ok(self.estratto(dal, al))
+        }
+
+        /// Conta un prelievo eseguito da un titolare nei suoi limiti di spesa.
+        ///
+        /// # Argomenti
+        ///
+        /// * `titolare` - Nome del titolare.
+        /// * `importo` - Importo restituito da `autorizza_importo`, nella valuta del conto.
+        /// * `data` - Data del prelievo.
+        pub(crate) fn registra_prelievo_di(&mut self, titolare: &str, importo: Denaro, data: NaiveDateTime) {
+            self.titolari.registra_prelievo(titolare, importo, data);
+        }
+
+        /// Sostituisce i titolari del conto, ad esempio con quelli letti da un archivio.
+        pub(crate) fn ripristina_titolari(&mut self, titolari: Titolari) {
+            self.titolari = titolari;
+        }
+
+        /// Converte un importo nella valuta del conto ai tassi attuali, se in un'altra valuta.
+        fn in_valuta_del_conto(&self, importo: Denaro) -> Result<Denaro, ErroreConto> {
+            if importo.valuta() == self.saldo.valuta() {
+                Ok(importo)
+            } else {
+                Ok(self.fornitore_cambi(importo.valuta(), self.saldo.valuta())?.converti(importo, self.saldo.valuta())?)
+            }
+        }
+
+        /// Restituisce l'estratto conto dei movimenti tra due giorni, estremi compresi.
+        ///
+        /// # Argomenti
+        ///
+        /// * `dal` - Primo giorno dell'estratto.
+        /// * `al` - Ultimo giorno dell'estratto.
+        pub fn estratto(&self, dal: NaiveDate, al: NaiveDate) -> Estratto {
+            self.registro.estratto(&self.nome_cliente, dal, al)
+        }
+
+        /// Inizia un'operazione composta salvando saldo, stato, lunghezza dei registri e stato antifrode
+        /// per poter annullare le operazioni successive. Fino a `conferma` o `ripristina` gli avvisi registrati
+        /// non vengono notificati.
+        pub(crate) fn punto_ripristino(&mut self) -> PuntoRipristino {
+            self.operazioni_composte += 1;
+            PuntoRipristino {
+                saldo: self.saldo,
+                stato: self.stato.stato(),
+                movimenti: self.registro.movimenti().len(),
+                divise: self.divise.iter().map(|(valuta, registro)| (*valuta, registro.movimenti().len())).collect(),
+                avvisi: self.avvisi_sospesi.len(),
+                bloccato: self.bloccato,
+                confermata: self.confermata,
+                segnalazioni: self.segnalazioni.len(),
+            }
+        }
+
+        /// Conclude con successo l'operazione composta iniziata dal punto di ripristino e, se non ce
+        /// ne sono altre in corso, notifica gli avvisi registrati nel frattempo.
+        pub(crate) fn conferma(&mut self, _punto: PuntoRipristino) {
+            self.operazioni_composte -= 1;
+            self.notifica_avvisi();
+        }
+
+        /// Riporta il conto al punto di ripristino, scartando i movimenti registrati dopo, gli avvisi
+        /// non ancora notificati e l'effetto delle regole antifrode: blocco, segnalazioni e conferma usata.
+        pub(crate) fn ripristina(&mut self, punto: PuntoRipristino) {
+            self.operazioni_composte -= 1;
+            self.avvisi_sospesi.truncate(punto.avvisi);
+            self.bloccato = punto.bloccato;
+            self.confermata = punto.confermata;
+            self.segnalazioni.truncate(punto.segnalazioni);
+            self.saldo = punto.saldo;
+            self.registro.annulla_dopo(punto.movimenti);
+            for (valuta, movimenti) in punto.divise {
+                if let Some(registro) = self.divise.get_mut(&valuta) {
+                    registro.annulla_dopo(movimenti);
                 }
             }
+            // Il ritorno allo stato precedente non è una transizione: gli hook non vengono chiamati
+            if punto.stato != self.stato.stato() {
+                self.imposta_stato(punto.stato);
+            }
+        }
+
+        /// Sostituisce il registro del conto, ad esempio con uno letto da un archivio, e porta saldo
+        /// e stato a quelli dell'ultimo movimento senza registrare né notificare cambi di stato.
+        ///
+        /// Le divise vanno ripristinate prima, perché lo stato dipende dal saldo complessivo: se manca
+        /// il tasso di una divisa viene restituito l'errore e lo stato resta quello precedente.
+        pub(crate) fn ripristina_registro(&mut self, registro: Registro) -> Result<(), ErroreConto> {
+            self.saldo = registro.saldo();
+            self.registro = registro;
+            let saldo = self.saldo_totale()?;
+            let stato = match &self.fasce {
+                Some(fasce) => fasce.stato_per(saldo, None),
+                None => Stato::per_saldo(saldo, self.limite_inferiore, self.limite_superiore),
+            };
+            self.imposta_stato(stato);
+            Ok(())
+        }
+
+        /// Aggiunge al conto una divisa con il suo registro, ad esempio letto da un archivio,
+        /// senza controllare che il tasso di cambio sia disponibile.
+        pub(crate) fn ripristina_divisa(&mut self, registro: Registro) {
+            self.divise.insert(registro.saldo_apertura().valuta(), registro);
         }
+
+        /// Sostituisce il comportamento del conto con quello dello stato indicato, senza chiamare gli hook.
+        fn imposta_stato(&mut self, stato: Stato) {
+            self.stato = match &self.fasce {
+                Some(fasce) => fasce::comportamento(fasce, stato),
+                None => stato.comportamento(),
+            };
+        }
+
+        /// Restituisce l'ora dell'orologio del conto, o la data dell'ultimo movimento se successiva,
+        /// così che i movimenti senza data esplicita non vengano mai rifiutati dal registro.
+        pub(crate) fn adesso(&self) -> NaiveDateTime {
+            let ora = self.orologio.adesso();
+            self.registro.movimenti().last().map_or(ora, |ultimo| ultimo.data.max(ora))
+        }
+
+        /// Blocca il conto: le operazioni successive restituiscono `ErroreConto::ContoBloccato`.
+        pub fn blocca(&mut self) {
+            self.bloccato = true;
+        }
+
+        /// Sblocca il conto.
+        pub fn sblocca(&mut self) {
+            self.bloccato = false;
+        }
+
+        /// Controlla che il conto accetti operazioni e che l'importo sia non negativo e nella valuta del conto.
+        fn verifica_importo(&self, importo: Denaro) -> Result<(), ErroreConto> {
+            if self.bloccato {
+                Err(ErroreConto::ContoBloccato)
+            } else if importo.valuta() != self.saldo.valuta() {
+                Err(ErroreConto::ImportoNonValido(ErroreDenaro::ValuteDiverse {
+                    attesa: self.saldo.valuta(),
+                    trovata: importo.valuta(),
+                }))
+            } else if importo.is_negativo() {
+                Err(ErroreConto::ImportoNegativo(importo))
+            } else {
+                Ok(())
+            }
+        }
+
+        /// Esegue un'operazione dello stato attuale e le eventuali commissioni (per operazione e di
+        /// cambio), le registra, aggiorna lo stato in base al nuovo saldo e registra gli avvisi delle
+        /// soglie attraversate.
+        ///
+        /// Il saldo viene modificato solo se sia l'operazione sia le commissioni vanno a buon fine.
+        fn esegui<F>(&mut self, operazione: Operazione, data: NaiveDateTime, commissione: Denaro, commissione_cambio: Denaro, azione: F) -> Result<Ricevuta, ErroreConto>
+        where
+            F: FnOnce(&dyn State, &mut Denaro) -> Result<(), ErroreConto>,
+        {
+            self.registro.verifica_data(data)?;
+            // Lo stato dipende dal saldo complessivo: senza i tassi delle divise non si muove denaro
+            self.saldo_totale()?;
+            let saldo_precedente = self.saldo;
+            let stato_precedente = self.stato.stato();
+            let mut saldo = self.saldo;
+            azione(self.stato.as_ref(), &mut saldo)?;
+            let dopo_operazione = saldo;
+            let addebiti = addebita_commissioni(self.stato.as_ref(), &mut saldo, commissione, commissione_cambio)?;
+            self.registro.registra(data, operazione.into(), dopo_operazione - saldo_precedente, dopo_operazione)?;
+            for (tipo, variazione, saldo) in addebiti {
+                self.registro.registra(data, tipo, variazione, saldo)?;
+            }
+            self.saldo = saldo;
+            self.aggiorna_stato_il(data)?;
+            self.registra_avvisi(saldo_precedente, data)?;
+            Ok(Ricevuta {
+                operazione,
+                importo: (dopo_operazione - saldo_precedente).abs(),
+                saldo_precedente,
+                saldo: self.saldo,
+                commissione: dopo_operazione - self.saldo,
+                stato_precedente,
+                stato: self.stato.stato(),
+            })
+        }
+
+        /// Registra un avviso per ogni soglia attraversata passando dal saldo precedente a quello attuale
+        /// (più lo zero, se il conto ha un fido) e chiama le funzioni di notifica, rimandandole alla
+        /// conferma se è in corso un'operazione composta.
+        fn registra_avvisi(&mut self, saldo_precedente: Denaro, data: NaiveDateTime) -> Result<(), ErroreRegistro> {
+            let zero = Denaro::zero(self.saldo.valuta());
+            let mut soglie = self.soglie.clone();
+            if !self.fido.is_zero() && !soglie.contains(&zero) {
+                soglie.push(zero);
+            }
+            for soglia in soglie {
+                if soglia.valuta() != self.saldo.valuta() {
+                    continue;
+                }
+                if let Some(verso) = Attraversamento::tra(soglia, saldo_precedente, self.saldo) {
+                    let movimento = self.registro.registra(data, TipoMovimento::Avviso { soglia, verso }, zero, self.saldo)?;
+                    self.avvisi_sospesi.push(movimento.clone());
+                }
+            }
+            self.notifica_avvisi();
+            Ok(())
+        }
+
+        /// Chiama le funzioni di notifica per gli avvisi sospesi, se non è in corso un'operazione composta.
+        fn notifica_avvisi(&mut self) {
+            if self.operazioni_composte > 0 {
+                return;
+            }
+            for movimento in std::mem::take(&mut self.avvisi_sospesi) {
+                for notifica in &self.notifiche {
+                    notifica(&movimento);
+                }
+            }
+        }
+
+        /// Ricalcola lo stato del conto in base al saldo complessivo e ai limiti, registrando l'eventuale
+        /// cambio di stato. Se manca il tasso di una divisa lo stato resta quello attuale.
+        pub fn aggiorna_stato(&mut self) {
+            let data = self.adesso();
+            // La data non precede l'ultimo movimento, quindi può mancare solo un tasso di cambio
+            let _ = self.aggiorna_stato_il(data);
+        }
+
+        /// Come `aggiorna_stato`, registrando l'eventuale cambio di stato con la data indicata.
+        fn aggiorna_stato_il(&mut self, data: NaiveDateTime) -> Result<(), ErroreConto> {
+            let precedente = self.stato.stato();
+            let saldo = self.saldo_totale()?;
+            let nuovo = match &self.fasce {
+                Some(fasce) => fasce.stato_per(saldo, Some(precedente.clone())),
+                None => Stato::per_saldo(saldo, self.limite_inferiore, self.limite_superiore),
+            };
+            if nuovo != precedente {
+                let tipo = TipoMovimento::CambioStato { da: precedente, a: nuovo.clone() };
+                self.registro.registra(data, tipo, Denaro::zero(self.saldo.valuta()), self.saldo)?;
+                self.cambia_stato(nuovo, data);
+            }
+            Ok(())
+        }
+
+        /// Porta il conto nello stato indicato, chiamando `on_exit` sul vecchio stato e `on_enter` sul nuovo.
+        fn cambia_stato(&mut self, nuovo: Stato, data: NaiveDateTime) {
+            let precedente = self.stato.stato();
+            if nuovo == precedente {
+                return;
+            }
+            let transizione = Transizione { da: precedente, a: nuovo.clone(), saldo: self.saldo, data };
+            self.stato.on_exit(&transizione);
+            self.imposta_stato(nuovo);
+            self.stato.on_enter(&transizione);
+        }
+    }
+
+    /// Addebita dopo un'operazione la commissione per operazione e quella di cambio, se non nulle,
+    /// restituendo per ciascuna tipo di movimento, variazione e saldo da registrare.
+    fn addebita_commissioni(
+        stato: &dyn State,
+        saldo: &mut Denaro,
+        commissione: Denaro,
+        commissione_cambio: Denaro,
+    ) -> Result<Vec<(TipoMovimento, Denaro, Denaro)>, ErroreConto> {
+        let mut addebiti = Vec::new();
+        for (tipo, importo) in [(TipoMovimento::Commissione, commissione), (TipoMovimento::CommissioneCambio, commissione_cambio)] {
+            if !importo.is_zero() {
+                let prima = *saldo;
+                stato.addebita_commissione(saldo, importo)?;
+                addebiti.push((tipo, *saldo - prima, *saldo));
+            }
+        }
+        Ok(addebiti)
+    }
+
+    /// Restituisce il saldo dopo l'ultimo movimento di un registro.
+    fn saldo_registro(registro: &Registro) -> Denaro {
+        registro.movimenti().last().map_or(registro.saldo_apertura(), |ultimo| ultimo.saldo)
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,459 @@
+/// Modulo `denaro` per la rappresentazione esatta di importi e tassi.
+pub mod denaro {
+    use std::cmp::Ordering;
+    use std::fmt;
+    use std::ops::{Add, Neg, Sub};
+    use std::str::FromStr;
+
+    /// Errori nella costruzione o nel calcolo di importi.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum ErroreDenaro {
+        /// Gli importi hanno valute diverse.
+        ValuteDiverse {
+            /// Valuta del primo importo.
+            attesa: Valuta,
+            /// Valuta del secondo importo.
+            trovata: Valuta,
+        },
+        /// Il risultato non è rappresentabile.
+        Overflow,
+        /// Il testo non è un numero decimale valido per la precisione richiesta.
+        FormatoNonValido(String),
+        /// Il codice valuta non è conosciuto.
+        ValutaSconosciuta(String),
+    }
+
+    impl fmt::Display for ErroreDenaro {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                ErroreDenaro::ValuteDiverse { attesa, trovata } => {
+                    write!(f, "valute diverse: attesa {}, trovata {}", attesa, trovata)
+                }
+                ErroreDenaro::Overflow => write!(f, "importo fuori dall'intervallo rappresentabile"),
+                ErroreDenaro::FormatoNonValido(testo) => write!(f, "importo non valido: '{}'", testo),
+                ErroreDenaro::ValutaSconosciuta(codice) => write!(f, "valuta sconosciuta: '{}'", codice),
+            }
+        }
+    }
+
+    impl std::error::Error for ErroreDenaro {}
+
+    /// Modo di arrotondamento applicato quando un risultato cade tra due unità minime.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub enum Arrotondamento {
+        /// Arrotondamento bancario: a metà si va verso la cifra pari.
+        #[default]
+        Bancario,
+        /// A metà si va lontano dallo zero.
+        MetaPerEccesso,
+        /// Si tronca verso lo zero.
+        Troncamento,
+    }
+
+    /// Divide `numeratore` per `denominatore` (positivo) arrotondando secondo il modo indicato.
+    pub(crate) fn dividi(numeratore: i128, denominatore: i128, modo: Arrotondamento) -> i128 {
+        let quoziente = numeratore / denominatore;
+        let resto = numeratore % denominatore;
+        if resto == 0 {
+            return quoziente;
+        }
+        let verso = if numeratore < 0 { -1 } else { 1 };
+        let doppio = 2 * resto.abs();
+        let allontana = match modo {
+            Arrotondamento::Troncamento => false,
+            Arrotondamento::MetaPerEccesso => doppio >= denominatore,
+            Arrotondamento::Bancario => match doppio.cmp(&denominatore) {
+                Ordering::Greater => true,
+                Ordering::Less => false,
+                Ordering::Equal => quoziente % 2 != 0,
+            },
+        };
+        if allontana {
+            quoziente + verso
+        } else {
+            quoziente
+        }
+    }
+
+    /// Legge un numero decimale (`-12.345`) come intero scalato di `10^decimali`.
+    ///
+    /// Restituisce `None` se il testo non è valido o ha più cifre decimali di quelle ammesse.
+    fn leggi_decimale(testo: &str, decimali: u32) -> Option<i128> {
+        let testo = testo.trim();
+        let (negativo, cifre) = match testo.strip_prefix('-') {
+            Some(resto) => (true, resto),
+            None => (false, testo.strip_prefix('+').unwrap_or(testo)),
+        };
+        let (intera, frazione) = cifre.split_once('.').unwrap_or((cifre, ""));
+        let valide = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+        if intera.is_empty() || !valide(intera) || !valide(frazione) || frazione.len() > decimali as usize {
+            return None;
+        }
+        let scala = 10i128.checked_pow(decimali)?;
+        let intera: i128 = intera.parse().ok()?;
+        let frazione: i128 = if frazione.is_empty() {
+            0
+        } else {
+            frazione.parse::<i128>().ok()? * 10i128.pow(decimali - frazione.len() as u32)
+        };
+        let valore = intera.checked_mul(scala)?.checked_add(frazione)?;
+        Some(if negativo { -valore } else { valore })
+    }
+
+    /// Scrive un intero scalato di `10^decimali` come numero decimale.
+    fn scrivi_decimale(valore: i128, decimali: u32) -> String {
+        let scala = 10i128.pow(decimali);
+        let segno = if valore < 0 { "-" } else { "" };
+        let (intera, frazione) = (valore.abs() / scala, valore.abs() % scala);
+        if decimali == 0 {
+            format!("{}{}", segno, intera)
+        } else {
+            format!("{}{}.{:0width$}", segno, intera, frazione, width = decimali as usize)
+        }
+    }
+
+    /// Valuta identificata dal codice ISO 4217, con il numero di cifre decimali delle sue unità minime.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct Valuta {
+        codice: [u8; 3],
+        decimali: u32,
+    }
+
+    impl Valuta {
+        /// Euro.
+        pub const EUR: Valuta = Valuta::nuova(*b"EUR", 2);
+        /// Dollaro statunitense.
+        pub const USD: Valuta = Valuta::nuova(*b"USD", 2);
+        /// Sterlina britannica.
+        pub const GBP: Valuta = Valuta::nuova(*b"GBP", 2);
+        /// Franco svizzero.
+        pub const CHF: Valuta = Valuta::nuova(*b"CHF", 2);
+        /// Yen giapponese (senza unità minime).
+        pub const JPY: Valuta = Valuta::nuova(*b"JPY", 0);
+
+        /// Valute conosciute da `da_codice`.
+        pub const CONOSCIUTE: [Valuta; 5] = [Valuta::EUR, Valuta::USD, Valuta::GBP, Valuta::CHF, Valuta::JPY];
+
+        /// Numero massimo di cifre decimali di una valuta: con di più un'unità non sarebbe
+        /// rappresentabile in unità minime.
+        pub const DECIMALI_MASSIMI: u32 = 18;
+
+        /// Crea una valuta con il codice e il numero di decimali indicati.
+        ///
+        /// # Argomenti
+        ///
+        /// * `codice` - Codice ISO 4217 in lettere maiuscole.
+        /// * `decimali` - Cifre decimali delle unità minime (2 per i centesimi), al massimo `DECIMALI_MASSIMI`.
+        ///
+        /// # Panico
+        ///
+        /// Panica se `decimali` supera `DECIMALI_MASSIMI`.
+        pub const fn nuova(codice: [u8; 3], decimali: u32) -> Valuta {
+            assert!(decimali <= Valuta::DECIMALI_MASSIMI, "Una valuta ha al massimo 18 cifre decimali");
+            Valuta { codice, decimali }
+        }
+
+        /// Restituisce la valuta conosciuta con il codice indicato.
+        ///
+        /// # Argomenti
+        ///
+        /// * `codice` - Codice ISO 4217, ad esempio `EUR`.
+        pub fn da_codice(codice: &str) -> Result<Valuta, ErroreDenaro> {
+            Valuta::CONOSCIUTE
+                .into_iter()
+                .find(|v| v.codice().eq_ignore_ascii_case(codice.trim()))
+                .ok_or_else(|| ErroreDenaro::ValutaSconosciuta(codice.to_string()))
+        }
+
+        /// Restituisce il codice della valuta.
+        pub fn codice(&self) -> &str {
+            std::str::from_utf8(&self.codice).unwrap_or("???")
+        }
+
+        /// Restituisce il numero di cifre decimali delle unità minime.
+        pub fn decimali(&self) -> u32 {
+            self.decimali
+        }
+    }
+
+    impl fmt::Display for Valuta {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", self.codice())
+        }
+    }
+
+    /// Tasso (ad esempio di interesse) a virgola fissa con nove cifre decimali.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+    pub struct Tasso {
+        miliardesimi: i64,
+    }
+
+    impl Tasso {
+        /// Cifre decimali di un tasso.
+        pub const DECIMALI: u32 = 9;
+        /// Tasso nullo.
+        pub const ZERO: Tasso = Tasso { miliardesimi: 0 };
+
+        /// Crea un tasso a partire dai miliardesimi (`50_000_000` corrisponde a 0.05).
+        pub const fn da_miliardesimi(miliardesimi: i64) -> Tasso {
+            Tasso { miliardesimi }
+        }
+
+        /// Restituisce il tasso in miliardesimi.
+        pub fn miliardesimi(&self) -> i64 {
+            self.miliardesimi
+        }
+
+        /// Crea un tasso dal numero decimale indicato (al massimo nove cifre decimali).
+        ///
+        /// # Argomenti
+        ///
+        /// * `testo` - Tasso in forma decimale, ad esempio `0.05` per il 5%.
+        pub fn da_decimale(testo: &str) -> Result<Tasso, ErroreDenaro> {
+            leggi_decimale(testo, Tasso::DECIMALI)
+                .and_then(|v| i64::try_from(v).ok())
+                .map(Tasso::da_miliardesimi)
+                .ok_or_else(|| ErroreDenaro::FormatoNonValido(testo.to_string()))
+        }
+
+        /// Indica se il tasso è negativo.
+        pub fn is_negativo(&self) -> bool {
+            self.miliardesimi < 0
+        }
+    }
+
+    impl FromStr for Tasso {
+        type Err = ErroreDenaro;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            Tasso::da_decimale(s)
+        }
+    }
+
+    impl fmt::Display for Tasso {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", scrivi_decimale(self.miliardesimi as i128, Tasso::DECIMALI))
+        }
+    }
+
+    /// Importo esatto in una valuta, espresso come numero intero di unità minime (ad esempio centesimi).
+    ///
+    /// Somme e differenze sono esatte; le moltiplicazioni per un tasso arrotondano all'unità minima
+    /// con il modo di arrotondamento indicato.
+    ///
+    /// # Esempio
+    ///
+    /// ```
+    /// use esercizio5::denaro::denaro::{Arrotondamento, Denaro, Tasso, Valuta};
+    ///
+    /// let saldo = Denaro::da_decimale("0.10", Valuta::EUR).unwrap() + Denaro::da_decimale("0.20", Valuta::EUR).unwrap();
+    /// assert_eq!(saldo.to_string(), "0.30 EUR");
+    /// let interessi = Denaro::da_decimale("0.50", Valuta::EUR).unwrap().moltiplica(Tasso::da_decimale("0.05").unwrap(), Arrotondamento::Bancario);
+    /// assert_eq!(interessi.unita_minime(), 2);
+    /// ```
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct Denaro {
+        unita_minime: i64,
+        valuta: Valuta,
+    }
+
+    impl Denaro {
+        /// Crea un importo dal numero di unità minime indicato.
+        ///
+        /// # Argomenti
+        ///
+        /// * `unita_minime` - Importo in unità minime (centesimi per l'euro).
+        /// * `valuta` - Valuta dell'importo.
+        pub const fn da_unita_minime(unita_minime: i64, valuta: Valuta) -> Denaro {
+            Denaro { unita_minime, valuta }
+        }
+
+        /// Restituisce l'importo nullo nella valuta indicata.
+        pub const fn zero(valuta: Valuta) -> Denaro {
+            Denaro::da_unita_minime(0, valuta)
+        }
+
+        /// Crea un importo da un numero decimale con al massimo i decimali della valuta.
+        ///
+        /// # Argomenti
+        ///
+        /// * `testo` - Importo in forma decimale, ad esempio `12.50`.
+        /// * `valuta` - Valuta dell'importo.
+        pub fn da_decimale(testo: &str, valuta: Valuta) -> Result<Denaro, ErroreDenaro> {
+            leggi_decimale(testo, valuta.decimali)
+                .and_then(|v| i64::try_from(v).ok())
+                .map(|v| Denaro::da_unita_minime(v, valuta))
+                .ok_or_else(|| ErroreDenaro::FormatoNonValido(testo.to_string()))
+        }
+
+        /// Crea un importo da un numero in virgola mobile, arrotondandolo all'unità minima.
+        ///
+        /// Restituisce `None` se il numero non è finito o non è rappresentabile.
+        ///
+        /// # Argomenti
+        ///
+        /// * `valore` - Importo in unità della valuta.
+        /// * `valuta` - Valuta dell'importo.
+        /// * `modo` - Modo di arrotondamento.
+        pub fn da_f64(valore: f64, valuta: Valuta, modo: Arrotondamento) -> Option<Denaro> {
+            if !valore.is_finite() {
+                return None;
+            }
+            // Il testo con 9 decimali è esatto entro la precisione del f64 e viene arrotondato in modo esatto
+            let scalato = leggi_decimale(&format!("{:.9}", valore), 9)?;
+            let unita = dividi(scalato, 10i128.pow(9 - valuta.decimali.min(9)), modo);
+            i64::try_from(unita).ok().map(|u| Denaro::da_unita_minime(u, valuta))
+        }
+
+        /// Restituisce l'importo in unità minime.
+        pub fn unita_minime(&self) -> i64 {
+            self.unita_minime
+        }
+
+        /// Restituisce la valuta dell'importo.
+        pub fn valuta(&self) -> Valuta {
+            self.valuta
+        }
+
+        /// Restituisce l'importo come numero in virgola mobile (solo per visualizzazione o statistiche).
+        pub fn in_f64(&self) -> f64 {
+            self.unita_minime as f64 / 10f64.powi(self.valuta.decimali as i32)
+        }
+
+        /// Restituisce l'importo come numero decimale senza codice valuta, ad esempio `-12.50`.
+        pub fn in_decimale(&self) -> String {
+            scrivi_decimale(self.unita_minime as i128, self.valuta.decimali)
+        }
+
+        /// Indica se l'importo è negativo.
+        pub fn is_negativo(&self) -> bool {
+            self.unita_minime < 0
+        }
+
+        /// Indica se l'importo è nullo.
+        pub fn is_zero(&self) -> bool {
+            self.unita_minime == 0
+        }
+
+        /// Restituisce il valore assoluto dell'importo.
+        ///
+        /// Il risultato satura ai limiti dell'intervallo rappresentabile.
+        pub fn abs(&self) -> Denaro {
+            Denaro::da_unita_minime(self.unita_minime.saturating_abs(), self.valuta)
+        }
+
+        /// Controlla che `altro` abbia la stessa valuta.
+        fn stessa_valuta(&self, altro: &Denaro) -> Result<(), ErroreDenaro> {
+            if self.valuta == altro.valuta {
+                Ok(())
+            } else {
+                Err(ErroreDenaro::ValuteDiverse { attesa: self.valuta, trovata: altro.valuta })
+            }
+        }
+
+        /// Somma due importi della stessa valuta.
+        pub fn somma(&self, altro: Denaro) -> Result<Denaro, ErroreDenaro> {
+            self.stessa_valuta(&altro)?;
+            let unita = self.unita_minime.checked_add(altro.unita_minime).ok_or(ErroreDenaro::Overflow)?;
+            Ok(Denaro::da_unita_minime(unita, self.valuta))
+        }
+
+        /// Sottrae un importo della stessa valuta.
+        pub fn sottrai(&self, altro: Denaro) -> Result<Denaro, ErroreDenaro> {
+            self.stessa_valuta(&altro)?;
+            let unita = self.unita_minime.checked_sub(altro.unita_minime).ok_or(ErroreDenaro::Overflow)?;
+            Ok(Denaro::da_unita_minime(unita, self.valuta))
+        }
+
+        /// Moltiplica l'importo per un tasso, arrotondando all'unità minima.
+        ///
+        /// # Argomenti
+        ///
+        /// * `tasso` - Il tasso da applicare.
+        /// * `modo` - Modo di arrotondamento.
+        pub fn moltiplica(&self, tasso: Tasso, modo: Arrotondamento) -> Denaro {
+            self.moltiplica_frazione(tasso.miliardesimi as i128, 10i128.pow(Tasso::DECIMALI), modo)
+        }
+
+        /// Moltiplica l'importo per `numeratore / denominatore`, arrotondando all'unità minima.
+        ///
+        /// Il risultato satura ai limiti dell'intervallo rappresentabile.
+        ///
+        /// # Argomenti
+        ///
+        /// * `numeratore` - Numeratore della frazione.
+        /// * `denominatore` - Denominatore della frazione, positivo.
+        /// * `modo` - Modo di arrotondamento.
+        pub fn moltiplica_frazione(&self, numeratore: i128, denominatore: i128, modo: Arrotondamento) -> Denaro {
+            assert!(denominatore > 0, "Il denominatore deve essere positivo");
+            let prodotto = (self.unita_minime as i128).saturating_mul(numeratore);
+            let unita = dividi(prodotto, denominatore, modo).clamp(i64::MIN as i128, i64::MAX as i128);
+            Denaro::da_unita_minime(unita as i64, self.valuta)
+        }
+    }
+
+    impl Add for Denaro {
+        type Output = Denaro;
+
+        /// Somma due importi.
+        ///
+        /// # Panico
+        ///
+        /// Panica se le valute sono diverse o in caso di overflow; usare `somma` per gestire l'errore.
+        fn add(self, altro: Denaro) -> Denaro {
+            self.somma(altro).unwrap_or_else(|e| panic!("{}", e))
+        }
+    }
+
+    impl Sub for Denaro {
+        type Output = Denaro;
+
+        /// Sottrae due importi.
+        ///
+        /// # Panico
+        ///
+        /// Panica se le valute sono diverse o in caso di overflow; usare `sottrai` per gestire l'errore.
+        fn sub(self, altro: Denaro) -> Denaro {
+            self.sottrai(altro).unwrap_or_else(|e| panic!("{}", e))
+        }
+    }
+
+    impl Neg for Denaro {
+        type Output = Denaro;
+
+        /// Cambia segno all'importo; il risultato satura ai limiti dell'intervallo rappresentabile.
+        fn neg(self) -> Denaro {
+            Denaro::da_unita_minime(self.unita_minime.saturating_neg(), self.valuta)
+        }
+    }
+
+    impl PartialOrd for Denaro {
+        /// Confronta due importi; importi in valute diverse non sono confrontabili.
+        fn partial_cmp(&self, altro: &Denaro) -> Option<Ordering> {
+            if self.valuta == altro.valuta {
+                Some(self.unita_minime.cmp(&altro.unita_minime))
+            } else {
+                None
+            }
+        }
+    }
+
+    impl fmt::Display for Denaro {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{} {}", self.in_decimale(), self.valuta)
+        }
+    }
+
+    impl FromStr for Denaro {
+        type Err = ErroreDenaro;
+
+        /// Legge un importo nel formato di `Display`, ad esempio `12.50 EUR`.
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let (importo, codice) = s
+                .trim()
+                .rsplit_once(' ')
+                .ok_or_else(|| ErroreDenaro::FormatoNonValido(s.to_string()))?;
+            Denaro::da_decimale(importo, Valuta::da_codice(codice)?)
+        }
+    }
+}
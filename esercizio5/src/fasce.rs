@@ -0,0 +1,417 @@
+/// Modulo `fasce` per definire gli stati dei conti tramite un file di configurazione.
+pub mod fasce {
+    use std::fmt;
+    use std::fs;
+    use std::path::Path;
+    use std::sync::Arc;
+    use crate::conto_bancario::conto_bancario::{ErroreConto, Operazione};
+    use crate::denaro::denaro::{Arrotondamento, Denaro, Tasso, Valuta};
+    use crate::stati::stati::{preleva_se_coperto, State, Stato, Transizione};
+
+    /// Funzione chiamata al passaggio di un conto da una fascia all'altra.
+    pub type Hook = Arc<dyn Fn(&Transizione) + Send + Sync>;
+
+    /// Errori nella lettura o nella validazione di una configurazione di fasce.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum ErroreFasce {
+        /// Il file di configurazione non può essere letto.
+        Io(String),
+        /// Una riga della configurazione non è valida.
+        Riga {
+            /// Numero della riga, a partire da 1.
+            numero: usize,
+            /// Descrizione del problema.
+            messaggio: String,
+        },
+        /// La configurazione non definisce nessuna fascia.
+        NessunaFascia,
+        /// Gli intervalli di saldo delle fasce si sovrappongono o lasciano scoperti alcuni saldi.
+        Intervalli(String),
+        /// Due fasce hanno lo stesso nome.
+        NomeDuplicato(String),
+        /// Nessuna fascia ha lo stato indicato.
+        FasciaSconosciuta(Stato),
+    }
+
+    impl fmt::Display for ErroreFasce {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                ErroreFasce::Io(errore) => write!(f, "impossibile leggere la configurazione: {}", errore),
+                ErroreFasce::Riga { numero, messaggio } => write!(f, "riga {}: {}", numero, messaggio),
+                ErroreFasce::NessunaFascia => write!(f, "nessuna fascia definita"),
+                ErroreFasce::Intervalli(messaggio) => write!(f, "intervalli di saldo non validi: {}", messaggio),
+                ErroreFasce::NomeDuplicato(nome) => write!(f, "fascia '{}' definita più volte", nome),
+                ErroreFasce::FasciaSconosciuta(stato) => write!(f, "nessuna fascia '{}'", stato),
+            }
+        }
+    }
+
+    impl std::error::Error for ErroreFasce {}
+
+    /// Fascia di saldo, con le operazioni consentite e le condizioni economiche.
+    #[derive(Clone)]
+    pub struct Fascia {
+        /// Stato del conto quando il saldo è nella fascia.
+        pub stato: Stato,
+        /// Saldo minimo della fascia (compreso), o `None` se la fascia non ha limite inferiore.
+        pub minimo: Option<Denaro>,
+        /// Saldo massimo della fascia (escluso), o `None` se la fascia non ha limite superiore.
+        pub massimo: Option<Denaro>,
+        /// Operazioni consentite nella fascia.
+        pub operazioni: Vec<Operazione>,
+        /// Tasso di interesse applicato quando gli interessi sono consentiti.
+        pub interesse: Tasso,
+        /// Commissione addebitata per ogni deposito o prelievo.
+        pub commissione: Denaro,
+        on_enter: Vec<Hook>,
+        on_exit: Vec<Hook>,
+    }
+
+    impl Fascia {
+        /// Indica se l'operazione è consentita nella fascia.
+        pub fn consente(&self, operazione: Operazione) -> bool {
+            self.operazioni.contains(&operazione)
+        }
+
+        /// Indica se il saldo è nella fascia allargata di `margine` da entrambi i lati.
+        fn contiene(&self, saldo: Denaro, margine: Denaro) -> bool {
+            self.minimo.is_none_or(|minimo| saldo >= minimo - margine) && self.massimo.is_none_or(|massimo| saldo < massimo + margine)
+        }
+    }
+
+    impl fmt::Debug for Fascia {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("Fascia")
+                .field("stato", &self.stato)
+                .field("minimo", &self.minimo)
+                .field("massimo", &self.massimo)
+                .field("operazioni", &self.operazioni)
+                .field("interesse", &self.interesse)
+                .field("commissione", &self.commissione)
+                .finish_non_exhaustive()
+        }
+    }
+
+    /// Insieme di fasce che definisce gli stati di un conto, letto da un file di configurazione.
+    ///
+    /// Le fasce coprono tutti i saldi senza sovrapporsi. Per evitare che un conto cambi stato a ogni
+    /// piccola oscillazione vicino a un confine, un conto resta nella fascia attuale finché il saldo
+    /// non ne esce di più dell'`isteresi`.
+    ///
+    /// Il formato del file è il seguente: righe `chiave = valore`, sezioni `[Nome]` per ogni fascia,
+    /// commenti che iniziano con `#`. Le chiavi generali sono `valuta` (obbligatoria, prima delle
+    /// fasce) e `isteresi`; quelle delle fasce sono `minimo`, `massimo`, `operazioni` (elenco separato
+    /// da virgole tra `deposito`, `prelievo` e `interessi`), `interesse` e `commissione`.
+    ///
+    /// # Esempio
+    ///
+    /// ```
+    /// use esercizio5::fasce::fasce::Fasce;
+    /// use esercizio5::stati::stati::Stato;
+    /// use esercizio5::denaro::denaro::{Denaro, Valuta};
+    ///
+    /// let fasce = Fasce::da_testo("
+    /// valuta = EUR
+    /// isteresi = 10
+    ///
+    /// [Rosso]
+    /// massimo = 0
+    /// operazioni = deposito
+    ///
+    /// [Bronzo]
+    /// minimo = 0
+    /// massimo = 1000
+    /// operazioni = deposito, prelievo
+    /// commissione = 0.50
+    ///
+    /// [Oro]
+    /// minimo = 1000
+    /// operazioni = deposito, prelievo, interessi
+    /// interesse = 0.02
+    /// ").unwrap();
+    ///
+    /// let euro = |importo: &str| Denaro::da_decimale(importo, Valuta::EUR).unwrap();
+    /// assert_eq!(fasce.stato_per(euro("500"), None), Stato::da_nome("Bronzo"));
+    /// // Un conto Oro resta Oro finché il saldo non scende sotto 990
+    /// assert_eq!(fasce.stato_per(euro("995"), Some(Stato::Oro)), Stato::Oro);
+    /// assert_eq!(fasce.stato_per(euro("985"), Some(Stato::Oro)), Stato::da_nome("Bronzo"));
+    /// ```
+    #[derive(Debug, Clone)]
+    pub struct Fasce {
+        valuta: Valuta,
+        isteresi: Denaro,
+        fasce: Vec<Fascia>,
+    }
+
+    impl Fasce {
+        /// Legge le fasce da un file di configurazione.
+        ///
+        /// # Argomenti
+        ///
+        /// * `percorso` - Il file da leggere.
+        pub fn carica<P: AsRef<Path>>(percorso: P) -> Result<Fasce, ErroreFasce> {
+            let testo = fs::read_to_string(percorso).map_err(|e| ErroreFasce::Io(e.to_string()))?;
+            Fasce::da_testo(&testo)
+        }
+
+        /// Legge le fasce dal testo di una configurazione.
+        ///
+        /// # Argomenti
+        ///
+        /// * `testo` - Il contenuto della configurazione.
+        pub fn da_testo(testo: &str) -> Result<Fasce, ErroreFasce> {
+            let mut valuta: Option<Valuta> = None;
+            let mut isteresi: Option<String> = None;
+            let mut fasce: Vec<Fascia> = Vec::new();
+
+            for (indice, riga) in testo.lines().enumerate() {
+                let numero = indice + 1;
+                let errore = |messaggio: String| ErroreFasce::Riga { numero, messaggio };
+                let riga = riga.split('#').next().unwrap_or("").trim();
+                if riga.is_empty() {
+                    continue;
+                }
+                if let Some(nome) = riga.strip_prefix('[').and_then(|r| r.strip_suffix(']')) {
+                    let valuta = valuta.ok_or_else(|| errore("la valuta deve precedere le fasce".to_string()))?;
+                    let nome = nome.trim();
+                    if nome.is_empty() {
+                        return Err(errore("nome di fascia vuoto".to_string()));
+                    }
+                    if fasce.iter().any(|f| f.stato.nome() == nome) {
+                        return Err(ErroreFasce::NomeDuplicato(nome.to_string()));
+                    }
+                    fasce.push(Fascia {
+                        stato: Stato::da_nome(nome),
+                        minimo: None,
+                        massimo: None,
+                        operazioni: Vec::new(),
+                        interesse: Tasso::ZERO,
+                        commissione: Denaro::zero(valuta),
+                        on_enter: Vec::new(),
+                        on_exit: Vec::new(),
+                    });
+                    continue;
+                }
+
+                let (chiave, valore) = riga
+                    .split_once('=')
+                    .map(|(c, v)| (c.trim(), v.trim()))
+                    .ok_or_else(|| errore(format!("attesa 'chiave = valore', trovato '{}'", riga)))?;
+                let importo = |valore: &str| {
+                    let valuta = valuta.ok_or_else(|| errore("la valuta deve precedere gli importi".to_string()))?;
+                    Denaro::da_decimale(valore, valuta).map_err(|e| errore(e.to_string()))
+                };
+                match (fasce.last_mut(), chiave) {
+                    (None, "valuta") => valuta = Some(Valuta::da_codice(valore).map_err(|e| errore(e.to_string()))?),
+                    (None, "isteresi") => isteresi = Some(valore.to_string()),
+                    (Some(fascia), "minimo") => fascia.minimo = Some(importo(valore)?),
+                    (Some(fascia), "massimo") => fascia.massimo = Some(importo(valore)?),
+                    (Some(fascia), "commissione") => fascia.commissione = importo(valore)?,
+                    (Some(fascia), "interesse") => {
+                        fascia.interesse = Tasso::da_decimale(valore).map_err(|e| errore(e.to_string()))?
+                    }
+                    (Some(fascia), "operazioni") => {
+                        fascia.operazioni = valore
+                            .split(',')
+                            .map(str::trim)
+                            .filter(|nome| !nome.is_empty())
+                            .map(|nome| leggi_operazione(nome).ok_or_else(|| errore(format!("operazione sconosciuta '{}'", nome))))
+                            .collect::<Result<_, _>>()?
+                    }
+                    _ => return Err(errore(format!("chiave '{}' non valida in questa posizione", chiave))),
+                }
+            }
+
+            let valuta = valuta.ok_or(ErroreFasce::NessunaFascia)?;
+            let isteresi = match isteresi {
+                Some(testo) => Denaro::da_decimale(&testo, valuta).map_err(|e| ErroreFasce::Intervalli(e.to_string()))?,
+                None => Denaro::zero(valuta),
+            };
+            if isteresi.is_negativo() {
+                return Err(ErroreFasce::Intervalli(format!("isteresi negativa ({})", isteresi)));
+            }
+            Fasce::nuove(valuta, isteresi, fasce)
+        }
+
+        /// Controlla che le fasce coprano tutti i saldi senza sovrapporsi e le ordina per saldo crescente.
+        fn nuove(valuta: Valuta, isteresi: Denaro, mut fasce: Vec<Fascia>) -> Result<Fasce, ErroreFasce> {
+            if fasce.is_empty() {
+                return Err(ErroreFasce::NessunaFascia);
+            }
+            // Le fasce senza minimo vanno per prime
+            fasce.sort_by_key(|f| f.minimo.map(|m| m.unita_minime()));
+            if let Some(primo) = fasce[0].minimo {
+                return Err(ErroreFasce::Intervalli(format!("nessuna fascia per i saldi sotto {}", primo)));
+            }
+            for coppia in fasce.windows(2) {
+                if coppia[0].massimo != coppia[1].minimo || coppia[1].minimo.is_none() {
+                    return Err(ErroreFasce::Intervalli(format!(
+                        "la fascia {} non inizia dove finisce la fascia {}",
+                        coppia[1].stato, coppia[0].stato
+                    )));
+                }
+            }
+            for fascia in &fasce {
+                if let (Some(minimo), Some(massimo)) = (fascia.minimo, fascia.massimo) {
+                    if minimo >= massimo {
+                        return Err(ErroreFasce::Intervalli(format!("la fascia {} è vuota", fascia.stato)));
+                    }
+                }
+            }
+            if let Some(ultimo) = fasce[fasce.len() - 1].massimo {
+                return Err(ErroreFasce::Intervalli(format!("nessuna fascia per i saldi da {} in su", ultimo)));
+            }
+            Ok(Fasce { valuta, isteresi, fasce })
+        }
+
+        /// Restituisce la valuta della configurazione.
+        pub fn valuta(&self) -> Valuta {
+            self.valuta
+        }
+
+        /// Restituisce il margine di isteresi.
+        pub fn isteresi(&self) -> Denaro {
+            self.isteresi
+        }
+
+        /// Restituisce le fasce, in ordine di saldo crescente.
+        pub fn fasce(&self) -> &[Fascia] {
+            &self.fasce
+        }
+
+        /// Restituisce la fascia con lo stato indicato, se esiste.
+        pub fn fascia(&self, stato: Stato) -> Option<&Fascia> {
+            self.fasce.iter().find(|f| f.stato == stato)
+        }
+
+        /// Restituisce lo stato di un conto con il saldo indicato.
+        ///
+        /// # Argomenti
+        ///
+        /// * `saldo` - Saldo del conto.
+        /// * `attuale` - Stato attuale del conto, se esiste: il conto vi resta finché il saldo
+        ///   non ne esce di più dell'isteresi.
+        pub fn stato_per(&self, saldo: Denaro, attuale: Option<Stato>) -> Stato {
+            if let Some(fascia) = attuale.and_then(|stato| self.fascia(stato)) {
+                if fascia.contiene(saldo, self.isteresi) {
+                    return fascia.stato.clone();
+                }
+            }
+            let zero = Denaro::zero(self.valuta);
+            self.fasce.iter().find(|f| f.contiene(saldo, zero)).map_or(self.fasce[0].stato.clone(), |f| f.stato.clone())
+        }
+
+        /// Registra una funzione da chiamare quando un conto entra nella fascia indicata.
+        ///
+        /// # Argomenti
+        ///
+        /// * `stato` - Stato della fascia.
+        /// * `hook` - Funzione da chiamare con la transizione.
+        pub fn on_enter(&mut self, stato: Stato, hook: impl Fn(&Transizione) + Send + Sync + 'static) -> Result<(), ErroreFasce> {
+            self.fascia_mut(stato)?.on_enter.push(Arc::new(hook));
+            Ok(())
+        }
+
+        /// Registra una funzione da chiamare quando un conto lascia la fascia indicata.
+        ///
+        /// # Argomenti
+        ///
+        /// * `stato` - Stato della fascia.
+        /// * `hook` - Funzione da chiamare con la transizione.
+        pub fn on_exit(&mut self, stato: Stato, hook: impl Fn(&Transizione) + Send + Sync + 'static) -> Result<(), ErroreFasce> {
+            self.fascia_mut(stato)?.on_exit.push(Arc::new(hook));
+            Ok(())
+        }
+
+        /// Restituisce la fascia con lo stato indicato o l'errore `FasciaSconosciuta`.
+        fn fascia_mut(&mut self, stato: Stato) -> Result<&mut Fascia, ErroreFasce> {
+            self.fasce.iter_mut().find(|f| f.stato == stato).ok_or(ErroreFasce::FasciaSconosciuta(stato))
+        }
+    }
+
+    /// Restituisce l'implementazione di `State` della fascia con lo stato indicato.
+    ///
+    /// Gli stati senza fascia nella configurazione hanno il comportamento predefinito di `Stato::comportamento`.
+    ///
+    /// # Argomenti
+    ///
+    /// * `fasce` - La configurazione.
+    /// * `stato` - Lo stato richiesto.
+    pub fn comportamento(fasce: &Arc<Fasce>, stato: Stato) -> Box<dyn State> {
+        match fasce.fasce.iter().position(|f| f.stato == stato) {
+            Some(indice) => Box::new(StatoFascia { fasce: Arc::clone(fasce), indice }),
+            None => stato.comportamento(),
+        }
+    }
+
+    /// Legge il nome di un'operazione della configurazione.
+    fn leggi_operazione(nome: &str) -> Option<Operazione> {
+        match nome.to_lowercase().as_str() {
+            "deposito" => Some(Operazione::Deposito),
+            "prelievo" => Some(Operazione::Prelievo),
+            "interessi" => Some(Operazione::Interessi),
+            _ => None,
+        }
+    }
+
+    /// Implementazione di `State` per una fascia di una configurazione.
+    pub struct StatoFascia {
+        fasce: Arc<Fasce>,
+        indice: usize,
+    }
+
+    impl StatoFascia {
+        /// Restituisce la fascia dello stato.
+        pub fn fascia(&self) -> &Fascia {
+            &self.fasce.fasce[self.indice]
+        }
+
+        /// Controlla che l'operazione sia consentita nella fascia.
+        fn verifica(&self, operazione: Operazione) -> Result<(), ErroreConto> {
+            if self.fascia().consente(operazione) {
+                Ok(())
+            } else {
+                Err(ErroreConto::OperazioneNonConsentita { stato: self.stato(), operazione })
+            }
+        }
+    }
+
+    impl State for StatoFascia {
+        fn deposita(&self, saldo: &mut Denaro, amount: Denaro) -> Result<(), ErroreConto> {
+            self.verifica(Operazione::Deposito)?;
+            let mut nuovo = saldo.somma(amount)?;
+            preleva_se_coperto(&mut nuovo, self.fascia().commissione)?;
+            *saldo = nuovo;
+            Ok(())
+        }
+
+        fn preleva(&self, saldo: &mut Denaro, amount: Denaro) -> Result<(), ErroreConto> {
+            self.verifica(Operazione::Prelievo)?;
+            preleva_se_coperto(saldo, amount.somma(self.fascia().commissione)?)
+        }
+
+        /// Accredita gli interessi al tasso della fascia, ignorando quello del conto; come depositi e
+        /// prelievi, è un errore se la fascia non consente gli interessi.
+        fn paga_interessi(&self, saldo: &mut Denaro, _: Tasso, arrotondamento: Arrotondamento) -> Result<(), ErroreConto> {
+            self.verifica(Operazione::Interessi)?;
+            *saldo = saldo.somma(saldo.moltiplica(self.fascia().interesse, arrotondamento))?;
+            Ok(())
+        }
+
+        fn stato(&self) -> Stato {
+            self.fascia().stato.clone()
+        }
+
+        fn on_enter(&self, transizione: &Transizione) {
+            for hook in &self.fascia().on_enter {
+                hook(transizione);
+            }
+        }
+
+        fn on_exit(&self, transizione: &Transizione) {
+            for hook in &self.fascia().on_exit {
+                hook(transizione);
+            }
+        }
+    }
+}
@@ -0,0 +1,347 @@
+/// Modulo `interessi` per la maturazione giornaliera e la capitalizzazione periodica degli interessi.
+pub mod interessi {
+    use std::collections::HashMap;
+    use std::fmt;
+    use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime};
+    use crate::conto_bancario::conto_bancario::{ContoBancario, ErroreConto, Ricevuta};
+    use crate::denaro::denaro::{dividi, Arrotondamento, Denaro, ErroreDenaro, Tasso, Valuta};
+    use crate::orologio::orologio::{Orologio, OrologioVirtuale};
+    use crate::stati::stati::Stato;
+
+    /// Errori restituiti dal motore degli interessi.
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum ErroreInteressi {
+        /// Il giorno da far maturare non segue l'ultimo già maturato.
+        GiornoNonConsecutivo {
+            /// Giorno successivo all'ultimo maturato.
+            atteso: NaiveDate,
+            /// Giorno richiesto.
+            trovato: NaiveDate,
+        },
+        /// Gli interessi maturati non sono rappresentabili.
+        Importo(ErroreDenaro),
+        /// Il conto ha rifiutato l'accredito o l'addebito.
+        Conto(ErroreConto),
+    }
+
+    impl fmt::Display for ErroreInteressi {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                ErroreInteressi::GiornoNonConsecutivo { atteso, trovato } => {
+                    write!(f, "interessi maturati fino al {}: il {} non è il giorno successivo", *atteso - Duration::days(1), trovato)
+                }
+                ErroreInteressi::Importo(errore) => write!(f, "interessi maturati: {}", errore),
+                ErroreInteressi::Conto(errore) => write!(f, "capitalizzazione rifiutata: {}", errore),
+            }
+        }
+    }
+
+    impl std::error::Error for ErroreInteressi {}
+
+    impl From<ErroreDenaro> for ErroreInteressi {
+        fn from(errore: ErroreDenaro) -> Self {
+            ErroreInteressi::Importo(errore)
+        }
+    }
+
+    impl From<ErroreConto> for ErroreInteressi {
+        fn from(errore: ErroreConto) -> Self {
+            ErroreInteressi::Conto(errore)
+        }
+    }
+
+    /// Convenzione per il conteggio dei giorni nel calcolo degli interessi.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Convenzione {
+        /// Giorni effettivi su un anno di 365 giorni.
+        Act365,
+        /// Mesi di 30 giorni su un anno di 360 giorni (regola "bond basis").
+        Trenta360,
+    }
+
+    impl Convenzione {
+        /// Restituisce i giorni da contare tra due date secondo la convenzione.
+        ///
+        /// # Argomenti
+        ///
+        /// * `da` - Data iniziale.
+        /// * `a` - Data finale.
+        pub fn giorni(&self, da: NaiveDate, a: NaiveDate) -> i64 {
+            match self {
+                Convenzione::Act365 => (a - da).num_days(),
+                Convenzione::Trenta360 => {
+                    let g1 = da.day().min(30) as i64;
+                    let g2 = if g1 == 30 { a.day().min(30) } else { a.day() } as i64;
+                    360 * (a.year() - da.year()) as i64 + 30 * (a.month() as i64 - da.month() as i64) + g2 - g1
+                }
+            }
+        }
+
+        /// Restituisce i giorni dell'anno secondo la convenzione.
+        pub fn base(&self) -> i64 {
+            match self {
+                Convenzione::Act365 => 365,
+                Convenzione::Trenta360 => 360,
+            }
+        }
+    }
+
+    /// Periodicità con cui gli interessi maturati vengono accreditati sul conto.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Capitalizzazione {
+        /// Alla fine di ogni mese.
+        Mensile,
+        /// Alla fine di marzo, giugno, settembre e dicembre.
+        Trimestrale,
+        /// Alla fine di dicembre.
+        Annuale,
+    }
+
+    impl Capitalizzazione {
+        /// Indica se il giorno è l'ultimo di un periodo di capitalizzazione.
+        pub fn fine_periodo(&self, giorno: NaiveDate) -> bool {
+            let domani = giorno + Duration::days(1);
+            if domani.month() == giorno.month() {
+                return false;
+            }
+            match self {
+                Capitalizzazione::Mensile => true,
+                Capitalizzazione::Trimestrale => giorno.month().is_multiple_of(3),
+                Capitalizzazione::Annuale => giorno.month() == 12,
+            }
+        }
+    }
+
+    /// Interessi maturati da un conto e non ancora accreditati.
+    ///
+    /// Gli interessi di ogni giorno sono sommati in modo esatto; l'arrotondamento all'unità minima
+    /// avviene solo alla capitalizzazione, e il resto viene riportato al periodo successivo.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Maturazione {
+        valuta: Valuta,
+        /// Interessi maturati, in unità minime moltiplicate per `Tasso` e per la base della convenzione.
+        numeratore: i128,
+        giorni_rosso: u32,
+        ultimo_giorno: Option<NaiveDate>,
+    }
+
+    impl Maturazione {
+        /// Crea una maturazione vuota.
+        ///
+        /// # Argomenti
+        ///
+        /// * `valuta` - Valuta del conto.
+        pub fn nuova(valuta: Valuta) -> Maturazione {
+            Maturazione { valuta, numeratore: 0, giorni_rosso: 0, ultimo_giorno: None }
+        }
+
+        /// Restituisce l'ultimo giorno maturato, se esiste.
+        pub fn ultimo_giorno(&self) -> Option<NaiveDate> {
+            self.ultimo_giorno
+        }
+
+        /// Restituisce i giorni del periodo in corso trascorsi in stato Rosso.
+        pub fn giorni_rosso(&self) -> u32 {
+            self.giorni_rosso
+        }
+    }
+
+    /// Motore degli interessi: tassi per stato, tasso passivo sui saldi negativi (fido utilizzato),
+    /// convenzione sui giorni, periodicità di capitalizzazione e commissione di scoperto per i periodi
+    /// trascorsi, anche in parte, in stato Rosso.
+    ///
+    /// # Esempio
+    ///
+    /// ```
+    /// use chrono::NaiveDate;
+    /// use esercizio5::conto_bancario::conto_bancario::ContoBancario;
+    /// use esercizio5::denaro::denaro::{Denaro, Tasso, Valuta};
+    /// use esercizio5::interessi::interessi::{Capitalizzazione, Convenzione, Maturazione, MotoreInteressi};
+    /// use esercizio5::stati::stati::Stato;
+    ///
+    /// let euro = |importo: &str| Denaro::da_decimale(importo, Valuta::EUR).unwrap();
+    /// let motore = MotoreInteressi::nuovo(Convenzione::Act365, Capitalizzazione::Mensile)
+    ///     .con_tasso(Stato::Oro, Tasso::da_decimale("0.0365").unwrap());
+    /// let mut conto = ContoBancario::new(String::from("Mario Rossi"), euro("10000"), euro("0"), euro("5000"), Tasso::ZERO);
+    /// let mut maturazione = Maturazione::nuova(Valuta::EUR);
+    ///
+    /// let inizio = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+    /// for giorno in inizio.iter_days().take(31) {
+    ///     motore.matura(&conto, &mut maturazione, giorno).unwrap();
+    ///     motore.capitalizza_se_dovuto(&mut conto, &mut maturazione, giorno).unwrap();
+    /// }
+    /// // 10000 * 3.65% * 31 / 365
+    /// assert_eq!(conto.saldo(), euro("10031"));
+    /// ```
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct MotoreInteressi {
+        convenzione: Convenzione,
+        capitalizzazione: Capitalizzazione,
+        tassi: HashMap<Stato, Tasso>,
+        tasso_scoperto: Option<Tasso>,
+        commissione_scoperto: Option<Denaro>,
+        arrotondamento: Arrotondamento,
+    }
+
+    impl MotoreInteressi {
+        /// Crea un motore senza tassi (tutti gli stati maturano zero) e senza commissioni.
+        ///
+        /// # Argomenti
+        ///
+        /// * `convenzione` - Convenzione per il conteggio dei giorni.
+        /// * `capitalizzazione` - Periodicità di accredito degli interessi.
+        pub fn nuovo(convenzione: Convenzione, capitalizzazione: Capitalizzazione) -> MotoreInteressi {
+            MotoreInteressi {
+                convenzione,
+                capitalizzazione,
+                tassi: HashMap::new(),
+                tasso_scoperto: None,
+                commissione_scoperto: None,
+                arrotondamento: Arrotondamento::Bancario,
+            }
+        }
+
+        /// Imposta il tasso annuo per i giorni trascorsi nello stato indicato. Un tasso negativo
+        /// applicato a un saldo positivo (o positivo applicato a un saldo negativo) produce interessi passivi.
+        pub fn con_tasso(mut self, stato: Stato, tasso: Tasso) -> MotoreInteressi {
+            self.tassi.insert(stato, tasso);
+            self
+        }
+
+        /// Imposta il tasso annuo applicato, al posto di quello dello stato, nei giorni chiusi con saldo
+        /// negativo. Un tasso positivo produce interessi passivi.
+        pub fn con_tasso_scoperto(mut self, tasso: Tasso) -> MotoreInteressi {
+            self.tasso_scoperto = Some(tasso);
+            self
+        }
+
+        /// Imposta la commissione addebitata alla fine di ogni periodo trascorso, anche solo per un giorno, in stato Rosso.
+        pub fn con_commissione_scoperto(mut self, commissione: Denaro) -> MotoreInteressi {
+            self.commissione_scoperto = Some(commissione);
+            self
+        }
+
+        /// Imposta il modo di arrotondamento degli interessi capitalizzati.
+        pub fn con_arrotondamento(mut self, arrotondamento: Arrotondamento) -> MotoreInteressi {
+            self.arrotondamento = arrotondamento;
+            self
+        }
+
+        /// Restituisce il tasso annuo applicato nello stato indicato.
+        pub fn tasso(&self, stato: Stato) -> Tasso {
+            self.tassi.get(&stato).copied().unwrap_or(Tasso::ZERO)
+        }
+
+        /// Restituisce il denominatore comune degli interessi maturati.
+        fn denominatore(&self) -> i128 {
+            10i128.pow(Tasso::DECIMALI) * self.convenzione.base() as i128
+        }
+
+        /// Fa maturare gli interessi di un giorno sul saldo e nello stato attuali del conto,
+        /// che vanno intesi come quelli di fine giornata.
+        ///
+        /// # Argomenti
+        ///
+        /// * `conto` - Il conto.
+        /// * `maturazione` - Gli interessi maturati dal conto.
+        /// * `giorno` - Il giorno da far maturare, successivo all'ultimo già maturato.
+        ///
+        /// Non si possono saltare giorni né maturarne uno due volte: il saldo di un giorno passato non
+        /// è noto, quindi in entrambi i casi viene restituito un errore e la maturazione resta invariata.
+        pub fn matura(&self, conto: &ContoBancario, maturazione: &mut Maturazione, giorno: NaiveDate) -> Result<(), ErroreInteressi> {
+            if let Some(ultimo) = maturazione.ultimo_giorno {
+                let atteso = ultimo + Duration::days(1);
+                if giorno != atteso {
+                    return Err(ErroreInteressi::GiornoNonConsecutivo { atteso, trovato: giorno });
+                }
+            }
+            let stato = conto.stato();
+            let peso = self.convenzione.giorni(giorno, giorno + Duration::days(1)) as i128;
+            let tasso = match self.tasso_scoperto {
+                Some(tasso) if conto.saldo().is_negativo() => tasso,
+                _ => self.tasso(stato.clone()),
+            };
+            maturazione.numeratore = (conto.saldo().unita_minime() as i128 * tasso.miliardesimi() as i128)
+                .checked_mul(peso)
+                .and_then(|interessi| maturazione.numeratore.checked_add(interessi))
+                .ok_or(ErroreDenaro::Overflow)?;
+            if stato == Stato::Rosso {
+                maturazione.giorni_rosso += 1;
+            }
+            maturazione.ultimo_giorno = Some(giorno);
+            Ok(())
+        }
+
+        /// Restituisce gli interessi maturati e non ancora accreditati, arrotondati all'unità minima
+        /// secondo il modo del motore, o un errore se non sono rappresentabili come `Denaro`.
+        pub fn maturato(&self, maturazione: &Maturazione) -> Result<Denaro, ErroreDenaro> {
+            let unita = dividi(maturazione.numeratore, self.denominatore(), self.arrotondamento);
+            let unita = i64::try_from(unita).map_err(|_| ErroreDenaro::Overflow)?;
+            Ok(Denaro::da_unita_minime(unita, maturazione.valuta))
+        }
+
+        /// Accredita gli interessi maturati e addebita l'eventuale commissione di scoperto,
+        /// alla fine del giorno indicato.
+        ///
+        /// Restituisce le ricevute dei movimenti registrati (nessuno se non c'è nulla da accreditare).
+        ///
+        /// # Argomenti
+        ///
+        /// * `conto` - Il conto.
+        /// * `maturazione` - Gli interessi maturati dal conto.
+        /// * `giorno` - Giorno di capitalizzazione.
+        pub fn capitalizza(&self, conto: &mut ContoBancario, maturazione: &mut Maturazione, giorno: NaiveDate) -> Result<Vec<Ricevuta>, ErroreInteressi> {
+            let data = NaiveDateTime::new(giorno, NaiveTime::from_hms_opt(23, 59, 59).unwrap());
+            let mut ricevute = Vec::new();
+            let interessi = self.maturato(maturazione)?;
+            if !interessi.is_zero() {
+                ricevute.push(conto.accredita_interessi_il(interessi, data)?);
+            }
+            maturazione.numeratore -= interessi.unita_minime() as i128 * self.denominatore();
+            if let Some(commissione) = self.commissione_scoperto.filter(|_| maturazione.giorni_rosso > 0) {
+                ricevute.push(conto.addebita_commissione_il(commissione, data)?);
+            }
+            maturazione.giorni_rosso = 0;
+            Ok(ricevute)
+        }
+
+        /// Come `capitalizza`, ma solo se il giorno chiude un periodo di capitalizzazione.
+        pub fn capitalizza_se_dovuto(&self, conto: &mut ContoBancario, maturazione: &mut Maturazione, giorno: NaiveDate) -> Result<Vec<Ricevuta>, ErroreInteressi> {
+            if self.capitalizzazione.fine_periodo(giorno) {
+                self.capitalizza(conto, maturazione, giorno)
+            } else {
+                Ok(Vec::new())
+            }
+        }
+
+        /// Simula il passare dei giorni dalla data dell'orologio fino al giorno indicato compreso:
+        /// ogni giorno matura gli interessi e, se chiude un periodo, li capitalizza; poi l'orologio
+        /// avanza all'inizio del giorno successivo.
+        ///
+        /// Restituisce le ricevute di tutti i movimenti registrati.
+        ///
+        /// # Argomenti
+        ///
+        /// * `conto` - Il conto, che dovrebbe usare lo stesso orologio.
+        /// * `maturazione` - Gli interessi maturati dal conto.
+        /// * `orologio` - L'orologio virtuale della simulazione.
+        /// * `fino_al` - Ultimo giorno da simulare.
+        pub fn simula(
+            &self,
+            conto: &mut ContoBancario,
+            maturazione: &mut Maturazione,
+            orologio: &OrologioVirtuale,
+            fino_al: NaiveDate,
+        ) -> Result<Vec<Ricevuta>, ErroreInteressi> {
+            let mut ricevute = Vec::new();
+            let mut giorno = orologio.oggi();
+            while giorno <= fino_al {
+                self.matura(conto, maturazione, giorno)?;
+                ricevute.extend(self.capitalizza_se_dovuto(conto, maturazione, giorno)?);
+                giorno += Duration::days(1);
+                orologio.imposta(giorno.and_hms_opt(0, 0, 0).unwrap());
+            }
+            Ok(ricevute)
+        }
+    }
+}
@@ -1,2 +1,19 @@
+#![allow(clippy::module_inception)]
+
+pub mod antifrode;
+pub mod archivio;
+pub mod banca;
+pub mod banca_concorrente;
+pub mod cambi;
+pub mod commissioni;
+pub mod console;
 pub mod conto_bancario;
-pub mod stati;
\ No newline at end of file
+pub mod denaro;
+pub mod fasce;
+pub mod interessi;
+pub mod ordini;
+pub mod orologio;
+pub mod rapporti;
+pub mod registro;
+pub mod stati;
+pub mod titolari;
@@ -0,0 +1,61 @@
+use std::env;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::process;
+use std::sync::Arc;
+use esercizio5::cambi::cambi::CambiDaFile;
+use esercizio5::console::console::{Interprete, AIUTO};
+use esercizio5::denaro::denaro::Valuta;
+
+/// Avvia l'interprete dei comandi bancari su una banca in euro.
+///
+/// Modalità disponibili in base agli argomenti:
+/// * nessun argomento: sessione interattiva sul terminale;
+/// * `<file> [file...]`: esegue gli script indicati in sequenza sulla stessa banca (`-` per
+///   l'input standard) e termina con codice 1 se almeno un comando ha dato errore;
+/// * `--cambi <file>` prima degli altri argomenti: legge dal file i tassi di cambio usati per
+///   divise e trasferimenti con cambio.
+fn main() {
+    let mut argomenti: Vec<String> = env::args().skip(1).collect();
+    let mut interprete = Interprete::nuovo(Valuta::EUR);
+    if argomenti.first().map(String::as_str) == Some("--cambi") {
+        let Some(percorso) = argomenti.get(1).cloned() else {
+            eprintln!("Manca il file dei tassi di cambio dopo --cambi");
+            process::exit(2);
+        };
+        match CambiDaFile::apri(&percorso) {
+            Ok(cambi) => interprete = interprete.con_cambi(Arc::new(cambi)),
+            Err(e) => {
+                eprintln!("Impossibile leggere i tassi di cambio {}: {}", percorso, e);
+                process::exit(2);
+            }
+        }
+        argomenti.drain(..2);
+    }
+    let mut uscita = io::stdout();
+    if argomenti.is_empty() {
+        println!("{}", AIUTO);
+        interprete
+            .sessione(io::stdin().lock(), &mut uscita, true)
+            .expect("Errore durante la lettura dell'input");
+        return;
+    }
+    let mut errori = 0;
+    for percorso in &argomenti {
+        let esito = if percorso == "-" {
+            interprete.sessione(io::stdin().lock(), &mut uscita, false)
+        } else {
+            File::open(percorso).and_then(|file| interprete.sessione(BufReader::new(file), &mut uscita, false))
+        };
+        match esito {
+            Ok(n) => errori += n,
+            Err(e) => {
+                eprintln!("Impossibile eseguire lo script {}: {}", percorso, e);
+                process::exit(2);
+            }
+        }
+    }
+    if errori > 0 {
+        process::exit(1);
+    }
+}
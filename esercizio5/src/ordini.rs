@@ -0,0 +1,378 @@
+/// Modulo `ordini` per gli ordini permanenti: prelievi e trasferimenti ricorrenti eseguiti alle scadenze.
+pub mod ordini {
+    use std::collections::BTreeMap;
+    use std::fmt;
+    use chrono::{Datelike, Duration, NaiveDate};
+    use crate::banca::banca::{Banca, ErroreBanca, IdConto};
+    use crate::conto_bancario::conto_bancario::ErroreConto;
+    use crate::denaro::denaro::Denaro;
+    use crate::orologio::orologio::{Orologio, OrologioVirtuale};
+    use crate::stati::stati::Stato;
+
+    /// Restituisce l'ultimo giorno del mese indicato.
+    fn fine_mese(anno: i32, mese: u32) -> NaiveDate {
+        let (anno, mese) = if mese == 12 { (anno + 1, 1) } else { (anno, mese + 1) };
+        NaiveDate::from_ymd_opt(anno, mese, 1).unwrap() - Duration::days(1)
+    }
+
+    /// Restituisce il giorno indicato del mese, o l'ultimo giorno se il mese è più corto.
+    fn giorno_del_mese(anno: i32, mese: u32, giorno: u32) -> NaiveDate {
+        let ultimo = fine_mese(anno, mese);
+        ultimo.with_day(giorno.clamp(1, ultimo.day())).unwrap()
+    }
+
+    /// Restituisce anno e mese successivi a quelli della data.
+    fn mese_successivo(data: NaiveDate) -> (i32, u32) {
+        if data.month() == 12 { (data.year() + 1, 1) } else { (data.year(), data.month() + 1) }
+    }
+
+    /// Periodicità di un ordine permanente.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Periodicita {
+        /// Ogni N giorni (almeno uno) a partire dalla data di inizio.
+        OgniGiorni(u32),
+        /// Ogni mese nel giorno indicato, o l'ultimo giorno del mese se il mese è più corto.
+        Mensile(u32),
+        /// L'ultimo giorno di ogni mese.
+        FineMese,
+    }
+
+    impl Periodicita {
+        /// Restituisce la prima scadenza non precedente alla data indicata.
+        ///
+        /// # Argomenti
+        ///
+        /// * `inizio` - Data da cui parte l'ordine.
+        pub fn prima(&self, inizio: NaiveDate) -> NaiveDate {
+            match self {
+                Periodicita::OgniGiorni(_) => inizio,
+                Periodicita::Mensile(giorno) => {
+                    let scadenza = giorno_del_mese(inizio.year(), inizio.month(), *giorno);
+                    if scadenza >= inizio { scadenza } else { self.successiva(scadenza) }
+                }
+                Periodicita::FineMese => fine_mese(inizio.year(), inizio.month()),
+            }
+        }
+
+        /// Restituisce la scadenza che segue quella indicata.
+        ///
+        /// # Argomenti
+        ///
+        /// * `scadenza` - Una scadenza dell'ordine.
+        pub fn successiva(&self, scadenza: NaiveDate) -> NaiveDate {
+            let (anno, mese) = mese_successivo(scadenza);
+            match self {
+                Periodicita::OgniGiorni(giorni) => scadenza + Duration::days((*giorni).max(1) as i64),
+                Periodicita::Mensile(giorno) => giorno_del_mese(anno, mese, *giorno),
+                Periodicita::FineMese => fine_mese(anno, mese),
+            }
+        }
+    }
+
+    /// Identificativo di un ordine permanente all'interno di un `Pianificatore`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct IdOrdine(pub u32);
+
+    impl fmt::Display for IdOrdine {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "ordine {}", self.0)
+        }
+    }
+
+    /// Movimento eseguito da un ordine permanente.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum TipoOrdine {
+        /// Prelievo verso l'esterno della banca.
+        Prelievo,
+        /// Trasferimento verso un altro conto della banca.
+        Trasferimento(IdConto),
+    }
+
+    /// Ordine permanente: un importo addebitato su un conto a ogni scadenza.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct OrdinePermanente {
+        /// Conto da addebitare.
+        pub conto: IdConto,
+        /// Prelievo o trasferimento.
+        pub tipo: TipoOrdine,
+        /// Importo di ogni esecuzione.
+        pub importo: Denaro,
+        /// Periodicità delle scadenze.
+        pub periodicita: Periodicita,
+        /// Ultimo giorno in cui l'ordine può scadere, se l'ordine non è a tempo indeterminato.
+        pub fine: Option<NaiveDate>,
+        scadenza: NaiveDate,
+        riprova: Option<(u32, NaiveDate)>,
+    }
+
+    impl OrdinePermanente {
+        /// Crea un ordine di prelievo ricorrente.
+        ///
+        /// # Argomenti
+        ///
+        /// * `conto` - Conto da addebitare.
+        /// * `importo` - Importo di ogni prelievo.
+        /// * `periodicita` - Periodicità delle scadenze.
+        /// * `inizio` - Data da cui parte l'ordine: la prima scadenza è la prima non precedente.
+        pub fn prelievo(conto: IdConto, importo: Denaro, periodicita: Periodicita, inizio: NaiveDate) -> OrdinePermanente {
+            OrdinePermanente {
+                conto,
+                tipo: TipoOrdine::Prelievo,
+                importo,
+                periodicita,
+                fine: None,
+                scadenza: periodicita.prima(inizio),
+                riprova: None,
+            }
+        }
+
+        /// Crea un ordine di trasferimento ricorrente tra due conti della stessa banca.
+        ///
+        /// # Argomenti
+        ///
+        /// * `da` - Conto da addebitare.
+        /// * `a` - Conto da accreditare.
+        /// * `importo` - Importo di ogni trasferimento.
+        /// * `periodicita` - Periodicità delle scadenze.
+        /// * `inizio` - Data da cui parte l'ordine: la prima scadenza è la prima non precedente.
+        pub fn trasferimento(da: IdConto, a: IdConto, importo: Denaro, periodicita: Periodicita, inizio: NaiveDate) -> OrdinePermanente {
+            OrdinePermanente { tipo: TipoOrdine::Trasferimento(a), ..OrdinePermanente::prelievo(da, importo, periodicita, inizio) }
+        }
+
+        /// Imposta l'ultimo giorno in cui l'ordine può scadere.
+        pub fn fino_al(mut self, fine: NaiveDate) -> OrdinePermanente {
+            self.fine = Some(fine);
+            self
+        }
+
+        /// Restituisce la prossima scadenza dell'ordine, senza contare gli eventuali nuovi tentativi.
+        pub fn scadenza(&self) -> NaiveDate {
+            self.scadenza
+        }
+
+        /// Restituisce il giorno in cui l'ordine va eseguito: il prossimo tentativo, se ce n'è uno, altrimenti la scadenza.
+        pub fn dovuto_il(&self) -> NaiveDate {
+            self.riprova.map_or(self.scadenza, |(_, giorno)| giorno)
+        }
+
+        /// Indica se l'ordine è terminato: la prossima scadenza supera la data di fine.
+        pub fn terminato(&self) -> bool {
+            self.fine.is_some_and(|fine| self.scadenza > fine)
+        }
+
+        /// Passa alla scadenza successiva, dimenticando i tentativi falliti.
+        fn avanza(&mut self) {
+            self.scadenza = self.periodicita.successiva(self.scadenza);
+            self.riprova = None;
+        }
+    }
+
+    /// Politica dei nuovi tentativi per gli ordini rifiutati per mancanza di fondi, o perché
+    /// il conto è in stato Rosso e non consente prelievi.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct PoliticaRiprova {
+        /// Numero massimo di nuovi tentativi per ogni scadenza.
+        pub tentativi: u32,
+        /// Giorni di attesa tra un tentativo e il successivo (almeno uno).
+        pub intervallo: u32,
+    }
+
+    impl PoliticaRiprova {
+        /// Nessun nuovo tentativo: la scadenza rifiutata viene saltata.
+        pub const MAI: PoliticaRiprova = PoliticaRiprova { tentativi: 0, intervallo: 1 };
+    }
+
+    impl Default for PoliticaRiprova {
+        /// Tre nuovi tentativi a un giorno di distanza.
+        fn default() -> Self {
+            PoliticaRiprova { tentativi: 3, intervallo: 1 }
+        }
+    }
+
+    /// Esito dell'esecuzione di un ordine a una scadenza.
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Esito {
+        /// Il movimento è stato eseguito.
+        Eseguito,
+        /// Il movimento è stato rifiutato e verrà ritentato il giorno indicato.
+        Riprova {
+            /// Errore restituito dalla banca.
+            errore: ErroreBanca,
+            /// Numero del nuovo tentativo, a partire da 1.
+            tentativo: u32,
+            /// Giorno del nuovo tentativo.
+            il: NaiveDate,
+        },
+        /// Il movimento è stato rifiutato e la scadenza è stata saltata.
+        Saltato(ErroreBanca),
+    }
+
+    /// Esecuzione di un ordine, restituita dal pianificatore.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Esecuzione {
+        /// Ordine eseguito.
+        pub ordine: IdOrdine,
+        /// Scadenza a cui si riferisce l'esecuzione.
+        pub scadenza: NaiveDate,
+        /// Giorno in cui l'ordine è stato eseguito (dopo la scadenza, per i nuovi tentativi).
+        pub giorno: NaiveDate,
+        /// Esito dell'esecuzione.
+        pub esito: Esito,
+    }
+
+    /// Indica se l'errore dipende dalla mancanza di fondi e quindi l'ordine può essere ritentato.
+    fn da_ritentare(errore: &ErroreBanca) -> bool {
+        matches!(
+            errore,
+            ErroreBanca::Conto { errore: ErroreConto::FondiInsufficienti { .. }, .. }
+                | ErroreBanca::Conto { errore: ErroreConto::OperazioneNonConsentita { stato: Stato::Rosso, .. }, .. }
+        )
+    }
+
+    /// Pianificatore degli ordini permanenti dei conti di una banca.
+    ///
+    /// Gli ordini dovuti nello stesso giorno vengono eseguiti in ordine di scadenza e, a parità
+    /// di scadenza, di identificativo. I movimenti sono datati con l'orologio dei conti, che
+    /// durante una simulazione dovrebbe essere lo stesso `OrologioVirtuale` del pianificatore.
+    ///
+    /// # Esempio
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use chrono::NaiveDate;
+    /// use esercizio5::banca::banca::Banca;
+    /// use esercizio5::conto_bancario::conto_bancario::ContoBancario;
+    /// use esercizio5::denaro::denaro::{Denaro, Tasso, Valuta};
+    /// use esercizio5::ordini::ordini::{OrdinePermanente, Periodicita, Pianificatore};
+    /// use esercizio5::orologio::orologio::OrologioVirtuale;
+    ///
+    /// let euro = |importo: &str| Denaro::da_decimale(importo, Valuta::EUR).unwrap();
+    /// let inizio = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+    /// let orologio = Arc::new(OrologioVirtuale::al_giorno(inizio));
+    /// let mut conto = ContoBancario::new(String::from("Mario Rossi"), euro("1000"), euro("0"), euro("5000"), Tasso::ZERO);
+    /// conto.imposta_orologio(orologio.clone());
+    /// let mut banca = Banca::nuova(Valuta::EUR);
+    /// let mario = banca.apri(conto).unwrap();
+    ///
+    /// let mut pianificatore = Pianificatore::nuovo();
+    /// pianificatore.aggiungi(OrdinePermanente::prelievo(mario, euro("100"), Periodicita::FineMese, inizio));
+    /// pianificatore.simula(&mut banca, &orologio, NaiveDate::from_ymd_opt(2024, 3, 31).unwrap());
+    /// assert_eq!(banca.conto(mario).unwrap().saldo(), euro("700"));
+    /// ```
+    #[derive(Debug, Clone, Default)]
+    pub struct Pianificatore {
+        ordini: BTreeMap<IdOrdine, OrdinePermanente>,
+        prossimo_id: u32,
+        politica: PoliticaRiprova,
+    }
+
+    impl Pianificatore {
+        /// Crea un pianificatore senza ordini, con la politica di riprova predefinita.
+        pub fn nuovo() -> Pianificatore {
+            Pianificatore::default()
+        }
+
+        /// Imposta la politica dei nuovi tentativi.
+        pub fn con_politica(mut self, politica: PoliticaRiprova) -> Pianificatore {
+            self.politica = politica;
+            self
+        }
+
+        /// Aggiunge un ordine e ne restituisce l'identificativo.
+        ///
+        /// # Argomenti
+        ///
+        /// * `ordine` - L'ordine da eseguire alle scadenze.
+        pub fn aggiungi(&mut self, ordine: OrdinePermanente) -> IdOrdine {
+            self.prossimo_id += 1;
+            let id = IdOrdine(self.prossimo_id);
+            self.ordini.insert(id, ordine);
+            id
+        }
+
+        /// Revoca un ordine e lo restituisce, se esiste.
+        pub fn revoca(&mut self, id: IdOrdine) -> Option<OrdinePermanente> {
+            self.ordini.remove(&id)
+        }
+
+        /// Restituisce l'ordine con l'identificativo indicato, se esiste.
+        pub fn ordine(&self, id: IdOrdine) -> Option<&OrdinePermanente> {
+            self.ordini.get(&id)
+        }
+
+        /// Restituisce gli ordini ancora attivi, in ordine di identificativo.
+        pub fn ordini(&self) -> impl Iterator<Item = (IdOrdine, &OrdinePermanente)> + '_ {
+            self.ordini.iter().map(|(id, ordine)| (*id, ordine))
+        }
+
+        /// Esegue, in ordine, tutti gli ordini dovuti entro il giorno indicato, comprese le scadenze
+        /// arretrate, e rimuove gli ordini terminati.
+        ///
+        /// Restituisce le esecuzioni nell'ordine in cui sono avvenute.
+        ///
+        /// # Argomenti
+        ///
+        /// * `banca` - La banca dei conti degli ordini.
+        /// * `giorno` - Il giorno corrente.
+        pub fn esegui_dovuti(&mut self, banca: &mut Banca, giorno: NaiveDate) -> Vec<Esecuzione> {
+            let mut esecuzioni = Vec::new();
+            loop {
+                let dovuto = self
+                    .ordini
+                    .iter()
+                    .filter(|(_, ordine)| !ordine.terminato() && ordine.dovuto_il() <= giorno)
+                    .min_by_key(|(id, ordine)| (ordine.dovuto_il(), **id))
+                    .map(|(id, _)| *id);
+                let Some(id) = dovuto else { break };
+                let politica = self.politica;
+                let ordine = self.ordini.get_mut(&id).unwrap();
+                let scadenza = ordine.scadenza;
+                let esito = match ordine.tipo {
+                    TipoOrdine::Prelievo => banca.preleva(ordine.conto, ordine.importo).map(|_| ()),
+                    TipoOrdine::Trasferimento(a) => banca.trasferisci(ordine.conto, a, ordine.importo).map(|_| ()),
+                };
+                let esito = match esito {
+                    Ok(()) => {
+                        ordine.avanza();
+                        Esito::Eseguito
+                    }
+                    Err(errore) => {
+                        let tentativo = ordine.riprova.map_or(1, |(fatti, _)| fatti + 1);
+                        if da_ritentare(&errore) && tentativo <= politica.tentativi {
+                            let il = giorno + Duration::days(politica.intervallo.max(1) as i64);
+                            ordine.riprova = Some((tentativo, il));
+                            Esito::Riprova { errore, tentativo, il }
+                        } else {
+                            ordine.avanza();
+                            Esito::Saltato(errore)
+                        }
+                    }
+                };
+                esecuzioni.push(Esecuzione { ordine: id, scadenza, giorno, esito });
+            }
+            self.ordini.retain(|_, ordine| !ordine.terminato());
+            esecuzioni
+        }
+
+        /// Simula il passare dei giorni dalla data dell'orologio fino al giorno indicato compreso:
+        /// ogni giorno esegue gli ordini dovuti, poi l'orologio avanza all'inizio del giorno successivo.
+        ///
+        /// Restituisce tutte le esecuzioni, nell'ordine in cui sono avvenute.
+        ///
+        /// # Argomenti
+        ///
+        /// * `banca` - La banca dei conti degli ordini, che dovrebbero usare lo stesso orologio.
+        /// * `orologio` - L'orologio virtuale della simulazione.
+        /// * `fino_al` - Ultimo giorno da simulare.
+        pub fn simula(&mut self, banca: &mut Banca, orologio: &OrologioVirtuale, fino_al: NaiveDate) -> Vec<Esecuzione> {
+            let mut esecuzioni = Vec::new();
+            let mut giorno = orologio.oggi();
+            while giorno <= fino_al {
+                esecuzioni.extend(self.esegui_dovuti(banca, giorno));
+                giorno += Duration::days(1);
+                orologio.imposta(giorno.and_hms_opt(0, 0, 0).unwrap());
+            }
+            esecuzioni
+        }
+    }
+}
@@ -0,0 +1,65 @@
+/// Modulo `orologio` per astrarre la data e l'ora usate dai conti.
+pub mod orologio {
+    use std::sync::Mutex;
+    use chrono::{Duration, Local, NaiveDate, NaiveDateTime};
+
+    /// Sorgente della data e dell'ora attuali.
+    pub trait Orologio: Send + Sync {
+        /// Restituisce la data e l'ora attuali.
+        fn adesso(&self) -> NaiveDateTime;
+
+        /// Restituisce la data attuale.
+        fn oggi(&self) -> NaiveDate {
+            self.adesso().date()
+        }
+    }
+
+    /// Orologio di sistema, con l'ora locale.
+    #[derive(Debug, Clone, Copy, Default)]
+    pub struct OrologioSistema;
+
+    impl Orologio for OrologioSistema {
+        fn adesso(&self) -> NaiveDateTime {
+            Local::now().naive_local()
+        }
+    }
+
+    /// Orologio che segna un istante scelto, fatto avanzare esplicitamente (per test e simulazioni).
+    #[derive(Debug)]
+    pub struct OrologioVirtuale {
+        istante: Mutex<NaiveDateTime>,
+    }
+
+    impl OrologioVirtuale {
+        /// Crea un orologio fermo all'istante indicato.
+        ///
+        /// # Argomenti
+        ///
+        /// * `istante` - L'istante iniziale.
+        pub fn nuovo(istante: NaiveDateTime) -> OrologioVirtuale {
+            OrologioVirtuale { istante: Mutex::new(istante) }
+        }
+
+        /// Crea un orologio fermo all'inizio del giorno indicato.
+        pub fn al_giorno(giorno: NaiveDate) -> OrologioVirtuale {
+            OrologioVirtuale::nuovo(giorno.and_hms_opt(0, 0, 0).unwrap())
+        }
+
+        /// Porta l'orologio all'istante indicato.
+        pub fn imposta(&self, istante: NaiveDateTime) {
+            *self.istante.lock().unwrap() = istante;
+        }
+
+        /// Fa avanzare l'orologio della durata indicata.
+        pub fn avanza(&self, durata: Duration) {
+            let mut istante = self.istante.lock().unwrap();
+            *istante += durata;
+        }
+    }
+
+    impl Orologio for OrologioVirtuale {
+        fn adesso(&self) -> NaiveDateTime {
+            *self.istante.lock().unwrap()
+        }
+    }
+}
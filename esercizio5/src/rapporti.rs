@@ -0,0 +1,380 @@
+/// Modulo `rapporti` per i rapporti periodici sui conti di una banca, esportabili in CSV e JSON.
+pub mod rapporti {
+    use chrono::{NaiveDate, NaiveDateTime};
+    use crate::banca::banca::{Banca, IdConto};
+    use crate::cambi::cambi::ErroreCambi;
+    use crate::conto_bancario::conto_bancario::ContoBancario;
+    use crate::denaro::denaro::Denaro;
+    use crate::registro::registro::{Movimento, Registro, TipoMovimento, FORMATO_DATA};
+    use crate::stati::stati::Stato;
+
+    /// Valore di una cella di un rapporto.
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Valore {
+        /// Testo libero.
+        Testo(String),
+        /// Numero intero.
+        Intero(i64),
+        /// Importo, scritto in forma decimale senza valuta.
+        Importo(Denaro),
+        /// Giorno, nel formato `AAAA-MM-GG`.
+        Giorno(NaiveDate),
+        /// Data e ora, nel formato dei registri.
+        Istante(NaiveDateTime),
+    }
+
+    impl Valore {
+        /// Restituisce il valore come testo, senza virgolette.
+        fn testo(&self) -> String {
+            match self {
+                Valore::Testo(testo) => testo.clone(),
+                Valore::Intero(numero) => numero.to_string(),
+                Valore::Importo(importo) => importo.in_decimale(),
+                Valore::Giorno(giorno) => giorno.format("%Y-%m-%d").to_string(),
+                Valore::Istante(istante) => istante.format(FORMATO_DATA).to_string(),
+            }
+        }
+
+        /// Restituisce il valore come campo CSV, tra virgolette se contiene separatori, virgolette o a capo.
+        fn in_csv(&self) -> String {
+            let testo = self.testo();
+            if testo.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", testo.replace('"', "\"\""))
+            } else {
+                testo
+            }
+        }
+
+        /// Restituisce il valore in JSON: numeri per interi e importi, stringhe per il resto.
+        fn in_json(&self) -> String {
+            match self {
+                Valore::Intero(_) | Valore::Importo(_) => self.testo(),
+                _ => stringa_json(&self.testo()),
+            }
+        }
+    }
+
+    impl From<&str> for Valore {
+        fn from(testo: &str) -> Self {
+            Valore::Testo(testo.to_string())
+        }
+    }
+
+    impl From<Stato> for Valore {
+        fn from(stato: Stato) -> Self {
+            Valore::Testo(stato.nome().to_string())
+        }
+    }
+
+    impl From<IdConto> for Valore {
+        fn from(id: IdConto) -> Self {
+            Valore::Intero(id.0 as i64)
+        }
+    }
+
+    /// Scrive una stringa JSON tra virgolette, con i caratteri speciali protetti.
+    fn stringa_json(testo: &str) -> String {
+        let mut json = String::from("\"");
+        for c in testo.chars() {
+            match c {
+                '"' => json.push_str("\\\""),
+                '\\' => json.push_str("\\\\"),
+                '\n' => json.push_str("\\n"),
+                '\r' => json.push_str("\\r"),
+                '\t' => json.push_str("\\t"),
+                c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+                c => json.push(c),
+            }
+        }
+        json.push('"');
+        json
+    }
+
+    /// Rapporto tabellare: un titolo, i nomi delle colonne e le righe.
+    ///
+    /// # Esempio
+    ///
+    /// ```
+    /// use chrono::NaiveDate;
+    /// use esercizio5::banca::banca::Banca;
+    /// use esercizio5::conto_bancario::conto_bancario::ContoBancario;
+    /// use esercizio5::denaro::denaro::{Denaro, Tasso, Valuta};
+    /// use esercizio5::rapporti::rapporti;
+    ///
+    /// let euro = |importo: &str| Denaro::da_decimale(importo, Valuta::EUR).unwrap();
+    /// let mut banca = Banca::nuova(Valuta::EUR);
+    /// banca.apri(ContoBancario::new(String::from("Rossi, Mario"), euro("100"), euro("0"), euro("500"), Tasso::ZERO)).unwrap();
+    ///
+    /// let rapporto = rapporti::saldi_fine_giornata(&banca, NaiveDate::from_ymd_opt(2024, 1, 1).unwrap());
+    /// assert_eq!(rapporto.in_csv(), "conto,intestatario,stato,saldo,valuta\n1,\"Rossi, Mario\",Argento,100.00,EUR\n");
+    /// assert!(rapporto.in_json().contains("{\"conto\": 1, \"intestatario\": \"Rossi, Mario\", \"stato\": \"Argento\", \"saldo\": 100.00, \"valuta\": \"EUR\"}"));
+    /// ```
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Rapporto {
+        /// Titolo del rapporto, riportato nel JSON.
+        pub titolo: String,
+        /// Nomi delle colonne.
+        pub colonne: Vec<&'static str>,
+        /// Righe del rapporto, con un valore per colonna.
+        pub righe: Vec<Vec<Valore>>,
+    }
+
+    impl Rapporto {
+        /// Restituisce il rapporto in formato CSV, con una riga di intestazione.
+        pub fn in_csv(&self) -> String {
+            let mut csv = format!("{}\n", self.colonne.join(","));
+            for riga in &self.righe {
+                let campi: Vec<String> = riga.iter().map(Valore::in_csv).collect();
+                csv.push_str(&format!("{}\n", campi.join(",")));
+            }
+            csv
+        }
+
+        /// Restituisce il rapporto in formato JSON: un oggetto con il titolo e le righe, ognuna
+        /// un oggetto con un campo per colonna.
+        pub fn in_json(&self) -> String {
+            let righe: Vec<String> = self
+                .righe
+                .iter()
+                .map(|riga| {
+                    let campi: Vec<String> = self
+                        .colonne
+                        .iter()
+                        .zip(riga)
+                        .map(|(colonna, valore)| format!("{}: {}", stringa_json(colonna), valore.in_json()))
+                        .collect();
+                    format!("    {{{}}}", campi.join(", "))
+                })
+                .collect();
+            let corpo = if righe.is_empty() { String::from("[]") } else { format!("[\n{}\n  ]", righe.join(",\n")) };
+            format!("{{\n  \"rapporto\": {},\n  \"righe\": {}\n}}\n", stringa_json(&self.titolo), corpo)
+        }
+    }
+
+    /// Ultimo istante del giorno indicato.
+    fn fine_giornata(giorno: NaiveDate) -> NaiveDateTime {
+        giorno.and_hms_nano_opt(23, 59, 59, 999_999_999).expect("orario valido")
+    }
+
+    /// Primo istante del giorno indicato.
+    fn inizio_giornata(giorno: NaiveDate) -> NaiveDateTime {
+        giorno.and_hms_opt(0, 0, 0).expect("orario valido")
+    }
+
+    /// Conti aperti e conti chiusi non prima dell'istante indicato, in ordine di identificativo:
+    /// i conti chiusi restano nei rapporti sui periodi in cui erano aperti.
+    fn conti_dal(banca: &Banca, istante: NaiveDateTime) -> Vec<(IdConto, &ContoBancario)> {
+        let aperti = banca.ids().filter_map(|id| banca.conto(id).map(|conto| (id, conto)));
+        let chiusi = banca
+            .ids_chiusi()
+            .filter_map(|id| banca.conto_chiuso(id).filter(|(_, chiusura)| *chiusura >= istante).map(|(conto, _)| (id, conto)));
+        let mut conti: Vec<_> = aperti.chain(chiusi).collect();
+        conti.sort_by_key(|(id, _)| *id);
+        conti
+    }
+
+    /// Registro del conto seguito da quelli delle divise, in ordine di valuta.
+    fn registri(conto: &ContoBancario) -> impl Iterator<Item = &Registro> {
+        let divise = conto.divise().into_iter().filter_map(|saldo| conto.registro_divisa(saldo.valuta()));
+        std::iter::once(conto.registro()).chain(divise)
+    }
+
+    /// Saldi del conto alla fine di un giorno: quello nella valuta del conto e quelli delle divise
+    /// con almeno un movimento entro l'istante indicato.
+    fn saldi_al(conto: &ContoBancario, istante: NaiveDateTime) -> Vec<Denaro> {
+        registri(conto)
+            .enumerate()
+            .filter(|(indice, registro)| *indice == 0 || registro.movimenti().first().is_some_and(|m| m.data <= istante))
+            .map(|(_, registro)| registro.saldo_al(istante))
+            .collect()
+    }
+
+    /// Ricostruisce lo stato del conto all'istante indicato dai cambi di stato del registro: quello
+    /// raggiunto con l'ultimo cambio precedente, o quello di partenza del primo cambio successivo.
+    fn stato_al(conto: &ContoBancario, istante: NaiveDateTime) -> Stato {
+        let mut successivo = None;
+        for m in conto.registro().movimenti().iter().rev() {
+            if let TipoMovimento::CambioStato { da, a } = &m.tipo {
+                if m.data <= istante {
+                    return a.clone();
+                }
+                successivo = Some(da.clone());
+            }
+        }
+        successivo.unwrap_or_else(|| conto.stato())
+    }
+
+    /// Posizione di uno stato nei rapporti: Rosso, Argento, Oro, poi gli stati personalizzati per nome.
+    fn ordine(stato: &Stato) -> (u8, &str) {
+        match stato {
+            Stato::Rosso => (0, ""),
+            Stato::Argento => (1, ""),
+            Stato::Oro => (2, ""),
+            Stato::Personalizzato(_) => (3, stato.nome()),
+        }
+    }
+
+    /// Saldi dei conti aperti alla fine del giorno indicato, ordinati per stato e poi per conto, con
+    /// una riga per la valuta del conto seguita da una per ogni divisa.
+    ///
+    /// Colonne: `conto,intestatario,stato,saldo,valuta`.
+    ///
+    /// # Argomenti
+    ///
+    /// * `banca` - La banca dei conti.
+    /// * `giorno` - Giorno del rapporto.
+    pub fn saldi_fine_giornata(banca: &Banca, giorno: NaiveDate) -> Rapporto {
+        let fine = fine_giornata(giorno);
+        let mut saldi: Vec<(IdConto, &ContoBancario, Stato, Denaro)> = Vec::new();
+        for (id, conto) in conti_dal(banca, fine) {
+            let stato = stato_al(conto, fine);
+            saldi.extend(saldi_al(conto, fine).into_iter().map(|saldo| (id, conto, stato.clone(), saldo)));
+        }
+        saldi.sort_by(|(id, _, stato, _), (altro_id, _, altro, _)| (ordine(stato), id).cmp(&(ordine(altro), altro_id)));
+        Rapporto {
+            titolo: format!("saldi di fine giornata {}", giorno.format("%Y-%m-%d")),
+            colonne: vec!["conto", "intestatario", "stato", "saldo", "valuta"],
+            righe: saldi
+                .into_iter()
+                .map(|(id, conto, stato, saldo)| {
+                    vec![id.into(), conto.nome_cliente.as_str().into(), stato.into(), Valore::Importo(saldo), Valore::Testo(saldo.valuta().to_string())]
+                })
+                .collect(),
+        }
+    }
+
+    /// Numero di conti e saldo complessivo per ogni stato alla fine del giorno indicato, con una riga
+    /// per la valuta della banca seguita da una per ogni valuta delle divise dei conti in quello stato.
+    ///
+    /// Colonne: `stato,conti,totale,valuta`.
+    ///
+    /// # Argomenti
+    ///
+    /// * `banca` - La banca dei conti.
+    /// * `giorno` - Giorno del rapporto.
+    pub fn totali_per_stato(banca: &Banca, giorno: NaiveDate) -> Rapporto {
+        let fine = fine_giornata(giorno);
+        let mut totali: Vec<(Stato, i64, Denaro)> = Vec::new();
+        for (_, conto) in conti_dal(banca, fine) {
+            let stato = stato_al(conto, fine);
+            for saldo in saldi_al(conto, fine) {
+                match totali.iter_mut().find(|(s, _, totale)| *s == stato && totale.valuta() == saldo.valuta()) {
+                    Some((_, conti, totale)) => {
+                        *conti += 1;
+                        *totale = *totale + saldo;
+                    }
+                    None => totali.push((stato.clone(), 1, saldo)),
+                }
+            }
+        }
+        // Per ogni stato la valuta della banca viene prima di quelle delle divise
+        totali.sort_by(|(stato, _, totale), (altro, _, altro_totale)| {
+            let valuta = |totale: &Denaro| (totale.valuta() != banca.valuta(), totale.valuta());
+            (ordine(stato), valuta(totale)).cmp(&(ordine(altro), valuta(altro_totale)))
+        });
+        Rapporto {
+            titolo: format!("totali per stato {}", giorno.format("%Y-%m-%d")),
+            colonne: vec!["stato", "conti", "totale", "valuta"],
+            righe: totali
+                .into_iter()
+                .map(|(stato, conti, totale)| vec![stato.into(), Valore::Intero(conti), Valore::Importo(totale), Valore::Testo(totale.valuta().to_string())])
+                .collect(),
+        }
+    }
+
+    /// Restituisce i movimenti del registro del conto tra due giorni, estremi compresi.
+    fn movimenti_tra(conto: &ContoBancario, dal: NaiveDate, al: NaiveDate) -> impl Iterator<Item = &Movimento> {
+        conto.registro().movimenti().iter().filter(move |m| (dal..=al).contains(&m.data.date()))
+    }
+
+    /// Titolo di un rapporto su un intervallo di giorni.
+    fn titolo(nome: &str, dal: NaiveDate, al: NaiveDate) -> String {
+        format!("{} dal {} al {}", nome, dal.format("%Y-%m-%d"), al.format("%Y-%m-%d"))
+    }
+
+    /// Interessi netti accreditati (o, se negativi, addebitati) a ogni conto tra due giorni, estremi
+    /// compresi, compresi i conti chiusi durante il periodo.
+    ///
+    /// Colonne: `conto,intestatario,interessi,valuta`.
+    ///
+    /// # Argomenti
+    ///
+    /// * `banca` - La banca dei conti.
+    /// * `dal` - Primo giorno del rapporto.
+    /// * `al` - Ultimo giorno del rapporto.
+    pub fn interessi_pagati(banca: &Banca, dal: NaiveDate, al: NaiveDate) -> Rapporto {
+        let righe = conti_dal(banca, inizio_giornata(dal))
+            .into_iter()
+            .map(|(id, conto)| {
+                let interessi = movimenti_tra(conto, dal, al)
+                    .filter(|m| m.tipo == TipoMovimento::Interessi)
+                    .fold(Denaro::zero(conto.saldo().valuta()), |totale, m| totale + m.variazione);
+                vec![id.into(), conto.nome_cliente.as_str().into(), Valore::Importo(interessi), Valore::Testo(interessi.valuta().to_string())]
+            })
+            .collect();
+        Rapporto { titolo: titolo("interessi pagati", dal, al), colonne: vec!["conto", "intestatario", "interessi", "valuta"], righe }
+    }
+
+    /// Cambi di stato dei conti tra due giorni, estremi compresi, in ordine di conto e di data,
+    /// compresi quelli dei conti chiusi in seguito.
+    ///
+    /// Colonne: `conto,intestatario,data,da,a`.
+    ///
+    /// # Argomenti
+    ///
+    /// * `banca` - La banca dei conti.
+    /// * `dal` - Primo giorno del rapporto.
+    /// * `al` - Ultimo giorno del rapporto.
+    pub fn cambi_di_stato(banca: &Banca, dal: NaiveDate, al: NaiveDate) -> Rapporto {
+        let mut righe = Vec::new();
+        for (id, conto) in conti_dal(banca, inizio_giornata(dal)) {
+            for m in movimenti_tra(conto, dal, al) {
+                if let TipoMovimento::CambioStato { da, a } = &m.tipo {
+                    righe.push(vec![id.into(), conto.nome_cliente.as_str().into(), Valore::Istante(m.data), da.clone().into(), a.clone().into()]);
+                }
+            }
+        }
+        Rapporto { titolo: titolo("cambi di stato", dal, al), colonne: vec!["conto", "intestatario", "data", "da", "a"], righe }
+    }
+
+    /// Movimenti di importo pari o superiore alla soglia tra due giorni, estremi compresi, in ordine
+    /// di conto e di registro (prima quello del conto, poi quelli delle divise) e di sequenza, compresi
+    /// quelli dei conti chiusi in seguito. I movimenti in una valuta diversa da quella della soglia
+    /// vengono confrontati con il loro controvalore ai tassi attuali del conto; senza un tasso di
+    /// cambio restituisce l'errore `ErroreCambi::TassoMancante`.
+    ///
+    /// Colonne: `conto,intestatario,sequenza,data,tipo,importo,saldo,valuta`.
+    ///
+    /// # Argomenti
+    ///
+    /// * `banca` - La banca dei conti.
+    /// * `dal` - Primo giorno del rapporto.
+    /// * `al` - Ultimo giorno del rapporto.
+    /// * `soglia` - Importo minimo, in valore assoluto, dei movimenti da riportare.
+    pub fn grandi_movimenti(banca: &Banca, dal: NaiveDate, al: NaiveDate, soglia: Denaro) -> Result<Rapporto, ErroreCambi> {
+        let mut righe = Vec::new();
+        for (id, conto) in conti_dal(banca, inizio_giornata(dal)) {
+            let movimenti = registri(conto).flat_map(|registro| registro.movimenti());
+            for m in movimenti.filter(|m| (dal..=al).contains(&m.data.date())) {
+                if conto.controvalore(m.variazione.abs(), soglia.valuta())? < soglia {
+                    continue;
+                }
+                righe.push(vec![
+                    id.into(),
+                    conto.nome_cliente.as_str().into(),
+                    Valore::Intero(m.sequenza as i64),
+                    Valore::Istante(m.data),
+                    m.tipo.nome().into(),
+                    Valore::Importo(m.variazione),
+                    Valore::Importo(m.saldo),
+                    Valore::Testo(m.saldo.valuta().to_string()),
+                ]);
+            }
+        }
+        Ok(Rapporto {
+            titolo: format!("{} da {}", titolo("grandi movimenti", dal, al), soglia),
+            colonne: vec!["conto", "intestatario", "sequenza", "data", "tipo", "importo", "saldo", "valuta"],
+            righe,
+        })
+    }
+}
@@ -0,0 +1,324 @@
+/// Modulo `registro` per il registro dei movimenti di un conto e gli estratti conto.
+pub mod registro {
+    use std::fmt;
+    use chrono::{NaiveDate, NaiveDateTime};
+    use crate::conto_bancario::conto_bancario::Operazione;
+    use crate::denaro::denaro::Denaro;
+    use crate::stati::stati::Stato;
+
+    /// Formato delle date nei movimenti, negli estratti e nei file CSV.
+    pub const FORMATO_DATA: &str = "%Y-%m-%d %H:%M:%S";
+
+    /// Tipo di un movimento registrato.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum TipoMovimento {
+        /// Versamento di denaro sul conto.
+        Deposito,
+        /// Prelievo di denaro dal conto.
+        Prelievo,
+        /// Accredito degli interessi.
+        Interessi,
+        /// Addebito di una commissione.
+        Commissione,
+        /// Addebito della commissione di cambio di una conversione.
+        CommissioneCambio,
+        /// Passaggio del conto da uno stato all'altro; non modifica il saldo.
+        CambioStato {
+            /// Stato precedente.
+            da: Stato,
+            /// Nuovo stato.
+            a: Stato,
+        },
+        /// Il saldo ha attraversato una soglia di notifica; non modifica il saldo.
+        Avviso {
+            /// Soglia attraversata.
+            soglia: Denaro,
+            /// Verso dell'attraversamento.
+            verso: Attraversamento,
+        },
+    }
+
+    /// Verso in cui il saldo attraversa una soglia.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Attraversamento {
+        /// Il saldo è sceso sotto la soglia.
+        Discesa,
+        /// Il saldo è tornato alla soglia o sopra.
+        Salita,
+    }
+
+    impl Attraversamento {
+        /// Restituisce il verso dell'attraversamento della soglia passando da un saldo all'altro, se c'è.
+        ///
+        /// # Argomenti
+        ///
+        /// * `soglia` - La soglia, che appartiene alla fascia superiore.
+        /// * `prima` - Saldo precedente.
+        /// * `dopo` - Saldo successivo.
+        pub fn tra(soglia: Denaro, prima: Denaro, dopo: Denaro) -> Option<Attraversamento> {
+            match (prima >= soglia, dopo >= soglia) {
+                (true, false) => Some(Attraversamento::Discesa),
+                (false, true) => Some(Attraversamento::Salita),
+                _ => None,
+            }
+        }
+    }
+
+    impl TipoMovimento {
+        /// Restituisce il nome del tipo di movimento, usato negli estratti.
+        pub fn nome(&self) -> &'static str {
+            match self {
+                TipoMovimento::Deposito => "Deposito",
+                TipoMovimento::Prelievo => "Prelievo",
+                TipoMovimento::Interessi => "Interessi",
+                TipoMovimento::Commissione => "Commissione",
+                TipoMovimento::CommissioneCambio => "CommissioneCambio",
+                TipoMovimento::CambioStato { .. } => "CambioStato",
+                TipoMovimento::Avviso { .. } => "Avviso",
+            }
+        }
+
+        /// Restituisce il dettaglio del movimento (gli stati per un cambio di stato, la soglia per un avviso),
+        /// altrimenti una stringa vuota.
+        pub fn dettaglio(&self) -> String {
+            match self {
+                TipoMovimento::CambioStato { da, a } => format!("{}->{}", da, a),
+                TipoMovimento::Avviso { soglia, verso: Attraversamento::Discesa } => format!("sotto {}", soglia),
+                TipoMovimento::Avviso { soglia, verso: Attraversamento::Salita } => format!("sopra {}", soglia),
+                _ => String::new(),
+            }
+        }
+    }
+
+    impl From<Operazione> for TipoMovimento {
+        fn from(operazione: Operazione) -> Self {
+            match operazione {
+                Operazione::Deposito => TipoMovimento::Deposito,
+                Operazione::Prelievo => TipoMovimento::Prelievo,
+                Operazione::Interessi => TipoMovimento::Interessi,
+                Operazione::Commissione => TipoMovimento::Commissione,
+            }
+        }
+    }
+
+    /// Movimento registrato su un conto.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Movimento {
+        /// Numero progressivo del movimento nel registro, a partire da 1.
+        pub sequenza: u64,
+        /// Data e ora del movimento.
+        pub data: NaiveDateTime,
+        /// Tipo di movimento.
+        pub tipo: TipoMovimento,
+        /// Variazione del saldo: positiva per gli accrediti, negativa per gli addebiti, nulla per i cambi di stato e gli avvisi.
+        pub variazione: Denaro,
+        /// Saldo dopo il movimento.
+        pub saldo: Denaro,
+    }
+
+    /// Errori nella registrazione di un movimento.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum ErroreRegistro {
+        /// La data del movimento precede quella dell'ultimo movimento registrato.
+        DataPrecedente {
+            /// Data dell'ultimo movimento registrato.
+            ultima: NaiveDateTime,
+            /// Data del movimento rifiutato.
+            data: NaiveDateTime,
+        },
+    }
+
+    impl fmt::Display for ErroreRegistro {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                ErroreRegistro::DataPrecedente { ultima, data } => write!(
+                    f,
+                    "il movimento del {} precede l'ultimo movimento registrato ({})",
+                    data.format(FORMATO_DATA),
+                    ultima.format(FORMATO_DATA)
+                ),
+            }
+        }
+    }
+
+    impl std::error::Error for ErroreRegistro {}
+
+    /// Registro in sola aggiunta dei movimenti di un conto.
+    ///
+    /// I movimenti hanno numeri di sequenza consecutivi e date non decrescenti; il saldo può essere
+    /// ricostruito in qualsiasi momento a partire dal saldo di apertura.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Registro {
+        saldo_apertura: Denaro,
+        movimenti: Vec<Movimento>,
+    }
+
+    impl Registro {
+        /// Crea un registro vuoto.
+        ///
+        /// # Argomenti
+        ///
+        /// * `saldo_apertura` - Saldo del conto prima del primo movimento.
+        pub fn nuovo(saldo_apertura: Denaro) -> Registro {
+            Registro { saldo_apertura, movimenti: Vec::new() }
+        }
+
+        /// Ricostruisce un registro a partire da movimenti già registrati, ad esempio letti da un archivio.
+        pub(crate) fn da_movimenti(saldo_apertura: Denaro, movimenti: Vec<Movimento>) -> Registro {
+            Registro { saldo_apertura, movimenti }
+        }
+
+        /// Restituisce il saldo del conto prima del primo movimento.
+        pub fn saldo_apertura(&self) -> Denaro {
+            self.saldo_apertura
+        }
+
+        /// Restituisce i movimenti registrati, in ordine di sequenza.
+        pub fn movimenti(&self) -> &[Movimento] {
+            &self.movimenti
+        }
+
+        /// Controlla che un movimento con la data indicata possa essere aggiunto al registro.
+        ///
+        /// # Argomenti
+        ///
+        /// * `data` - Data del movimento.
+        pub fn verifica_data(&self, data: NaiveDateTime) -> Result<(), ErroreRegistro> {
+            match self.movimenti.last() {
+                Some(ultimo) if ultimo.data > data => Err(ErroreRegistro::DataPrecedente { ultima: ultimo.data, data }),
+                _ => Ok(()),
+            }
+        }
+
+        /// Aggiunge un movimento in coda al registro e lo restituisce.
+        ///
+        /// Solo il conto aggiunge movimenti, così il registro resta coerente con il suo saldo.
+        ///
+        /// # Argomenti
+        ///
+        /// * `data` - Data del movimento, non precedente all'ultimo movimento registrato.
+        /// * `tipo` - Tipo di movimento.
+        /// * `variazione` - Variazione del saldo con segno.
+        /// * `saldo` - Saldo dopo il movimento.
+        pub(crate) fn registra(
+            &mut self,
+            data: NaiveDateTime,
+            tipo: TipoMovimento,
+            variazione: Denaro,
+            saldo: Denaro,
+        ) -> Result<&Movimento, ErroreRegistro> {
+            self.verifica_data(data)?;
+            let sequenza = self.movimenti.len() as u64 + 1;
+            self.movimenti.push(Movimento { sequenza, data, tipo, variazione, saldo });
+            Ok(&self.movimenti[self.movimenti.len() - 1])
+        }
+
+        /// Scarta i movimenti successivi ai primi `lunghezza`, per annullare un'operazione non confermata.
+        pub(crate) fn annulla_dopo(&mut self, lunghezza: usize) {
+            self.movimenti.truncate(lunghezza);
+        }
+
+        /// Ricostruisce il saldo sommando al saldo di apertura le variazioni di tutti i movimenti.
+        pub fn saldo(&self) -> Denaro {
+            self.movimenti.iter().fold(self.saldo_apertura, |saldo, m| saldo + m.variazione)
+        }
+
+        /// Ricostruisce il saldo al termine dell'istante indicato, considerando i movimenti fino a quella data compresa.
+        ///
+        /// # Argomenti
+        ///
+        /// * `data` - Istante in cui calcolare il saldo.
+        pub fn saldo_al(&self, data: NaiveDateTime) -> Denaro {
+            self.movimenti
+                .iter()
+                .take_while(|m| m.data <= data)
+                .fold(self.saldo_apertura, |saldo, m| saldo + m.variazione)
+        }
+
+        /// Indica se il saldo registrato dopo ogni movimento coincide con quello ricostruito dalle variazioni.
+        pub fn coerente(&self) -> bool {
+            let mut saldo = self.saldo_apertura;
+            self.movimenti.iter().all(|m| {
+                saldo = saldo + m.variazione;
+                saldo == m.saldo
+            })
+        }
+
+        /// Restituisce l'estratto conto dei movimenti tra due giorni, estremi compresi.
+        ///
+        /// # Argomenti
+        ///
+        /// * `intestatario` - Nome del titolare del conto, riportato nell'estratto.
+        /// * `dal` - Primo giorno dell'estratto.
+        /// * `al` - Ultimo giorno dell'estratto.
+        pub fn estratto(&self, intestatario: &str, dal: NaiveDate, al: NaiveDate) -> Estratto {
+            let precedenti = self.movimenti.iter().take_while(|m| m.data.date() < dal);
+            let saldo_iniziale = precedenti.fold(self.saldo_apertura, |saldo, m| saldo + m.variazione);
+            let movimenti: Vec<Movimento> = self
+                .movimenti
+                .iter()
+                .filter(|m| (dal..=al).contains(&m.data.date()))
+                .cloned()
+                .collect();
+            let saldo_finale = movimenti.iter().fold(saldo_iniziale, |saldo, m| saldo + m.variazione);
+            Estratto { intestatario: intestatario.to_string(), dal, al, saldo_iniziale, movimenti, saldo_finale }
+        }
+    }
+
+    /// Estratto conto dei movimenti in un intervallo di giorni.
+    ///
+    /// Con `Display` l'estratto è mostrato come testo leggibile; `in_csv` lo restituisce in formato CSV.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Estratto {
+        /// Nome del titolare del conto.
+        pub intestatario: String,
+        /// Primo giorno dell'estratto.
+        pub dal: NaiveDate,
+        /// Ultimo giorno dell'estratto.
+        pub al: NaiveDate,
+        /// Saldo all'inizio del primo giorno.
+        pub saldo_iniziale: Denaro,
+        /// Movimenti dell'intervallo, in ordine di sequenza.
+        pub movimenti: Vec<Movimento>,
+        /// Saldo alla fine dell'ultimo giorno.
+        pub saldo_finale: Denaro,
+    }
+
+    impl Estratto {
+        /// Restituisce i movimenti dell'estratto in formato CSV, con intestazione
+        /// `sequenza,data,tipo,dettaglio,variazione,saldo,valuta`.
+        pub fn in_csv(&self) -> String {
+            let mut csv = String::from("sequenza,data,tipo,dettaglio,variazione,saldo,valuta\n");
+            for m in &self.movimenti {
+                csv.push_str(&format!(
+                    "{},{},{},{},{},{},{}\n",
+                    m.sequenza,
+                    m.data.format(FORMATO_DATA),
+                    m.tipo.nome(),
+                    m.tipo.dettaglio(),
+                    m.variazione.in_decimale(),
+                    m.saldo.in_decimale(),
+                    m.saldo.valuta()
+                ));
+            }
+            csv
+        }
+    }
+
+    impl fmt::Display for Estratto {
+        /// Implementazione della formattazione per visualizzare l'estratto conto.
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            writeln!(f, "Estratto conto di {} dal {} al {}", self.intestatario, self.dal, self.al)?;
+            writeln!(f, "Saldo iniziale: {}", self.saldo_iniziale)?;
+            for m in &self.movimenti {
+                let segno = if m.variazione.is_negativo() { "" } else { "+" };
+                write!(f, "{:>5} {} {:<12}", m.sequenza, m.data.format(FORMATO_DATA), m.tipo.nome())?;
+                match m.tipo {
+                    TipoMovimento::CambioStato { .. } | TipoMovimento::Avviso { .. } => writeln!(f, " {}", m.tipo.dettaglio())?,
+                    _ => writeln!(f, " {:>14} {:>14}", format!("{}{}", segno, m.variazione.in_decimale()), m.saldo.to_string())?,
+                }
+            }
+            writeln!(f, "Saldo finale: {}", self.saldo_finale)
+        }
+    }
+}
@@ -1,21 +1,64 @@
 /// Modulo `stati` per la gestione degli stati dei conti bancari.
 pub mod stati {
+    use std::fmt;
+    use std::sync::Arc;
+    use chrono::NaiveDateTime;
+    use crate::conto_bancario::conto_bancario::{ErroreConto, Operazione};
+    use crate::denaro::denaro::{Arrotondamento, Denaro, Tasso};
+
+    /// Passaggio di un conto da uno stato all'altro, passato agli hook `on_exit` e `on_enter`.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Transizione {
+        /// Stato che il conto lascia.
+        pub da: Stato,
+        /// Stato in cui il conto entra.
+        pub a: Stato,
+        /// Saldo del conto al momento del passaggio.
+        pub saldo: Denaro,
+        /// Data del passaggio.
+        pub data: NaiveDateTime,
+    }
+
     /// Definisce il comportamento di uno stato del conto bancario.
-    pub trait State {
+    ///
+    /// Le operazioni non consentite nello stato restituiscono un errore e lasciano il saldo invariato.
+    /// Gli stati devono essere `Send + Sync` perché i conti possano essere condivisi tra thread.
+    pub trait State: Send + Sync {
         /// Deposita una certa quantità di denaro nel conto.
-        fn deposita(&self, saldo: &mut f64, amount: f64);
+        fn deposita(&self, saldo: &mut Denaro, amount: Denaro) -> Result<(), ErroreConto>;
 
         /// Preleva una certa quantità di denaro dal conto.
-        fn preleva(&self, saldo: &mut f64, amount: f64);
+        fn preleva(&self, saldo: &mut Denaro, amount: Denaro) -> Result<(), ErroreConto>;
+
+        /// Calcola e aggiunge gli interessi al saldo del conto, arrotondati all'unità minima.
+        fn paga_interessi(&self, saldo: &mut Denaro, interesse: Tasso, arrotondamento: Arrotondamento) -> Result<(), ErroreConto>;
 
-        /// Calcola e aggiunge gli interessi al saldo del conto.
-        fn paga_interessi(&self, saldo: &mut f64, interesse: f64);
+        /// Aggiunge al saldo interessi già calcolati (negativi per gli interessi passivi).
+        /// Per impostazione predefinita sono accreditati in ogni stato.
+        fn accredita_interessi(&self, saldo: &mut Denaro, importo: Denaro) -> Result<(), ErroreConto> {
+            *saldo = saldo.somma(importo)?;
+            Ok(())
+        }
+
+        /// Addebita una commissione della banca. Per impostazione predefinita è addebitata in ogni
+        /// stato, anche se il saldo diventa negativo.
+        fn addebita_commissione(&self, saldo: &mut Denaro, importo: Denaro) -> Result<(), ErroreConto> {
+            *saldo = saldo.sottrai(importo)?;
+            Ok(())
+        }
 
         /// Restituisce lo stato attuale del conto.
         fn stato(&self) -> Stato;
+
+        /// Chiamato quando il conto entra in questo stato. Per impostazione predefinita non fa nulla.
+        fn on_enter(&self, _transizione: &Transizione) {}
+
+        /// Chiamato quando il conto lascia questo stato. Per impostazione predefinita non fa nulla.
+        fn on_exit(&self, _transizione: &Transizione) {}
     }
 
     /// Enumerazione degli stati possibili di un conto bancario.
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
     pub enum Stato {
         /// Stato "Rosso", che indica un saldo inferiore al limite inferiore.
         Rosso,
@@ -25,19 +68,101 @@ pub mod stati {
 
         /// Stato "Oro", che indica un saldo superiore al limite superiore.
         Oro,
+
+        /// Stato definito da una configurazione di fasce (vedi il modulo `fasce`), identificato dal nome.
+        Personalizzato(Arc<str>),
+    }
+
+    impl Stato {
+        /// Restituisce lo stato corrispondente al saldo indicato.
+        ///
+        /// # Argomenti
+        ///
+        /// * `saldo` - Saldo del conto.
+        /// * `limite_inferiore` - Sotto questo saldo il conto è in stato Rosso.
+        /// * `limite_superiore` - Da questo saldo in su il conto è in stato Oro.
+        pub fn per_saldo(saldo: Denaro, limite_inferiore: Denaro, limite_superiore: Denaro) -> Stato {
+            if saldo < limite_inferiore {
+                Stato::Rosso
+            } else if saldo < limite_superiore {
+                Stato::Argento
+            } else {
+                Stato::Oro
+            }
+        }
+
+        /// Restituisce lo stato con il nome indicato: uno degli stati predefiniti se il nome coincide,
+        /// altrimenti uno stato personalizzato.
+        ///
+        /// # Argomenti
+        ///
+        /// * `nome` - Nome dello stato.
+        pub fn da_nome(nome: &str) -> Stato {
+            match nome {
+                "Rosso" => Stato::Rosso,
+                "Argento" => Stato::Argento,
+                "Oro" => Stato::Oro,
+                _ => Stato::Personalizzato(Arc::from(nome)),
+            }
+        }
+
+        /// Restituisce il nome dello stato.
+        pub fn nome(&self) -> &str {
+            match self {
+                Stato::Rosso => "Rosso",
+                Stato::Argento => "Argento",
+                Stato::Oro => "Oro",
+                Stato::Personalizzato(nome) => nome,
+            }
+        }
+
+        /// Restituisce l'implementazione di `State` predefinita corrispondente.
+        ///
+        /// Gli stati personalizzati hanno il comportamento definito dalla loro configurazione
+        /// (vedi `Fasce::comportamento`); qui ricevono uno stato che rifiuta ogni operazione.
+        pub fn comportamento(&self) -> Box<dyn State> {
+            match self {
+                Stato::Rosso => Box::new(StatoRosso),
+                Stato::Argento => Box::new(StatoArgento),
+                Stato::Oro => Box::new(StatoOro),
+                Stato::Personalizzato(_) => Box::new(StatoNonConfigurato(self.clone())),
+            }
+        }
+    }
+
+    impl fmt::Display for Stato {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", self.nome())
+        }
+    }
+
+    /// Preleva dal saldo solo se resta non negativo.
+    pub(crate) fn preleva_se_coperto(saldo: &mut Denaro, amount: Denaro) -> Result<(), ErroreConto> {
+        let residuo = saldo.sottrai(amount)?;
+        if !residuo.is_negativo() {
+            *saldo = residuo;
+            Ok(())
+        } else {
+            Err(ErroreConto::FondiInsufficienti { saldo: *saldo, richiesto: amount })
+        }
     }
 
     /// Implementazione dello stato "Rosso" del conto bancario.
     pub struct StatoRosso;
 
     impl State for StatoRosso {
-        fn deposita(&self, saldo: &mut f64, amount: f64) {
-            *saldo += amount;
+        fn deposita(&self, saldo: &mut Denaro, amount: Denaro) -> Result<(), ErroreConto> {
+            *saldo = saldo.somma(amount)?;
+            Ok(())
         }
 
-        fn preleva(&self, _: &mut f64, _: f64) {}
+        fn preleva(&self, _: &mut Denaro, _: Denaro) -> Result<(), ErroreConto> {
+            Err(ErroreConto::OperazioneNonConsentita { stato: Stato::Rosso, operazione: Operazione::Prelievo })
+        }
 
-        fn paga_interessi(&self, _: &mut f64, _: f64) {}
+        fn paga_interessi(&self, _: &mut Denaro, _: Tasso, _: Arrotondamento) -> Result<(), ErroreConto> {
+            Ok(())
+        }
 
         fn stato(&self) -> Stato {
             Stato::Rosso
@@ -48,17 +173,18 @@ pub mod stati {
     pub struct StatoArgento;
 
     impl State for StatoArgento {
-        fn deposita(&self, saldo: &mut f64, amount: f64) {
-            *saldo += amount;
+        fn deposita(&self, saldo: &mut Denaro, amount: Denaro) -> Result<(), ErroreConto> {
+            *saldo = saldo.somma(amount)?;
+            Ok(())
         }
 
-        fn preleva(&self, saldo: &mut f64, amount: f64) {
-            if *saldo - amount >= 0.0 {
-                *saldo -= amount;
-            }
+        fn preleva(&self, saldo: &mut Denaro, amount: Denaro) -> Result<(), ErroreConto> {
+            preleva_se_coperto(saldo, amount)
         }
 
-        fn paga_interessi(&self, _: &mut f64, _: f64) {}
+        fn paga_interessi(&self, _: &mut Denaro, _: Tasso, _: Arrotondamento) -> Result<(), ErroreConto> {
+            Ok(())
+        }
 
         fn stato(&self) -> Stato {
             Stato::Argento
@@ -69,22 +195,43 @@ pub mod stati {
     pub struct StatoOro;
 
     impl State for StatoOro {
-        fn deposita(&self, saldo: &mut f64, amount: f64) {
-            *saldo += amount;
+        fn deposita(&self, saldo: &mut Denaro, amount: Denaro) -> Result<(), ErroreConto> {
+            *saldo = saldo.somma(amount)?;
+            Ok(())
         }
 
-        fn preleva(&self, saldo: &mut f64, amount: f64) {
-            if *saldo - amount >= 0.0 {
-                *saldo -= amount;
-            }
+        fn preleva(&self, saldo: &mut Denaro, amount: Denaro) -> Result<(), ErroreConto> {
+            preleva_se_coperto(saldo, amount)
         }
 
-        fn paga_interessi(&self, saldo: &mut f64, interesse: f64) {
-            *saldo += *saldo * interesse;
+        fn paga_interessi(&self, saldo: &mut Denaro, interesse: Tasso, arrotondamento: Arrotondamento) -> Result<(), ErroreConto> {
+            *saldo = saldo.somma(saldo.moltiplica(interesse, arrotondamento))?;
+            Ok(())
         }
 
         fn stato(&self) -> Stato {
             Stato::Oro
         }
     }
+
+    /// Stato personalizzato di cui non è disponibile la configurazione: rifiuta ogni operazione.
+    pub struct StatoNonConfigurato(pub Stato);
+
+    impl State for StatoNonConfigurato {
+        fn deposita(&self, _: &mut Denaro, _: Denaro) -> Result<(), ErroreConto> {
+            Err(ErroreConto::OperazioneNonConsentita { stato: self.0.clone(), operazione: Operazione::Deposito })
+        }
+
+        fn preleva(&self, _: &mut Denaro, _: Denaro) -> Result<(), ErroreConto> {
+            Err(ErroreConto::OperazioneNonConsentita { stato: self.0.clone(), operazione: Operazione::Prelievo })
+        }
+
+        fn paga_interessi(&self, _: &mut Denaro, _: Tasso, _: Arrotondamento) -> Result<(), ErroreConto> {
+            Err(ErroreConto::OperazioneNonConsentita { stato: self.0.clone(), operazione: Operazione::Interessi })
+        }
+
+        fn stato(&self) -> Stato {
+            self.0.clone()
+        }
+    }
 }
@@ -0,0 +1,353 @@
+/// Modulo `titolari` per i titolari di un conto, i loro ruoli e i limiti di spesa.
+pub mod titolari {
+    use std::collections::HashMap;
+    use std::fmt;
+    use chrono::{NaiveDate, NaiveDateTime};
+    use crate::denaro::denaro::{Denaro, Valuta};
+
+    /// Ruolo di un titolare, che determina le operazioni consentite.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Ruolo {
+        /// Intestatario: può eseguire ogni operazione e gestire gli altri titolari.
+        Intestatario,
+        /// Cointestatario: può consultare il conto, depositare e prelevare.
+        Cointestatario,
+        /// Può solo consultare il conto.
+        SolaLettura,
+        /// Delegato: può consultare il conto, depositare e prelevare fino a un tetto complessivo.
+        Delegato {
+            /// Importo complessivo che il delegato può prelevare, nella valuta del conto.
+            tetto: Denaro,
+        },
+    }
+
+    impl Ruolo {
+        /// Indica se il ruolo consente l'operazione indicata.
+        pub fn consente(&self, permesso: Permesso) -> bool {
+            match self {
+                Ruolo::Intestatario => true,
+                Ruolo::Cointestatario | Ruolo::Delegato { .. } => permesso != Permesso::Gestione,
+                Ruolo::SolaLettura => permesso == Permesso::Consultazione,
+            }
+        }
+    }
+
+    impl fmt::Display for Ruolo {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Ruolo::Intestatario => write!(f, "intestatario"),
+                Ruolo::Cointestatario => write!(f, "cointestatario"),
+                Ruolo::SolaLettura => write!(f, "sola lettura"),
+                Ruolo::Delegato { tetto } => write!(f, "delegato fino a {}", tetto),
+            }
+        }
+    }
+
+    /// Tipo di operazione per cui si chiede l'autorizzazione.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Permesso {
+        /// Consultazione di saldo ed estratto conto.
+        Consultazione,
+        /// Deposito sul conto.
+        Deposito,
+        /// Prelievo o trasferimento dal conto.
+        Prelievo,
+        /// Aggiunta e rimozione dei titolari.
+        Gestione,
+    }
+
+    impl fmt::Display for Permesso {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Permesso::Consultazione => write!(f, "consultazione"),
+                Permesso::Deposito => write!(f, "deposito"),
+                Permesso::Prelievo => write!(f, "prelievo"),
+                Permesso::Gestione => write!(f, "gestione dei titolari"),
+            }
+        }
+    }
+
+    /// Errori di autorizzazione delle operazioni dei titolari.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum ErroreAutorizzazione {
+        /// Nessun titolare del conto ha il nome indicato.
+        TitolareSconosciuto(String),
+        /// Un titolare con lo stesso nome è già presente.
+        TitolareEsistente(String),
+        /// Il ruolo del titolare non consente l'operazione.
+        NonConsentito {
+            /// Nome del titolare.
+            titolare: String,
+            /// Ruolo del titolare.
+            ruolo: Ruolo,
+            /// Operazione richiesta.
+            permesso: Permesso,
+        },
+        /// Il prelievo porterebbe il delegato oltre il suo tetto complessivo.
+        TettoSuperato {
+            /// Nome del titolare.
+            titolare: String,
+            /// Tetto del delegato.
+            tetto: Denaro,
+            /// Importo già prelevato dal delegato.
+            speso: Denaro,
+            /// Importo richiesto.
+            richiesto: Denaro,
+        },
+        /// Il prelievo porterebbe il titolare oltre il suo limite giornaliero.
+        LimiteGiornaliero {
+            /// Nome del titolare.
+            titolare: String,
+            /// Limite giornaliero del titolare.
+            limite: Denaro,
+            /// Importo già prelevato dal titolare nello stesso giorno.
+            speso: Denaro,
+            /// Importo richiesto.
+            richiesto: Denaro,
+        },
+        /// Il conto deve avere almeno un intestatario.
+        UltimoIntestatario(String),
+        /// Il limite giornaliero o il tetto del titolare è negativo o non è nella valuta del conto.
+        LimiteNonValido {
+            /// Nome del titolare.
+            titolare: String,
+            /// Limite o tetto indicato.
+            limite: Denaro,
+            /// Valuta del conto.
+            valuta: Valuta,
+        },
+    }
+
+    impl fmt::Display for ErroreAutorizzazione {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                ErroreAutorizzazione::TitolareSconosciuto(nome) => write!(f, "{} non è titolare del conto", nome),
+                ErroreAutorizzazione::TitolareEsistente(nome) => write!(f, "{} è già titolare del conto", nome),
+                ErroreAutorizzazione::NonConsentito { titolare, ruolo, permesso } => {
+                    write!(f, "{} ({}) non è autorizzato: {}", titolare, ruolo, permesso)
+                }
+                ErroreAutorizzazione::TettoSuperato { titolare, tetto, speso, richiesto } => {
+                    write!(f, "{} supererebbe il tetto di {}: già prelevati {}, richiesti {}", titolare, tetto, speso, richiesto)
+                }
+                ErroreAutorizzazione::LimiteGiornaliero { titolare, limite, speso, richiesto } => {
+                    write!(f, "{} supererebbe il limite giornaliero di {}: già prelevati {}, richiesti {}", titolare, limite, speso, richiesto)
+                }
+                ErroreAutorizzazione::UltimoIntestatario(nome) => write!(f, "{} è l'ultimo intestatario del conto", nome),
+                ErroreAutorizzazione::LimiteNonValido { titolare, limite, valuta } => {
+                    write!(f, "limite di {} non valido: {} (serve un importo non negativo in {})", titolare, limite, valuta)
+                }
+            }
+        }
+    }
+
+    impl std::error::Error for ErroreAutorizzazione {}
+
+    /// Titolare di un conto.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Titolare {
+        /// Nome del titolare, unico tra i titolari del conto.
+        pub nome: String,
+        /// Ruolo del titolare.
+        pub ruolo: Ruolo,
+        /// Importo massimo che il titolare può prelevare in un giorno, se limitato.
+        pub limite_giornaliero: Option<Denaro>,
+    }
+
+    impl Titolare {
+        /// Crea un titolare senza limite giornaliero.
+        ///
+        /// # Argomenti
+        ///
+        /// * `nome` - Nome del titolare.
+        /// * `ruolo` - Ruolo del titolare.
+        pub fn nuovo(nome: &str, ruolo: Ruolo) -> Titolare {
+            Titolare { nome: nome.to_string(), ruolo, limite_giornaliero: None }
+        }
+
+        /// Imposta l'importo massimo che il titolare può prelevare in un giorno.
+        pub fn con_limite_giornaliero(mut self, limite: Denaro) -> Titolare {
+            self.limite_giornaliero = Some(limite);
+            self
+        }
+    }
+
+    /// Prelievi di un titolare: quelli del giorno dell'ultimo prelievo e quelli complessivi.
+    #[derive(Debug, Clone, Copy)]
+    struct Spesa {
+        giorno: NaiveDate,
+        del_giorno: Denaro,
+        totale: Denaro,
+    }
+
+    /// Titolari di un conto, con i prelievi eseguiti da ciascuno per controllarne i limiti.
+    ///
+    /// # Esempio
+    ///
+    /// ```
+    /// use chrono::NaiveDate;
+    /// use esercizio5::denaro::denaro::{Denaro, Valuta};
+    /// use esercizio5::titolari::titolari::{ErroreAutorizzazione, Permesso, Ruolo, Titolare, Titolari};
+    ///
+    /// let euro = |importo: &str| Denaro::da_decimale(importo, Valuta::EUR).unwrap();
+    /// let oggi = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap().and_hms_opt(9, 0, 0).unwrap();
+    /// let mut titolari = Titolari::nuovi("Mario Rossi", Valuta::EUR);
+    /// titolari.aggiungi("Mario Rossi", Titolare::nuovo("Luca", Ruolo::Delegato { tetto: euro("100") })).unwrap();
+    ///
+    /// assert!(titolari.autorizza("Luca", Permesso::Prelievo, euro("100"), oggi).is_ok());
+    /// assert!(matches!(titolari.autorizza("Luca", Permesso::Gestione, euro("0"), oggi), Err(ErroreAutorizzazione::NonConsentito { .. })));
+    /// assert!(matches!(titolari.autorizza("Luca", Permesso::Prelievo, euro("100.01"), oggi), Err(ErroreAutorizzazione::TettoSuperato { .. })));
+    /// ```
+    #[derive(Debug, Clone)]
+    pub struct Titolari {
+        valuta: Valuta,
+        elenco: Vec<Titolare>,
+        spese: HashMap<String, Spesa>,
+    }
+
+    impl Titolari {
+        /// Crea l'elenco dei titolari con il solo intestatario indicato.
+        ///
+        /// # Argomenti
+        ///
+        /// * `intestatario` - Nome dell'intestatario.
+        /// * `valuta` - Valuta del conto, in cui sono espressi limiti e tetti.
+        pub fn nuovi(intestatario: &str, valuta: Valuta) -> Titolari {
+            Titolari { valuta, elenco: vec![Titolare::nuovo(intestatario, Ruolo::Intestatario)], spese: HashMap::new() }
+        }
+
+        /// Restituisce i titolari, nell'ordine in cui sono stati aggiunti.
+        pub fn elenco(&self) -> &[Titolare] {
+            &self.elenco
+        }
+
+        /// Restituisce il titolare con il nome indicato, se presente.
+        pub fn titolare(&self, nome: &str) -> Option<&Titolare> {
+            self.elenco.iter().find(|titolare| titolare.nome == nome)
+        }
+
+        /// Ricostruisce i titolari con i prelievi già eseguiti, ad esempio letti da un archivio.
+        pub(crate) fn ripristinati(valuta: Valuta, elenco: Vec<Titolare>, spese: Vec<(String, NaiveDate, Denaro, Denaro)>) -> Titolari {
+            let spese = spese.into_iter().map(|(nome, giorno, del_giorno, totale)| (nome, Spesa { giorno, del_giorno, totale })).collect();
+            Titolari { valuta, elenco, spese }
+        }
+
+        /// Restituisce i prelievi di ogni titolare: giorno dell'ultimo prelievo, importo prelevato
+        /// in quel giorno e importo complessivo.
+        pub(crate) fn spese(&self) -> Vec<(&str, NaiveDate, Denaro, Denaro)> {
+            let mut spese: Vec<_> = self.spese.iter().map(|(nome, spesa)| (nome.as_str(), spesa.giorno, spesa.del_giorno, spesa.totale)).collect();
+            spese.sort_by_key(|(nome, ..)| *nome);
+            spese
+        }
+
+        /// Aggiunge un titolare, se chi lo chiede è un intestatario. Limite giornaliero e tetto
+        /// devono essere non negativi e nella valuta del conto.
+        ///
+        /// # Argomenti
+        ///
+        /// * `da` - Nome del titolare che chiede l'operazione.
+        /// * `titolare` - Il nuovo titolare.
+        pub fn aggiungi(&mut self, da: &str, titolare: Titolare) -> Result<(), ErroreAutorizzazione> {
+            self.verifica_ruolo(da, Permesso::Gestione)?;
+            if self.titolare(&titolare.nome).is_some() {
+                return Err(ErroreAutorizzazione::TitolareEsistente(titolare.nome));
+            }
+            let tetto = match titolare.ruolo {
+                Ruolo::Delegato { tetto } => Some(tetto),
+                _ => None,
+            };
+            for limite in titolare.limite_giornaliero.into_iter().chain(tetto) {
+                if limite.valuta() != self.valuta || limite.is_negativo() {
+                    return Err(ErroreAutorizzazione::LimiteNonValido { titolare: titolare.nome, limite, valuta: self.valuta });
+                }
+            }
+            self.elenco.push(titolare);
+            Ok(())
+        }
+
+        /// Rimuove un titolare e lo restituisce, se chi lo chiede è un intestatario; l'ultimo
+        /// intestatario non può essere rimosso.
+        ///
+        /// # Argomenti
+        ///
+        /// * `da` - Nome del titolare che chiede l'operazione.
+        /// * `nome` - Nome del titolare da rimuovere.
+        pub fn rimuovi(&mut self, da: &str, nome: &str) -> Result<Titolare, ErroreAutorizzazione> {
+            self.verifica_ruolo(da, Permesso::Gestione)?;
+            let posizione = self
+                .elenco
+                .iter()
+                .position(|titolare| titolare.nome == nome)
+                .ok_or_else(|| ErroreAutorizzazione::TitolareSconosciuto(nome.to_string()))?;
+            let intestatari = self.elenco.iter().filter(|titolare| titolare.ruolo == Ruolo::Intestatario).count();
+            if self.elenco[posizione].ruolo == Ruolo::Intestatario && intestatari == 1 {
+                return Err(ErroreAutorizzazione::UltimoIntestatario(nome.to_string()));
+            }
+            self.spese.remove(nome);
+            Ok(self.elenco.remove(posizione))
+        }
+
+        /// Restituisce l'importo prelevato dal titolare nel giorno indicato.
+        pub fn speso_il(&self, nome: &str, giorno: NaiveDate) -> Denaro {
+            match self.spese.get(nome) {
+                Some(spesa) if spesa.giorno == giorno => spesa.del_giorno,
+                _ => Denaro::zero(self.valuta),
+            }
+        }
+
+        /// Restituisce l'importo complessivamente prelevato dal titolare.
+        pub fn speso(&self, nome: &str) -> Denaro {
+            self.spese.get(nome).map_or(Denaro::zero(self.valuta), |spesa| spesa.totale)
+        }
+
+        /// Controlla che il titolare possa eseguire l'operazione: che il suo ruolo la consenta e,
+        /// per i prelievi, che l'importo non superi il limite giornaliero né il tetto del delegato.
+        ///
+        /// # Argomenti
+        ///
+        /// * `nome` - Nome del titolare.
+        /// * `permesso` - Operazione richiesta.
+        /// * `importo` - Importo dell'operazione, nella valuta del conto.
+        /// * `data` - Data dell'operazione.
+        pub fn autorizza(&self, nome: &str, permesso: Permesso, importo: Denaro, data: NaiveDateTime) -> Result<(), ErroreAutorizzazione> {
+            let titolare = self.verifica_ruolo(nome, permesso)?;
+            if permesso != Permesso::Prelievo {
+                return Ok(());
+            }
+            if let Some(limite) = titolare.limite_giornaliero {
+                let speso = self.speso_il(nome, data.date());
+                if speso + importo > limite {
+                    return Err(ErroreAutorizzazione::LimiteGiornaliero { titolare: titolare.nome.clone(), limite, speso, richiesto: importo });
+                }
+            }
+            if let Ruolo::Delegato { tetto } = titolare.ruolo {
+                let speso = self.speso(nome);
+                if speso + importo > tetto {
+                    return Err(ErroreAutorizzazione::TettoSuperato { titolare: titolare.nome.clone(), tetto, speso, richiesto: importo });
+                }
+            }
+            Ok(())
+        }
+
+        /// Registra un prelievo eseguito dal titolare, per i controlli dei limiti successivi.
+        pub(crate) fn registra_prelievo(&mut self, nome: &str, importo: Denaro, data: NaiveDateTime) {
+            let zero = Denaro::zero(self.valuta);
+            let spesa = self.spese.entry(nome.to_string()).or_insert(Spesa { giorno: data.date(), del_giorno: zero, totale: zero });
+            if spesa.giorno != data.date() {
+                spesa.giorno = data.date();
+                spesa.del_giorno = zero;
+            }
+            spesa.del_giorno = spesa.del_giorno + importo;
+            spesa.totale = spesa.totale + importo;
+        }
+
+        /// Restituisce il titolare se esiste e il suo ruolo consente l'operazione.
+        fn verifica_ruolo(&self, nome: &str, permesso: Permesso) -> Result<&Titolare, ErroreAutorizzazione> {
+            let titolare = self.titolare(nome).ok_or_else(|| ErroreAutorizzazione::TitolareSconosciuto(nome.to_string()))?;
+            if titolare.ruolo.consente(permesso) {
+                Ok(titolare)
+            } else {
+                Err(ErroreAutorizzazione::NonConsentito { titolare: nome.to_string(), ruolo: titolare.ruolo, permesso })
+            }
+        }
+    }
+}
@@ -0,0 +1,25 @@
+// Ogni file di test usa solo alcune di queste funzioni
+#![allow(dead_code)]
+
+use chrono::NaiveDate;
+use esercizio5::denaro::denaro::{Denaro, Tasso, Valuta};
+
+/// Importo in euro dal numero decimale indicato.
+pub fn eur(importo: &str) -> Denaro {
+    Denaro::da_decimale(importo, Valuta::EUR).unwrap()
+}
+
+/// Importo in dollari dal numero decimale indicato.
+pub fn usd(importo: &str) -> Denaro {
+    Denaro::da_decimale(importo, Valuta::USD).unwrap()
+}
+
+/// Tasso dal numero decimale indicato.
+pub fn tasso(valore: &str) -> Tasso {
+    Tasso::da_decimale(valore).unwrap()
+}
+
+/// Data dall'anno, mese e giorno indicati.
+pub fn giorno(a: i32, m: u32, g: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(a, m, g).unwrap()
+}
@@ -0,0 +1,164 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use chrono::{Duration, NaiveDate, NaiveDateTime};
+    use esercizio5::antifrode::antifrode::{Antifrode, Azione, Oscillazione, PrelievoAnomalo, Regola, Richiesta, Velocita};
+    use esercizio5::banca::banca::{Banca, ErroreBanca};
+    use esercizio5::conto_bancario::conto_bancario::{ContoBancario, ErroreConto, Operazione};
+    use esercizio5::denaro::denaro::{Tasso, Valuta};
+    use esercizio5::stati::stati::Stato;
+    use crate::common::eur;
+
+    /// Ora e minuti del primo gennaio 2024.
+    fn alle(ora: u32, minuti: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 1, 1).unwrap().and_hms_opt(ora, minuti, 0).unwrap()
+    }
+
+    /// Conto Argento tra 0 e 500 con le regole indicate.
+    fn sorvegliato(saldo: &str, antifrode: Antifrode) -> ContoBancario {
+        let mut conto = ContoBancario::new("Mario Rossi".to_string(), eur(saldo), eur("0"), eur("500"), Tasso::ZERO);
+        conto.imposta_antifrode(Arc::new(antifrode));
+        conto
+    }
+
+    fn regole(conto: &ContoBancario) -> Vec<(&str, Azione)> {
+        conto.segnalazioni().iter().map(|s| (s.regola.as_str(), s.azione)).collect()
+    }
+
+    #[test]
+    fn test_velocita_segnala_senza_rifiutare() {
+        let velocita = Velocita { massimo: 3, finestra: Duration::hours(1) };
+        let mut conto = sorvegliato("100", Antifrode::nuovo().con_regola(velocita, Azione::Segnala));
+        conto.deposita_il(eur("10"), alle(9, 0)).unwrap();
+        conto.preleva_il(eur("10"), alle(9, 10)).unwrap();
+        conto.deposita_il(eur("10"), alle(9, 20)).unwrap();
+        assert!(conto.segnalazioni().is_empty());
+
+        let ricevuta = conto.preleva_il(eur("10"), alle(9, 30)).unwrap();
+        assert_eq!(ricevuta.saldo, eur("100"));
+        assert_eq!(regole(&conto), vec![("velocita", Azione::Segnala)]);
+        let segnalazione = &conto.segnalazioni()[0];
+        assert_eq!(segnalazione.motivo, "3 operazioni nelle ultime 1 ore");
+        assert_eq!(segnalazione.richiesta, Richiesta { operazione: Operazione::Prelievo, importo: eur("10"), data: alle(9, 30) });
+
+        // Fuori dalla finestra le operazioni vecchie non contano più
+        conto.deposita_il(eur("10"), alle(10, 25)).unwrap();
+        assert_eq!(conto.segnalazioni().len(), 1);
+    }
+
+    #[test]
+    fn test_prelievo_anomalo_blocca_il_conto() {
+        let regola = PrelievoAnomalo { moltiplicatore: 5, storico_minimo: 3 };
+        let mut conto = sorvegliato("400", Antifrode::nuovo().con_regola(regola, Azione::Blocca));
+        // Senza storico sufficiente la regola non scatta
+        conto.preleva_il(eur("10"), alle(9, 0)).unwrap();
+        conto.preleva_il(eur("20"), alle(10, 0)).unwrap();
+        conto.preleva_il(eur("120"), alle(11, 0)).unwrap();
+        assert!(conto.segnalazioni().is_empty());
+
+        // Media 50: fino a 250 il prelievo è accettato, i depositi non vengono valutati
+        conto.deposita_il(eur("400"), alle(12, 0)).unwrap();
+        conto.preleva_il(eur("250"), alle(13, 0)).unwrap();
+        let saldo = conto.saldo();
+        let errore = conto.preleva_il(eur("500.01"), alle(14, 0)).unwrap_err();
+        match &errore {
+            ErroreConto::OperazioneSospetta(segnalazione) => {
+                assert_eq!(segnalazione.regola, "prelievo anomalo");
+                assert_eq!(segnalazione.motivo, "prelievo di 500.01 EUR oltre 5 volte la media di 100.00 EUR");
+            }
+            altro => panic!("errore inatteso: {:?}", altro),
+        }
+        assert!(errore.to_string().starts_with("operazione sospetta, conto bloccato: prelievo anomalo"));
+        assert!(conto.bloccato);
+        assert_eq!(conto.saldo(), saldo);
+        assert_eq!(conto.preleva_il(eur("1"), alle(15, 0)), Err(ErroreConto::ContoBloccato));
+
+        conto.sblocca();
+        conto.preleva_il(eur("1"), alle(15, 0)).unwrap();
+        assert_eq!(conto.segnalazioni().len(), 1);
+    }
+
+    #[test]
+    fn test_oscillazione_richiede_conferma() {
+        let oscillazione = Oscillazione { massimo: 2, finestra: Duration::hours(24) };
+        let mut conto = sorvegliato("450", Antifrode::nuovo().con_regola(oscillazione, Azione::RichiediConferma));
+        conto.deposita_il(eur("100"), alle(9, 0)).unwrap();
+        assert_eq!(conto.stato(), Stato::Oro);
+        conto.preleva_il(eur("100"), alle(10, 0)).unwrap();
+        assert_eq!(conto.stato(), Stato::Argento);
+
+        let errore = conto.deposita_il(eur("100"), alle(11, 0)).unwrap_err();
+        assert!(matches!(&errore, ErroreConto::ConfermaRichiesta(s) if s.motivo == "2 passaggi tra Argento e Oro nelle ultime 24 ore"));
+        assert_eq!(conto.saldo(), eur("450"));
+
+        conto.conferma_operazione();
+        assert_eq!(conto.deposita_il(eur("100"), alle(11, 0)).unwrap().stato, Stato::Oro);
+        // La conferma vale per una sola operazione
+        assert!(matches!(conto.preleva_il(eur("10"), alle(12, 0)), Err(ErroreConto::ConfermaRichiesta(_))));
+        assert!(!conto.bloccato);
+        assert_eq!(regole(&conto).len(), 3);
+    }
+
+    /// Regola personalizzata: nessun deposito oltre i 1000 euro.
+    struct DepositoGrande;
+
+    impl Regola for DepositoGrande {
+        fn nome(&self) -> &str {
+            "deposito grande"
+        }
+
+        fn valuta(&self, _conto: &ContoBancario, richiesta: &Richiesta) -> Option<String> {
+            (richiesta.operazione == Operazione::Deposito && richiesta.importo > eur("1000")).then(|| "oltre 1000 EUR".to_string())
+        }
+    }
+
+    #[test]
+    fn test_vale_l_azione_piu_grave() {
+        let antifrode = Antifrode::nuovo()
+            .con_regola(DepositoGrande, Azione::Segnala)
+            .con_regola(Velocita { massimo: 1, finestra: Duration::hours(1) }, Azione::RichiediConferma)
+            .con_regola(DepositoGrande, Azione::Blocca);
+        let mut conto = sorvegliato("100", antifrode);
+        conto.deposita_il(eur("10"), alle(9, 0)).unwrap();
+
+        conto.conferma_operazione();
+        let errore = conto.deposita_il(eur("2000"), alle(9, 30)).unwrap_err();
+        assert!(matches!(errore, ErroreConto::OperazioneSospetta(s) if s.regola == "deposito grande" && s.azione == Azione::Blocca));
+        assert!(conto.bloccato);
+        assert_eq!(
+            regole(&conto),
+            vec![("deposito grande", Azione::Segnala), ("velocita", Azione::RichiediConferma), ("deposito grande", Azione::Blocca)]
+        );
+
+        // Le operazioni della banca non passano dalle regole
+        conto.sblocca();
+        conto.paga_interessi_il(alle(9, 45)).unwrap();
+        conto.addebita_commissione_il(eur("1"), alle(9, 50)).unwrap();
+        assert_eq!(conto.segnalazioni().len(), 3);
+    }
+
+    #[test]
+    fn test_trasferimento_annullato_ripristina_l_antifrode() {
+        let ogni_operazione = Velocita { massimo: 0, finestra: Duration::hours(1) };
+        let mut banca = Banca::nuova(Valuta::EUR);
+        let mut mario = sorvegliato("100", Antifrode::nuovo().con_regola(ogni_operazione, Azione::RichiediConferma));
+        mario.conferma_operazione();
+        let mario = banca.apri(mario).unwrap();
+        let anna = banca.apri(sorvegliato("100", Antifrode::nuovo())).unwrap();
+
+        // Il prelievo confermato riesce ma il deposito no: la conferma non va persa e la segnalazione sparisce
+        banca.blocca(anna).unwrap();
+        assert_eq!(banca.trasferisci(mario, anna, eur("10")), Err(ErroreBanca::Conto { id: anna, errore: ErroreConto::ContoBloccato }));
+        assert!(banca.conto(mario).unwrap().segnalazioni().is_empty());
+        banca.sblocca(anna).unwrap();
+        assert_eq!(banca.trasferisci(mario, anna, eur("10")).unwrap().addebito.saldo, eur("90"));
+        assert_eq!(banca.conto(mario).unwrap().segnalazioni().len(), 1);
+
+        // Un blocco deciso sul prelievo stesso resta anche se il trasferimento fallisce
+        let luca = banca.apri(sorvegliato("100", Antifrode::nuovo().con_regola(ogni_operazione, Azione::Blocca))).unwrap();
+        assert!(matches!(banca.trasferisci(luca, anna, eur("10")), Err(ErroreBanca::Conto { errore: ErroreConto::OperazioneSospetta(_), .. })));
+        assert!(banca.conto(luca).unwrap().bloccato);
+    }
+}
@@ -0,0 +1,257 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+    use esercizio5::archivio::archivio::{BancaPersistente, ErroreArchivio, FILE_ISTANTANEA, FILE_LOG};
+    use esercizio5::banca::banca::IdConto;
+    use esercizio5::cambi::cambi::TabellaCambi;
+    use esercizio5::denaro::denaro::{Denaro, Tasso, Valuta};
+    use esercizio5::registro::registro::Registro;
+    use esercizio5::titolari::titolari::{Ruolo, Titolare};
+    use crate::common::{eur, tasso, usd};
+
+    /// Cartella vuota riservata al test indicato.
+    fn cartella(test: &str) -> PathBuf {
+        let cartella = std::env::temp_dir().join(format!("esercizio5-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&cartella);
+        cartella
+    }
+
+    /// Saldo, blocco e registro di ogni conto, per confrontare la banca prima e dopo il ripristino.
+    fn fotografia(banca: &BancaPersistente) -> Vec<(IdConto, Denaro, bool, Registro)> {
+        let banca = banca.banca();
+        banca.ids().map(|id| {
+            let conto = banca.conto(id).unwrap();
+            (id, conto.saldo(), conto.bloccato, conto.registro().clone())
+        }).collect()
+    }
+
+    /// Registri delle divise di ogni conto, in ordine di valuta.
+    fn fotografia_divise(banca: &BancaPersistente) -> Vec<(IdConto, Vec<Registro>)> {
+        let banca = banca.banca();
+        banca.ids().map(|id| {
+            let conto = banca.conto(id).unwrap();
+            (id, conto.divise().iter().map(|saldo| conto.registro_divisa(saldo.valuta()).unwrap().clone()).collect())
+        }).collect()
+    }
+
+    /// Apre due conti ed esegue alcune operazioni, tra cui una rifiutata.
+    fn popola(banca: &mut BancaPersistente) -> (IdConto, IdConto) {
+        let mario = banca.apri_conto("Mario\tRossi\\", eur("100"), eur("0"), eur("500"), Tasso::da_decimale("0.05").unwrap(), eur("200")).unwrap();
+        let anna = banca.apri_conto("Anna Bianchi", eur("50"), eur("10"), eur("500"), Tasso::ZERO, eur("0")).unwrap();
+        banca.deposita(mario, eur("450")).unwrap();
+        banca.trasferisci(mario, anna, eur("125.50")).unwrap();
+        banca.paga_interessi(mario).unwrap();
+        assert!(banca.preleva(anna, eur("1000")).is_err());
+        banca.preleva(mario, eur("500")).unwrap();
+        banca.blocca(anna).unwrap();
+        (mario, anna)
+    }
+
+    fn righe(percorso: &Path) -> usize {
+        fs::read_to_string(percorso).unwrap().lines().count()
+    }
+
+    #[test]
+    fn test_ripristino_dal_log() {
+        let cartella = cartella("log");
+        let mut banca = BancaPersistente::apri(&cartella, Valuta::EUR).unwrap().con_istantanea_ogni(0);
+        let (mario, _) = popola(&mut banca);
+        let prima = fotografia(&banca);
+        assert_eq!(banca.banca().conto(mario).unwrap().nome_cliente, "Mario\tRossi\\");
+        assert!(banca.banca().conto(mario).unwrap().saldo().is_negativo());
+        drop(banca);
+
+        let banca = BancaPersistente::apri(&cartella, Valuta::EUR).unwrap();
+        assert_eq!(fotografia(&banca), prima);
+        assert_eq!(banca.sequenza(), 8);
+        assert!(banca.banca().conservato());
+        assert_eq!(banca.banca().conto(mario).unwrap().nome_cliente, "Mario\tRossi\\");
+        assert!(matches!(BancaPersistente::apri(&cartella, Valuta::USD), Err(ErroreArchivio::ValutaDiversa { .. })));
+        fs::remove_dir_all(&cartella).unwrap();
+    }
+
+    #[test]
+    fn test_istantanee_periodiche() {
+        let cartella = cartella("istantanee");
+        let mut banca = BancaPersistente::apri(&cartella, Valuta::EUR).unwrap().con_istantanea_ogni(3);
+        let (mario, anna) = popola(&mut banca);
+        assert!(cartella.join(FILE_ISTANTANEA).exists());
+        assert!(righe(&cartella.join(FILE_LOG)) <= 3);
+        let prima = fotografia(&banca);
+        drop(banca);
+
+        let mut banca = BancaPersistente::apri(&cartella, Valuta::EUR).unwrap().con_istantanea_ogni(3);
+        assert_eq!(fotografia(&banca), prima);
+        banca.sblocca(anna).unwrap();
+        banca.trasferisci(anna, mario, eur("10")).unwrap();
+        banca.istantanea().unwrap();
+        assert_eq!(righe(&cartella.join(FILE_LOG)), 0);
+        let dopo = fotografia(&banca);
+        drop(banca);
+
+        let banca = BancaPersistente::apri(&cartella, Valuta::EUR).unwrap();
+        assert_eq!(fotografia(&banca), dopo);
+        assert_eq!(banca.sequenza(), 10);
+        assert!(banca.banca().conservato());
+        fs::remove_dir_all(&cartella).unwrap();
+    }
+
+    #[test]
+    fn test_log_troncato() {
+        let cartella = cartella("troncato");
+        let mut banca = BancaPersistente::apri(&cartella, Valuta::EUR).unwrap().con_istantanea_ogni(0);
+        let (mario, _) = popola(&mut banca);
+        let prima = fotografia(&banca);
+        banca.deposita(mario, eur("42")).unwrap();
+        drop(banca);
+
+        let log = cartella.join(FILE_LOG);
+        let completo = fs::read(&log).unwrap();
+        let inizio_ultima = completo[..completo.len() - 1].iter().rposition(|&b| b == b'\n').unwrap() + 1;
+        // Un'interruzione in qualsiasi punto dell'ultima scrittura perde solo l'ultima operazione
+        for lunghezza in inizio_ultima..completo.len() {
+            fs::write(&log, &completo[..lunghezza]).unwrap();
+            let banca = BancaPersistente::apri(&cartella, Valuta::EUR).unwrap();
+            assert_eq!(fotografia(&banca), prima, "log troncato a {} byte", lunghezza);
+            assert_eq!(fs::metadata(&log).unwrap().len(), inizio_ultima as u64);
+        }
+
+        // Dopo il ripristino il log riprende dalla parte valida
+        let mut banca = BancaPersistente::apri(&cartella, Valuta::EUR).unwrap();
+        banca.deposita(mario, eur("7")).unwrap();
+        let dopo = fotografia(&banca);
+        drop(banca);
+        assert_eq!(fotografia(&BancaPersistente::apri(&cartella, Valuta::EUR).unwrap()), dopo);
+        fs::remove_dir_all(&cartella).unwrap();
+    }
+
+    #[test]
+    fn test_righe_danneggiate() {
+        let cartella = cartella("danneggiato");
+        let mut banca = BancaPersistente::apri(&cartella, Valuta::EUR).unwrap().con_istantanea_ogni(0);
+        popola(&mut banca);
+        drop(banca);
+
+        let log = cartella.join(FILE_LOG);
+        let originale = fs::read_to_string(&log).unwrap();
+        fs::write(&log, originale.replacen("450.00 EUR", "950.00 EUR", 1)).unwrap();
+        assert_eq!(
+            BancaPersistente::apri(&cartella, Valuta::EUR).err(),
+            Some(ErroreArchivio::Corrotto { file: FILE_LOG.to_string(), riga: 3 })
+        );
+
+        // Un'istantanea danneggiata o troncata viene rifiutata
+        fs::write(&log, originale).unwrap();
+        BancaPersistente::apri(&cartella, Valuta::EUR).unwrap().istantanea().unwrap();
+        let istantanea = cartella.join(FILE_ISTANTANEA);
+        let testo = fs::read_to_string(&istantanea).unwrap();
+        fs::write(&istantanea, testo.replacen("Anna", "Anne", 1)).unwrap();
+        assert!(matches!(BancaPersistente::apri(&cartella, Valuta::EUR), Err(ErroreArchivio::Corrotto { .. })));
+        let senza_fine: String = testo.lines().take(testo.lines().count() - 1).map(|riga| format!("{}\n", riga)).collect();
+        fs::write(&istantanea, senza_fine).unwrap();
+        assert!(matches!(BancaPersistente::apri(&cartella, Valuta::EUR), Err(ErroreArchivio::Corrotto { .. })));
+        fs::remove_dir_all(&cartella).unwrap();
+    }
+
+    #[test]
+    fn test_divise() {
+        let cartella = cartella("divise");
+        let cambi = Arc::new(TabellaCambi::nuova().con_tasso(Valuta::EUR, Valuta::USD, tasso("1.25")));
+        let apri = || BancaPersistente::apri_con_cambi(&cartella, Valuta::EUR, cambi.clone()).unwrap();
+        let mut banca = apri().con_istantanea_ogni(0);
+        let (mario, anna) = popola(&mut banca);
+        banca.sblocca(anna).unwrap();
+        banca.deposita(mario, eur("200")).unwrap();
+        banca.apri_divisa(anna, Valuta::USD).unwrap();
+        let ricevuta = banca.trasferisci_con_cambio(mario, anna, eur("40"), Valuta::USD).unwrap();
+        assert_eq!(ricevuta.accredito.saldo, usd("50"));
+        banca.preleva(anna, usd("20")).unwrap();
+        banca.apri_divisa(mario, Valuta::USD).unwrap();
+        banca.trasferisci_con_cambio(anna, mario, usd("10"), Valuta::USD).unwrap();
+        banca.chiudi_conto(mario).unwrap();
+        let chiuso = |banca: &BancaPersistente| banca.banca().conto_chiuso(mario).map(|(conto, data)| (conto.saldo_in(Valuta::USD), conto.registro().clone(), data));
+        let prima = (fotografia(&banca), fotografia_divise(&banca), chiuso(&banca));
+        assert!(prima.2.is_some());
+        assert_eq!(prima.1[0].1[0].saldo_apertura(), usd("0"));
+        drop(banca);
+
+        // Le operazioni in divisa si ripetono dal log e sopravvivono a un'istantanea
+        let mut banca = apri();
+        assert_eq!((fotografia(&banca), fotografia_divise(&banca), chiuso(&banca)), prima);
+        assert_eq!(banca.banca().conto(anna).unwrap().saldo_in(Valuta::USD), Some(usd("20")));
+        banca.istantanea().unwrap();
+        drop(banca);
+        let banca = apri();
+        assert_eq!((fotografia(&banca), fotografia_divise(&banca), chiuso(&banca)), prima);
+        assert!(banca.banca().conservato());
+
+        // Senza tassi di cambio le divise non si possono ripristinare
+        assert!(BancaPersistente::apri(&cartella, Valuta::EUR).is_err());
+        fs::remove_dir_all(&cartella).unwrap();
+    }
+
+    #[test]
+    fn test_cambio_ripristinato_al_tasso_registrato() {
+        let cartella = cartella("cambio-registrato");
+        let cambi = Arc::new(TabellaCambi::nuova().con_tasso(Valuta::EUR, Valuta::USD, tasso("1.10")));
+        let mut banca = BancaPersistente::apri_con_cambi(&cartella, Valuta::EUR, cambi).unwrap().con_istantanea_ogni(0);
+        let (mario, anna) = popola(&mut banca);
+        banca.sblocca(anna).unwrap();
+        banca.deposita(mario, eur("300")).unwrap();
+        banca.apri_divisa(anna, Valuta::USD).unwrap();
+        banca.aggiungi_titolare(mario, "Mario\tRossi\\", Titolare::nuovo("Luca", Ruolo::Cointestatario)).unwrap();
+        banca.trasferisci_con_cambio(mario, anna, eur("100"), Valuta::USD).unwrap();
+        banca.trasferisci_con_cambio_come("Luca", mario, anna, eur("10"), Valuta::USD).unwrap();
+        let prima = (fotografia(&banca), fotografia_divise(&banca));
+        assert_eq!(banca.banca().conto(anna).unwrap().saldo_in(Valuta::USD), Some(usd("121")));
+        drop(banca);
+
+        // Il ripristino applica le conversioni registrate, non i tassi del nuovo fornitore
+        let cambi = Arc::new(TabellaCambi::nuova().con_tasso(Valuta::EUR, Valuta::USD, tasso("2")));
+        let banca = BancaPersistente::apri_con_cambi(&cartella, Valuta::EUR, cambi).unwrap();
+        assert_eq!((fotografia(&banca), fotografia_divise(&banca)), prima);
+        assert!(banca.banca().conservato());
+        fs::remove_dir_all(&cartella).unwrap();
+    }
+
+    #[test]
+    fn test_titolari() {
+        let cartella = cartella("titolari");
+        let mut banca = BancaPersistente::apri(&cartella, Valuta::EUR).unwrap().con_istantanea_ogni(0);
+        let mario = banca.apri_conto("Mario", eur("500"), eur("0"), eur("1000"), Tasso::ZERO, eur("0")).unwrap();
+        let anna = banca.apri_conto("Anna", eur("0"), eur("0"), eur("1000"), Tasso::ZERO, eur("0")).unwrap();
+        let luca = Titolare::nuovo("Luca\tB", Ruolo::Delegato { tetto: eur("200") }).con_limite_giornaliero(eur("150"));
+        banca.aggiungi_titolare(mario, "Mario", luca).unwrap();
+        banca.aggiungi_titolare(mario, "Mario", Titolare::nuovo("Giulia", Ruolo::SolaLettura)).unwrap();
+        banca.aggiungi_titolare(mario, "Mario", Titolare::nuovo("Sara", Ruolo::Cointestatario)).unwrap();
+        banca.rimuovi_titolare(mario, "Mario", "Sara").unwrap();
+        banca.preleva_come("Luca\tB", mario, eur("50")).unwrap();
+        banca.trasferisci_come("Luca\tB", mario, anna, eur("70")).unwrap();
+        banca.deposita_come("Luca\tB", mario, eur("5")).unwrap();
+        assert!(banca.deposita_come("Giulia", mario, eur("5")).is_err());
+        let titolari = |banca: &BancaPersistente| {
+            let conto = banca.banca().conto(mario).unwrap();
+            (conto.titolari().elenco().to_vec(), conto.titolari().speso("Luca\tB"), conto.saldo())
+        };
+        let prima = titolari(&banca);
+        assert_eq!(prima.0.len(), 3);
+        assert_eq!(prima.1, eur("120"));
+        drop(banca);
+
+        // Titolari e prelievi si ripetono dal log e sopravvivono a un'istantanea
+        let mut banca = BancaPersistente::apri(&cartella, Valuta::EUR).unwrap();
+        assert_eq!(titolari(&banca), prima);
+        banca.istantanea().unwrap();
+        drop(banca);
+        let mut banca = BancaPersistente::apri(&cartella, Valuta::EUR).unwrap();
+        assert_eq!(titolari(&banca), prima);
+        assert!(banca.preleva_come("Luca\tB", mario, eur("80.01")).is_err());
+        banca.preleva_come("Luca\tB", mario, eur("30")).unwrap();
+        assert!(banca.banca().conservato());
+        fs::remove_dir_all(&cartella).unwrap();
+    }
+}
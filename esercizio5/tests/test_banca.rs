@@ -0,0 +1,154 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use esercizio5::banca::banca::{Banca, ErroreBanca, IdConto};
+    use esercizio5::conto_bancario::conto_bancario::{ContoBancario, ErroreConto, Operazione};
+    use esercizio5::denaro::denaro::{Denaro, Tasso, Valuta};
+    use esercizio5::stati::stati::Stato;
+    use proptest::prelude::*;
+    use crate::common::eur;
+
+    fn conto(nome: &str, saldo: &str) -> ContoBancario {
+        ContoBancario::new(nome.to_string(), eur(saldo), eur("50"), eur("150"), Tasso::da_decimale("0.02").unwrap())
+    }
+
+    #[test]
+    fn test_trasferimento_aggiorna_stati() {
+        let mut banca = Banca::nuova(Valuta::EUR);
+        let mario = banca.apri(conto("Mario Rossi", "200")).unwrap();
+        let anna = banca.apri(conto("Anna Bianchi", "10")).unwrap();
+        assert_eq!((mario, anna), (IdConto(1), IdConto(2)));
+
+        let ricevuta = banca.trasferisci(mario, anna, eur("100")).unwrap();
+        assert_eq!(ricevuta.addebito.operazione, Operazione::Prelievo);
+        assert_eq!((ricevuta.addebito.stato_precedente, ricevuta.addebito.stato), (Stato::Oro, Stato::Argento));
+        assert_eq!((ricevuta.accredito.stato_precedente, ricevuta.accredito.stato), (Stato::Rosso, Stato::Argento));
+        assert_eq!(banca.totale(), eur("210"));
+        assert!(banca.conservato());
+    }
+
+    #[test]
+    fn test_trasferimento_rifiutato_non_lascia_tracce() {
+        let mut banca = Banca::nuova(Valuta::EUR);
+        let mario = banca.apri(conto("Mario Rossi", "120")).unwrap();
+        let anna = banca.apri(conto("Anna Bianchi", "10")).unwrap();
+        let movimenti = banca.conto(mario).unwrap().registro().movimenti().len();
+
+        // Il prelievo riesce ma il deposito su un conto bloccato no: il prelievo viene annullato
+        banca.blocca(anna).unwrap();
+        assert_eq!(
+            banca.trasferisci(mario, anna, eur("100")),
+            Err(ErroreBanca::Conto { id: anna, errore: ErroreConto::ContoBloccato })
+        );
+        let origine = banca.conto(mario).unwrap();
+        assert_eq!(origine.saldo(), eur("120"));
+        assert_eq!(origine.stato(), Stato::Argento);
+        assert_eq!(origine.registro().movimenti().len(), movimenti);
+
+        // Un conto in rosso non può essere l'origine di un trasferimento
+        banca.sblocca(anna).unwrap();
+        assert_eq!(
+            banca.trasferisci(anna, mario, eur("5")),
+            Err(ErroreBanca::Conto {
+                id: anna,
+                errore: ErroreConto::OperazioneNonConsentita { stato: Stato::Rosso, operazione: Operazione::Prelievo },
+            })
+        );
+        assert_eq!(banca.trasferisci(mario, mario, eur("5")), Err(ErroreBanca::StessoConto(mario)));
+        assert_eq!(banca.trasferisci(mario, IdConto(9), eur("5")), Err(ErroreBanca::ContoInesistente(IdConto(9))));
+        assert_eq!(banca.conto(mario).unwrap().saldo(), eur("120"));
+        assert!(banca.conservato());
+    }
+
+    #[test]
+    fn test_flussi_esterni_e_chiusura() {
+        let mut banca = Banca::nuova(Valuta::EUR);
+        let mario = banca.apri(conto("Mario Rossi", "200")).unwrap();
+        let anna = banca.apri(conto("Anna Bianchi", "80")).unwrap();
+        banca.deposita(anna, eur("20")).unwrap();
+        banca.preleva(mario, eur("30")).unwrap();
+        assert_eq!(banca.paga_interessi(mario).unwrap().importo, eur("3.40"));
+        assert_eq!(banca.totale_atteso(), eur("273.40"));
+        assert!(banca.conservato());
+
+        let chiuso = banca.chiudi(mario).unwrap();
+        assert_eq!(chiuso.saldo(), eur("173.40"));
+        assert_eq!(banca.totale(), eur("100"));
+        assert!(banca.conservato());
+        assert_eq!(banca.numero_conti(), 1);
+        assert_eq!(banca.conto_chiuso(mario).unwrap().0.saldo(), eur("173.40"));
+        assert_eq!(banca.ids_chiusi().collect::<Vec<_>>(), vec![mario]);
+        assert!(banca.deposita(mario, eur("1")).is_err());
+
+        let dollari = ContoBancario::new("John Smith".to_string(), Denaro::zero(Valuta::USD), Denaro::zero(Valuta::USD), Denaro::zero(Valuta::USD), Tasso::ZERO);
+        assert!(matches!(banca.apri(dollari), Err(ErroreBanca::ValutaDiversa { .. })));
+    }
+
+    /// Operazione casuale su una banca con `CONTI` conti.
+    #[derive(Debug, Clone)]
+    enum Azione {
+        Deposita(u32, i64),
+        Preleva(u32, i64),
+        Trasferisci(u32, u32, i64),
+        Interessi(u32),
+        Blocca(u32),
+        Sblocca(u32),
+    }
+
+    const CONTI: u32 = 4;
+
+    fn azione() -> impl Strategy<Value = Azione> {
+        let id = 1..=CONTI;
+        let centesimi = 0i64..30_000;
+        prop_oneof![
+            1 => (id.clone(), centesimi.clone()).prop_map(|(i, c)| Azione::Deposita(i, c)),
+            1 => (id.clone(), centesimi.clone()).prop_map(|(i, c)| Azione::Preleva(i, c)),
+            4 => (id.clone(), 1..=CONTI + 1, centesimi).prop_map(|(i, j, c)| Azione::Trasferisci(i, j, c)),
+            1 => id.clone().prop_map(Azione::Interessi),
+            1 => id.clone().prop_map(Azione::Blocca),
+            1 => id.prop_map(Azione::Sblocca),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn prop_denaro_conservato(saldi in prop::collection::vec(0i64..30_000, CONTI as usize),
+                                  azioni in prop::collection::vec(azione(), 1..60)) {
+            let mut banca = Banca::nuova(Valuta::EUR);
+            for (i, saldo) in saldi.iter().enumerate() {
+                let saldo = Denaro::da_unita_minime(*saldo, Valuta::EUR);
+                banca.apri(ContoBancario::new(format!("Cliente {}", i), saldo, eur("50"), eur("150"), Tasso::da_decimale("0.01").unwrap())).unwrap();
+            }
+
+            for azione in azioni {
+                let saldi_prima: Vec<Denaro> = banca.ids().map(|id| banca.conto(id).unwrap().saldo()).collect();
+                let totale_prima = banca.totale();
+                match azione {
+                    Azione::Deposita(i, c) => { let _ = banca.deposita(IdConto(i), Denaro::da_unita_minime(c, Valuta::EUR)); }
+                    Azione::Preleva(i, c) => { let _ = banca.preleva(IdConto(i), Denaro::da_unita_minime(c, Valuta::EUR)); }
+                    Azione::Interessi(i) => { let _ = banca.paga_interessi(IdConto(i)); }
+                    Azione::Blocca(i) => banca.blocca(IdConto(i)).unwrap(),
+                    Azione::Sblocca(i) => banca.sblocca(IdConto(i)).unwrap(),
+                    Azione::Trasferisci(i, j, c) => {
+                        let esito = banca.trasferisci(IdConto(i), IdConto(j), Denaro::da_unita_minime(c, Valuta::EUR));
+                        // Un trasferimento non cambia mai il totale; se fallisce non cambia nessun saldo
+                        prop_assert_eq!(banca.totale(), totale_prima);
+                        if esito.is_err() {
+                            let saldi_dopo: Vec<Denaro> = banca.ids().map(|id| banca.conto(id).unwrap().saldo()).collect();
+                            prop_assert_eq!(saldi_dopo, saldi_prima);
+                        }
+                    }
+                }
+                prop_assert!(banca.conservato());
+                for id in banca.ids() {
+                    let conto = banca.conto(id).unwrap();
+                    prop_assert!(!conto.saldo().is_negativo());
+                    prop_assert_eq!(conto.registro().saldo(), conto.saldo());
+                    prop_assert!(conto.registro().coerente());
+                    prop_assert_eq!(conto.stato(), Stato::per_saldo(conto.saldo(), conto.limite_inferiore, conto.limite_superiore));
+                }
+            }
+        }
+    }
+}
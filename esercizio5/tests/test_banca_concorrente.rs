@@ -0,0 +1,165 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::thread;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use esercizio5::banca::banca::{ErroreBanca, IdConto};
+    use esercizio5::banca_concorrente::banca_concorrente::BancaConcorrente;
+    use esercizio5::cambi::cambi::TabellaCambi;
+    use esercizio5::conto_bancario::conto_bancario::{ContoBancario, ErroreConto};
+    use esercizio5::denaro::denaro::{Denaro, Tasso, Valuta};
+    use esercizio5::stati::stati::Stato;
+    use esercizio5::titolari::titolari::{ErroreAutorizzazione, Ruolo, Titolare};
+    use crate::common::{eur, tasso, usd};
+
+    fn banca_con_conti(numero: usize, saldo: &str) -> (Arc<BancaConcorrente>, Vec<IdConto>) {
+        let banca = Arc::new(BancaConcorrente::nuova(Valuta::EUR));
+        let ids = (0..numero)
+            .map(|i| {
+                let conto = ContoBancario::new(format!("Cliente {}", i), eur(saldo), eur("50"), eur("150"), Tasso::da_decimale("0.001").unwrap());
+                banca.apri(conto).unwrap()
+            })
+            .collect();
+        (banca, ids)
+    }
+
+    #[test]
+    fn test_banca_condivisibile_tra_thread() {
+        fn richiede_send_sync<T: Send + Sync>() {}
+        richiede_send_sync::<BancaConcorrente>();
+        richiede_send_sync::<ContoBancario>();
+    }
+
+    #[test]
+    fn test_trasferimenti_incrociati_senza_stalli() {
+        let (banca, ids) = banca_con_conti(2, "1000");
+        let (a, b) = (ids[0], ids[1]);
+        let thread: Vec<_> = (0..8)
+            .map(|t| {
+                let banca = Arc::clone(&banca);
+                // Metà dei thread trasferisce da a verso b, l'altra metà in senso opposto
+                let (da, verso) = if t % 2 == 0 { (a, b) } else { (b, a) };
+                thread::spawn(move || {
+                    for _ in 0..500 {
+                        let _ = banca.trasferisci(da, verso, eur("0.50"));
+                    }
+                })
+            })
+            .collect();
+        for t in thread {
+            t.join().unwrap();
+        }
+        assert_eq!(banca.totale(), eur("2000"));
+        assert!(banca.conservato());
+    }
+
+    #[test]
+    fn test_trasferimento_con_cambio() {
+        let cambi = Arc::new(TabellaCambi::nuova().con_tasso(Valuta::EUR, Valuta::USD, tasso("1.25")));
+        let banca = BancaConcorrente::nuova(Valuta::EUR);
+        let conto = |nome: &str| {
+            let mut conto = ContoBancario::new(nome.to_string(), eur("100"), eur("0"), eur("1000"), Tasso::ZERO);
+            conto.imposta_cambi(cambi.clone());
+            banca.apri(conto).unwrap()
+        };
+        let (mario, anna) = (conto("Mario"), conto("Anna"));
+        banca.apri_divisa(anna, Valuta::USD).unwrap();
+
+        let ricevuta = banca.trasferisci_con_cambio(mario, anna, eur("40"), Valuta::USD).unwrap();
+        assert_eq!((ricevuta.addebito.saldo, ricevuta.accredito.saldo), (eur("60"), usd("50")));
+        let ricevuta = banca.trasferisci_con_cambio(anna, mario, usd("25"), Valuta::EUR).unwrap();
+        assert_eq!((ricevuta.addebito.saldo, ricevuta.accredito.saldo), (usd("25"), eur("80")));
+        banca.deposita(anna, usd("5")).unwrap();
+        assert_eq!(banca.con_conto(anna, |conto| conto.saldo_in(Valuta::USD)).unwrap(), Some(usd("30")));
+        // Il denaro convertito in dollari esce dal totale in euro, quello riconvertito vi rientra
+        assert_eq!(banca.totale(), eur("180"));
+        assert!(banca.conservato());
+        assert!(banca.trasferisci_con_cambio(mario, anna, eur("1"), Valuta::GBP).is_err());
+        assert!(banca.conservato());
+    }
+
+    #[test]
+    fn test_operazioni_dei_titolari() {
+        let cambi = Arc::new(TabellaCambi::nuova().con_tasso(Valuta::EUR, Valuta::USD, tasso("1.25")));
+        let banca = BancaConcorrente::nuova(Valuta::EUR);
+        let conto = |nome: &str| {
+            let mut conto = ContoBancario::new(nome.to_string(), eur("500"), eur("0"), eur("1000"), Tasso::ZERO);
+            conto.imposta_cambi(cambi.clone());
+            banca.apri(conto).unwrap()
+        };
+        let (mario, anna) = (conto("Mario"), conto("Anna"));
+        banca.apri_divisa(anna, Valuta::USD).unwrap();
+        banca.aggiungi_titolare(mario, "Mario", Titolare::nuovo("Luca", Ruolo::Delegato { tetto: eur("100") })).unwrap();
+        banca.aggiungi_titolare(mario, "Mario", Titolare::nuovo("Giulia", Ruolo::SolaLettura)).unwrap();
+
+        banca.preleva_come("Luca", mario, eur("40")).unwrap();
+        banca.trasferisci_come("Luca", mario, anna, eur("30")).unwrap();
+        banca.trasferisci_con_cambio_come("Luca", mario, anna, eur("20"), Valuta::USD).unwrap();
+        let tetto_superato = |risultato: Result<_, ErroreBanca>| {
+            matches!(risultato, Err(ErroreBanca::Conto { errore: ErroreConto::Autorizzazione(ErroreAutorizzazione::TettoSuperato { .. }), .. }))
+        };
+        assert!(tetto_superato(banca.trasferisci_come("Luca", mario, anna, eur("10.01")).map(|_| ())));
+        assert!(tetto_superato(banca.trasferisci_con_cambio_come("Luca", mario, anna, eur("10.01"), Valuta::USD).map(|_| ())));
+        banca.deposita_come("Luca", mario, eur("10")).unwrap();
+        assert!(banca.deposita_come("Giulia", mario, eur("10")).is_err());
+        assert_eq!(banca.con_conto(mario, |conto| conto.titolari().speso("Luca")).unwrap(), eur("90"));
+
+        assert_eq!(banca.rimuovi_titolare(mario, "Mario", "Luca").unwrap().nome, "Luca");
+        assert!(banca.preleva_come("Luca", mario, eur("1")).is_err());
+        assert_eq!(banca.saldo(mario).unwrap(), eur("420"));
+        assert!(banca.conservato());
+    }
+
+    #[test]
+    fn test_stress_conservazione() {
+        let (banca, ids) = banca_con_conti(6, "120");
+        let operatori: Vec<_> = (0..8u64)
+            .map(|seme| {
+                let banca = Arc::clone(&banca);
+                let ids = ids.clone();
+                thread::spawn(move || {
+                    let mut rng = StdRng::seed_from_u64(seme);
+                    for _ in 0..2000 {
+                        let id = ids[rng.gen_range(0..ids.len())];
+                        let importo = Denaro::da_unita_minime(rng.gen_range(0..5000), Valuta::EUR);
+                        match rng.gen_range(0..10) {
+                            0 => drop(banca.deposita(id, importo)),
+                            1 => drop(banca.preleva(id, importo)),
+                            2 => drop(banca.paga_interessi(id)),
+                            _ => drop(banca.trasferisci(id, ids[rng.gen_range(0..ids.len())], importo)),
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        // Mentre gli operatori lavorano, ogni lettura dei totali deve essere coerente
+        let controllore = {
+            let banca = Arc::clone(&banca);
+            thread::spawn(move || {
+                for _ in 0..200 {
+                    assert!(banca.conservato());
+                }
+            })
+        };
+        for t in operatori {
+            t.join().unwrap();
+        }
+        controllore.join().unwrap();
+
+        assert!(banca.conservato());
+        for id in ids {
+            banca
+                .con_conto(id, |conto| {
+                    assert!(!conto.saldo().is_negativo());
+                    assert_eq!(conto.registro().saldo(), conto.saldo());
+                    assert!(conto.registro().coerente());
+                    assert_eq!(conto.stato(), Stato::per_saldo(conto.saldo(), conto.limite_inferiore, conto.limite_superiore));
+                })
+                .unwrap();
+        }
+    }
+}
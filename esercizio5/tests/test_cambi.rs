@@ -0,0 +1,185 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::sync::Arc;
+    use chrono::{NaiveDate, NaiveDateTime};
+    use esercizio5::banca::banca::{Banca, ErroreBanca};
+    use esercizio5::cambi::cambi::{CambiDaFile, ErroreCambi, FornitoreCambi, TabellaCambi};
+    use esercizio5::commissioni::commissioni::Commissioni;
+    use esercizio5::conto_bancario::conto_bancario::{ContoBancario, Conversione, ErroreConto};
+    use esercizio5::denaro::denaro::{Denaro, ErroreDenaro, Tasso, Valuta};
+    use esercizio5::registro::registro::TipoMovimento;
+    use esercizio5::stati::stati::Stato;
+    use crate::common::{eur, tasso, usd};
+
+    fn giorno(giorno: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 3, giorno).unwrap().and_hms_opt(12, 0, 0).unwrap()
+    }
+
+    fn cambi() -> Arc<TabellaCambi> {
+        Arc::new(TabellaCambi::nuova().con_tasso(Valuta::EUR, Valuta::USD, tasso("1.25")))
+    }
+
+    /// Conto in euro Argento tra 0 e 1000, con la divisa in dollari se richiesta.
+    fn conto(saldo: &str, dollari: bool) -> ContoBancario {
+        let mut conto = ContoBancario::new("Mario Rossi".to_string(), eur(saldo), eur("0"), eur("1000"), Tasso::ZERO);
+        conto.imposta_cambi(cambi());
+        if dollari {
+            conto.apri_divisa(Valuta::USD).unwrap();
+        }
+        conto
+    }
+
+    #[test]
+    fn test_tabella_e_file_dei_cambi() {
+        let tabella = TabellaCambi::leggi("EUR USD 1.25\n\n  # commento\nGBP EUR 1.17\n").unwrap();
+        assert_eq!(tabella.tasso(Valuta::USD, Valuta::EUR), Ok(tasso("0.8")));
+        assert_eq!(tabella.tasso(Valuta::GBP, Valuta::GBP), Ok(tasso("1")));
+        assert_eq!(tabella.converti(Denaro::da_decimale("10", Valuta::GBP).unwrap(), Valuta::EUR), Ok(eur("11.70")));
+        assert_eq!(tabella.tasso(Valuta::GBP, Valuta::USD), Err(ErroreCambi::TassoMancante { da: Valuta::GBP, a: Valuta::USD }));
+        assert!(matches!(TabellaCambi::leggi("EUR USD\n"), Err(ErroreCambi::Formato { riga: 1, .. })));
+        assert!(matches!(TabellaCambi::leggi("# cambi\nEUR XYZ 2\n"), Err(ErroreCambi::Formato { riga: 2, .. })));
+        assert!(matches!(TabellaCambi::leggi("EUR USD -1\n"), Err(ErroreCambi::Formato { riga: 1, .. })));
+
+        let percorso = std::env::temp_dir().join(format!("esercizio5-cambi-{}.txt", std::process::id()));
+        fs::write(&percorso, "EUR USD 1.25\n").unwrap();
+        let file = CambiDaFile::apri(&percorso).unwrap();
+        assert_eq!(file.converti(eur("10"), Valuta::USD), Ok(usd("12.50")));
+        // Un file non valido lascia in uso i tassi precedenti
+        fs::write(&percorso, "EUR USD uno\n").unwrap();
+        assert!(matches!(file.ricarica(), Err(ErroreCambi::Formato { riga: 1, .. })));
+        assert_eq!(file.converti(eur("10"), Valuta::USD), Ok(usd("12.50")));
+        fs::write(&percorso, "EUR USD 1.5\n").unwrap();
+        file.ricarica().unwrap();
+        assert_eq!(file.converti(eur("10"), Valuta::USD), Ok(usd("15.00")));
+        fs::remove_file(&percorso).unwrap();
+        assert!(matches!(CambiDaFile::apri(&percorso), Err(ErroreCambi::Io(_))));
+    }
+
+    #[test]
+    fn test_divise_e_stato_sul_totale() {
+        let mut senza_cambi = ContoBancario::new("Anna".to_string(), eur("10"), eur("0"), eur("100"), Tasso::ZERO);
+        assert_eq!(
+            senza_cambi.apri_divisa(Valuta::USD),
+            Err(ErroreConto::Cambio(ErroreCambi::TassoMancante { da: Valuta::USD, a: Valuta::EUR }))
+        );
+        assert!(conto("0", false).apri_divisa(Valuta::GBP).is_err());
+
+        let mut conto = conto("900", false);
+        assert_eq!(
+            conto.deposita_il(usd("10"), giorno(1)),
+            Err(ErroreConto::ImportoNonValido(ErroreDenaro::ValuteDiverse { attesa: Valuta::EUR, trovata: Valuta::USD }))
+        );
+        conto.apri_divisa(Valuta::USD).unwrap();
+        assert_eq!(conto.divise(), vec![usd("0")]);
+
+        // 900 euro più 250 dollari (200 euro) superano il limite dell'Oro
+        let ricevuta = conto.deposita_il(usd("250"), giorno(2)).unwrap();
+        assert_eq!((ricevuta.saldo_precedente, ricevuta.saldo, ricevuta.commissione), (usd("0"), usd("250"), usd("0")));
+        assert_eq!((ricevuta.stato_precedente, ricevuta.stato), (Stato::Argento, Stato::Oro));
+        assert_eq!(conto.saldo(), eur("900"));
+        assert_eq!(conto.saldo_in(Valuta::USD), Some(usd("250")));
+        assert_eq!(conto.saldo_totale(), Ok(eur("1100")));
+
+        conto.preleva_il(usd("200"), giorno(3)).unwrap();
+        assert_eq!(conto.stato(), Stato::Argento);
+        assert_eq!(conto.preleva_il(usd("60"), giorno(4)), Err(ErroreConto::FondiInsufficienti { saldo: usd("50"), richiesto: usd("60") }));
+
+        let tipi: Vec<TipoMovimento> = conto.registro_divisa(Valuta::USD).unwrap().movimenti().iter().map(|m| m.tipo.clone()).collect();
+        assert_eq!(tipi, vec![TipoMovimento::Deposito, TipoMovimento::Prelievo]);
+        let tipi: Vec<TipoMovimento> = conto.registro().movimenti().iter().map(|m| m.tipo.clone()).collect();
+        assert_eq!(
+            tipi,
+            vec![
+                TipoMovimento::CambioStato { da: Stato::Argento, a: Stato::Oro },
+                TipoMovimento::CambioStato { da: Stato::Oro, a: Stato::Argento },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_conversione_tra_saldi_del_conto() {
+        let mut conto = conto("800", true);
+        conto.commissioni = Commissioni::nuove().con_commissione_cambio(tasso("0.01"));
+        let conversione = conto.converti_il(eur("100"), Valuta::USD, giorno(1)).unwrap();
+        assert_eq!(conversione, Conversione { importo: eur("100"), commissione: eur("1"), tasso: tasso("1.25"), controvalore: usd("125") });
+        assert_eq!(conto.saldo(), eur("699"));
+        assert_eq!(conto.saldo_in(Valuta::USD), Some(usd("125")));
+        assert_eq!(conto.saldo_totale(), Ok(eur("799")));
+        let variazioni: Vec<(TipoMovimento, Denaro)> = conto.registro().movimenti().iter().map(|m| (m.tipo.clone(), m.variazione)).collect();
+        assert_eq!(variazioni, vec![(TipoMovimento::Prelievo, eur("-100")), (TipoMovimento::CommissioneCambio, eur("-1"))]);
+
+        // Importo e commissione devono essere coperti entrambi, altrimenti nulla cambia
+        assert_eq!(
+            conto.converti_il(usd("125"), Valuta::EUR, giorno(2)),
+            Err(ErroreConto::FondiInsufficienti { saldo: usd("125"), richiesto: usd("126.25") })
+        );
+        assert_eq!(conto.saldo_in(Valuta::USD), Some(usd("125")));
+        assert_eq!(conto.registro_divisa(Valuta::USD).unwrap().movimenti().len(), 1);
+        assert!(matches!(conto.converti_il(eur("10"), Valuta::GBP, giorno(2)), Err(ErroreConto::Cambio(_))));
+        assert_eq!(conto.saldo(), eur("699"));
+        assert_eq!(conto.registro().movimenti().len(), 2);
+    }
+
+    #[test]
+    fn test_trasferimenti_tra_valute() {
+        let mut banca = Banca::nuova(Valuta::EUR);
+        let mut mario = conto("900", false);
+        mario.commissioni = Commissioni::nuove().con_commissione_cambio(tasso("0.005"));
+        let mario = banca.apri(mario).unwrap();
+        let anna = banca.apri(conto("0", true)).unwrap();
+        let luca = banca.apri(conto("0", false)).unwrap();
+
+        let ricevuta = banca.trasferisci_con_cambio_il(mario, anna, eur("200"), Valuta::USD, giorno(1)).unwrap();
+        assert_eq!(ricevuta.conversione.commissione, eur("1"));
+        assert_eq!((ricevuta.addebito.saldo, ricevuta.addebito.commissione), (eur("699"), eur("1")));
+        assert_eq!(ricevuta.accredito.saldo, usd("250"));
+        assert_eq!(banca.conto(anna).unwrap().saldo_in(Valuta::USD), Some(usd("250")));
+        assert!(banca.conservato());
+        assert_eq!(banca.totale(), eur("699"));
+
+        // Senza la divisa di arrivo il trasferimento viene annullato
+        assert!(matches!(
+            banca.trasferisci_con_cambio_il(mario, luca, eur("100"), Valuta::USD, giorno(2)),
+            Err(ErroreBanca::Conto { id, errore: ErroreConto::ImportoNonValido(_) }) if id == luca
+        ));
+        assert_eq!(banca.conto(mario).unwrap().saldo(), eur("699"));
+        assert_eq!(banca.conto(mario).unwrap().registro().movimenti().len(), 2);
+
+        // Depositi e trasferimenti in dollari non toccano il totale in euro della banca
+        banca.deposita_il(anna, usd("50"), giorno(3)).unwrap();
+        banca.trasferisci_con_cambio_il(anna, luca, usd("100"), Valuta::EUR, giorno(4)).unwrap();
+        assert_eq!(banca.conto(anna).unwrap().saldo_in(Valuta::USD), Some(usd("200")));
+        assert_eq!(banca.conto(luca).unwrap().saldo(), eur("80"));
+        assert!(banca.conservato());
+        assert_eq!(banca.trasferisci_con_cambio_il(anna, anna, usd("1"), Valuta::EUR, giorno(5)), Err(ErroreBanca::StessoConto(anna)));
+    }
+
+    #[test]
+    fn test_tasso_mancante() {
+        let senza_cambi = ContoBancario::new("Anna".to_string(), eur("10"), eur("0"), eur("100"), Tasso::ZERO);
+        assert_eq!(
+            senza_cambi.preventivo_cambio(eur("5"), Valuta::USD),
+            Err(ErroreConto::Cambio(ErroreCambi::TassoMancante { da: Valuta::EUR, a: Valuta::USD }))
+        );
+
+        // Se il tasso di una divisa sparisce lo stato non è più calcolabile e le operazioni vengono rifiutate
+        let percorso = std::env::temp_dir().join(format!("esercizio5-cambi-mancanti-{}.txt", std::process::id()));
+        fs::write(&percorso, "EUR USD 1.25\n").unwrap();
+        let file = Arc::new(CambiDaFile::apri(&percorso).unwrap());
+        let mut conto = ContoBancario::new("Mario Rossi".to_string(), eur("900"), eur("0"), eur("1000"), Tasso::ZERO);
+        conto.imposta_cambi(file.clone());
+        conto.apri_divisa(Valuta::USD).unwrap();
+        conto.deposita_il(usd("100"), giorno(1)).unwrap();
+        fs::write(&percorso, "GBP EUR 1.17\n").unwrap();
+        file.ricarica().unwrap();
+        fs::remove_file(&percorso).unwrap();
+        let mancante = ErroreConto::Cambio(ErroreCambi::TassoMancante { da: Valuta::USD, a: Valuta::EUR });
+        assert_eq!(conto.deposita_il(eur("200"), giorno(2)), Err(mancante.clone()));
+        assert_eq!(conto.saldo_totale(), Err(mancante));
+        assert_eq!(conto.saldo(), eur("900"));
+        assert_eq!(conto.registro().movimenti().len(), 0);
+    }
+}
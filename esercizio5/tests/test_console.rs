@@ -0,0 +1,210 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::process::Command;
+    use std::sync::Arc;
+    use chrono::NaiveDate;
+    use esercizio5::banca::banca::IdConto;
+    use esercizio5::cambi::cambi::TabellaCambi;
+    use esercizio5::console::console::{Comando, Interprete};
+    use esercizio5::denaro::denaro::{Tasso, Valuta};
+    use esercizio5::stati::stati::Stato;
+    use esercizio5::titolari::titolari::{Ruolo, Titolare};
+    use crate::common::{eur, tasso, usd};
+
+    fn interpreta(riga: &str) -> Result<Option<Comando>, String> {
+        Comando::interpreta(riga, Valuta::EUR)
+    }
+
+    #[test]
+    fn test_interpretazione() {
+        assert_eq!(
+            interpreta("APRI \"Mario Rossi\" 100 0 500.50 0.03"),
+            Ok(Some(Comando::Apri {
+                nome: "Mario Rossi".to_string(),
+                saldo: eur("100"),
+                limite_inferiore: eur("0"),
+                limite_superiore: eur("500.50"),
+                interesse: Tasso::da_decimale("0.03").unwrap(),
+            }))
+        );
+        assert_eq!(
+            interpreta("  come Mario trasferisci #1 2 10.5 "),
+            Ok(Some(Comando::Trasferisci("Mario".to_string(), IdConto(1), IdConto(2), eur("10.5"))))
+        );
+        assert_eq!(
+            interpreta("estratto 3 2024-01-01"),
+            Ok(Some(Comando::Estratto(IdConto(3), NaiveDate::from_ymd_opt(2024, 1, 1), None)))
+        );
+        assert_eq!(interpreta("stato"), Ok(Some(Comando::Stato(None))));
+        assert_eq!(interpreta("divisa 1 usd"), Ok(Some(Comando::ApriDivisa(IdConto(1), Valuta::USD))));
+        assert_eq!(interpreta("come Mario deposita 1 10 USD"), Ok(Some(Comando::Deposita("Mario".to_string(), IdConto(1), usd("10")))));
+        assert_eq!(interpreta("# commento"), Ok(None));
+        assert_eq!(interpreta(""), Ok(None));
+
+        assert!(interpreta("come Mario deposita 1").unwrap_err().contains("argomenti non validi per deposita"));
+        assert!(interpreta("come Mario deposita uno 10").unwrap_err().contains("conto non valido"));
+        assert!(interpreta("come Mario preleva 1 10.001").unwrap_err().contains("importo non valido"));
+        assert!(interpreta("divisa 1 XYZ").unwrap_err().contains("valuta non valida"));
+        assert!(interpreta("come Mario cambia 1 2 10 EUR").unwrap_err().contains("argomenti non validi per cambia"));
+        // Le operazioni sul denaro indicano sempre il titolare che le esegue
+        for riga in ["deposita 1 10", "preleva 1 10", "trasferisci 1 2 10", "cambia 1 2 10 EUR USD"] {
+            assert!(interpreta(riga).unwrap_err().contains("va eseguito per conto di un titolare"), "{}", riga);
+        }
+        assert!(interpreta("apri \"Mario 1 0 1").unwrap_err().contains("virgolette"));
+        assert_eq!(interpreta("bonifico 1 2"), Err("comando sconosciuto: bonifico".to_string()));
+    }
+
+    #[test]
+    fn test_interpretazione_dei_titolari() {
+        assert_eq!(
+            interpreta("titolare 1 Mario \"Luca B\" delegato 300 50"),
+            Ok(Some(Comando::Titolare(
+                IdConto(1),
+                "Mario".to_string(),
+                Titolare::nuovo("Luca B", Ruolo::Delegato { tetto: eur("300") }).con_limite_giornaliero(eur("50"))
+            )))
+        );
+        assert_eq!(
+            interpreta("titolare 1 Mario Giulia lettura"),
+            Ok(Some(Comando::Titolare(IdConto(1), "Mario".to_string(), Titolare::nuovo("Giulia", Ruolo::SolaLettura))))
+        );
+        assert_eq!(interpreta("revoca 1 Mario Giulia"), Ok(Some(Comando::Revoca(IdConto(1), "Mario".to_string(), "Giulia".to_string()))));
+        assert_eq!(
+            interpreta("come Luca cambia 1 2 10 EUR USD"),
+            Ok(Some(Comando::Cambia("Luca".to_string(), IdConto(1), IdConto(2), eur("10"), Valuta::USD)))
+        );
+        assert!(interpreta("titolare 1 Mario Luca delegato").unwrap_err().contains("ruolo non valido"));
+        assert!(interpreta("titolare 1 Mario Luca capo").unwrap_err().contains("ruolo non valido"));
+        assert!(interpreta("come Luca interessi 1").unwrap_err().contains("interessi non si può eseguire"));
+        assert!(interpreta("come Luca").unwrap_err().contains("argomenti non validi per come"));
+    }
+
+    #[test]
+    fn test_sessione_con_titolari() {
+        let script = "\
+apri Mario 500 0 1000
+apri Anna 0 0 1000
+titolare 1 Mario Luca delegato 100
+titolare 1 Luca Piero cointestatario
+come Luca preleva 1 60
+come Luca trasferisci 1 2 50
+come Anna deposita 1 10
+revoca 1 Mario Luca
+come Luca preleva 1 1
+";
+        let mut interprete = Interprete::nuovo(Valuta::EUR);
+        let mut uscita = Vec::new();
+        let errori = interprete.sessione(script.as_bytes(), &mut uscita, false).unwrap();
+        let uscita = String::from_utf8(uscita).unwrap();
+        assert_eq!(errori, 4, "{}", uscita);
+        assert!(uscita.contains("Aggiunto il titolare Luca (delegato fino a 100.00 EUR) al conto #1\n"));
+        assert!(uscita.contains("> come Luca preleva 1 60\n#1 Mario: saldo 440.00 EUR, stato Argento\n"));
+        assert!(uscita.contains("Errore alla riga 6: conto #1: Luca supererebbe il tetto di 100.00 EUR"));
+        assert!(uscita.contains("Rimosso il titolare Luca (delegato fino a 100.00 EUR) dal conto #1\n"));
+        assert!(uscita.ends_with("Errore alla riga 9: conto #1: Luca non è titolare del conto\n"));
+        assert_eq!(interprete.banca().conto(IdConto(2)).unwrap().saldo(), eur("0"));
+    }
+
+    #[test]
+    fn test_sessione_con_script() {
+        let script = "\
+# due conti e qualche operazione
+apri Mario 100 0 500
+apri \"Anna Bianchi\" 20 10 1000
+come Mario deposita 1 450
+come Mario trasferisci 1 2 100
+come \"Anna Bianchi\" preleva 2 500
+stato
+esci
+come Mario deposita 1 1
+";
+        let mut interprete = Interprete::nuovo(Valuta::EUR);
+        let mut uscita = Vec::new();
+        let errori = interprete.sessione(script.as_bytes(), &mut uscita, false).unwrap();
+        assert_eq!(errori, 1);
+        assert_eq!(
+            String::from_utf8(uscita).unwrap(),
+            "\
+> apri Mario 100 0 500
+Aperto il conto #1 Mario: saldo 100.00 EUR, stato Argento
+> apri \"Anna Bianchi\" 20 10 1000
+Aperto il conto #2 Anna Bianchi: saldo 20.00 EUR, stato Argento
+> come Mario deposita 1 450
+#1 Mario: saldo 550.00 EUR, stato Oro (era Argento)
+> come Mario trasferisci 1 2 100
+#1 Mario: saldo 450.00 EUR, stato Argento (era Oro)
+#2 Anna Bianchi: saldo 120.00 EUR, stato Argento
+> come \"Anna Bianchi\" preleva 2 500
+Errore alla riga 6: conto #2: fondi insufficienti: saldo 120.00 EUR, richiesti 500.00 EUR
+> stato
+#1 Mario: saldo 450.00 EUR, stato Argento
+#2 Anna Bianchi: saldo 120.00 EUR, stato Argento
+> esci
+"
+        );
+        // Dopo esci il resto dello script non viene eseguito
+        assert_eq!(interprete.banca().conto(IdConto(1)).unwrap().saldo(), eur("450"));
+        assert_eq!(interprete.banca().conto(IdConto(1)).unwrap().stato(), Stato::Argento);
+    }
+
+    #[test]
+    fn test_sessione_con_divise() {
+        let cambi = Arc::new(TabellaCambi::nuova().con_tasso(Valuta::EUR, Valuta::USD, tasso("1.25")));
+        let mut interprete = Interprete::nuovo(Valuta::EUR).con_cambi(cambi);
+        let script = "apri Mario 100 0 500\napri Anna 50 0 500\ndivisa 2 USD\ncome Mario cambia 1 2 40 EUR USD\ncome Anna preleva 2 20 USD\nstato\n";
+        let mut uscita = Vec::new();
+        let errori = interprete.sessione(script.as_bytes(), &mut uscita, false).unwrap();
+        let uscita = String::from_utf8(uscita).unwrap();
+        assert_eq!(errori, 0, "{}", uscita);
+        assert!(uscita.contains("Cambio 40.00 EUR -> 50.00 USD, commissione 0.00 EUR\n"));
+        assert!(uscita.ends_with("#1 Mario: saldo 60.00 EUR, stato Argento\n#2 Anna: saldo 50.00 EUR, stato Argento, divise 30.00 USD\n"));
+
+        // Senza tassi di cambio la divisa non si può aprire
+        let mut interprete = Interprete::nuovo(Valuta::EUR);
+        let mut uscita = Vec::new();
+        assert_eq!(interprete.sessione("apri Mario 100 0 500\ndivisa 1 USD\n".as_bytes(), &mut uscita, false).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_sessione_interattiva() {
+        let mut interprete = Interprete::nuovo(Valuta::EUR);
+        let mut uscita = Vec::new();
+        let errori = interprete.sessione("stato\napri Mario 5 10 100\ncome Mario preleva 1 1\n".as_bytes(), &mut uscita, true).unwrap();
+        assert_eq!(errori, 1);
+        assert_eq!(
+            String::from_utf8(uscita).unwrap(),
+            "> Nessun conto aperto\n\
+             > Aperto il conto #1 Mario: saldo 5.00 EUR, stato Rosso\n\
+             > Errore: conto #1: operazione Prelievo non consentita nello stato Rosso\n\
+             > "
+        );
+    }
+
+    #[test]
+    fn test_binario_con_file_di_script() {
+        let percorso = std::env::temp_dir().join(format!("esercizio5-script-{}.txt", std::process::id()));
+        fs::write(&percorso, "apri Mario 100 0 500\ninteressi 1\nestratto 1\n").unwrap();
+        let esito = Command::new(env!("CARGO_BIN_EXE_esercizio5")).arg(&percorso).output().unwrap();
+        assert!(esito.status.success());
+        let testo = String::from_utf8(esito.stdout).unwrap();
+        assert!(testo.contains("Estratto conto di Mario"));
+        assert!(testo.ends_with("#1 Mario: saldo 100.00 EUR, stato Argento\n"));
+
+        fs::write(&percorso, "come Mario preleva 1 10\n").unwrap();
+        let esito = Command::new(env!("CARGO_BIN_EXE_esercizio5")).arg(&percorso).output().unwrap();
+        assert_eq!(esito.status.code(), Some(1));
+        assert!(String::from_utf8(esito.stdout).unwrap().contains("Errore alla riga 1: il conto #1 non esiste"));
+
+        let tassi = percorso.with_extension("cambi");
+        fs::write(&tassi, "EUR USD 1.25\n").unwrap();
+        fs::write(&percorso, "apri Mario 100 0 500\ndivisa 1 USD\ncome Mario deposita 1 10 USD\n").unwrap();
+        let esito = Command::new(env!("CARGO_BIN_EXE_esercizio5")).arg("--cambi").arg(&tassi).arg(&percorso).output().unwrap();
+        assert!(esito.status.success());
+        assert!(String::from_utf8(esito.stdout).unwrap().ends_with("#1 Mario: saldo 10.00 USD, stato Argento\n"));
+        fs::remove_file(&tassi).unwrap();
+        fs::remove_file(&percorso).unwrap();
+    }
+}
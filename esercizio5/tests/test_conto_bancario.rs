@@ -1,39 +1,107 @@
+mod common;
+
 #[cfg(test)]
 mod tests {
-    use esercizio5::conto_bancario::conto_bancario::ContoBancario;
+    use esercizio5::conto_bancario::conto_bancario::{ContoBancario, ErroreConto, Operazione};
+    use esercizio5::denaro::denaro::{Denaro, ErroreDenaro, Valuta};
     use esercizio5::stati::stati::*;
+    use crate::common::{eur, tasso};
 
     #[test]
     fn test_nuovo_conto_bancario_stato_rosso() {
-        let conto = ContoBancario::new("Mario Rossi".to_string(), 100.0, 50.0, 150.0, 0.05);
-        matches!(conto.stato.stato(), Stato::Rosso);
+        let conto = ContoBancario::new("Mario Rossi".to_string(), eur("100"), eur("50"), eur("150"), tasso("0.05"));
+        matches!(conto.stato(), Stato::Rosso);
     }
     #[test]
     fn test_nuovo_conto_bancario_stato_argento() {
-        let conto = ContoBancario::new("Giuseppe Verdi".to_string(), 80.0, 50.0, 150.0, 0.05);
-        matches!(conto.stato.stato(), Stato::Argento);
+        let conto = ContoBancario::new("Giuseppe Verdi".to_string(), eur("80"), eur("50"), eur("150"), tasso("0.05"));
+        matches!(conto.stato(), Stato::Argento);
     }
     #[test]
     fn test_nuovo_conto_bancario_stato_oro() {
-        let conto = ContoBancario::new("Alessandro Volta".to_string(), 200.0, 50.0, 150.0, 0.05);
-        matches!(conto.stato.stato(), Stato::Oro);
+        let conto = ContoBancario::new("Alessandro Volta".to_string(), eur("200"), eur("50"), eur("150"), tasso("0.05"));
+        matches!(conto.stato(), Stato::Oro);
     }
     #[test]
     fn test_deposito_conto_bancario() {
-        let mut conto = ContoBancario::new("Mario Rossi".to_string(), 100.0, 50.0, 150.0, 0.05);
-        conto.stato.deposita(&mut conto.saldo, 50.0);
-        assert_eq!(conto.saldo, 150.0);
+        let mut conto = ContoBancario::new("Mario Rossi".to_string(), eur("100"), eur("50"), eur("150"), tasso("0.05"));
+        conto.deposita(eur("50")).unwrap();
+        assert_eq!(conto.saldo(), eur("150"));
     }
     #[test]
     fn test_preleva_conto_bancario() {
-        let mut conto = ContoBancario::new("Giuseppe Verdi".to_string(), 100.0, 50.0, 150.0, 0.05);
-        conto.stato.preleva(&mut conto.saldo, 50.0);
-        assert_eq!(conto.saldo, 50.0);
+        let mut conto = ContoBancario::new("Giuseppe Verdi".to_string(), eur("100"), eur("50"), eur("150"), tasso("0.05"));
+        conto.preleva(eur("50")).unwrap();
+        assert_eq!(conto.saldo(), eur("50"));
     }
     #[test]
     fn test_paga_interessi_conto_bancario() {
-        let mut conto = ContoBancario::new("Alessandro Volta".to_string(), 200.0, 50.0, 150.0, 0.05);
-        conto.stato.paga_interessi(&mut conto.saldo, 0.05);
-        assert_eq!(conto.saldo, 210.0);
+        let mut conto = ContoBancario::new("Alessandro Volta".to_string(), eur("200"), eur("50"), eur("150"), tasso("0.05"));
+        conto.paga_interessi().unwrap();
+        assert_eq!(conto.saldo(), eur("210"));
+    }
+    #[test]
+    fn test_deposita_aggiorna_stato() {
+        let mut conto = ContoBancario::new("Mario Rossi".to_string(), eur("20"), eur("50"), eur("150"), tasso("0.05"));
+        let ricevuta = conto.deposita(eur("50")).unwrap();
+        assert_eq!(ricevuta.operazione, Operazione::Deposito);
+        assert_eq!(ricevuta.importo, eur("50"));
+        assert_eq!((ricevuta.saldo_precedente, ricevuta.saldo), (eur("20"), eur("70")));
+        assert_eq!((&ricevuta.stato_precedente, &ricevuta.stato), (&Stato::Rosso, &Stato::Argento));
+        assert!(ricevuta.cambio_stato());
+        assert_eq!(conto.stato(), Stato::Argento);
+        conto.deposita(eur("100")).unwrap();
+        assert_eq!(conto.stato(), Stato::Oro);
+    }
+    #[test]
+    fn test_preleva_errori() {
+        let mut conto = ContoBancario::new("Giuseppe Verdi".to_string(), eur("100"), eur("50"), eur("150"), tasso("0.05"));
+        assert_eq!(
+            conto.preleva(eur("120")),
+            Err(ErroreConto::FondiInsufficienti { saldo: eur("100"), richiesto: eur("120") })
+        );
+        assert_eq!(conto.preleva(eur("-5")), Err(ErroreConto::ImportoNegativo(eur("-5"))));
+        assert_eq!(
+            conto.deposita(Denaro::da_decimale("5", Valuta::USD).unwrap()),
+            Err(ErroreConto::ImportoNonValido(ErroreDenaro::ValuteDiverse { attesa: Valuta::EUR, trovata: Valuta::USD }))
+        );
+        assert_eq!(conto.saldo(), eur("100"));
+
+        // Scendendo sotto il limite inferiore il conto passa a Rosso e non consente prelievi
+        assert_eq!(conto.preleva(eur("60")).unwrap().stato, Stato::Rosso);
+        assert_eq!(
+            conto.preleva(eur("10")),
+            Err(ErroreConto::OperazioneNonConsentita { stato: Stato::Rosso, operazione: Operazione::Prelievo })
+        );
+        assert_eq!(conto.saldo(), eur("40"));
+    }
+    #[test]
+    fn test_conto_bloccato() {
+        let mut conto = ContoBancario::new("Alessandro Volta".to_string(), eur("200"), eur("50"), eur("150"), tasso("0.05"));
+        conto.blocca();
+        assert_eq!(conto.deposita(eur("10")), Err(ErroreConto::ContoBloccato));
+        assert_eq!(conto.paga_interessi(), Err(ErroreConto::ContoBloccato));
+        conto.sblocca();
+        assert_eq!(conto.paga_interessi().unwrap().importo, eur("10"));
+    }
+    #[test]
+    fn test_interessi_solo_in_oro() {
+        let mut conto = ContoBancario::new("Giuseppe Verdi".to_string(), eur("100"), eur("50"), eur("150"), tasso("0.05"));
+        let ricevuta = conto.paga_interessi().unwrap();
+        assert_eq!(ricevuta.importo, eur("0"));
+        assert_eq!(conto.saldo(), eur("100"));
+    }
+    #[test]
+    fn test_interessi_a_tasso_negativo() {
+        let mut conto = ContoBancario::new("Alessandro Volta".to_string(), eur("200"), eur("50"), eur("150"), tasso("-0.05"));
+        assert_eq!(conto.paga_interessi(), Err(ErroreConto::TassoNegativo(tasso("-0.05"))));
+        assert_eq!(conto.saldo(), eur("200"));
+        assert!(conto.registro().movimenti().is_empty());
+    }
+    #[test]
+    #[should_panic(expected = "nella valuta del saldo")]
+    fn test_limiti_in_altra_valuta() {
+        let dollari = Denaro::da_decimale("150", Valuta::USD).unwrap();
+        ContoBancario::new("Mario Rossi".to_string(), eur("200"), eur("50"), dollari, tasso("0.05"));
     }
 }
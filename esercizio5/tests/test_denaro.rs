@@ -0,0 +1,78 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use esercizio5::denaro::denaro::*;
+    use crate::common::eur;
+
+    #[test]
+    fn test_somma_esatta() {
+        let mut saldo = Denaro::zero(Valuta::EUR);
+        for _ in 0..10 {
+            saldo = saldo + eur("0.10");
+        }
+        assert_eq!(saldo, eur("1"));
+        assert_eq!((eur("0.10") + eur("0.20")).to_string(), "0.30 EUR");
+        assert_eq!((eur("5") - eur("7.25")).to_string(), "-2.25 EUR");
+    }
+
+    #[test]
+    fn test_arrotondamenti() {
+        // 0.25 EUR * 1/2 = 0.125 EUR: a metà tra 0.12 e 0.13
+        let importo = eur("0.25");
+        assert_eq!(importo.moltiplica_frazione(1, 2, Arrotondamento::Bancario), eur("0.12"));
+        assert_eq!(importo.moltiplica_frazione(1, 2, Arrotondamento::MetaPerEccesso), eur("0.13"));
+        assert_eq!(importo.moltiplica_frazione(1, 2, Arrotondamento::Troncamento), eur("0.12"));
+        assert_eq!(eur("0.35").moltiplica_frazione(1, 2, Arrotondamento::Bancario), eur("0.18"));
+        assert_eq!((-importo).moltiplica_frazione(1, 2, Arrotondamento::MetaPerEccesso), eur("-0.13"));
+        assert_eq!(Denaro::da_f64(2.675, Valuta::EUR, Arrotondamento::MetaPerEccesso), Some(eur("2.68")));
+        assert_eq!(Denaro::da_f64(f64::NAN, Valuta::EUR, Arrotondamento::Bancario), None);
+    }
+
+    #[test]
+    fn test_interessi_ripetuti_senza_deriva() {
+        let tasso = Tasso::da_decimale("0.01").unwrap();
+        let mut saldo = eur("1000");
+        for _ in 0..12 {
+            saldo = saldo + saldo.moltiplica(tasso, Arrotondamento::Bancario);
+        }
+        // Ogni passo è arrotondato al centesimo: il risultato è deterministico
+        assert_eq!(saldo, eur("1126.84"));
+    }
+
+    #[test]
+    fn test_formato_e_valute() {
+        assert_eq!("12.50 EUR".parse::<Denaro>(), Ok(eur("12.5")));
+        assert_eq!("1500 JPY".parse::<Denaro>().unwrap().unita_minime(), 1500);
+        assert!(Denaro::da_decimale("1.5", Valuta::JPY).is_err());
+        assert!(Denaro::da_decimale("1.234", Valuta::EUR).is_err());
+        assert!(Denaro::da_decimale("abc", Valuta::EUR).is_err());
+        assert_eq!("3 XYZ".parse::<Denaro>(), Err(ErroreDenaro::ValutaSconosciuta("XYZ".to_string())));
+        assert_eq!(Tasso::da_decimale("0.05").unwrap().to_string(), "0.050000000");
+
+        let dollari = Denaro::da_decimale("1", Valuta::USD).unwrap();
+        assert_eq!(
+            eur("1").somma(dollari),
+            Err(ErroreDenaro::ValuteDiverse { attesa: Valuta::EUR, trovata: Valuta::USD })
+        );
+        assert_eq!(eur("1").partial_cmp(&dollari), None);
+        assert!(eur("1") < eur("1.01"));
+        assert_eq!(Denaro::da_unita_minime(i64::MAX, Valuta::EUR).somma(eur("0.01")), Err(ErroreDenaro::Overflow));
+    }
+
+    #[test]
+    fn test_segno_ai_limiti() {
+        let minimo = Denaro::da_unita_minime(i64::MIN, Valuta::EUR);
+        let massimo = Denaro::da_unita_minime(i64::MAX, Valuta::EUR);
+        assert_eq!(-minimo, massimo);
+        assert_eq!(minimo.abs(), massimo);
+        assert_eq!(-massimo, Denaro::da_unita_minime(-i64::MAX, Valuta::EUR));
+        assert_eq!(eur("-2.50").abs(), eur("2.50"));
+    }
+
+    #[test]
+    #[should_panic(expected = "al massimo 18 cifre decimali")]
+    fn test_valuta_con_troppi_decimali() {
+        Valuta::nuova(*b"XXX", Valuta::DECIMALI_MASSIMI + 1);
+    }
+}
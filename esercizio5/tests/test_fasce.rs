@@ -0,0 +1,163 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use esercizio5::banca::banca::{Banca, ErroreBanca};
+    use esercizio5::conto_bancario::conto_bancario::{ContoBancario, ErroreConto, Operazione};
+    use esercizio5::denaro::denaro::{Denaro, Valuta};
+    use esercizio5::fasce::fasce::{ErroreFasce, Fasce};
+    use esercizio5::registro::registro::TipoMovimento;
+    use esercizio5::stati::stati::Stato;
+    use crate::common::eur;
+
+    const CONFIGURAZIONE: &str = "\
+# Fasce di prova
+valuta = EUR
+isteresi = 5
+
+[Rosso]
+massimo = 0
+operazioni = deposito
+
+[Bronzo]
+minimo = 0
+massimo = 100
+operazioni = deposito, prelievo
+commissione = 0.50
+
+[Oro]   # la fascia più alta
+minimo = 100
+operazioni = deposito, prelievo, interessi
+interesse = 0.10
+";
+
+    #[test]
+    fn test_lettura_configurazione() {
+        let percorso = std::env::temp_dir().join(format!("fasce_{}.conf", std::process::id()));
+        std::fs::write(&percorso, CONFIGURAZIONE).unwrap();
+        let fasce = Fasce::carica(&percorso).unwrap();
+        std::fs::remove_file(&percorso).unwrap();
+
+        let nomi: Vec<&str> = fasce.fasce().iter().map(|f| f.stato.nome()).collect();
+        assert_eq!(nomi, vec!["Rosso", "Bronzo", "Oro"]);
+        assert_eq!(fasce.isteresi(), eur("5"));
+        let bronzo = fasce.fascia(Stato::da_nome("Bronzo")).unwrap();
+        assert_eq!((bronzo.minimo, bronzo.massimo), (Some(eur("0")), Some(eur("100"))));
+        assert!(bronzo.consente(Operazione::Prelievo) && !bronzo.consente(Operazione::Interessi));
+        assert_eq!(Stato::da_nome("Bronzo"), Stato::da_nome("Bronzo"));
+        assert_eq!(Stato::da_nome("Bronzo").to_string(), "Bronzo");
+        assert!(Fasce::carica("/percorso/inesistente.conf").is_err());
+    }
+
+    #[test]
+    fn test_configurazioni_non_valide() {
+        let errore = |testo: &str| Fasce::da_testo(testo).unwrap_err();
+        assert_eq!(errore(""), ErroreFasce::NessunaFascia);
+        assert!(matches!(errore("[Oro]\nminimo = 0"), ErroreFasce::Riga { numero: 1, .. }));
+        assert!(matches!(errore("valuta = EUR\n[A]\noperazioni = bonifico"), ErroreFasce::Riga { numero: 3, .. }));
+        assert!(matches!(errore("valuta = EUR\n[A]\ncolore = blu"), ErroreFasce::Riga { numero: 3, .. }));
+        assert!(matches!(errore("valuta = EUR\n[A]\nmassimo = 10\n[B]\nminimo = 20"), ErroreFasce::Intervalli(_)));
+        assert!(matches!(errore("valuta = EUR\n[A]\nminimo = 10"), ErroreFasce::Intervalli(_)));
+        assert!(matches!(errore("valuta = EUR\n[A]\nmassimo = 10"), ErroreFasce::Intervalli(_)));
+        assert_eq!(errore("valuta = EUR\n[A]\n[A]"), ErroreFasce::NomeDuplicato("A".to_string()));
+        assert!(matches!(errore("valuta = EUR\nisteresi = -1\n[A]"), ErroreFasce::Intervalli(_)));
+    }
+
+    #[test]
+    fn test_regole_delle_fasce() {
+        let fasce = Arc::new(Fasce::da_testo(CONFIGURAZIONE).unwrap());
+        let bronzo = Stato::da_nome("Bronzo");
+        let mut conto = ContoBancario::con_fasce("Mario Rossi".to_string(), eur("50"), Arc::clone(&fasce)).unwrap();
+        assert_eq!(conto.stato(), bronzo);
+
+        // In Bronzo ogni operazione costa 0.50 e gli interessi non sono consentiti
+        conto.preleva(eur("10")).unwrap();
+        assert_eq!(conto.saldo(), eur("39.50"));
+        assert_eq!(
+            conto.paga_interessi(),
+            Err(ErroreConto::OperazioneNonConsentita { stato: bronzo.clone(), operazione: Operazione::Interessi })
+        );
+        assert_eq!(conto.preleva(eur("39.50")), Err(ErroreConto::FondiInsufficienti { saldo: eur("39.50"), richiesto: eur("40") }));
+
+        // In Oro gli interessi sono pagati al tasso della fascia
+        conto.deposita(eur("100.50")).unwrap();
+        assert_eq!((conto.saldo(), conto.stato()), (eur("139.50"), Stato::Oro));
+        assert_eq!(conto.paga_interessi().unwrap().importo, eur("13.95"));
+
+        let dollari = Denaro::da_decimale("1", Valuta::USD).unwrap();
+        assert!(ContoBancario::con_fasce("John Smith".to_string(), dollari, fasce).is_err());
+    }
+
+    #[test]
+    fn test_isteresi() {
+        let fasce = Arc::new(Fasce::da_testo(CONFIGURAZIONE).unwrap());
+        let mut conto = ContoBancario::con_fasce("Anna Bianchi".to_string(), eur("100"), fasce).unwrap();
+        assert_eq!(conto.stato(), Stato::Oro);
+
+        // Oscillando di poco sotto il confine il conto resta Oro
+        for _ in 0..3 {
+            conto.preleva(eur("3")).unwrap();
+            assert_eq!(conto.stato(), Stato::Oro);
+            conto.deposita(eur("3")).unwrap();
+        }
+        conto.preleva(eur("6")).unwrap();
+        assert_eq!(conto.stato(), Stato::da_nome("Bronzo"));
+        // Tornare appena sopra il confine non basta per rientrare in Oro
+        conto.deposita(eur("7")).unwrap();
+        assert_eq!((conto.saldo(), conto.stato()), (eur("100.50"), Stato::da_nome("Bronzo")));
+        conto.deposita(eur("5")).unwrap();
+        assert_eq!(conto.stato(), Stato::Oro);
+
+        let cambi = conto.registro().movimenti().iter().filter(|m| matches!(m.tipo, TipoMovimento::CambioStato { .. })).count();
+        assert_eq!(cambi, 2);
+    }
+
+    #[test]
+    fn test_hook_di_transizione() {
+        let eventi = Arc::new(Mutex::new(Vec::new()));
+        let mut fasce = Fasce::da_testo(CONFIGURAZIONE).unwrap();
+        let registra = |prefisso: &'static str| {
+            let eventi = Arc::clone(&eventi);
+            move |t: &esercizio5::stati::stati::Transizione| eventi.lock().unwrap().push(format!("{} {}->{} {}", prefisso, t.da, t.a, t.saldo))
+        };
+        fasce.on_enter(Stato::Oro, registra("entra")).unwrap();
+        fasce.on_exit(Stato::Oro, registra("esce")).unwrap();
+        fasce.on_exit(Stato::da_nome("Bronzo"), registra("esce")).unwrap();
+        assert_eq!(fasce.on_enter(Stato::Argento, |_| {}), Err(ErroreFasce::FasciaSconosciuta(Stato::Argento)));
+
+        let mut conto = ContoBancario::con_fasce("Mario Rossi".to_string(), eur("90"), Arc::new(fasce)).unwrap();
+        conto.deposita(eur("20.50")).unwrap();
+        conto.preleva(eur("50")).unwrap();
+        assert_eq!(
+            *eventi.lock().unwrap(),
+            vec![
+                "esce Bronzo->Oro 110.00 EUR",
+                "entra Bronzo->Oro 110.00 EUR",
+                "esce Oro->Bronzo 60.00 EUR",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ripristino_senza_hook() {
+        let eventi = Arc::new(Mutex::new(Vec::new()));
+        let mut fasce = Fasce::da_testo(CONFIGURAZIONE).unwrap();
+        let osservatore = Arc::clone(&eventi);
+        fasce.on_enter(Stato::Oro, move |t| osservatore.lock().unwrap().push(format!("entra {}->{}", t.da, t.a))).unwrap();
+        let fasce = Arc::new(fasce);
+
+        let mut banca = Banca::nuova(Valuta::EUR);
+        let mario = banca.apri(ContoBancario::con_fasce("Mario Rossi".to_string(), eur("110"), Arc::clone(&fasce)).unwrap()).unwrap();
+        let anna = banca.apri(ContoBancario::con_fasce("Anna Bianchi".to_string(), eur("10"), fasce).unwrap()).unwrap();
+        banca.blocca(anna).unwrap();
+
+        // Il prelievo porta Mario in Bronzo, ma il deposito fallisce: il ritorno in Oro non è una transizione
+        assert_eq!(
+            banca.trasferisci(mario, anna, eur("20")),
+            Err(ErroreBanca::Conto { id: anna, errore: ErroreConto::ContoBloccato })
+        );
+        assert_eq!(banca.conto(mario).unwrap().stato(), Stato::Oro);
+        assert!(eventi.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,131 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use chrono::NaiveDate;
+    use esercizio5::conto_bancario::conto_bancario::{ContoBancario, Operazione};
+    use esercizio5::denaro::denaro::{Denaro, ErroreDenaro, Tasso, Valuta};
+    use esercizio5::interessi::interessi::{Capitalizzazione, Convenzione, ErroreInteressi, Maturazione, MotoreInteressi};
+    use esercizio5::orologio::orologio::{Orologio, OrologioVirtuale};
+    use esercizio5::registro::registro::TipoMovimento;
+    use esercizio5::stati::stati::Stato;
+    use crate::common::{eur, giorno, tasso};
+
+    /// Conto Oro oltre 5000, Rosso sotto 100, con un orologio virtuale al giorno indicato.
+    fn conto(saldo: &str, inizio: NaiveDate) -> (ContoBancario, Arc<OrologioVirtuale>) {
+        let orologio = Arc::new(OrologioVirtuale::al_giorno(inizio));
+        let mut conto = ContoBancario::new("Mario Rossi".to_string(), eur(saldo), eur("100"), eur("5000"), Tasso::ZERO);
+        conto.imposta_orologio(orologio.clone());
+        (conto, orologio)
+    }
+
+    #[test]
+    fn test_convenzioni() {
+        let act = Convenzione::Act365;
+        let trenta = Convenzione::Trenta360;
+        assert_eq!(act.giorni(giorno(2024, 1, 1), giorno(2025, 1, 1)), 366);
+        assert_eq!(trenta.giorni(giorno(2024, 1, 1), giorno(2025, 1, 1)), 360);
+        assert_eq!(trenta.giorni(giorno(2024, 1, 31), giorno(2024, 3, 1)), 31);
+        assert_eq!(trenta.giorni(giorno(2024, 1, 30), giorno(2024, 1, 31)), 0);
+        assert_eq!(trenta.giorni(giorno(2023, 2, 28), giorno(2023, 3, 1)), 3);
+        assert!(Capitalizzazione::Mensile.fine_periodo(giorno(2024, 2, 29)));
+        assert!(!Capitalizzazione::Mensile.fine_periodo(giorno(2023, 2, 27)));
+        assert!(Capitalizzazione::Trimestrale.fine_periodo(giorno(2024, 6, 30)));
+        assert!(!Capitalizzazione::Trimestrale.fine_periodo(giorno(2024, 5, 31)));
+        assert!(Capitalizzazione::Annuale.fine_periodo(giorno(2024, 12, 31)));
+    }
+
+    #[test]
+    fn test_capitalizzazione_composta() {
+        let motore = MotoreInteressi::nuovo(Convenzione::Trenta360, Capitalizzazione::Mensile).con_tasso(Stato::Oro, tasso("0.12"));
+        let (mut mensile, orologio) = conto("10000", giorno(2024, 1, 1));
+        let mut maturazione = Maturazione::nuova(Valuta::EUR);
+        let ricevute = motore.simula(&mut mensile, &mut maturazione, &orologio, giorno(2024, 12, 31)).unwrap();
+        // 1% al mese composto per 12 mesi, ogni accredito arrotondato al centesimo
+        assert_eq!(ricevute.len(), 12);
+        assert_eq!(ricevute[0].importo, eur("100"));
+        assert_eq!(mensile.saldo(), eur("11268.25"));
+        assert_eq!(orologio.oggi(), giorno(2025, 1, 1));
+
+        let annuale = MotoreInteressi::nuovo(Convenzione::Trenta360, Capitalizzazione::Annuale).con_tasso(Stato::Oro, tasso("0.12"));
+        let (mut conto_annuale, orologio) = conto("10000", giorno(2024, 1, 1));
+        let mut maturazione = Maturazione::nuova(Valuta::EUR);
+        annuale.simula(&mut conto_annuale, &mut maturazione, &orologio, giorno(2024, 11, 30)).unwrap();
+        assert_eq!(annuale.maturato(&maturazione).unwrap(), eur("1100"));
+        annuale.simula(&mut conto_annuale, &mut maturazione, &orologio, giorno(2024, 12, 31)).unwrap();
+        assert_eq!(conto_annuale.saldo(), eur("11200"));
+        assert_eq!(annuale.maturato(&maturazione).unwrap(), eur("0"));
+    }
+
+    #[test]
+    fn test_maturazione_sul_saldo_giornaliero() {
+        let motore = MotoreInteressi::nuovo(Convenzione::Act365, Capitalizzazione::Trimestrale)
+            .con_tasso(Stato::Argento, tasso("0.0365"))
+            .con_tasso(Stato::Oro, tasso("0.073"));
+        let (mut conto, orologio) = conto("1000", giorno(2023, 1, 1));
+        let mut maturazione = Maturazione::nuova(Valuta::EUR);
+
+        // 10 giorni in Argento a 1000, poi 80 giorni in Oro a 10000
+        motore.simula(&mut conto, &mut maturazione, &orologio, giorno(2023, 1, 10)).unwrap();
+        assert_eq!(motore.maturato(&maturazione).unwrap(), eur("1"));
+        conto.deposita(eur("9000")).unwrap();
+        assert_eq!(conto.registro().movimenti()[0].data.date(), giorno(2023, 1, 11));
+        let ricevute = motore.simula(&mut conto, &mut maturazione, &orologio, giorno(2023, 3, 31)).unwrap();
+        assert_eq!(ricevute.len(), 1);
+        assert_eq!(ricevute[0].operazione, Operazione::Interessi);
+        assert_eq!(ricevute[0].importo, eur("161"));
+        assert_eq!(conto.saldo(), eur("10161"));
+    }
+
+    #[test]
+    fn test_interessi_negativi_e_commissione_di_scoperto() {
+        let motore = MotoreInteressi::nuovo(Convenzione::Trenta360, Capitalizzazione::Mensile)
+            .con_tasso(Stato::Rosso, tasso("-0.36"))
+            .con_commissione_scoperto(eur("5"));
+        let (mut conto, orologio) = conto("50", giorno(2024, 4, 1));
+        let mut maturazione = Maturazione::nuova(Valuta::EUR);
+        motore.simula(&mut conto, &mut maturazione, &orologio, giorno(2024, 4, 30)).unwrap();
+        // 50 * -36% / 12 = -1.50 di interessi, più 5 di commissione
+        assert_eq!(conto.saldo(), eur("43.50"));
+        let tipi: Vec<TipoMovimento> = conto.registro().movimenti().iter().map(|m| m.tipo.clone()).collect();
+        assert_eq!(tipi, vec![TipoMovimento::Interessi, TipoMovimento::Commissione]);
+        assert_eq!(conto.registro().movimenti()[0].variazione, eur("-1.50"));
+        assert_eq!(maturazione.giorni_rosso(), 0);
+
+        // Un mese senza giorni in Rosso non paga la commissione
+        conto.deposita(eur("1000")).unwrap();
+        motore.simula(&mut conto, &mut maturazione, &orologio, giorno(2024, 5, 31)).unwrap();
+        assert_eq!(conto.saldo(), eur("1043.50"));
+        assert!(conto.registro().coerente());
+    }
+
+    #[test]
+    fn test_giorni_non_consecutivi() {
+        let motore = MotoreInteressi::nuovo(Convenzione::Act365, Capitalizzazione::Mensile).con_tasso(Stato::Argento, tasso("0.0365"));
+        let (conto, _) = conto("1000", giorno(2024, 1, 1));
+        let mut maturazione = Maturazione::nuova(Valuta::EUR);
+        motore.matura(&conto, &mut maturazione, giorno(2024, 1, 1)).unwrap();
+
+        // Né i giorni saltati né quelli già maturati vengono contati
+        let errore = ErroreInteressi::GiornoNonConsecutivo { atteso: giorno(2024, 1, 2), trovato: giorno(2024, 1, 3) };
+        assert_eq!(motore.matura(&conto, &mut maturazione, giorno(2024, 1, 3)), Err(errore));
+        let errore = ErroreInteressi::GiornoNonConsecutivo { atteso: giorno(2024, 1, 2), trovato: giorno(2024, 1, 1) };
+        assert_eq!(motore.matura(&conto, &mut maturazione, giorno(2024, 1, 1)), Err(errore));
+        assert_eq!(maturazione.ultimo_giorno(), Some(giorno(2024, 1, 1)));
+        motore.matura(&conto, &mut maturazione, giorno(2024, 1, 2)).unwrap();
+        assert_eq!(motore.maturato(&maturazione).unwrap(), eur("0.20"));
+    }
+
+    #[test]
+    fn test_maturato_non_rappresentabile() {
+        let motore = MotoreInteressi::nuovo(Convenzione::Act365, Capitalizzazione::Annuale).con_tasso(Stato::Oro, tasso("100"));
+        let saldo = Denaro::da_unita_minime(9_000_000_000_000_000_000, Valuta::EUR);
+        let conto = ContoBancario::new("Mario Rossi".to_string(), saldo, eur("100"), eur("5000"), Tasso::ZERO);
+        let mut maturazione = Maturazione::nuova(Valuta::EUR);
+        for giorno in giorno(2024, 1, 1).iter_days().take(4) {
+            motore.matura(&conto, &mut maturazione, giorno).unwrap();
+        }
+        assert_eq!(motore.maturato(&maturazione), Err(ErroreDenaro::Overflow));
+    }
+}
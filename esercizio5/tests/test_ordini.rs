@@ -0,0 +1,126 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use chrono::NaiveDate;
+    use esercizio5::banca::banca::{Banca, ErroreBanca, IdConto};
+    use esercizio5::conto_bancario::conto_bancario::{ContoBancario, ErroreConto, Operazione};
+    use esercizio5::denaro::denaro::{Tasso, Valuta};
+    use esercizio5::ordini::ordini::{Esito, OrdinePermanente, Periodicita, Pianificatore, PoliticaRiprova};
+    use esercizio5::orologio::orologio::OrologioVirtuale;
+    use esercizio5::stati::stati::Stato;
+    use crate::common::{eur, giorno};
+
+    /// Banca con un conto per ogni saldo (Rosso sotto 10, Oro da 5000), tutti con lo stesso orologio virtuale.
+    fn banca(saldi: &[&str], inizio: NaiveDate) -> (Banca, Vec<IdConto>, Arc<OrologioVirtuale>) {
+        let orologio = Arc::new(OrologioVirtuale::al_giorno(inizio));
+        let mut banca = Banca::nuova(Valuta::EUR);
+        let ids = saldi
+            .iter()
+            .map(|saldo| {
+                let mut conto = ContoBancario::new("Mario Rossi".to_string(), eur(saldo), eur("10"), eur("5000"), Tasso::ZERO);
+                conto.imposta_orologio(orologio.clone());
+                banca.apri(conto).unwrap()
+            })
+            .collect();
+        (banca, ids, orologio)
+    }
+
+    #[test]
+    fn test_periodicita() {
+        let mensile = Periodicita::Mensile(31);
+        assert_eq!(mensile.prima(giorno(2024, 1, 15)), giorno(2024, 1, 31));
+        assert_eq!(mensile.successiva(giorno(2024, 1, 31)), giorno(2024, 2, 29));
+        assert_eq!(mensile.successiva(giorno(2024, 2, 29)), giorno(2024, 3, 31));
+        assert_eq!(Periodicita::Mensile(5).prima(giorno(2024, 12, 6)), giorno(2025, 1, 5));
+
+        assert_eq!(Periodicita::FineMese.prima(giorno(2023, 2, 10)), giorno(2023, 2, 28));
+        assert_eq!(Periodicita::FineMese.successiva(giorno(2023, 12, 31)), giorno(2024, 1, 31));
+
+        assert_eq!(Periodicita::OgniGiorni(10).prima(giorno(2024, 1, 1)), giorno(2024, 1, 1));
+        assert_eq!(Periodicita::OgniGiorni(10).successiva(giorno(2024, 12, 25)), giorno(2025, 1, 4));
+    }
+
+    #[test]
+    fn test_trasferimenti_mensili_fino_alla_fine() {
+        let (mut banca, ids, orologio) = banca(&["1000", "0"], giorno(2024, 1, 1));
+        let mut pianificatore = Pianificatore::nuovo();
+        let affitto = pianificatore.aggiungi(
+            OrdinePermanente::trasferimento(ids[0], ids[1], eur("300"), Periodicita::Mensile(5), giorno(2024, 1, 1)).fino_al(giorno(2024, 2, 28)),
+        );
+
+        let esecuzioni = pianificatore.simula(&mut banca, &orologio, giorno(2024, 4, 30));
+        let giorni: Vec<NaiveDate> = esecuzioni.iter().map(|e| e.giorno).collect();
+        assert_eq!(giorni, vec![giorno(2024, 1, 5), giorno(2024, 2, 5)]);
+        assert!(esecuzioni.iter().all(|e| e.esito == Esito::Eseguito));
+        assert_eq!(banca.conto(ids[0]).unwrap().saldo(), eur("400"));
+        assert_eq!(banca.conto(ids[1]).unwrap().saldo(), eur("600"));
+        assert!(pianificatore.ordine(affitto).is_none());
+        assert!(banca.conservato());
+
+        // I movimenti sono datati con l'orologio virtuale
+        let primo = &banca.conto(ids[1]).unwrap().registro().movimenti()[0];
+        assert_eq!(primo.data, giorno(2024, 1, 5).and_hms_opt(0, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn test_nuovi_tentativi_per_fondi_insufficienti() {
+        let (mut banca, ids, orologio) = banca(&["50"], giorno(2024, 3, 1));
+        let politica = PoliticaRiprova { tentativi: 2, intervallo: 2 };
+        let mut pianificatore = Pianificatore::nuovo().con_politica(politica);
+        let id = pianificatore.aggiungi(OrdinePermanente::prelievo(ids[0], eur("100"), Periodicita::FineMese, giorno(2024, 3, 1)));
+
+        let esecuzioni = pianificatore.simula(&mut banca, &orologio, giorno(2024, 4, 2));
+        let insufficienti = ErroreBanca::Conto { id: ids[0], errore: ErroreConto::FondiInsufficienti { saldo: eur("50"), richiesto: eur("100") } };
+        assert_eq!(esecuzioni.len(), 2);
+        assert_eq!(esecuzioni[0].giorno, giorno(2024, 3, 31));
+        assert_eq!(esecuzioni[0].esito, Esito::Riprova { errore: insufficienti.clone(), tentativo: 1, il: giorno(2024, 4, 2) });
+        assert_eq!(esecuzioni[1].scadenza, giorno(2024, 3, 31));
+        assert_eq!(esecuzioni[1].esito, Esito::Riprova { errore: insufficienti, tentativo: 2, il: giorno(2024, 4, 4) });
+
+        banca.deposita(ids[0], eur("150")).unwrap();
+        let esecuzioni = pianificatore.simula(&mut banca, &orologio, giorno(2024, 4, 30));
+        let esiti: Vec<_> = esecuzioni.into_iter().map(|e| (e.scadenza, e.giorno, e.esito)).collect();
+        assert_eq!(
+            esiti,
+            vec![(giorno(2024, 3, 31), giorno(2024, 4, 4), Esito::Eseguito), (giorno(2024, 4, 30), giorno(2024, 4, 30), Esito::Eseguito)]
+        );
+        assert_eq!(banca.conto(ids[0]).unwrap().saldo(), eur("0"));
+        assert_eq!(pianificatore.ordine(id).unwrap().scadenza(), giorno(2024, 5, 31));
+    }
+
+    #[test]
+    fn test_stato_rosso_e_scadenza_saltata() {
+        let (mut banca, ids, orologio) = banca(&["5"], giorno(2024, 1, 1));
+        let mut pianificatore = Pianificatore::nuovo().con_politica(PoliticaRiprova { tentativi: 1, intervallo: 1 });
+        pianificatore.aggiungi(OrdinePermanente::prelievo(ids[0], eur("1"), Periodicita::OgniGiorni(7), giorno(2024, 1, 1)));
+
+        let esecuzioni = pianificatore.simula(&mut banca, &orologio, giorno(2024, 1, 8));
+        let rosso = ErroreBanca::Conto { id: ids[0], errore: ErroreConto::OperazioneNonConsentita { stato: Stato::Rosso, operazione: Operazione::Prelievo } };
+        let esiti: Vec<(NaiveDate, Esito)> = esecuzioni.into_iter().map(|e| (e.giorno, e.esito)).collect();
+        assert_eq!(
+            esiti,
+            vec![
+                (giorno(2024, 1, 1), Esito::Riprova { errore: rosso.clone(), tentativo: 1, il: giorno(2024, 1, 2) }),
+                (giorno(2024, 1, 2), Esito::Saltato(rosso.clone())),
+                (giorno(2024, 1, 8), Esito::Riprova { errore: rosso, tentativo: 1, il: giorno(2024, 1, 9) }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ordini_dello_stesso_giorno_in_ordine() {
+        let (mut banca, ids, orologio) = banca(&["150", "0", "0"], giorno(2024, 1, 1));
+        let mut pianificatore = Pianificatore::nuovo().con_politica(PoliticaRiprova::MAI);
+        let primo = pianificatore.aggiungi(OrdinePermanente::trasferimento(ids[0], ids[1], eur("100"), Periodicita::FineMese, giorno(2024, 1, 1)));
+        let secondo = pianificatore.aggiungi(OrdinePermanente::trasferimento(ids[0], ids[2], eur("100"), Periodicita::FineMese, giorno(2024, 1, 1)));
+        // Un ordine con scadenza precedente passa avanti anche se aggiunto dopo
+        let terzo = pianificatore.aggiungi(OrdinePermanente::prelievo(ids[0], eur("20"), Periodicita::Mensile(30), giorno(2024, 1, 1)));
+
+        let esecuzioni = pianificatore.simula(&mut banca, &orologio, giorno(2024, 1, 31));
+        let ordine: Vec<_> = esecuzioni.iter().map(|e| (e.ordine, matches!(e.esito, Esito::Eseguito))).collect();
+        assert_eq!(ordine, vec![(terzo, true), (primo, true), (secondo, false)]);
+        assert_eq!(banca.conto(ids[0]).unwrap().saldo(), eur("30"));
+    }
+}
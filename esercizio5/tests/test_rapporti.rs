@@ -0,0 +1,148 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use chrono::{NaiveDate, NaiveDateTime};
+    use esercizio5::banca::banca::Banca;
+    use esercizio5::cambi::cambi::{ErroreCambi, TabellaCambi};
+    use esercizio5::conto_bancario::conto_bancario::ContoBancario;
+    use esercizio5::denaro::denaro::{Denaro, Tasso, Valuta};
+    use esercizio5::rapporti::rapporti::{self, Valore};
+    use crate::common::{eur, tasso, usd};
+
+    fn giorno(giorno: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 5, giorno).unwrap()
+    }
+
+    fn alle(g: u32, ora: u32) -> NaiveDateTime {
+        giorno(g).and_hms_opt(ora, 0, 0).unwrap()
+    }
+
+    /// Due conti tra 0 e 1000: Mario passa all'Oro il primo giorno, riceve gli interessi e torna
+    /// all'Argento il secondo con un trasferimento ad Anna.
+    fn banca() -> Banca {
+        let mut banca = Banca::nuova(Valuta::EUR);
+        let conto = |nome: &str, saldo: &str, interesse: &str| {
+            ContoBancario::new(nome.to_string(), eur(saldo), eur("0"), eur("1000"), Tasso::da_decimale(interesse).unwrap())
+        };
+        let mario = banca.apri(conto("Mario Rossi", "500", "0.01")).unwrap();
+        let anna = banca.apri(conto("Anna \"Nina\" Bianchi, jr", "50", "0")).unwrap();
+        banca.deposita_il(mario, eur("600"), alle(1, 10)).unwrap();
+        banca.preleva_il(anna, eur("20"), alle(1, 11)).unwrap();
+        banca.paga_interessi_il(mario, alle(1, 18)).unwrap();
+        banca.trasferisci_il(mario, anna, eur("311"), alle(2, 9)).unwrap();
+        assert!(banca.preleva_il(anna, eur("400"), alle(3, 9)).is_err());
+        banca
+    }
+
+    #[test]
+    fn test_saldi_e_totali_per_stato() {
+        let banca = banca();
+        assert_eq!(
+            rapporti::saldi_fine_giornata(&banca, giorno(1)).in_csv(),
+            "conto,intestatario,stato,saldo,valuta\n\
+             2,\"Anna \"\"Nina\"\" Bianchi, jr\",Argento,30.00,EUR\n\
+             1,Mario Rossi,Oro,1111.00,EUR\n"
+        );
+        // Prima di ogni movimento vale lo stato di partenza del primo cambio
+        let apertura = rapporti::saldi_fine_giornata(&banca, NaiveDate::from_ymd_opt(2024, 4, 30).unwrap());
+        assert_eq!(apertura.righe[0], vec![Valore::Intero(1), Valore::from("Mario Rossi"), Valore::from("Argento"), Valore::Importo(eur("500")), Valore::from("EUR")]);
+
+        assert_eq!(rapporti::totali_per_stato(&banca, giorno(1)).in_csv(), "stato,conti,totale,valuta\nArgento,1,30.00,EUR\nOro,1,1111.00,EUR\n");
+        assert_eq!(
+            rapporti::totali_per_stato(&banca, giorno(2)).in_json(),
+            "{\n  \"rapporto\": \"totali per stato 2024-05-02\",\n  \"righe\": [\n    {\"stato\": \"Argento\", \"conti\": 2, \"totale\": 1141.00, \"valuta\": \"EUR\"}\n  ]\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_interessi_e_cambi_di_stato() {
+        let banca = banca();
+        assert_eq!(
+            rapporti::interessi_pagati(&banca, giorno(1), giorno(3)).in_csv(),
+            "conto,intestatario,interessi,valuta\n1,Mario Rossi,11.00,EUR\n2,\"Anna \"\"Nina\"\" Bianchi, jr\",0.00,EUR\n"
+        );
+        assert_eq!(rapporti::interessi_pagati(&banca, giorno(2), giorno(3)).righe[0][2], Valore::Importo(eur("0")));
+
+        let cambi = rapporti::cambi_di_stato(&banca, giorno(1), giorno(3));
+        assert_eq!(
+            cambi.in_csv(),
+            "conto,intestatario,data,da,a\n1,Mario Rossi,2024-05-01 10:00:00,Argento,Oro\n1,Mario Rossi,2024-05-02 09:00:00,Oro,Argento\n"
+        );
+        assert_eq!(rapporti::cambi_di_stato(&banca, giorno(2), giorno(2)).righe.len(), 1);
+        let vuoto = rapporti::cambi_di_stato(&banca, giorno(3), giorno(4));
+        assert_eq!(vuoto.in_json(), "{\n  \"rapporto\": \"cambi di stato dal 2024-05-03 al 2024-05-04\",\n  \"righe\": []\n}\n");
+    }
+
+    #[test]
+    fn test_grandi_movimenti() {
+        let banca = banca();
+        let rapporto = rapporti::grandi_movimenti(&banca, giorno(1), giorno(3), eur("311")).unwrap();
+        assert_eq!(
+            rapporto.in_csv(),
+            "conto,intestatario,sequenza,data,tipo,importo,saldo,valuta\n\
+             1,Mario Rossi,1,2024-05-01 10:00:00,Deposito,600.00,1100.00,EUR\n\
+             1,Mario Rossi,4,2024-05-02 09:00:00,Prelievo,-311.00,800.00,EUR\n\
+             2,\"Anna \"\"Nina\"\" Bianchi, jr\",2,2024-05-02 09:00:00,Deposito,311.00,341.00,EUR\n"
+        );
+        let json = rapporto.in_json();
+        assert!(json.starts_with("{\n  \"rapporto\": \"grandi movimenti dal 2024-05-01 al 2024-05-03 da 311.00 EUR\",\n"));
+        assert!(json.contains(
+            "    {\"conto\": 2, \"intestatario\": \"Anna \\\"Nina\\\" Bianchi, jr\", \"sequenza\": 2, \"data\": \"2024-05-02 09:00:00\", \
+             \"tipo\": \"Deposito\", \"importo\": 311.00, \"saldo\": 341.00, \"valuta\": \"EUR\"}\n  ]"
+        ));
+        assert_eq!(rapporti::grandi_movimenti(&banca, giorno(2), giorno(3), eur("311.01")).unwrap().righe.len(), 0);
+        // Senza tassi di cambio i movimenti in euro non si possono confrontare con una soglia in dollari
+        assert_eq!(
+            rapporti::grandi_movimenti(&banca, giorno(1), giorno(3), Denaro::da_decimale("1", Valuta::USD).unwrap()),
+            Err(ErroreCambi::TassoMancante { da: Valuta::EUR, a: Valuta::USD })
+        );
+    }
+
+    #[test]
+    fn test_divise_e_conti_chiusi() {
+        let cambi = Arc::new(TabellaCambi::nuova().con_tasso(Valuta::EUR, Valuta::USD, tasso("1.25")));
+        let mut banca = Banca::nuova(Valuta::EUR);
+        let conto = |nome: &str, saldo: &str| {
+            let mut conto = ContoBancario::new(nome.to_string(), eur(saldo), eur("0"), eur("1000"), Tasso::ZERO);
+            conto.imposta_cambi(cambi.clone());
+            conto
+        };
+        let mario = banca.apri(conto("Mario Rossi", "500")).unwrap();
+        let anna = banca.apri(conto("Anna Bianchi", "50")).unwrap();
+        banca.apri_divisa(anna, Valuta::USD).unwrap();
+        banca.trasferisci_con_cambio_il(mario, anna, eur("400"), Valuta::USD, alle(1, 10)).unwrap();
+        banca.preleva_il(anna, usd("100"), alle(2, 9)).unwrap();
+        banca.chiudi_il(mario, alle(2, 12)).unwrap();
+
+        // Le divise hanno una riga propria; un conto chiuso compare solo prima della chiusura
+        assert_eq!(
+            rapporti::saldi_fine_giornata(&banca, giorno(1)).in_csv(),
+            "conto,intestatario,stato,saldo,valuta\n\
+             1,Mario Rossi,Argento,100.00,EUR\n\
+             2,Anna Bianchi,Argento,50.00,EUR\n\
+             2,Anna Bianchi,Argento,500.00,USD\n"
+        );
+        assert_eq!(
+            rapporti::saldi_fine_giornata(&banca, giorno(2)).in_csv(),
+            "conto,intestatario,stato,saldo,valuta\n2,Anna Bianchi,Argento,50.00,EUR\n2,Anna Bianchi,Argento,400.00,USD\n"
+        );
+        assert_eq!(rapporti::saldi_fine_giornata(&banca, NaiveDate::from_ymd_opt(2024, 4, 30).unwrap()).righe.len(), 2);
+        assert_eq!(rapporti::totali_per_stato(&banca, giorno(1)).in_csv(), "stato,conti,totale,valuta\nArgento,2,150.00,EUR\nArgento,1,500.00,USD\n");
+        assert_eq!(rapporti::interessi_pagati(&banca, giorno(1), giorno(3)).righe.len(), 2);
+        assert_eq!(rapporti::interessi_pagati(&banca, giorno(3), giorno(3)).righe.len(), 1);
+
+        // I movimenti in dollari sono confrontati con la soglia in euro al loro controvalore
+        let grandi = rapporti::grandi_movimenti(&banca, giorno(1), giorno(3), eur("100")).unwrap();
+        let righe: Vec<(Valore, Valore, Valore)> = grandi.righe.iter().map(|riga| (riga[0].clone(), riga[5].clone(), riga[7].clone())).collect();
+        assert_eq!(
+            righe,
+            vec![
+                (Valore::Intero(1), Valore::Importo(eur("-400")), Valore::from("EUR")),
+                (Valore::Intero(2), Valore::Importo(usd("500")), Valore::from("USD")),
+            ]
+        );
+        assert_eq!(rapporti::grandi_movimenti(&banca, giorno(1), giorno(3), usd("100")).unwrap().righe.len(), 3);
+    }
+}
@@ -0,0 +1,104 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveDateTime};
+    use esercizio5::conto_bancario::conto_bancario::{ContoBancario, ErroreConto};
+    use esercizio5::denaro::denaro::Tasso;
+    use esercizio5::registro::registro::{ErroreRegistro, TipoMovimento};
+    use esercizio5::stati::stati::Stato;
+    use crate::common::eur;
+
+    fn data(testo: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(testo, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    fn giorno(testo: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(testo, "%Y-%m-%d").unwrap()
+    }
+
+    /// Conto con alcuni movimenti a gennaio e febbraio 2024.
+    fn conto_con_movimenti() -> ContoBancario {
+        let mut conto = ContoBancario::new("Mario Rossi".to_string(), eur("100"), eur("50"), eur("150"), Tasso::da_decimale("0.01").unwrap());
+        conto.deposita_il(eur("80"), data("2024-01-10 09:00:00")).unwrap();
+        conto.paga_interessi_il(data("2024-01-31 23:59:59")).unwrap();
+        conto.preleva_il(eur("100.80"), data("2024-02-03 12:30:00")).unwrap();
+        conto.deposita_il(eur("10"), data("2024-02-20 08:00:00")).unwrap();
+        conto
+    }
+
+    #[test]
+    fn test_movimenti_e_ricostruzione_saldo() {
+        let conto = conto_con_movimenti();
+        let registro = conto.registro();
+        let tipi: Vec<TipoMovimento> = registro.movimenti().iter().map(|m| m.tipo.clone()).collect();
+        assert_eq!(
+            tipi,
+            vec![
+                TipoMovimento::Deposito,
+                TipoMovimento::CambioStato { da: Stato::Argento, a: Stato::Oro },
+                TipoMovimento::Interessi,
+                TipoMovimento::Prelievo,
+                TipoMovimento::CambioStato { da: Stato::Oro, a: Stato::Argento },
+                TipoMovimento::Deposito,
+            ]
+        );
+        let sequenze: Vec<u64> = registro.movimenti().iter().map(|m| m.sequenza).collect();
+        assert_eq!(sequenze, vec![1, 2, 3, 4, 5, 6]);
+        assert_eq!(registro.movimenti()[2].variazione, eur("1.80"));
+        assert_eq!(registro.movimenti()[3].variazione, eur("-100.80"));
+
+        assert_eq!(registro.saldo(), conto.saldo());
+        assert_eq!(conto.saldo(), eur("91"));
+        assert!(registro.coerente());
+        assert_eq!(registro.saldo_al(data("2024-01-31 00:00:00")), eur("180"));
+        assert_eq!(registro.saldo_al(data("2023-12-31 00:00:00")), eur("100"));
+    }
+
+    #[test]
+    fn test_operazioni_rifiutate_non_registrate() {
+        let mut conto = conto_con_movimenti();
+        let movimenti = conto.registro().movimenti().len();
+        assert!(conto.preleva_il(eur("1000"), data("2024-03-01 00:00:00")).is_err());
+        assert_eq!(
+            conto.deposita_il(eur("1"), data("2024-02-01 00:00:00")),
+            Err(ErroreConto::Registro(ErroreRegistro::DataPrecedente {
+                ultima: data("2024-02-20 08:00:00"),
+                data: data("2024-02-01 00:00:00"),
+            }))
+        );
+        assert_eq!(conto.registro().movimenti().len(), movimenti);
+        assert_eq!(conto.saldo(), eur("91"));
+
+        // Senza data esplicita il movimento segue sempre l'ultimo registrato
+        conto.deposita(eur("1")).unwrap();
+        assert!(conto.registro().movimenti().last().unwrap().data >= data("2024-02-20 08:00:00"));
+    }
+
+    #[test]
+    fn test_estratto_testo_e_csv() {
+        let conto = conto_con_movimenti();
+        let estratto = conto.estratto(giorno("2024-02-01"), giorno("2024-02-29"));
+        assert_eq!(estratto.saldo_iniziale, eur("181.80"));
+        assert_eq!(estratto.saldo_finale, eur("91"));
+        assert_eq!(estratto.movimenti.len(), 3);
+
+        assert_eq!(
+            estratto.in_csv(),
+            "sequenza,data,tipo,dettaglio,variazione,saldo,valuta\n\
+             4,2024-02-03 12:30:00,Prelievo,,-100.80,81.00,EUR\n\
+             5,2024-02-03 12:30:00,CambioStato,Oro->Argento,0.00,81.00,EUR\n\
+             6,2024-02-20 08:00:00,Deposito,,10.00,91.00,EUR\n"
+        );
+
+        let testo = estratto.to_string();
+        assert!(testo.starts_with("Estratto conto di Mario Rossi dal 2024-02-01 al 2024-02-29\nSaldo iniziale: 181.80 EUR\n"));
+        assert!(testo.contains("    4 2024-02-03 12:30:00 Prelievo            -100.80      81.00 EUR\n"));
+        assert!(testo.contains("CambioStato  Oro->Argento\n"));
+        assert!(testo.ends_with("Saldo finale: 91.00 EUR\n"));
+
+        let vuoto = conto.estratto(giorno("2024-03-01"), giorno("2024-03-31"));
+        assert!(vuoto.movimenti.is_empty());
+        assert_eq!((vuoto.saldo_iniziale, vuoto.saldo_finale), (eur("91"), eur("91")));
+    }
+}
@@ -0,0 +1,192 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use chrono::NaiveDate;
+    use esercizio5::banca::banca::{Banca, ErroreBanca};
+    use esercizio5::commissioni::commissioni::Commissioni;
+    use esercizio5::conto_bancario::conto_bancario::{ContoBancario, ErroreConto, Operazione};
+    use esercizio5::denaro::denaro::{Tasso, Valuta};
+    use esercizio5::interessi::interessi::{Capitalizzazione, Convenzione, Maturazione, MotoreInteressi};
+    use esercizio5::registro::registro::{Attraversamento, TipoMovimento};
+    use esercizio5::stati::stati::Stato;
+    use crate::common::eur;
+
+    /// Conto Rosso sotto zero, Oro da 5000, con il fido indicato.
+    fn con_fido(saldo: &str, fido: &str) -> ContoBancario {
+        let mut conto = ContoBancario::new("Mario Rossi".to_string(), eur(saldo), eur("0"), eur("5000"), Tasso::ZERO);
+        conto.fido = eur(fido);
+        conto
+    }
+
+    fn tipi(conto: &ContoBancario) -> Vec<TipoMovimento> {
+        conto.registro().movimenti().iter().map(|m| m.tipo.clone()).collect()
+    }
+
+    #[test]
+    fn test_prelievo_nel_fido() {
+        let mut conto = con_fido("100", "500");
+        assert_eq!(conto.stato(), Stato::Argento);
+
+        let ricevuta = conto.preleva(eur("300")).unwrap();
+        assert_eq!(ricevuta.saldo, eur("-200"));
+        assert_eq!(ricevuta.stato, Stato::Rosso);
+        assert_eq!(conto.fido_disponibile(), eur("300"));
+        assert_eq!(
+            tipi(&conto),
+            vec![
+                TipoMovimento::Prelievo,
+                TipoMovimento::CambioStato { da: Stato::Argento, a: Stato::Rosso },
+                TipoMovimento::Avviso { soglia: eur("0"), verso: Attraversamento::Discesa },
+            ]
+        );
+
+        // In Rosso i prelievi restano vietati, anche se il fido non è esaurito
+        assert_eq!(
+            conto.preleva(eur("10")),
+            Err(ErroreConto::OperazioneNonConsentita { stato: Stato::Rosso, operazione: Operazione::Prelievo })
+        );
+        conto.deposita(eur("250")).unwrap();
+        assert_eq!(conto.registro().movimenti().last().unwrap().tipo, TipoMovimento::Avviso { soglia: eur("0"), verso: Attraversamento::Salita });
+        assert!(conto.registro().coerente());
+    }
+
+    #[test]
+    fn test_fido_superato() {
+        let mut conto = con_fido("100", "500");
+        assert_eq!(conto.preleva(eur("600.01")), Err(ErroreConto::FondiInsufficienti { saldo: eur("100"), richiesto: eur("600.01") }));
+        assert_eq!(conto.preleva(eur("600")).unwrap().saldo, eur("-500"));
+        assert_eq!(conto.fido_disponibile(), eur("0"));
+
+        // Senza fido il comportamento è quello di sempre
+        let mut senza = con_fido("100", "0");
+        assert_eq!(senza.preleva(eur("100.01")), Err(ErroreConto::FondiInsufficienti { saldo: eur("100"), richiesto: eur("100.01") }));
+        assert!(senza.registro().movimenti().is_empty());
+    }
+
+    #[test]
+    fn test_commissioni_per_stato() {
+        let mut conto = con_fido("100", "0");
+        conto.commissioni = Commissioni::nuove()
+            .con_commissione_operazione(Stato::Argento, eur("1"))
+            .con_canone_mensile(Stato::Argento, eur("2"))
+            .con_canone_mensile(Stato::Oro, eur("0"));
+
+        let ricevuta = conto.deposita(eur("50")).unwrap();
+        assert_eq!(ricevuta.importo, eur("50"));
+        assert_eq!(ricevuta.commissione, eur("1"));
+        assert_eq!(ricevuta.saldo, eur("149"));
+        let movimenti = conto.registro().movimenti();
+        assert_eq!((movimenti[0].tipo.clone(), movimenti[0].variazione), (TipoMovimento::Deposito, eur("50")));
+        assert_eq!((movimenti[1].tipo.clone(), movimenti[1].variazione), (TipoMovimento::Commissione, eur("-1")));
+
+        // Il disponibile deve coprire anche la commissione
+        assert_eq!(conto.preleva(eur("149")), Err(ErroreConto::FondiInsufficienti { saldo: eur("149"), richiesto: eur("150") }));
+        assert_eq!(conto.preleva(eur("148")).unwrap().saldo, eur("0"));
+
+        let data = NaiveDate::from_ymd_opt(2100, 1, 31).unwrap().and_hms_opt(23, 59, 59).unwrap();
+        // Il deposito paga la commissione dell'Argento, lo stato in cui è stato eseguito
+        conto.deposita(eur("6000")).unwrap();
+        assert_eq!(conto.stato(), Stato::Oro);
+        assert_eq!(conto.addebita_canone_il(data).unwrap(), None);
+        conto.preleva(eur("5000")).unwrap();
+        assert_eq!(conto.addebita_canone_il(data).unwrap().unwrap().saldo, eur("997"));
+        assert!(conto.registro().coerente());
+    }
+
+    #[test]
+    fn test_interessi_di_scoperto() {
+        let mut conto = con_fido("-365", "1000");
+        let motore = MotoreInteressi::nuovo(Convenzione::Act365, Capitalizzazione::Mensile)
+            .con_tasso(Stato::Argento, Tasso::da_decimale("0.01").unwrap())
+            .con_tasso_scoperto(Tasso::da_decimale("0.10").unwrap());
+        let mut maturazione = Maturazione::nuova(Valuta::EUR);
+        let inizio = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        for giorno in inizio.iter_days().take(31) {
+            motore.matura(&conto, &mut maturazione, giorno).unwrap();
+            motore.capitalizza_se_dovuto(&mut conto, &mut maturazione, giorno).unwrap();
+        }
+        // -365 * 10% * 31 / 365
+        assert_eq!(conto.saldo(), eur("-368.10"));
+        let ultimo = conto.registro().movimenti().last().unwrap();
+        assert_eq!((ultimo.tipo.clone(), ultimo.variazione), (TipoMovimento::Interessi, eur("-3.10")));
+    }
+
+    #[test]
+    fn test_avvisi_e_notifiche() {
+        let mut conto = con_fido("100", "0");
+        conto.soglie = vec![eur("50"), eur("20")];
+        let ricevuti = Arc::new(Mutex::new(Vec::new()));
+        let copia = ricevuti.clone();
+        conto.on_avviso(move |movimento| copia.lock().unwrap().push(movimento.tipo.dettaglio()));
+
+        conto.preleva(eur("90")).unwrap();
+        conto.preleva(eur("5")).unwrap();
+        conto.deposita(eur("40")).unwrap();
+        assert_eq!(*ricevuti.lock().unwrap(), vec!["sotto 50.00 EUR", "sotto 20.00 EUR", "sopra 20.00 EUR"]);
+
+        let avvisi = conto.registro().movimenti().iter().filter(|m| matches!(m.tipo, TipoMovimento::Avviso { .. })).count();
+        assert_eq!(avvisi, 3);
+        assert!(conto.estratto(NaiveDate::MIN, NaiveDate::MAX).to_string().contains("Avviso       sotto 50.00 EUR\n"));
+    }
+
+    #[test]
+    fn test_banca_con_commissioni_conserva_il_denaro() {
+        let mut banca = Banca::nuova(Valuta::EUR);
+        let commissioni = Commissioni::nuove().con_commissione_operazione(Stato::Argento, eur("0.50"));
+        let mut mario = con_fido("100", "200");
+        mario.commissioni = commissioni.clone();
+        let mut anna = con_fido("100", "0");
+        anna.commissioni = commissioni;
+        let (mario, anna) = (banca.apri(mario).unwrap(), banca.apri(anna).unwrap());
+
+        let ricevuta = banca.trasferisci(mario, anna, eur("250")).unwrap();
+        assert_eq!(ricevuta.commissioni(), eur("1"));
+        assert_eq!(banca.conto(mario).unwrap().saldo(), eur("-150.50"));
+        assert_eq!(banca.conto(anna).unwrap().saldo(), eur("349.50"));
+        banca.deposita(mario, eur("10")).unwrap();
+        assert!(banca.conservato());
+    }
+
+    #[test]
+    fn test_avvisi_di_un_trasferimento_annullato() {
+        let mut banca = Banca::nuova(Valuta::EUR);
+        let mut mario = con_fido("100", "0");
+        mario.soglie = vec![eur("50")];
+        let ricevuti = Arc::new(Mutex::new(Vec::new()));
+        let copia = ricevuti.clone();
+        mario.on_avviso(move |movimento| copia.lock().unwrap().push(movimento.tipo.dettaglio()));
+        let (mario, anna) = (banca.apri(mario).unwrap(), banca.apri(con_fido("100", "0")).unwrap());
+
+        // Il prelievo attraversa la soglia, ma il trasferimento viene annullato: nessuna notifica
+        banca.blocca(anna).unwrap();
+        assert_eq!(
+            banca.trasferisci(mario, anna, eur("60")),
+            Err(ErroreBanca::Conto { id: anna, errore: ErroreConto::ContoBloccato })
+        );
+        assert!(ricevuti.lock().unwrap().is_empty());
+
+        // A trasferimento concluso l'avviso viene notificato una volta sola
+        banca.sblocca(anna).unwrap();
+        banca.trasferisci(mario, anna, eur("60")).unwrap();
+        assert_eq!(*ricevuti.lock().unwrap(), vec!["sotto 50.00 EUR"]);
+    }
+
+    #[test]
+    fn test_commissione_di_deposito_oltre_il_fido() {
+        let mut conto = con_fido("0.20", "0");
+        conto.commissioni = Commissioni::nuove()
+            .con_commissione_operazione(Stato::Argento, eur("0.50"))
+            .con_commissione_operazione(Stato::Rosso, eur("0.50"));
+
+        // Il deposito non copre la commissione e il saldo finirebbe sotto zero senza fido
+        assert_eq!(conto.deposita(eur("0.10")), Err(ErroreConto::FondiInsufficienti { saldo: eur("0.20"), richiesto: eur("0.40") }));
+        assert!(conto.registro().movimenti().is_empty());
+        assert_eq!(conto.deposita(eur("0.30")).unwrap().saldo, eur("0"));
+
+        // Con un fido la commissione può usarlo
+        conto.fido = eur("1");
+        assert_eq!(conto.deposita(eur("0.10")).unwrap().saldo, eur("-0.40"));
+    }
+}
@@ -0,0 +1,189 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use chrono::{NaiveDate, NaiveDateTime};
+    use esercizio5::banca::banca::{Banca, ErroreBanca};
+    use esercizio5::cambi::cambi::TabellaCambi;
+    use esercizio5::conto_bancario::conto_bancario::{ContoBancario, ErroreConto};
+    use esercizio5::denaro::denaro::{Tasso, Valuta};
+    use esercizio5::titolari::titolari::{ErroreAutorizzazione, Permesso, Ruolo, Titolare};
+    use crate::common::{eur, tasso, usd};
+
+    fn alle(giorno: u32, ora: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 6, giorno).unwrap().and_hms_opt(ora, 0, 0).unwrap()
+    }
+
+    fn negato(errore: ErroreAutorizzazione) -> Result<(), ErroreConto> {
+        Err(ErroreConto::Autorizzazione(errore))
+    }
+
+    /// Conto di Mario Rossi tra 0 e 10000, con un cointestatario, un titolare in sola lettura
+    /// e un delegato fino a 300 euro.
+    fn conto() -> ContoBancario {
+        let mut conto = ContoBancario::new("Mario Rossi".to_string(), eur("1000"), eur("0"), eur("10000"), Tasso::ZERO);
+        conto.aggiungi_titolare("Mario Rossi", Titolare::nuovo("Anna Rossi", Ruolo::Cointestatario).con_limite_giornaliero(eur("200"))).unwrap();
+        conto.aggiungi_titolare("Mario Rossi", Titolare::nuovo("Giulia", Ruolo::SolaLettura)).unwrap();
+        conto.aggiungi_titolare("Mario Rossi", Titolare::nuovo("Luca", Ruolo::Delegato { tetto: eur("300") })).unwrap();
+        conto
+    }
+
+    #[test]
+    fn test_ruoli_e_gestione_dei_titolari() {
+        let mut conto = conto();
+        let nomi: Vec<&str> = conto.titolari().elenco().iter().map(|t| t.nome.as_str()).collect();
+        assert_eq!(nomi, vec!["Mario Rossi", "Anna Rossi", "Giulia", "Luca"]);
+
+        let zero = eur("0");
+        assert_eq!(conto.autorizza("Giulia", Permesso::Consultazione, zero, alle(1, 9)), Ok(()));
+        assert_eq!(
+            conto.autorizza("Giulia", Permesso::Deposito, eur("10"), alle(1, 9)),
+            negato(ErroreAutorizzazione::NonConsentito { titolare: "Giulia".to_string(), ruolo: Ruolo::SolaLettura, permesso: Permesso::Deposito })
+        );
+        assert_eq!(conto.autorizza("Luca", Permesso::Deposito, eur("10"), alle(1, 9)), Ok(()));
+        assert_eq!(conto.autorizza("Piero", Permesso::Consultazione, zero, alle(1, 9)), negato(ErroreAutorizzazione::TitolareSconosciuto("Piero".to_string())));
+
+        // Solo gli intestatari gestiscono i titolari, e l'ultimo non può essere rimosso
+        assert!(matches!(
+            conto.aggiungi_titolare("Anna Rossi", Titolare::nuovo("Piero", Ruolo::SolaLettura)),
+            Err(ErroreConto::Autorizzazione(ErroreAutorizzazione::NonConsentito { permesso: Permesso::Gestione, .. }))
+        ));
+        assert_eq!(
+            conto.aggiungi_titolare("Mario Rossi", Titolare::nuovo("Giulia", Ruolo::Cointestatario)),
+            negato(ErroreAutorizzazione::TitolareEsistente("Giulia".to_string()))
+        );
+        assert_eq!(
+            conto.rimuovi_titolare("Mario Rossi", "Mario Rossi"),
+            Err(ErroreConto::Autorizzazione(ErroreAutorizzazione::UltimoIntestatario("Mario Rossi".to_string())))
+        );
+        assert_eq!(conto.rimuovi_titolare("Mario Rossi", "Giulia").unwrap().ruolo, Ruolo::SolaLettura);
+        assert!(conto.titolari().titolare("Giulia").is_none());
+
+        conto.aggiungi_titolare("Mario Rossi", Titolare::nuovo("Sara", Ruolo::Intestatario)).unwrap();
+        conto.rimuovi_titolare("Sara", "Mario Rossi").unwrap();
+        assert!(matches!(conto.preleva_come_il("Mario Rossi", eur("1"), alle(1, 10)), Err(ErroreConto::Autorizzazione(ErroreAutorizzazione::TitolareSconosciuto(_)))));
+    }
+
+    #[test]
+    fn test_limiti_non_validi() {
+        let mut conto = conto();
+        let non_valido = |limite| negato(ErroreAutorizzazione::LimiteNonValido { titolare: "Piero".to_string(), limite, valuta: Valuta::EUR });
+        assert_eq!(conto.aggiungi_titolare("Mario Rossi", Titolare::nuovo("Piero", Ruolo::Delegato { tetto: usd("100") })), non_valido(usd("100")));
+        assert_eq!(conto.aggiungi_titolare("Mario Rossi", Titolare::nuovo("Piero", Ruolo::Delegato { tetto: eur("-1") })), non_valido(eur("-1")));
+        assert_eq!(
+            conto.aggiungi_titolare("Mario Rossi", Titolare::nuovo("Piero", Ruolo::Cointestatario).con_limite_giornaliero(usd("50"))),
+            non_valido(usd("50"))
+        );
+        assert_eq!(
+            conto.aggiungi_titolare("Mario Rossi", Titolare::nuovo("Piero", Ruolo::Cointestatario).con_limite_giornaliero(eur("-0.01"))),
+            non_valido(eur("-0.01"))
+        );
+        assert!(conto.titolari().titolare("Piero").is_none());
+        conto.aggiungi_titolare("Mario Rossi", Titolare::nuovo("Piero", Ruolo::Delegato { tetto: eur("0") }).con_limite_giornaliero(eur("0"))).unwrap();
+    }
+
+    #[test]
+    fn test_limite_giornaliero() {
+        let mut conto = conto();
+        conto.preleva_come_il("Anna Rossi", eur("150"), alle(1, 9)).unwrap();
+        assert_eq!(
+            conto.preleva_come_il("Anna Rossi", eur("60"), alle(1, 18)).map(|_| ()),
+            negato(ErroreAutorizzazione::LimiteGiornaliero { titolare: "Anna Rossi".to_string(), limite: eur("200"), speso: eur("150"), richiesto: eur("60") })
+        );
+        assert_eq!(conto.saldo(), eur("850"));
+        conto.preleva_come_il("Anna Rossi", eur("50"), alle(1, 19)).unwrap();
+
+        // Il giorno dopo il limite riparte, mentre i prelievi interni della banca non lo toccano
+        assert_eq!(conto.titolari().speso_il("Anna Rossi", alle(2, 0).date()), eur("0"));
+        conto.preleva_come_il("Anna Rossi", eur("200"), alle(2, 9)).unwrap();
+        conto.preleva_il(eur("100"), alle(2, 10)).unwrap();
+        assert_eq!(conto.titolari().speso("Anna Rossi"), eur("400"));
+        assert_eq!(conto.saldo(), eur("500"));
+
+        // Un prelievo rifiutato dal conto non consuma il limite
+        conto.preleva_come_il("Mario Rossi", eur("500"), alle(3, 9)).unwrap();
+        assert!(matches!(conto.preleva_come_il("Anna Rossi", eur("1"), alle(3, 10)), Err(ErroreConto::FondiInsufficienti { .. })));
+        assert_eq!(conto.titolari().speso_il("Anna Rossi", alle(3, 0).date()), eur("0"));
+    }
+
+    #[test]
+    fn test_tetto_del_delegato_e_consultazione() {
+        let mut conto = conto();
+        conto.preleva_come_il("Luca", eur("200"), alle(1, 9)).unwrap();
+        conto.deposita_come_il("Luca", eur("500"), alle(2, 9)).unwrap();
+        // Il tetto è complessivo: non riparte il giorno dopo né con i depositi
+        assert_eq!(
+            conto.preleva_come_il("Luca", eur("100.01"), alle(3, 9)).map(|_| ()),
+            negato(ErroreAutorizzazione::TettoSuperato { titolare: "Luca".to_string(), tetto: eur("300"), speso: eur("200"), richiesto: eur("100.01") })
+        );
+        conto.preleva_come_il("Luca", eur("100"), alle(3, 9)).unwrap();
+        assert_eq!(conto.saldo(), eur("1200"));
+
+        let estratto = conto.estratto_come("Giulia", alle(1, 0).date(), alle(3, 0).date()).unwrap();
+        assert_eq!(estratto, conto.estratto(alle(1, 0).date(), alle(3, 0).date()));
+        assert!(matches!(
+            conto.estratto_come("Piero", alle(1, 0).date(), alle(3, 0).date()),
+            Err(ErroreConto::Autorizzazione(ErroreAutorizzazione::TitolareSconosciuto(_)))
+        ));
+    }
+
+    #[test]
+    fn test_trasferimento_per_conto_di_un_titolare() {
+        let mut banca = Banca::nuova(Valuta::EUR);
+        let mario = banca.apri(conto()).unwrap();
+        let anna = banca.apri(ContoBancario::new("Anna Bianchi".to_string(), eur("0"), eur("0"), eur("10000"), Tasso::ZERO)).unwrap();
+
+        banca.trasferisci_come_il("Luca", mario, anna, eur("250"), alle(1, 9)).unwrap();
+        assert_eq!(banca.conto(mario).unwrap().titolari().speso("Luca"), eur("250"));
+        assert!(matches!(
+            banca.trasferisci_come_il("Luca", mario, anna, eur("100"), alle(1, 10)),
+            Err(ErroreBanca::Conto { id, errore: ErroreConto::Autorizzazione(ErroreAutorizzazione::TettoSuperato { .. }) }) if id == mario
+        ));
+        assert!(matches!(
+            banca.trasferisci_come_il("Giulia", mario, anna, eur("1"), alle(1, 10)),
+            Err(ErroreBanca::Conto { errore: ErroreConto::Autorizzazione(ErroreAutorizzazione::NonConsentito { .. }), .. })
+        ));
+        // I titolari del conto di destinazione non contano
+        assert!(banca.trasferisci_come_il("Anna Bianchi", mario, anna, eur("1"), alle(1, 10)).is_err());
+        assert_eq!(banca.conto(anna).unwrap().saldo(), eur("250"));
+        assert!(banca.conservato());
+    }
+
+    #[test]
+    fn test_operazioni_in_divisa_per_conto_di_un_titolare() {
+        let cambi = Arc::new(TabellaCambi::nuova().con_tasso(Valuta::EUR, Valuta::USD, tasso("1.25")));
+        let mut mario = conto();
+        mario.imposta_cambi(cambi.clone());
+        mario.apri_divisa(Valuta::USD).unwrap();
+        let mut anna = ContoBancario::new("Anna Bianchi".to_string(), eur("0"), eur("0"), eur("10000"), Tasso::ZERO);
+        anna.imposta_cambi(cambi);
+        anna.apri_divisa(Valuta::USD).unwrap();
+
+        // Le conversioni interne al conto richiedono il prelievo ma non consumano i limiti
+        assert!(matches!(
+            mario.converti_come_il("Giulia", eur("10"), Valuta::USD, alle(1, 8)),
+            Err(ErroreConto::Autorizzazione(ErroreAutorizzazione::NonConsentito { permesso: Permesso::Prelievo, .. }))
+        ));
+        mario.converti_come_il("Luca", eur("400"), Valuta::USD, alle(1, 8)).unwrap();
+        assert_eq!(mario.titolari().speso("Luca"), eur("0"));
+
+        let mut banca = Banca::nuova(Valuta::EUR);
+        let (mario, anna) = (banca.apri(mario).unwrap(), banca.apri(anna).unwrap());
+        banca.trasferisci_con_cambio_come_il("Luca", mario, anna, eur("250"), Valuta::USD, alle(1, 9)).unwrap();
+        assert!(matches!(
+            banca.trasferisci_con_cambio_come_il("Luca", mario, anna, eur("60"), Valuta::USD, alle(1, 10)),
+            Err(ErroreBanca::Conto { id, errore: ErroreConto::Autorizzazione(ErroreAutorizzazione::TettoSuperato { .. }) }) if id == mario
+        ));
+        // I prelievi in dollari contano nel tetto in euro al loro controvalore
+        banca.preleva_come_il("Luca", mario, usd("62.50"), alle(1, 11)).unwrap();
+        assert_eq!(banca.conto(mario).unwrap().titolari().speso("Luca"), eur("300"));
+        assert!(banca.preleva_come_il("Luca", mario, usd("1.25"), alle(1, 12)).is_err());
+        assert!(matches!(
+            banca.deposita_come_il("Giulia", anna, usd("1"), alle(1, 12)),
+            Err(ErroreBanca::Conto { errore: ErroreConto::Autorizzazione(ErroreAutorizzazione::TitolareSconosciuto(_)), .. })
+        ));
+        assert_eq!(banca.conto(anna).unwrap().saldo_in(Valuta::USD), Some(usd("312.50")));
+        assert_eq!(banca.conto(mario).unwrap().saldo_in(Valuta::USD), Some(usd("437.50")));
+    }
+}